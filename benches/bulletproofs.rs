@@ -7,33 +7,33 @@ extern crate rand;
 use rand::{OsRng, Rng};
 
 extern crate curve25519_dalek;
-use curve25519_dalek::scalar::Scalar;
 
 extern crate ristretto_bulletproofs;
 use ristretto_bulletproofs::{PedersenGenerators, Generators};
 use ristretto_bulletproofs::ProofTranscript;
 use ristretto_bulletproofs::RangeProof;
+use ristretto_bulletproofs::{Blinding, SecretValue};
 
 fn bench_create_helper(n: usize, c: &mut Criterion) {
     c.bench_function(&format!("create_rangeproof_n_{}", n), move |b| {
-        let generators = Generators::new(PedersenGenerators::default(), n, 1);
+        let generators = Generators::new(b"Bench", PedersenGenerators::default(), n, 1);
         let mut rng = OsRng::new().unwrap();
 
-        let v: u64 = rng.gen_range(0, (1 << (n - 1)) - 1);
-        let v_blinding = Scalar::random(&mut rng);
+        let v = SecretValue::new(rng.gen_range(0, (1 << (n - 1)) - 1));
+        let v_blinding = Blinding::random(&mut rng);
 
         b.iter(|| {
             // Each proof creation requires a clean transcript.
-            let mut transcript = ProofTranscript::new(b"RangeproofTest");
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
 
             RangeProof::generate_proof(
                 generators.share(0),
                 &mut transcript,
                 &mut rng,
                 n,
-                v,
+                &v,
                 &v_blinding,
-            )
+            ).unwrap()
         })
     });
 }
@@ -41,27 +41,27 @@ fn bench_create_helper(n: usize, c: &mut Criterion) {
 fn bench_verify_helper(n: usize, c: &mut Criterion) {
     c.bench_function(&format!("verify_rangeproof_n_{}", n), move |b| {
         let pg = PedersenGenerators::default();
-        let generators = Generators::new(pg.clone(), n, 1);
+        let generators = Generators::new(b"Bench", pg.clone(), n, 1);
         let mut rng = OsRng::new().unwrap();
 
-        let mut transcript = ProofTranscript::new(b"RangeproofTest");
-        let v: u64 = rng.gen_range(0, (1 << (n - 1)) - 1);
-        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let v = SecretValue::new(rng.gen_range(0, (1 << (n - 1)) - 1));
+        let v_blinding = Blinding::random(&mut rng);
 
-        let vc =  pg.commit(Scalar::from_u64(v), v_blinding);
+        let vc =  pg.commit(v.to_scalar(), *v_blinding.as_scalar());
 
         let rp = RangeProof::generate_proof(
             generators.share(0),
             &mut transcript,
             &mut rng,
             n,
-            v,
+            &v,
             &v_blinding,
-        );
+        ).unwrap();
 
         b.iter(|| {
             // Each verification requires a clean transcript.
-            let mut transcript = ProofTranscript::new(b"RangeproofTest");
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
 
             rp.verify(&vc, generators.share(0), &mut transcript, &mut rng, n)
         });
@@ -0,0 +1,9 @@
+//! Prints the test vectors of the `test_vectors` module as JSON.
+
+extern crate ristretto_bulletproofs;
+
+use ristretto_bulletproofs::test_vectors;
+
+fn main() {
+    println!("{}", test_vectors::to_json(&test_vectors::generate()));
+}
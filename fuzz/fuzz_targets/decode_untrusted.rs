@@ -0,0 +1,101 @@
+#![no_main]
+#![feature(try_from)]
+
+//! Feeds untrusted bytes to every decoder of the crate, which must
+//! return errors instead of panicking.
+//!
+//! Whatever a decoder accepts must also survive a round trip: the
+//! canonical encodings, `to_bytes` and CBOR, must reproduce the input
+//! exactly, and the other formats must decode their own re-encoding to
+//! the same proof.
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bincode;
+extern crate ristretto_bulletproofs;
+extern crate serde_json;
+
+use std::convert::TryFrom;
+
+use ristretto_bulletproofs::bundle::ProofBundle;
+use ristretto_bulletproofs::confidential_tx::{BalanceProof, ConfidentialTx};
+use ristretto_bulletproofs::delegation::DelegationRequest;
+use ristretto_bulletproofs::embedded::SmallRangeProof;
+use ristretto_bulletproofs::envelope::ProofEnvelope;
+use ristretto_bulletproofs::json::{InnerProductProofJson, ProofBundleJson, RangeProofJson};
+use ristretto_bulletproofs::one_of_many::OneOfManyProof;
+use ristretto_bulletproofs::polynomial_commitment::OpeningProof;
+use ristretto_bulletproofs::r1cs::{JointProof, R1CSProof};
+use ristretto_bulletproofs::sigma::{OrProof, SigmaProof};
+use ristretto_bulletproofs::snapshot::TranscriptSnapshot;
+use ristretto_bulletproofs::solvency::SolvencyProof;
+use ristretto_bulletproofs::vector_commitment::PositionProof;
+use ristretto_bulletproofs::{cbor, proto};
+use ristretto_bulletproofs::{Generators, InnerProductProof, RangeProof};
+
+/// Checks that the serde types accept their own bincode encoding back.
+macro_rules! bincode_round_trip {
+    ($bytes:expr, $($ty:ty),*) => {
+        $(
+            if let Ok(value) = bincode::deserialize::<$ty>($bytes) {
+                let encoded = bincode::serialize(&value).unwrap();
+                bincode::deserialize::<$ty>(&encoded).unwrap();
+            }
+        )*
+    };
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = RangeProof::from_bytes(data) {
+        assert_eq!(proof.to_bytes(), data);
+    }
+    if let Ok(proof) = InnerProductProof::from_bytes(data) {
+        assert_eq!(proof.to_bytes(), data);
+    }
+    if let Ok(proof) = cbor::decode_range_proof(data) {
+        assert_eq!(cbor::encode_range_proof(&proof), data);
+    }
+    if let Ok(proof) = proto::decode_range_proof(data) {
+        let encoded = proto::encode_range_proof(&proof);
+        let decoded = proto::decode_range_proof(&encoded).unwrap();
+        assert_eq!(decoded.to_bytes(), proof.to_bytes());
+    }
+    if let Ok(dto) = serde_json::from_slice::<RangeProofJson>(data) {
+        if let Ok(proof) = RangeProof::try_from(dto) {
+            let decoded = RangeProof::try_from(RangeProofJson::from(&proof)).unwrap();
+            assert_eq!(decoded.to_bytes(), proof.to_bytes());
+        }
+    }
+    if let Ok(dto) = serde_json::from_slice::<InnerProductProofJson>(data) {
+        let _ = InnerProductProof::try_from(dto);
+    }
+    if let Ok(dto) = serde_json::from_slice::<ProofBundleJson>(data) {
+        let _ = ProofBundle::try_from(dto);
+    }
+    if let Ok(envelope) = ProofEnvelope::from_bytes(data) {
+        assert!(ProofEnvelope::from_bytes(&envelope.to_bytes()).is_ok());
+    }
+    if let Ok(snapshot) = TranscriptSnapshot::from_bytes(data) {
+        assert_eq!(TranscriptSnapshot::from_bytes(&snapshot.to_bytes()), Ok(snapshot));
+    }
+    let _ = SmallRangeProof::from_bytes(data);
+    let _ = Generators::from_bytes(data);
+
+    bincode_round_trip!(
+        data,
+        RangeProof,
+        InnerProductProof,
+        ProofBundle,
+        SigmaProof,
+        OrProof,
+        OneOfManyProof,
+        SolvencyProof,
+        ConfidentialTx,
+        BalanceProof,
+        OpeningProof,
+        PositionProof,
+        R1CSProof,
+        JointProof,
+        DelegationRequest
+    );
+});
@@ -0,0 +1,148 @@
+#![no_main]
+#![allow(non_snake_case)]
+
+//! Feeds untrusted statements and proofs to the verifiers of the
+//! protocols built on the range proof and the inner-product argument,
+//! and untrusted requests to the helper of a delegated range proof,
+//! which must return errors instead of panicking.
+//!
+//! The input is a byte selecting the protocol, a byte selecting the
+//! bitsize \\(n\\), a power of two up to 64, and the bincode encoding of
+//! the public inputs of the statement followed by the proof, so that
+//! the sizes of the statement are fuzzed along with the proof.
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bincode;
+extern crate curve25519_dalek;
+extern crate rand;
+extern crate ristretto_bulletproofs;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand::ChaChaRng;
+
+use ristretto_bulletproofs::confidential_tx::{BalanceProof, ConfidentialTx};
+use ristretto_bulletproofs::delegation::DelegationRequest;
+use ristretto_bulletproofs::one_of_many::OneOfManyProof;
+use ristretto_bulletproofs::polynomial_commitment::OpeningProof;
+use ristretto_bulletproofs::r1cs::{ConstraintSystem, JointProof, R1CSProof, Variable, Verifier};
+use ristretto_bulletproofs::sigma::{OrProof, SigmaProof, Statement};
+use ristretto_bulletproofs::solvency::SolvencyProof;
+use ristretto_bulletproofs::vector_commitment::{PositionProof, VectorCommitment};
+use ristretto_bulletproofs::{Generators, PedersenGenerators, ProofTranscript, RangeProof};
+
+fn transcript() -> ProofTranscript {
+    ProofTranscript::new(b"Fuzz").unwrap()
+}
+
+/// Commits the `values` and constrains consecutive triples of them to
+/// \\(a \cdot b = c\\).
+fn mul_gadget(verifier: &mut Verifier, values: &[RistrettoPoint]) {
+    let vars: Vec<Variable> = values.iter().map(|V| verifier.commit(*V)).collect();
+    for triple in vars.chunks(3).filter(|triple| triple.len() == 3) {
+        let (_, _, o) = verifier.multiply(triple[0].into(), triple[1].into());
+        verifier.constrain(o - triple[2]);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let n = 1 << (data[1] % 7);
+    let bytes = &data[2..];
+
+    let generators = Generators::new(b"Fuzz", PedersenGenerators::default(), 64, 2);
+    let share = generators.share(0);
+    let pg = share.pedersen_generators;
+    let mut rng = ChaChaRng::new_unseeded();
+
+    match data[0] % 11 {
+        0 => {
+            if let Ok((V, proof)) = bincode::deserialize::<(RistrettoPoint, SigmaProof)>(bytes) {
+                let _ = proof.verify(&Statement::opening(pg, &V), &mut transcript());
+                let _ = proof.verify(&Statement::dlog(&pg.B_blinding, &V), &mut transcript());
+            }
+        }
+        1 => {
+            if let Ok((Vs, proof)) = bincode::deserialize::<(Vec<RistrettoPoint>, OrProof)>(bytes)
+            {
+                let statements: Vec<Statement> =
+                    Vs.iter().map(|V| Statement::opening(pg, V)).collect();
+                let _ = proof.verify(&statements, &mut transcript());
+            }
+        }
+        2 => {
+            type Input = (RistrettoPoint, Vec<RistrettoPoint>, OneOfManyProof);
+            if let Ok((V, set, proof)) = bincode::deserialize::<Input>(bytes) {
+                let _ = proof.verify(pg, &mut transcript(), &V, &set);
+            }
+        }
+        3 => {
+            if let Ok((liabilities, reserve, proof)) =
+                bincode::deserialize::<(Vec<RistrettoPoint>, RistrettoPoint, SolvencyProof)>(bytes)
+            {
+                let _ = proof.verify(share, &mut transcript(), &mut rng, n, &liabilities, &reserve);
+            }
+        }
+        4 => {
+            if let Ok((tx, proof)) = bincode::deserialize::<(ConfidentialTx, BalanceProof)>(bytes) {
+                let _ = tx.verify(&proof, share, &mut transcript(), &mut rng, n);
+            }
+        }
+        5 => {
+            if let Ok((C, x, y, proof)) =
+                bincode::deserialize::<(RistrettoPoint, Scalar, Scalar, OpeningProof)>(bytes)
+            {
+                let _ = proof.verify(share, &mut transcript(), n, &C, &x, &y);
+            }
+        }
+        6 => {
+            if let Ok((C, openings, proof)) =
+                bincode::deserialize::<(RistrettoPoint, Vec<(u64, Scalar)>, PositionProof)>(bytes)
+            {
+                let C = VectorCommitment::from_point(C);
+                let openings: Vec<(usize, Scalar)> =
+                    openings.iter().map(|&(i, v)| (i as usize, v)).collect();
+                let _ = proof.verify(share, &mut transcript(), n, &C, &openings);
+            }
+        }
+        7 => {
+            if let Ok((values, proof)) =
+                bincode::deserialize::<(Vec<RistrettoPoint>, R1CSProof)>(bytes)
+            {
+                let mut transcript = transcript();
+                let mut verifier = Verifier::new(generators.all(), &mut transcript);
+                mul_gadget(&mut verifier, &values);
+                let _ = verifier.verify(&proof, &mut rng);
+            }
+        }
+        8 => {
+            if let Ok((values, V, proof)) =
+                bincode::deserialize::<(Vec<RistrettoPoint>, RistrettoPoint, JointProof)>(bytes)
+            {
+                let mut transcript = transcript();
+                let mut verifier = Verifier::new(generators.all(), &mut transcript);
+                mul_gadget(&mut verifier, &values);
+                let _ = verifier.verify_with_range_proof(&proof, &mut rng, n, &V);
+            }
+        }
+        9 => {
+            if let Ok(request) = bincode::deserialize::<DelegationRequest>(bytes) {
+                let _ = request.prove(share);
+            }
+        }
+        _ => {
+            type Input = (Vec<RistrettoPoint>, RistrettoPoint, RangeProof, R1CSProof);
+            if let Ok((values, V, range_proof, proof)) = bincode::deserialize::<Input>(bytes) {
+                let mut transcript = transcript();
+                let mut verifier = Verifier::new(generators.all(), &mut transcript);
+                if verifier.commit_with_range_proof(&mut rng, n, V, &range_proof).is_ok() {
+                    mul_gadget(&mut verifier, &values);
+                    let _ = verifier.verify(&proof, &mut rng);
+                }
+            }
+        }
+    }
+});
@@ -0,0 +1,85 @@
+#![no_main]
+#![allow(non_snake_case)]
+
+//! Feeds untrusted bytes to the decoders and verifiers, which must
+//! return errors instead of panicking.
+//!
+//! The input is a byte selecting the verifier's bitsize, a byte
+//! selecting the generators' bitsize, the 32-byte encoding of the value
+//! commitment \\(V\\), and the encoding of a proof.  Both bitsizes range
+//! over the powers of two up to 128, so that misconfigured verifiers are
+//! exercised as well.
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate curve25519_dalek;
+extern crate rand;
+extern crate ristretto_bulletproofs;
+
+use std::iter;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use rand::ChaChaRng;
+
+use ristretto_bulletproofs::envelope::ProofEnvelope;
+use ristretto_bulletproofs::view::ProofView;
+use ristretto_bulletproofs::{Generators, InnerProductProof, PedersenGenerators, ProofTranscript};
+use ristretto_bulletproofs::{RangeProof, VerificationAccumulator, Verifier};
+
+fn transcript() -> ProofTranscript {
+    ProofTranscript::new(b"Fuzz").unwrap()
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 34 {
+        return;
+    }
+    let n = 1 << (data[0] % 8);
+    let generators_n = 1 << (data[1] % 8);
+    let mut V_bytes = [0u8; 32];
+    V_bytes.copy_from_slice(&data[2..34]);
+    let V = match CompressedRistretto(V_bytes).decompress() {
+        Some(V) => V,
+        None => return,
+    };
+    let bytes = &data[34..];
+
+    let generators = Generators::new(b"Fuzz", PedersenGenerators::default(), generators_n, 1);
+    let share = generators.share(0);
+    // Mismatched bitsizes and generators are rejected here, once.
+    let verifier = match Verifier::new(share, n) {
+        Ok(verifier) => verifier,
+        Err(_) => return,
+    };
+    let strict = match Verifier::new(share, n) {
+        Ok(verifier) => verifier.strict(),
+        Err(_) => return,
+    };
+    let mut rng = ChaChaRng::new_unseeded();
+
+    if let Ok(proof) = RangeProof::from_bytes(bytes) {
+        for verifier in [&verifier, &strict].iter() {
+            let _ = verifier.verify(&proof, &V, &mut transcript(), &mut rng);
+            let _ = verifier.verify_streaming(&proof, &V, &mut transcript(), &mut rng);
+            let _ = verifier.verify_fallible(&proof, &V, &mut transcript(), &mut rng);
+            let _ = verifier.verify_uniform_time(&proof, &V, &mut transcript(), &mut rng);
+            let _ = verifier.verify_with_diagnostics(&proof, &V, &mut transcript());
+        }
+        let _ = proof.verify(&V, share, &mut transcript(), &mut rng, n);
+
+        let mut acc = VerificationAccumulator::new(generators_n);
+        if acc.accumulate(&verifier, &proof, &V, &mut transcript(), &mut rng).is_ok() {
+            let _ = acc.finalize(&verifier);
+        }
+    }
+    if let Ok(view) = ProofView::from_bytes(bytes) {
+        let _ = verifier.verify_view(&view, &V, &mut transcript(), &mut rng);
+    }
+    if let Ok(ipp) = InnerProductProof::from_bytes(bytes) {
+        let B = &share.pedersen_generators.B;
+        let _ = ipp.verify(&mut transcript(), iter::repeat(Scalar::one()), &V, B, share.G, share.H);
+    }
+    let _ = ProofEnvelope::from_bytes(bytes);
+    let _ = Generators::from_bytes(bytes);
+});
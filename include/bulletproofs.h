@@ -0,0 +1,83 @@
+/*
+ * C interface to ristretto-bulletproofs, built with the `ffi` feature.
+ * See the documentation of the `ffi` module for the conventions.
+ */
+
+#ifndef BULLETPROOFS_H
+#define BULLETPROOFS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    BP_OK = 0,
+    BP_NULL_POINTER = 1,
+    BP_INVALID_ARGUMENT = 2,
+    BP_INVALID_ENCODING = 3,
+    BP_VERIFICATION_FAILED = 4,
+    BP_BUFFER_TOO_SMALL = 5,
+    BP_RNG_UNAVAILABLE = 6,
+    BP_PANIC = 7,
+} bp_status;
+
+#define BP_MAX_ROUNDS 6
+
+typedef struct {
+    size_t rounds;
+    uint8_t A[32];
+    uint8_t S[32];
+    uint8_t T_1[32];
+    uint8_t T_2[32];
+    uint8_t t_x[32];
+    uint8_t t_x_blinding[32];
+    uint8_t e_blinding[32];
+    uint8_t L[BP_MAX_ROUNDS][32];
+    uint8_t R[BP_MAX_ROUNDS][32];
+    uint8_t a[32];
+    uint8_t b[32];
+} bp_range_proof_parts;
+
+typedef struct BpGenerators bp_generators;
+typedef struct BpRangeProof bp_range_proof;
+typedef struct BpProofBundle bp_proof_bundle;
+
+bp_status bp_generators_new(const uint8_t *domain, size_t domain_len, size_t n,
+                            bp_generators **out);
+void bp_generators_free(bp_generators *gens);
+
+bp_status bp_commit(const bp_generators *gens, uint64_t value, const uint8_t blinding[32],
+                    uint8_t out[32]);
+
+bp_status bp_prove(const bp_generators *gens, const uint8_t *label, size_t label_len,
+                   uint64_t value, const uint8_t blinding[32], bp_range_proof **out);
+bp_status bp_verify(const bp_generators *gens, const uint8_t *label, size_t label_len,
+                    const bp_range_proof *proof, const uint8_t commitment[32]);
+
+bp_status bp_prove_many(const bp_generators *gens, const uint8_t *label, size_t label_len,
+                        const uint64_t *values, const uint8_t *blindings, size_t count,
+                        bp_proof_bundle **out);
+bp_status bp_verify_many(const bp_generators *gens, const uint8_t *label, size_t label_len,
+                         const bp_proof_bundle *bundle, const uint8_t *commitments,
+                         size_t count);
+
+bp_status bp_range_proof_to_bytes(const bp_range_proof *proof, uint8_t *out, size_t out_len,
+                                  size_t *written);
+bp_status bp_range_proof_from_bytes(const uint8_t *bytes, size_t len, bp_range_proof **out);
+bp_status bp_range_proof_to_parts(const bp_range_proof *proof, bp_range_proof_parts *out);
+bp_status bp_range_proof_from_parts(const bp_range_proof_parts *parts, bp_range_proof **out);
+void bp_range_proof_free(bp_range_proof *proof);
+
+bp_status bp_proof_bundle_to_bytes(const bp_proof_bundle *bundle, uint8_t *out,
+                                   size_t out_len, size_t *written);
+bp_status bp_proof_bundle_from_bytes(const uint8_t *bytes, size_t len, bp_proof_bundle **out);
+void bp_proof_bundle_free(bp_proof_bundle *bundle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BULLETPROOFS_H */
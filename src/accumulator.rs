@@ -0,0 +1,294 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `accumulator` module contains a verification accumulator that
+//! defers the expensive part of range proof verification, so that it
+//! can be paid once for proofs spread over many batches or blocks.
+//!
+//! Each proof's verification equation splits into terms for the
+//! generators \\(B, \widetilde{B}, \mathbf{G}, \mathbf{H}\\), shared by
+//! all proofs, and terms for the proof's own points \\(A, S, V, T\_1,
+//! T\_2, L\_j, R\_j\\).  Accumulating a proof evaluates its own terms
+//! right away, which costs a multiscalar multiplication of size
+//! \\(5 + 2 \lg n\\), and adds the generator scalars to the accumulator,
+//! both weighted by a random scalar.  The accumulator thus stays the
+//! size of one proof's generator terms, and `finalize` checks every
+//! accumulated proof with a single multiscalar multiplication of size
+//! \\(2n + 2\\).
+//!
+//! Unlike the `BatchVerifier`, the accumulator does not keep the proofs
+//! and does not borrow the generators, so it can be serialized and kept
+//! between blocks.  A failed `finalize` does not say which proof was
+//! invalid.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let verifier = Verifier::new(generators.share(0), n)?;
+//! let mut acc = VerificationAccumulator::new(n);
+//! for (proof, V) in block {
+//!     let mut transcript = ProofTranscript::new(b"RangeproofTest")?;
+//!     acc.accumulate(&verifier, &proof, &V, &mut transcript, &mut rng)?;
+//! }
+//! // ... later blocks ...
+//! acc.finalize(&verifier)?;
+//! ```
+
+use core::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+
+use backend::MultiscalarMul;
+use errors::ProofError;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier};
+
+/// `VerificationAccumulator` holds the deferred generator terms of any
+/// number of `n`-bit range proof verifications.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationAccumulator {
+    /// Scalar for the Pedersen base \\(B\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    B: Scalar,
+    /// Scalar for the Pedersen blinding base \\(\widetilde{B}\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    B_blinding: Scalar,
+    /// Scalars for the generators \\(\mathbf{G}\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    G: Vec<Scalar>,
+    /// Scalars for the generators \\(\mathbf{H}\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    H: Vec<Scalar>,
+    /// Sum of the evaluated proof-specific terms
+    P: RistrettoPoint,
+    /// Number of accumulated proofs
+    count: usize,
+}
+
+impl VerificationAccumulator {
+    /// Creates an empty accumulator for `n`-bit range proofs.
+    pub fn new(n: usize) -> Self {
+        VerificationAccumulator {
+            B: Scalar::zero(),
+            B_blinding: Scalar::zero(),
+            G: vec![Scalar::zero(); n],
+            H: vec![Scalar::zero(); n],
+            P: RistrettoPoint::identity(),
+            count: 0,
+        }
+    }
+
+    /// Returns the number of accumulated proofs.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns `true` if no proofs have been accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Accumulates the verification of a rangeproof `proof` for the
+    /// commitment \\(V\\), deferring the generator terms to `finalize`.
+    ///
+    /// Returns an error if `verifier` is not for `n`-bit proofs, in
+    /// which case nothing is accumulated.
+    pub fn accumulate<M: MultiscalarMul, R: Rng>(
+        &mut self,
+        verifier: &Verifier<M>,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        if !self.fits(verifier.generators().G.len(), verifier.generators().H.len()) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        verifier.precheck(proof, V, transcript)?;
+
+        let terms = verifier.verification_terms(proof, V, transcript, rng)?;
+
+        // Random weight for this proof's equation
+        let r = Scalar::random(rng);
+        // Computed first, so that an error leaves the accumulator as it was.
+        let P = verifier
+            .backend()
+            .vartime_multiscalar_mul(&terms.proof_scalars, &terms.proof_points)?;
+
+        self.B += r * terms.B;
+        self.B_blinding += r * terms.B_blinding;
+        for (G_i, g_i) in self.G.iter_mut().zip(terms.G.iter()) {
+            *G_i += r * g_i;
+        }
+        for (H_i, h_i) in self.H.iter_mut().zip(terms.H.iter()) {
+            *H_i += r * h_i;
+        }
+        self.P += P * r;
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Merges the proofs accumulated in `other` into this accumulator.
+    ///
+    /// Returns an error if `other` is for proofs of a different size,
+    /// or if the merged count would overflow, in which case nothing is
+    /// merged.
+    pub fn merge(&mut self, other: &VerificationAccumulator) -> Result<(), ProofError> {
+        if !self.fits(other.G.len(), other.H.len()) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let count = self.count.checked_add(other.count).ok_or(ProofError::FormatError)?;
+
+        self.B += other.B;
+        self.B_blinding += other.B_blinding;
+        for (G_i, g_i) in self.G.iter_mut().zip(other.G.iter()) {
+            *G_i += g_i;
+        }
+        for (H_i, h_i) in self.H.iter_mut().zip(other.H.iter()) {
+            *H_i += h_i;
+        }
+        self.P += other.P;
+        self.count = count;
+
+        Ok(())
+    }
+
+    /// Verifies all the accumulated proofs with a single multiscalar
+    /// multiplication over the generators of `verifier`.
+    ///
+    /// An empty accumulator verifies successfully.
+    pub fn finalize<M: MultiscalarMul>(self, verifier: &Verifier<M>) -> Result<(), ProofError> {
+        let gens = verifier.generators();
+        if !self.fits(gens.G.len(), gens.H.len()) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let scalars: Vec<Scalar> = iter::once(self.B)
+            .chain(iter::once(self.B_blinding))
+            .chain(self.G.into_iter())
+            .chain(self.H.into_iter())
+            .collect();
+        let points: Vec<RistrettoPoint> = iter::once(&gens.pedersen_generators.B)
+            .chain(iter::once(&gens.pedersen_generators.B_blinding))
+            .chain(gens.G.iter())
+            .chain(gens.H.iter())
+            .cloned()
+            .collect();
+
+        let check = verifier.backend().vartime_multiscalar_mul(&scalars, &points)? + self.P;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns `true` if the accumulator has `G_len` and `H_len` scalars
+    /// for \\(\mathbf{G}\\) and \\(\mathbf{H}\\), which a deserialized
+    /// accumulator need not have for both.
+    fn fits(&self, G_len: usize, H_len: usize) -> bool {
+        self.G.len() == G_len && self.H.len() == H_len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    fn proofs(generators: &Generators, n: usize, count: u64) -> Vec<(RangeProof, RistrettoPoint)> {
+        let mut rng = OsRng::new().unwrap();
+        (0..count)
+            .map(|v| {
+                let v_blinding = Scalar::random(&mut rng);
+                let V = generators
+                    .share(0)
+                    .pedersen_generators
+                    .commit(Scalar::from_u64(v), v_blinding);
+                let mut transcript = ProofTranscript::new(b"AccumulatorTest").unwrap();
+                let proof = RangeProof::generate_proof(
+                    generators.share(0),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                    &SecretValue::new(v),
+                    &Blinding::new(v_blinding),
+                ).unwrap();
+                (proof, V)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accumulated_proofs_verify_across_blocks() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let mut rng = OsRng::new().unwrap();
+        let proofs = proofs(&generators, n, 4);
+
+        // Two blocks, the first kept in serialized form in between.
+        let mut first = VerificationAccumulator::new(n);
+        let mut second = VerificationAccumulator::new(n);
+        for (j, &(ref proof, ref V)) in proofs.iter().enumerate() {
+            let acc = if j < 2 { &mut first } else { &mut second };
+            let mut transcript = ProofTranscript::new(b"AccumulatorTest").unwrap();
+            acc.accumulate(&verifier, proof, V, &mut transcript, &mut rng)
+                .unwrap();
+        }
+        let bytes = bincode::serialize(&first).unwrap();
+        let mut acc: VerificationAccumulator = bincode::deserialize(&bytes).unwrap();
+        acc.merge(&second).unwrap();
+        assert_eq!(acc.len(), 4);
+        assert!(acc.finalize(&verifier).is_ok());
+
+        // A proof for the wrong commitment spoils the accumulator.
+        let mut acc = VerificationAccumulator::new(n);
+        for &(ref proof, _) in proofs.iter() {
+            let mut transcript = ProofTranscript::new(b"AccumulatorTest").unwrap();
+            acc.accumulate(&verifier, proof, &proofs[0].1, &mut transcript, &mut rng)
+                .unwrap();
+        }
+        assert!(acc.finalize(&verifier).is_err());
+    }
+
+    #[test]
+    fn sizes_must_match() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let mut rng = OsRng::new().unwrap();
+        let proofs = proofs(&generators, n, 1);
+
+        let mut acc = VerificationAccumulator::new(8);
+        let mut transcript = ProofTranscript::new(b"AccumulatorTest").unwrap();
+        assert!(
+            acc.accumulate(&verifier, &proofs[0].0, &proofs[0].1, &mut transcript, &mut rng)
+                .is_err()
+        );
+        assert!(acc.is_empty());
+        assert!(acc.merge(&VerificationAccumulator::new(n)).is_err());
+        assert!(VerificationAccumulator::new(8).finalize(&verifier).is_err());
+        assert!(VerificationAccumulator::new(n).finalize(&verifier).is_ok());
+
+        // Deserialized accumulators can have any lengths and count.
+        let mut lopsided = VerificationAccumulator::new(n);
+        lopsided.H.pop();
+        assert!(VerificationAccumulator::new(n).merge(&lopsided).is_err());
+        assert!(lopsided.finalize(&verifier).is_err());
+        let mut full = VerificationAccumulator::new(n);
+        full.count = usize::max_value();
+        assert_eq!(full.clone().merge(&full), Err(ProofError::FormatError));
+    }
+}
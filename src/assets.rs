@@ -0,0 +1,238 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `assets` module contains support for confidential assets, where
+//! each commitment also hides which asset it is denominated in.
+//!
+//! Every asset has its own value generator \\(H\_a\\), derived by hashing
+//! the asset identifier.  A blinded asset tag \\(A = H\_a + t \widetilde{B}\\)
+//! hides the asset, and a value \\(v\\) of that asset is committed as
+//! \\(v A + r \widetilde{B}\\), i.e. a Pedersen commitment with the
+//! value base \\(A\\).  Range proofs for such commitments use
+//! `asset_view` to swap the value base of the generators.
+//!
+//! A `SurjectionProof` shows that an output tag blinds the same asset
+//! as one of a list of input tags, without revealing which: the
+//! difference of the two tags is a multiple of \\(\widetilde{B}\\), which
+//! is a one-out-of-many proof over the input tags.
+//!
+//! Since commitments to different assets use independent value bases,
+//! the inputs and outputs of a multi-asset transaction balance exactly
+//! when every asset balances separately, so the usual excess proof
+//! applies unchanged.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let tag = assets::blinded_asset_tag(&pg, b"USD", &tag_blinding);
+//! let asset_pg = assets::asset_pedersen_generators(&pg, tag);
+//! let V = asset_pg.commit(Scalar::from_u64(100), *v_blinding.as_scalar());
+//! let proof = RangeProof::generate_proof(
+//!     assets::asset_view(generators.share(0), &asset_pg),
+//!     &mut transcript, &mut rng, 64, &SecretValue::new(100), &v_blinding,
+//! )?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use sha2::{Digest, Sha512};
+
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use one_of_many::OneOfManyProof;
+use proof_transcript::ProofTranscript;
+use secret::Blinding;
+
+/// Returns the value generator \\(H\_a\\) of the asset `asset_id`.
+pub fn asset_generator(asset_id: &[u8]) -> RistrettoPoint {
+    let mut hash = Sha512::default();
+    hash.input(b"Bulletproofs.Asset");
+    hash.input(asset_id);
+    RistrettoPoint::from_hash(hash)
+}
+
+/// Returns the asset tag \\(H\_a + t \widetilde{B}\\) of the asset
+/// `asset_id`, blinded with `tag_blinding`.
+pub fn blinded_asset_tag(
+    pg: &PedersenGenerators,
+    asset_id: &[u8],
+    tag_blinding: &Blinding,
+) -> RistrettoPoint {
+    asset_generator(asset_id) + pg.B_blinding * tag_blinding.as_scalar()
+}
+
+/// Returns the Pedersen generators committing to values of the asset
+/// with the (possibly blinded) tag `tag`.
+pub fn asset_pedersen_generators(
+    pg: &PedersenGenerators,
+    tag: RistrettoPoint,
+) -> PedersenGenerators {
+    PedersenGenerators::new(tag, pg.B_blinding)
+}
+
+/// Returns the `generators` with their Pedersen generators replaced by
+/// the asset-specific `asset_generators`, for range proofs of
+/// asset-tagged commitments.
+pub fn asset_view<'a>(
+    generators: GeneratorsView<'a>,
+    asset_generators: &'a PedersenGenerators,
+) -> GeneratorsView<'a> {
+    GeneratorsView {
+        pedersen_generators: asset_generators,
+        G: generators.G,
+        H: generators.H,
+    }
+}
+
+/// A proof that an asset tag blinds the same asset as one of a list of
+/// asset tags.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SurjectionProof {
+    /// Proof that the output tag minus one of the input tags is a
+    /// multiple of the blinding base
+    proof: OneOfManyProof,
+}
+
+impl SurjectionProof {
+    /// Proves that `output_tag` blinds the same asset as
+    /// `input_tags[index]`, where `tag_blinding_difference` is the
+    /// output tag's blinding factor minus the input tag's.
+    ///
+    /// Returns `ProofError::InvalidStatement` if `index` is out of range.
+    pub fn prove<R: Rng>(
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        output_tag: &RistrettoPoint,
+        input_tags: &[RistrettoPoint],
+        index: usize,
+        tag_blinding_difference: &Blinding,
+    ) -> Result<SurjectionProof, ProofError> {
+        transcript.commit(b"Surjection");
+        let proof = OneOfManyProof::prove(
+            pg,
+            transcript,
+            rng,
+            output_tag,
+            input_tags,
+            index,
+            tag_blinding_difference,
+        )?;
+        Ok(SurjectionProof { proof })
+    }
+
+    /// Verifies that `output_tag` blinds the same asset as one of the
+    /// `input_tags`.
+    pub fn verify(
+        &self,
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        output_tag: &RistrettoPoint,
+        input_tags: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        transcript.commit(b"Surjection");
+        self.proof.verify(pg, transcript, output_tag, input_tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::Generators;
+    use range_proof::RangeProof;
+    use rand::OsRng;
+    use secret::SecretValue;
+
+    #[test]
+    fn asset_tagged_range_proof() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        let tag = blinded_asset_tag(pg, b"USD", &Blinding::random(&mut rng));
+        let asset_pg = asset_pedersen_generators(pg, tag);
+        let v_blinding = Blinding::random(&mut rng);
+        let V = asset_pg.commit(Scalar::from_u64(1000), *v_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            asset_view(generators.share(0), &asset_pg),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(1000),
+            &v_blinding,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        assert!(
+            proof
+                .verify(
+                    &V,
+                    asset_view(generators.share(0), &asset_pg),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                )
+                .is_ok()
+        );
+
+        // The same commitment does not verify under another asset.
+        let other_pg = asset_pedersen_generators(pg, asset_generator(b"EUR"));
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        assert!(
+            proof
+                .verify(
+                    &V,
+                    asset_view(generators.share(0), &other_pg),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn surjection_proof() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+
+        let input_blindings = [Blinding::random(&mut rng), Blinding::random(&mut rng)];
+        let input_tags = [
+            blinded_asset_tag(&pg, b"USD", &input_blindings[0]),
+            blinded_asset_tag(&pg, b"EUR", &input_blindings[1]),
+        ];
+        let output_blinding = Blinding::random(&mut rng);
+        let output_tag = blinded_asset_tag(&pg, b"EUR", &output_blinding);
+
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        let proof = SurjectionProof::prove(
+            &pg,
+            &mut transcript,
+            &mut rng,
+            &output_tag,
+            &input_tags,
+            1,
+            &Blinding::new(output_blinding.as_scalar() - input_blindings[1].as_scalar()),
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        assert!(
+            proof
+                .verify(&pg, &mut transcript, &output_tag, &input_tags)
+                .is_ok()
+        );
+
+        // The output asset is not among the inputs.
+        let mut transcript = ProofTranscript::new(b"AssetsTest").unwrap();
+        assert!(
+            proof
+                .verify(&pg, &mut transcript, &output_tag, &input_tags[..1])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `backend` module defines the multiscalar multiplication
+//! interface used by verification and the inner product argument.
+//!
+//! Integrators can implement `MultiscalarMul` to plug in GPU or
+//! hardware-accelerated implementations, and pass them to
+//! `Verifier::with_backend`.  `DefaultBackend` runs on the CPU using
+//! the implementations in this crate.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use util;
+
+/// A multiscalar multiplication implementation.
+///
+/// Both methods compute \\( \sum\_i s\_i P\_i \\), and return
+/// `ProofError::FormatError` if the slices have different lengths
+/// instead of panicking, since the lengths of a verifier's slices
+/// depend on the proof.
+pub trait MultiscalarMul {
+    /// Computes a multiscalar multiplication in constant time.
+    ///
+    /// This is used whenever any of the scalars are secret.
+    fn multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<RistrettoPoint, ProofError>;
+
+    /// Computes a multiscalar multiplication in variable time.
+    ///
+    /// This is only used when all the scalars are public.
+    fn vartime_multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<RistrettoPoint, ProofError>;
+}
+
+/// The default CPU implementation of `MultiscalarMul`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultBackend;
+
+impl MultiscalarMul for DefaultBackend {
+    fn multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<RistrettoPoint, ProofError> {
+        if scalars.len() != points.len() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(util::multiscalar_mul(scalars, points))
+    }
+
+    fn vartime_multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[RistrettoPoint],
+    ) -> Result<RistrettoPoint, ProofError> {
+        util::vartime_multiscalar_mul(scalars, points)
+    }
+}
@@ -0,0 +1,346 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `batch` module contains a verifier that accumulates many range
+//! proofs and checks them all with a single multiscalar multiplication.
+//!
+//! Each proof's verification equation is multiplied by an independent
+//! random weight before the equations are summed, so the combined
+//! check passes only if (with overwhelming probability) every proof
+//! is valid.  The generator terms are shared between all proofs, which
+//! is where the savings come from.
+//!
+//! `BatchVerifier` takes `&mut self` to add proofs; to feed it from
+//! several threads, wrap it in a `Mutex`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut batch = BatchVerifier::new(Verifier::new(generators.share(0), n)?);
+//! for (proof, V) in proofs {
+//!     batch.add(proof, V, ProofTranscript::new(b"RangeproofTest")?);
+//! }
+//! match batch.settle_or_identify(&mut rng) {
+//!     Ok(()) => { /* all proofs are valid */ }
+//!     Err(culprits) => { /* indices of the invalid proofs */ }
+//! }
+//! ```
+
+use core::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+
+use backend::{DefaultBackend, MultiscalarMul};
+use errors::ProofError;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier};
+
+/// A proof waiting to be verified.
+struct Entry {
+    proof: RangeProof,
+    V: RistrettoPoint,
+    /// The value generator, if not the Pedersen base
+    B: Option<RistrettoPoint>,
+    transcript: ProofTranscript,
+}
+
+/// `BatchVerifier` accumulates range proofs and verifies them together.
+pub struct BatchVerifier<'a, M = DefaultBackend> {
+    verifier: Verifier<'a, M>,
+    entries: Vec<Entry>,
+}
+
+impl<'a, M: MultiscalarMul> BatchVerifier<'a, M> {
+    /// Creates an empty batch, using `verifier` for the parameters and
+    /// the multiscalar multiplication backend.
+    pub fn new(verifier: Verifier<'a, M>) -> Self {
+        BatchVerifier {
+            verifier,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a proof `proof` for the commitment `V` to the batch.
+    ///
+    /// The `transcript` must be in the state the proof was created in,
+    /// i.e. typically a fresh `ProofTranscript` with the proof's label.
+    pub fn add(&mut self, proof: RangeProof, V: RistrettoPoint, transcript: ProofTranscript) {
+        self.entries.push(Entry {
+            proof,
+            V,
+            B: None,
+            transcript,
+        });
+    }
+
+    /// Adds a proof `proof` for the commitment `V` with the value
+    /// generator `B` to the batch, as verified by
+    /// `Verifier::verify_with_value_generator`.
+    ///
+    /// Proofs for different value generators can share a batch.
+    pub fn add_with_value_generator(
+        &mut self,
+        proof: RangeProof,
+        V: RistrettoPoint,
+        B: RistrettoPoint,
+        transcript: ProofTranscript,
+    ) {
+        self.entries.push(Entry {
+            proof,
+            V,
+            B: Some(B),
+            transcript,
+        });
+    }
+
+    /// Returns the number of proofs in the batch.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the batch contains no proofs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verifies all the proofs in the batch with a single multiscalar
+    /// multiplication.
+    ///
+    /// An empty batch verifies successfully.
+    pub fn settle<R: Rng>(self, rng: &mut R) -> Result<(), ProofError> {
+        self.check_all(rng)
+    }
+
+    /// Verifies all the proofs in the batch with a single multiscalar
+    /// multiplication, and if that fails, verifies each proof
+    /// separately, returning the indices of the invalid proofs in the
+    /// order they were added.
+    pub fn settle_or_identify<R: Rng>(self, rng: &mut R) -> Result<(), Vec<usize>> {
+        if self.check_all(rng).is_ok() {
+            return Ok(());
+        }
+
+        let culprits = self.entries
+            .iter()
+            .enumerate()
+            .filter(|&(_, entry)| {
+                let mut transcript = entry.transcript.fork();
+                let B = entry
+                    .B
+                    .unwrap_or(self.verifier.generators().pedersen_generators.B);
+                self.verifier
+                    .verify_with_value_generator(&entry.proof, &entry.V, &B, &mut transcript, rng)
+                    .is_err()
+            })
+            .map(|(j, _)| j)
+            .collect();
+
+        Err(culprits)
+    }
+
+    fn check_all<R: Rng>(&self, rng: &mut R) -> Result<(), ProofError> {
+        verify_batch(
+            &self.verifier,
+            self.entries
+                .iter()
+                .map(|entry| (&entry.proof, &entry.V, entry.B.as_ref(), entry.transcript.fork())),
+            rng,
+        )
+    }
+}
+
+/// Verifies the proofs `entries`, each with its commitment, value
+/// generator if not the Pedersen base, and transcript, with a single
+/// multiscalar multiplication.
+pub(crate) fn verify_batch<'e, M, R, I>(
+    verifier: &Verifier<M>,
+    entries: I,
+    rng: &mut R,
+) -> Result<(), ProofError>
+where
+    M: MultiscalarMul,
+    R: Rng,
+    I: Iterator<
+        Item = (
+            &'e RangeProof,
+            &'e RistrettoPoint,
+            Option<&'e RistrettoPoint>,
+            ProofTranscript,
+        ),
+    >,
+{
+    let gens = verifier.generators();
+    let n = gens.G.len();
+
+    let mut B = Scalar::zero();
+    let mut B_blinding = Scalar::zero();
+    let mut G = vec![Scalar::zero(); n];
+    let mut H = vec![Scalar::zero(); n];
+    let mut proof_scalars = Vec::new();
+    let mut proof_points = Vec::new();
+
+    for (proof, V, value_generator, mut transcript) in entries {
+        verifier.precheck(proof, V, &transcript)?;
+        let terms = verifier.verification_terms(proof, V, &mut transcript, rng)?;
+
+        // Random weight for this proof's equation
+        let r = Scalar::random(rng);
+
+        match value_generator {
+            Some(value_generator) => {
+                proof_scalars.push(r * terms.B);
+                proof_points.push(*value_generator);
+            }
+            None => B += r * terms.B,
+        }
+        B_blinding += r * terms.B_blinding;
+        for (G_i, g_i) in G.iter_mut().zip(terms.G.iter()) {
+            *G_i += r * g_i;
+        }
+        for (H_i, h_i) in H.iter_mut().zip(terms.H.iter()) {
+            *H_i += r * h_i;
+        }
+        proof_scalars.extend(terms.proof_scalars.iter().map(|s| r * s));
+        proof_points.extend(terms.proof_points.into_iter());
+    }
+
+    let scalars: Vec<Scalar> = iter::once(B)
+        .chain(iter::once(B_blinding))
+        .chain(G.into_iter())
+        .chain(H.into_iter())
+        .chain(proof_scalars.into_iter())
+        .collect();
+    let points: Vec<RistrettoPoint> = iter::once(&gens.pedersen_generators.B)
+        .chain(iter::once(&gens.pedersen_generators.B_blinding))
+        .chain(gens.G.iter())
+        .chain(gens.H.iter())
+        .chain(proof_points.iter())
+        .cloned()
+        .collect();
+
+    let check = verifier.backend().vartime_multiscalar_mul(&scalars, &points)?;
+
+    if check.is_identity() {
+        Ok(())
+    } else {
+        Err(ProofError::VerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assets;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn batch_verifies_and_identifies_culprits() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut proofs = Vec::new();
+        for v in 0..4u64 {
+            let v_blinding = Scalar::random(&mut rng);
+            let V = generators
+                .share(0)
+                .pedersen_generators
+                .commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"BatchTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+            proofs.push((proof, V));
+        }
+
+        let mut batch = BatchVerifier::new(Verifier::new(generators.share(0), n).unwrap());
+        for &(ref proof, V) in proofs.iter() {
+            batch.add(proof.clone(), V, ProofTranscript::new(b"BatchTest").unwrap());
+        }
+        assert_eq!(batch.len(), 4);
+        assert!(batch.settle(&mut rng).is_ok());
+
+        // Swap the commitments of proofs 1 and 2, invalidating both.
+        let mut batch = BatchVerifier::new(Verifier::new(generators.share(0), n).unwrap());
+        for (j, &(ref proof, _)) in proofs.iter().enumerate() {
+            let V = match j {
+                1 => proofs[2].1,
+                2 => proofs[1].1,
+                _ => proofs[j].1,
+            };
+            batch.add(proof.clone(), V, ProofTranscript::new(b"BatchTest").unwrap());
+        }
+        assert_eq!(batch.settle_or_identify(&mut rng), Err(vec![1, 2]));
+    }
+
+    #[test]
+    fn batch_mixes_value_generators() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        let mut proofs = Vec::new();
+        for &(v, asset_id) in [(10u64, &b"USD"[..]), (20u64, &b"EUR"[..])].iter() {
+            let B = assets::asset_generator(asset_id);
+            let v_blinding = Scalar::random(&mut rng);
+            let asset_pg = PedersenGenerators::new(B, pg.B_blinding);
+            let V = asset_pg.commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"BatchTest").unwrap();
+            let proof = RangeProof::generate_proof_with_value_generator(
+                generators.share(0),
+                &B,
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+            proofs.push((proof, V, B));
+        }
+
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let (ref proof, ref V, ref B) = proofs[0];
+        let mut transcript = ProofTranscript::new(b"BatchTest").unwrap();
+        assert!(
+            verifier
+                .verify_with_value_generator(proof, V, B, &mut transcript, &mut rng)
+                .is_ok()
+        );
+        let mut transcript = ProofTranscript::new(b"BatchTest").unwrap();
+        assert!(verifier.verify(proof, V, &mut transcript, &mut rng).is_err());
+
+        let mut batch = BatchVerifier::new(verifier);
+        for &(ref proof, V, B) in proofs.iter() {
+            let transcript = ProofTranscript::new(b"BatchTest").unwrap();
+            batch.add_with_value_generator(proof.clone(), V, B, transcript);
+        }
+        assert!(batch.settle(&mut rng).is_ok());
+
+        // Each proof only verifies for its own value generator.
+        let mut batch = BatchVerifier::new(Verifier::new(generators.share(0), n).unwrap());
+        for &(ref proof, V, _) in proofs.iter() {
+            batch.add_with_value_generator(
+                proof.clone(),
+                V,
+                proofs[0].2,
+                ProofTranscript::new(b"BatchTest").unwrap(),
+            );
+        }
+        assert_eq!(batch.settle_or_identify(&mut rng), Err(vec![1]));
+    }
+}
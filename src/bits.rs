@@ -0,0 +1,206 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `bits` module contains helpers for decomposing values into bits
+//! and converting between `u64` and `Scalar`.
+//!
+//! Bits are always in little-endian order: the `i`-th entry of a
+//! decomposition is the coefficient of \\(2^i\\).
+//!
+//! The decompositions of secret values select each entry with
+//! `ConditionallyAssignable` rather than branching on the bit.  With
+//! the `timing-tests` feature, a statistical timing test checks that
+//! decomposing values with all bits set takes as long as decomposing
+//! values with none.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate curve25519_dalek;
+//! # extern crate ristretto_bulletproofs;
+//! # use curve25519_dalek::scalar::Scalar;
+//! # use ristretto_bulletproofs::bits;
+//! # fn main() {
+//! let bits = bits::bits_of(6, 4);
+//! assert_eq!(bits, vec![Scalar::zero(), Scalar::one(), Scalar::one(), Scalar::zero()]);
+//!
+//! let x = bits::u64_to_scalar(6);
+//! assert_eq!(bits::scalar_to_u64(&x), Some(6));
+//! # }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConditionallyAssignable, ConstantTimeEq};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+/// Returns the `i`-th bit of `value` as a `Choice`, without branching.
+///
+/// Panics if `i >= 64`.
+pub fn bit_choice(value: u64, i: usize) -> Choice {
+    assert!(i < 64, "bit index out of range");
+    Choice::from(((value >> i) & 1) as u8)
+}
+
+/// Returns the low `n` bits of `value` as `Choice`s, without branching
+/// on their values.
+///
+/// Panics if `n > 64`.
+pub fn bit_choices(value: u64, n: usize) -> Vec<Choice> {
+    assert!(n <= 64, "cannot decompose a u64 into more than 64 bits");
+    (0..n).map(|i| bit_choice(value, i)).collect()
+}
+
+/// Returns the low `n` bits of `value` as scalars equal to \\(0\\) or
+/// \\(1\\), without branching on their values.
+///
+/// Panics if `n > 64`.
+pub fn bits_of(value: u64, n: usize) -> Vec<Scalar> {
+    assert!(n <= 64, "cannot decompose a u64 into more than 64 bits");
+    (0..n)
+        .map(|i| {
+            let mut bit = Scalar::zero();
+            bit.conditional_assign(&Scalar::one(), bit_choice(value, i));
+            bit
+        })
+        .collect()
+}
+
+/// Returns the vectors \\(\mathbf{a}\_L\\) and \\(\mathbf{a}\_R\\) of a range
+/// proof of the low `n` bits of `value`: the bits, and the bits minus
+/// one.  Each entry is selected in constant time, so the decomposition
+/// neither branches nor indexes on the bits of `value`.
+///
+/// Panics if `n > 64`.
+pub fn bit_vectors(value: u64, n: usize) -> (Vec<Scalar>, Vec<Scalar>) {
+    assert!(n <= 64, "cannot decompose a u64 into more than 64 bits");
+    let mut a_L = Vec::with_capacity(n);
+    let mut a_R = Vec::with_capacity(n);
+    for i in 0..n {
+        let bit = bit_choice(value, i);
+        let mut a_L_i = Scalar::zero();
+        a_L_i.conditional_assign(&Scalar::one(), bit);
+        let mut a_R_i = -Scalar::one();
+        a_R_i.conditional_assign(&Scalar::zero(), bit);
+        a_L.push(a_L_i);
+        a_R.push(a_R_i);
+    }
+    (a_L, a_R)
+}
+
+/// Returns the mask selecting the low `n` bits of a `u64`.
+///
+/// Panics if `n > 64`.
+pub fn range_mask(n: usize) -> u64 {
+    assert!(n <= 64, "cannot decompose a u64 into more than 64 bits");
+    if n == 64 {
+        u64::max_value()
+    } else {
+        (1 << n) - 1
+    }
+}
+
+/// Returns whether `value` is less than \\(2^n\\), as a `Choice`,
+/// without branching on `value`.
+///
+/// Panics if `n > 64`.
+pub fn in_range(value: u64, n: usize) -> Choice {
+    (value & !range_mask(n)).ct_eq(&0)
+}
+
+/// Converts a `u64` into a `Scalar`.
+pub fn u64_to_scalar(value: u64) -> Scalar {
+    Scalar::from_u64(value)
+}
+
+/// Converts a `Scalar` into a `u64`, if it is less than \\(2^{64}\\).
+pub fn scalar_to_u64(scalar: &Scalar) -> Option<u64> {
+    let bytes = scalar.as_bytes();
+    if bytes[8..].iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(LittleEndian::read_u64(&bytes[..8]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_recompose_to_value() {
+        let value = 0xdead_beef_u64;
+        let bits = bits_of(value, 64);
+        let recomposed = bits
+            .iter()
+            .rev()
+            .fold(Scalar::zero(), |acc, b| acc + acc + b);
+        assert_eq!(recomposed, Scalar::from_u64(value));
+
+        let choices = bit_choices(value, 64);
+        for (i, c) in choices.iter().enumerate() {
+            assert_eq!(c.unwrap_u8() as u64, (value >> i) & 1);
+        }
+
+        assert_eq!(in_range(value, 32).unwrap_u8(), 1);
+        assert_eq!(in_range(value, 16).unwrap_u8(), 0);
+        assert_eq!(in_range(u64::max_value(), 64).unwrap_u8(), 1);
+        assert_eq!(value & range_mask(16), 0xbeef);
+
+        let (a_L, a_R) = bit_vectors(value, 64);
+        assert_eq!(a_L, bits);
+        for (a_L_i, a_R_i) in a_L.iter().zip(a_R.iter()) {
+            assert_eq!(a_L_i - a_R_i, Scalar::one());
+        }
+    }
+
+    /// Compares the times to decompose values with all bits and with no
+    /// bits set with Welch's t-test, as dudect does, failing if they
+    /// differ with a t-statistic far beyond the noise of a shared
+    /// machine.
+    #[test]
+    #[cfg(feature = "timing-tests")]
+    fn bit_decomposition_time_is_independent_of_the_value() {
+        use std::time::Instant;
+
+        const SAMPLES: usize = 20_000;
+        const THRESHOLD: f64 = 10.0;
+
+        let mut times = [Vec::with_capacity(SAMPLES), Vec::with_capacity(SAMPLES)];
+        for i in 0..2 * SAMPLES {
+            // Interleave the classes so that drift affects both alike.
+            let class = i % 2;
+            let value = if class == 0 { 0 } else { u64::max_value() };
+            let start = Instant::now();
+            let vectors = bit_vectors(value, 64);
+            let elapsed = start.elapsed();
+            assert_eq!(vectors.0.len(), 64);
+            times[class].push(elapsed.subsec_nanos() as f64);
+        }
+
+        let stats: Vec<(f64, f64)> = times
+            .iter()
+            .map(|t| {
+                let mean = t.iter().sum::<f64>() / t.len() as f64;
+                let var = t.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>()
+                    / (t.len() - 1) as f64;
+                (mean, var)
+            })
+            .collect();
+        let t = (stats[0].0 - stats[1].0)
+            / (stats[0].1 / SAMPLES as f64 + stats[1].1 / SAMPLES as f64).sqrt();
+        assert!(t.abs() < THRESHOLD, "t-statistic {} exceeds {}", t, THRESHOLD);
+    }
+
+    #[test]
+    fn u64_scalar_round_trip() {
+        for &v in [0u64, 1, 255, 256, u64::max_value()].iter() {
+            assert_eq!(scalar_to_u64(&u64_to_scalar(v)), Some(v));
+        }
+        let too_big = Scalar::from_u64(u64::max_value()) + Scalar::one();
+        assert_eq!(scalar_to_u64(&too_big), None);
+        assert_eq!(scalar_to_u64(&-Scalar::one()), None);
+    }
+}
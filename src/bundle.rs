@@ -0,0 +1,238 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `bundle` module contains bundles of range proofs made in one
+//! transcript session, which verify only all together.
+//!
+//! Proving a bundle first commits all the value commitments to the
+//! transcript, then proves each value in turn on the same transcript.
+//! Every proof's challenges thus depend on every commitment of the
+//! bundle and on all the proofs before it, so no proof can be taken
+//! out of the bundle, reordered or verified for a different set of
+//! commitments.  This suits applications where the statement is "all
+//! of these values are in range" rather than each value on its own.
+//!
+//! A bundle is verified with a single multiscalar multiplication over
+//! the generators, using a `VerificationAccumulator`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Payout")?;
+//! let (bundle, commitments) =
+//!     ProofBundle::prove_many(generators.share(0), &mut transcript, &mut rng, 64, &statements)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Payout")?;
+//! bundle.verify(generators.share(0), &mut transcript, &mut rng, 64, &commitments)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use subtle::{Choice, ConstantTimeEq};
+
+use accumulator::VerificationAccumulator;
+use ct;
+use errors::ProofError;
+use generators::GeneratorsView;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier};
+use secret::{Blinding, SecretValue};
+
+/// A bundle of range proofs bound to one transcript session.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProofBundle {
+    /// The range proofs, in the order of their statements
+    proofs: Vec<RangeProof>,
+}
+
+impl ProofBundle {
+    /// Proves that each value `v`, committed with the blinding factor
+    /// `v_blinding`, of the `statements` `(v, v_blinding)` is in
+    /// \\([0, 2^n)\\), returning the bundle and the value commitments in
+    /// the order of the statements.
+    ///
+    /// Returns the errors of `RangeProof::generate_proof` for an
+    /// unsupported `n` or generators of the wrong size.
+    pub fn prove_many<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        statements: &[(SecretValue, Blinding)],
+    ) -> Result<(ProofBundle, Vec<RistrettoPoint>), ProofError> {
+        let commitments: Vec<RistrettoPoint> = statements
+            .iter()
+            .map(|&(ref v, ref v_blinding)| {
+                generators
+                    .pedersen_generators
+                    .commit(v.to_scalar(), *v_blinding.as_scalar())
+            })
+            .collect();
+
+        commit_statement(transcript, n, &commitments);
+        let proofs = statements
+            .iter()
+            .map(|&(ref v, ref v_blinding)| {
+                RangeProof::generate_proof(generators, transcript, rng, n, v, v_blinding)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((ProofBundle { proofs }, commitments))
+    }
+
+    /// Returns the number of proofs in the bundle.
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Returns `true` if the bundle contains no proofs.
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Returns the proofs of the bundle, in the order of their statements.
+    pub fn proofs(&self) -> &[RangeProof] {
+        &self.proofs
+    }
+
+    /// Assembles a bundle from its proofs.
+    #[cfg(any(feature = "json", feature = "fuzzing"))]
+    pub(crate) fn from_proofs(proofs: Vec<RangeProof>) -> ProofBundle {
+        ProofBundle { proofs }
+    }
+
+    /// Verifies that each of the `commitments` holds a value in
+    /// \\([0, 2^n)\\).
+    ///
+    /// Returns an error if any proof is invalid, or if the number of
+    /// commitments differs from the number of proofs.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        commitments: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        if commitments.len() != self.proofs.len() {
+            return Err(ProofError::WrongNumCommitments);
+        }
+
+        commit_statement(transcript, n, commitments);
+        let verifier = Verifier::new(generators, n)?;
+        let mut acc = VerificationAccumulator::new(n);
+        for (proof, V) in self.proofs.iter().zip(commitments.iter()) {
+            acc.accumulate(&verifier, proof, V, transcript, rng)?;
+        }
+        acc.finalize(&verifier)
+    }
+}
+
+/// Commits the size of the bundle and all its `commitments` to the
+/// transcript, before any of its proofs.
+fn commit_statement(transcript: &mut ProofTranscript, n: usize, commitments: &[RistrettoPoint]) {
+    transcript.commit(b"ProofBundle");
+    transcript.commit_u64(n as u64);
+    transcript.commit_u64(commitments.len() as u64);
+    for V in commitments.iter() {
+        transcript.commit(V.compress().as_bytes());
+    }
+}
+
+impl ConstantTimeEq for ProofBundle {
+    fn ct_eq(&self, other: &ProofBundle) -> Choice {
+        ct::slices_eq(&self.proofs, &other.proofs)
+    }
+}
+
+impl PartialEq for ProofBundle {
+    fn eq(&self, other: &ProofBundle) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for ProofBundle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+
+    #[test]
+    fn bundles_verify_as_a_unit() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let statements: Vec<(SecretValue, Blinding)> = (0..3)
+            .map(|v| (SecretValue::new(1000 + v), Blinding::random(&mut rng)))
+            .collect();
+
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        let (bundle, commitments) = ProofBundle::prove_many(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &statements,
+        ).unwrap();
+        assert_eq!(bundle.len(), 3);
+
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        assert!(
+            bundle
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &commitments)
+                .is_ok()
+        );
+
+        // Reordered or missing commitments do not verify.
+        let mut reordered = commitments.clone();
+        reordered.swap(0, 1);
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        assert!(
+            bundle
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &reordered)
+                .is_err()
+        );
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        assert!(
+            bundle
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &commitments[..2])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn proofs_cannot_leave_their_bundle() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let statements: Vec<(SecretValue, Blinding)> = (7..9)
+            .map(|v| (SecretValue::new(v), Blinding::random(&mut rng)))
+            .collect();
+
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        let (bundle, commitments) = ProofBundle::prove_many(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &statements,
+        ).unwrap();
+
+        // The first proof alone, in a bundle of its own, does not verify.
+        let single = ProofBundle {
+            proofs: vec![bundle.proofs[0].clone()],
+        };
+        let mut transcript = ProofTranscript::new(b"BundleTest").unwrap();
+        assert!(
+            single
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &commitments[..1])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,282 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `cbor` module encodes range proofs in canonical CBOR
+//! ([RFC 7049, section 3.9](https://tools.ietf.org/html/rfc7049#section-3.9)),
+//! so that the encoding of a proof is unique and can be hashed or
+//! referenced stably.
+//!
+//! A proof is a map from the integer keys below to values:
+//!
+//! | key | field          | value                                     |
+//! |-----|----------------|-------------------------------------------|
+//! | 1–4 | \\(A, S, T\_1, T\_2\\) | 32-byte compressed points         |
+//! | 5–7 | \\(t(x), \tilde{t}(x), \tilde{e}\\) | 32-byte scalars      |
+//! | 8   | inner-product argument | map of 1: `[L]`, 2: `[R]`, 3: a, 4: b |
+//!
+//! The decoder is strict: it only accepts the encoding the encoder
+//! produces.  Indefinite lengths, integers not in their shortest form,
+//! duplicate, unknown or unsorted keys, tags and trailing bytes are all
+//! rejected, as are points that do not decompress and non-canonical
+//! scalars.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let bytes = cbor::encode_range_proof(&proof);
+//! let proof = cbor::decode_range_proof(&bytes)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use errors::ProofError;
+use inner_product_proof::InnerProductProof;
+use range_proof::{RangeCommitments, RangeProof};
+use util;
+
+/// The major type of unsigned integers.
+const UNSIGNED: u8 = 0;
+/// The major type of byte strings.
+const BYTES: u8 = 2;
+/// The major type of arrays.
+const ARRAY: u8 = 4;
+/// The major type of maps.
+const MAP: u8 = 5;
+
+/// Encodes `proof` in canonical CBOR.
+pub fn encode_range_proof(proof: &RangeProof) -> Vec<u8> {
+    let (c, ipp_proof) = proof.parts();
+    let mut out = Vec::new();
+    put_head(&mut out, MAP, 8);
+    for (key, P) in [c.A, c.S, c.T_1, c.T_2].iter().enumerate() {
+        put_head(&mut out, UNSIGNED, key as u64 + 1);
+        put_bytes(&mut out, P.compress().as_bytes());
+    }
+    for (key, s) in [c.t_x, c.t_x_blinding, c.e_blinding].iter().enumerate() {
+        put_head(&mut out, UNSIGNED, key as u64 + 5);
+        put_bytes(&mut out, s.as_bytes());
+    }
+    put_head(&mut out, UNSIGNED, 8);
+
+    put_head(&mut out, MAP, 4);
+    for (key, points) in [&ipp_proof.L_vec, &ipp_proof.R_vec].iter().enumerate() {
+        put_head(&mut out, UNSIGNED, key as u64 + 1);
+        put_head(&mut out, ARRAY, points.len() as u64);
+        for P in points.iter() {
+            put_bytes(&mut out, P.compress().as_bytes());
+        }
+    }
+    put_head(&mut out, UNSIGNED, 3);
+    put_bytes(&mut out, ipp_proof.a.as_bytes());
+    put_head(&mut out, UNSIGNED, 4);
+    put_bytes(&mut out, ipp_proof.b.as_bytes());
+    out
+}
+
+/// Decodes a proof from its canonical CBOR encoding.
+///
+/// Returns `InvalidPoint` or `InvalidScalar` for a point or scalar that
+/// does not decode, and `FormatError` if `bytes` is otherwise not
+/// exactly the canonical encoding of a proof.
+pub fn decode_range_proof(bytes: &[u8]) -> Result<RangeProof, ProofError> {
+    let mut decoder = Decoder { bytes };
+    decoder.map(8)?;
+    let A = decoder.entry(1).and_then(util::read_point)?;
+    let S = decoder.entry(2).and_then(util::read_point)?;
+    let T_1 = decoder.entry(3).and_then(util::read_point)?;
+    let T_2 = decoder.entry(4).and_then(util::read_point)?;
+    let t_x = decoder.entry(5).and_then(util::read_scalar)?;
+    let t_x_blinding = decoder.entry(6).and_then(util::read_scalar)?;
+    let e_blinding = decoder.entry(7).and_then(util::read_scalar)?;
+
+    decoder.key(8)?;
+    decoder.map(4)?;
+    decoder.key(1)?;
+    let L_vec = decoder.points()?;
+    decoder.key(2)?;
+    let R_vec = decoder.points()?;
+    let a = decoder.entry(3).and_then(util::read_scalar)?;
+    let b = decoder.entry(4).and_then(util::read_scalar)?;
+
+    if !decoder.bytes.is_empty() || L_vec.len() != R_vec.len() {
+        return Err(ProofError::FormatError);
+    }
+    let commitments = RangeCommitments {
+        A,
+        S,
+        T_1,
+        T_2,
+        t_x,
+        t_x_blinding,
+        e_blinding,
+    };
+    let ipp_proof = InnerProductProof { L_vec, R_vec, a, b };
+    Ok(RangeProof::from_parts(commitments, ipp_proof))
+}
+
+/// Appends the head of a data item of type `major` with the argument
+/// `value`, in its shortest form.
+fn put_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if value <= 0xff {
+        out.push(major | 24);
+        out.push(value as u8);
+    } else if value <= 0xffff {
+        out.push(major | 25);
+        let mut buf = [0u8; 2];
+        BigEndian::write_u16(&mut buf, value as u16);
+        out.extend_from_slice(&buf);
+    } else if value <= 0xffff_ffff {
+        out.push(major | 26);
+        let mut buf = [0u8; 4];
+        BigEndian::write_u32(&mut buf, value as u32);
+        out.extend_from_slice(&buf);
+    } else {
+        out.push(major | 27);
+        let mut buf = [0u8; 8];
+        BigEndian::write_u64(&mut buf, value);
+        out.extend_from_slice(&buf);
+    }
+}
+
+/// Appends the byte string `bytes`.
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_head(out, BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Reads canonical data items.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Reads the head of a data item of type `major`, returning its
+    /// argument.
+    fn head(&mut self, major: u8) -> Result<u64, ProofError> {
+        let initial = self.take(1)?[0];
+        if initial >> 5 != major {
+            return Err(ProofError::FormatError);
+        }
+        let (value, min) = match initial & 0x1f {
+            info @ 0...23 => return Ok(u64::from(info)),
+            24 => (u64::from(self.take(1)?[0]), 24),
+            25 => (u64::from(BigEndian::read_u16(self.take(2)?)), 0x100),
+            26 => (u64::from(BigEndian::read_u32(self.take(4)?)), 0x1_0000),
+            27 => (BigEndian::read_u64(self.take(8)?), 0x1_0000_0000),
+            // Reserved values, and 31 for indefinite lengths
+            _ => return Err(ProofError::FormatError),
+        };
+        if value < min {
+            return Err(ProofError::FormatError);
+        }
+        Ok(value)
+    }
+
+    /// Reads the head of a map with `len` entries.
+    fn map(&mut self, len: u64) -> Result<(), ProofError> {
+        if self.head(MAP)? != len {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+
+    /// Reads the key `key`.
+    fn key(&mut self, key: u64) -> Result<(), ProofError> {
+        if self.head(UNSIGNED)? != key {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+
+    /// Reads the key `key` and the byte string it maps to.
+    fn entry(&mut self, key: u64) -> Result<&'a [u8], ProofError> {
+        self.key(key)?;
+        let len = self.head(BYTES)?;
+        if len > self.bytes.len() as u64 {
+            return Err(ProofError::FormatError);
+        }
+        self.take(len as usize)
+    }
+
+    /// Reads an array of points.
+    fn points(&mut self) -> Result<Vec<RistrettoPoint>, ProofError> {
+        let len = self.head(ARRAY)?;
+        // Each point takes 34 bytes, so this bounds the allocation.
+        if len > self.bytes.len() as u64 / 34 {
+            return Err(ProofError::FormatError);
+        }
+        let mut points = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            if self.head(BYTES)? != 32 {
+                return Err(ProofError::FormatError);
+            }
+            points.push(util::read_point(self.take(32)?)?);
+        }
+        Ok(points)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProofError> {
+        if len > self.bytes.len() {
+            return Err(ProofError::FormatError);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn encoding_is_canonical_and_strict() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"CborTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(300),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let bytes = encode_range_proof(&proof);
+        let decoded = decode_range_proof(&bytes).unwrap();
+        assert_eq!(encode_range_proof(&decoded), bytes);
+
+        // An indefinite-length map
+        let mut indefinite = bytes.clone();
+        indefinite[0] = 0xbf;
+        assert!(decode_range_proof(&indefinite).is_err());
+
+        // The key 1 twice: the second entry starts after the first key,
+        // the two-byte head of the first point and the point.
+        let mut duplicate = bytes.clone();
+        assert_eq!(duplicate[36], 2);
+        duplicate[36] = 1;
+        assert!(decode_range_proof(&duplicate).is_err());
+
+        // Trailing bytes
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(decode_range_proof(&trailing).is_err());
+    }
+}
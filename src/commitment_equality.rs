@@ -0,0 +1,199 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `commitment_equality` module contains a sigma protocol proving
+//! that two Pedersen commitments, possibly under different generators,
+//! commit to the same value.
+//!
+//! For \\(C\_1 = v B\_1 + r\_1 \widetilde{B}\_1\\) and
+//! \\(C\_2 = v B\_2 + r\_2 \widetilde{B}\_2\\), the prover sends
+//! \\(T\_1 = k B\_1 + k\_1 \widetilde{B}\_1\\) and
+//! \\(T\_2 = k B\_2 + k\_2 \widetilde{B}\_2\\) with a shared nonce
+//! \\(k\\), receives a challenge \\(c\\) from the transcript, and answers
+//! \\(s = k + c v\\), \\(s\_i = k\_i + c r\_i\\).  Since \\(s\\) is
+//! shared, the verifier's checks \\(s B\_i + s\_i \widetilde{B}\_i = T\_i + c C\_i\\)
+//! bind both commitments to the same value.
+//!
+//! This bridges a value committed with this crate's generators and a
+//! value committed in another system using the same group.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Bridge")?;
+//! let proof = CommitmentEqualityProof::prove(
+//!     &ours, &theirs, &mut transcript, &mut rng, &v, &r_ours, &r_theirs,
+//! );
+//!
+//! let mut transcript = ProofTranscript::new(b"Bridge")?;
+//! proof.verify(&ours, &theirs, &mut transcript, &C_ours, &C_theirs)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar};
+use util;
+
+/// A proof that two Pedersen commitments commit to the same value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CommitmentEqualityProof {
+    /// Nonce commitment under the first generators
+    T_1: RistrettoPoint,
+    /// Nonce commitment under the second generators
+    T_2: RistrettoPoint,
+    /// Response for the shared value
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    s: Scalar,
+    /// Response for the first blinding factor
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    s_1: Scalar,
+    /// Response for the second blinding factor
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    s_2: Scalar,
+}
+
+impl CommitmentEqualityProof {
+    /// Proves that the commitments to `value` under `gens_1` with
+    /// `blinding_1` and under `gens_2` with `blinding_2` commit to the
+    /// same value.
+    pub fn prove<R: Rng>(
+        gens_1: &PedersenGenerators,
+        gens_2: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        value: &SecretScalar,
+        blinding_1: &Blinding,
+        blinding_2: &Blinding,
+    ) -> CommitmentEqualityProof {
+        let (value, blinding_1, blinding_2) =
+            (value.as_scalar(), blinding_1.as_scalar(), blinding_2.as_scalar());
+        let C_1 = gens_1.commit(*value, *blinding_1);
+        let C_2 = gens_2.commit(*value, *blinding_2);
+        transcript_prefix(transcript, gens_1, gens_2, &C_1, &C_2);
+
+        let k = Scalar::random(rng);
+        let k_1 = Scalar::random(rng);
+        let k_2 = Scalar::random(rng);
+        let T_1 = gens_1.commit(k, k_1);
+        let T_2 = gens_2.commit(k, k_2);
+
+        transcript.commit(T_1.compress().as_bytes());
+        transcript.commit(T_2.compress().as_bytes());
+        let c = transcript.challenge_scalar_labeled(b"equality c");
+
+        CommitmentEqualityProof {
+            T_1,
+            T_2,
+            s: k + c * value,
+            s_1: k_1 + c * blinding_1,
+            s_2: k_2 + c * blinding_2,
+        }
+    }
+
+    /// Verifies that `C_1`, under `gens_1`, and `C_2`, under `gens_2`,
+    /// commit to the same value.
+    pub fn verify(
+        &self,
+        gens_1: &PedersenGenerators,
+        gens_2: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        C_1: &RistrettoPoint,
+        C_2: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        transcript_prefix(transcript, gens_1, gens_2, C_1, C_2);
+        transcript.commit(self.T_1.compress().as_bytes());
+        transcript.commit(self.T_2.compress().as_bytes());
+        let c = transcript.challenge_scalar_labeled(b"equality c");
+
+        // s B_i + s_i B_blinding_i - c C_i - T_i = 0
+        let check = |gens: &PedersenGenerators, s_i: &Scalar, C_i: &RistrettoPoint, T_i| {
+            util::vartime_multiscalar_mul(
+                &[self.s, *s_i, -c, -Scalar::one()],
+                &[gens.B, gens.B_blinding, *C_i, T_i],
+            ) == Ok(RistrettoPoint::identity())
+        };
+
+        if check(gens_1, &self.s_1, C_1, self.T_1) && check(gens_2, &self.s_2, C_2, self.T_2) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(
+    transcript: &mut ProofTranscript,
+    gens_1: &PedersenGenerators,
+    gens_2: &PedersenGenerators,
+    C_1: &RistrettoPoint,
+    C_2: &RistrettoPoint,
+) {
+    transcript.commit(b"CommitmentEquality");
+    for point in [gens_1.B, gens_1.B_blinding, gens_2.B, gens_2.B_blinding].iter() {
+        transcript.commit(point.compress().as_bytes());
+    }
+    transcript.commit(C_1.compress().as_bytes());
+    transcript.commit(C_2.compress().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+    use sha2::Sha512;
+
+    fn other_generators() -> PedersenGenerators {
+        PedersenGenerators::new(
+            RistrettoPoint::hash_from_bytes::<Sha512>(b"Other B"),
+            RistrettoPoint::hash_from_bytes::<Sha512>(b"Other B_blinding"),
+        )
+    }
+
+    #[test]
+    fn equal_values_verify() {
+        let ours = PedersenGenerators::default();
+        let theirs = other_generators();
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretScalar::new(Scalar::from_u64(1234));
+        let r_1 = Blinding::random(&mut rng);
+        let r_2 = Blinding::random(&mut rng);
+
+        let mut transcript = ProofTranscript::new(b"EqualityTest").unwrap();
+        let proof = CommitmentEqualityProof::prove(
+            &ours,
+            &theirs,
+            &mut transcript,
+            &mut rng,
+            &v,
+            &r_1,
+            &r_2,
+        );
+
+        let (v, r_1, r_2) = (*v.as_scalar(), *r_1.as_scalar(), *r_2.as_scalar());
+        let C_1 = ours.commit(v, r_1);
+        let C_2 = theirs.commit(v, r_2);
+        let mut transcript = ProofTranscript::new(b"EqualityTest").unwrap();
+        assert!(
+            proof
+                .verify(&ours, &theirs, &mut transcript, &C_1, &C_2)
+                .is_ok()
+        );
+
+        // A commitment to another value with the same blinding
+        let C_2 = theirs.commit(v + Scalar::one(), r_2);
+        let mut transcript = ProofTranscript::new(b"EqualityTest").unwrap();
+        assert!(
+            proof
+                .verify(&ours, &theirs, &mut transcript, &C_1, &C_2)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,336 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `comparison` module contains a proof that one committed value
+//! is at least another, and a proof that a list of committed values is
+//! sorted.
+//!
+//! For commitments \\(A\\) and \\(B\\) to \\(a\\) and \\(b\\), the
+//! difference \\(A - B\\) commits to \\(a - b\\) with the difference of
+//! the blinding factors, and a range proof for it shows that
+//! \\(0 \leq a - b < 2^n\\).
+//!
+//! A list \\(C\_0, \ldots, C\_{k-1}\\) is sorted in non-decreasing order
+//! when each difference \\(C\_{i+1} - C\_i\\) holds such a value.  A
+//! `SortedProof` proves all the differences in one `ProofBundle`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Comparison")?;
+//! let proof = comparison::prove_greater_equal(
+//!     generators.share(0), &mut transcript, &mut rng, 64, &A, &B,
+//!     &[(a, a_blinding), (b, b_blinding)],
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Comparison")?;
+//! proof.verify(generators.share(0), &mut transcript, &mut rng, 64, &A, &B)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use bundle::ProofBundle;
+use errors::ProofError;
+use generators::GeneratorsView;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// A proof that one committed value is at least another.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GreaterEqualProof {
+    /// Range proof for the difference of the committed values
+    difference_proof: RangeProof,
+}
+
+/// Proves that the value committed in `A` is at least the value
+/// committed in `B`, given the `openings` of `A` and `B` as pairs of a
+/// value and a blinding factor.
+///
+/// Returns `InvalidOpening` if the openings do not match the
+/// commitments, and `ValueOutOfRange` if the difference of the values
+/// is negative or does not fit in `n` bits.  `InvalidBitsize` and
+/// `InvalidGeneratorsLength` are as for `RangeProof::generate_proof`.
+pub fn prove_greater_equal<R: Rng>(
+    generators: GeneratorsView,
+    transcript: &mut ProofTranscript,
+    rng: &mut R,
+    n: usize,
+    A: &RistrettoPoint,
+    B: &RistrettoPoint,
+    openings: &[(SecretValue, Blinding); 2],
+) -> Result<GreaterEqualProof, ProofError> {
+    let (ref a, ref a_blinding) = openings[0];
+    let (ref b, ref b_blinding) = openings[1];
+    let pg = generators.pedersen_generators;
+    if *A != pg.commit(a.to_scalar(), *a_blinding.as_scalar())
+        || *B != pg.commit(b.to_scalar(), *b_blinding.as_scalar())
+    {
+        return Err(ProofError::InvalidOpening);
+    }
+
+    let difference = a.value().checked_sub(b.value()).ok_or(ProofError::ValueOutOfRange)?;
+    if n < 64 && difference >= (1u64 << n) {
+        return Err(ProofError::ValueOutOfRange);
+    }
+
+    transcript_prefix(transcript, A, B);
+    let difference_proof = RangeProof::generate_proof(
+        generators,
+        transcript,
+        rng,
+        n,
+        &SecretValue::new(difference),
+        &Blinding::new(a_blinding.as_scalar() - b_blinding.as_scalar()),
+    )?;
+
+    Ok(GreaterEqualProof { difference_proof })
+}
+
+impl GreaterEqualProof {
+    /// Verifies that the value committed in `A` is at least the value
+    /// committed in `B`, by at most \\(2^n - 1\\).
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        A: &RistrettoPoint,
+        B: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        transcript_prefix(transcript, A, B);
+        self.difference_proof
+            .verify(&(A - B), generators, transcript, rng, n)
+    }
+}
+
+/// A proof that a list of committed values is sorted in non-decreasing
+/// order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SortedProof {
+    /// Range proofs for the differences of consecutive values
+    differences: ProofBundle,
+}
+
+/// Proves that the values committed in `commitments` are sorted in
+/// non-decreasing order, given the `openings` of the commitments as
+/// pairs of a value and a blinding factor.
+///
+/// Returns `WrongNumCommitments` if there are not as many openings as
+/// commitments, `InvalidOpening` if the openings do not match the
+/// commitments, and `ValueOutOfRange` if the difference of two
+/// consecutive values is negative or does not fit in `n` bits, and the
+/// errors of `ProofBundle::prove_many` for an unsupported `n`.
+pub fn prove_sorted<R: Rng>(
+    generators: GeneratorsView,
+    transcript: &mut ProofTranscript,
+    rng: &mut R,
+    n: usize,
+    commitments: &[RistrettoPoint],
+    openings: &[(SecretValue, Blinding)],
+) -> Result<SortedProof, ProofError> {
+    let pg = generators.pedersen_generators;
+    if commitments.len() != openings.len() {
+        return Err(ProofError::WrongNumCommitments);
+    }
+    if commitments
+        .iter()
+        .zip(openings.iter())
+        .any(|(C, opening)| *C != pg.commit(opening.0.to_scalar(), *opening.1.as_scalar()))
+    {
+        return Err(ProofError::InvalidOpening);
+    }
+
+    let mut differences = Vec::with_capacity(openings.len().saturating_sub(1));
+    for pair in openings.windows(2) {
+        let difference = pair[1]
+            .0
+            .value()
+            .checked_sub(pair[0].0.value())
+            .ok_or(ProofError::ValueOutOfRange)?;
+        if n < 64 && difference >= (1u64 << n) {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        let blinding = pair[1].1.as_scalar() - pair[0].1.as_scalar();
+        differences.push((SecretValue::new(difference), Blinding::new(blinding)));
+    }
+
+    sorted_transcript_prefix(transcript, commitments);
+    let (differences, _) = ProofBundle::prove_many(generators, transcript, rng, n, &differences)?;
+    Ok(SortedProof { differences })
+}
+
+impl SortedProof {
+    /// Verifies that the values committed in `commitments` are sorted
+    /// in non-decreasing order, with consecutive values at most
+    /// \\(2^n - 1\\) apart.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        commitments: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        let differences: Vec<RistrettoPoint> = commitments
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+
+        sorted_transcript_prefix(transcript, commitments);
+        self.differences
+            .verify(generators, transcript, rng, n, &differences)
+    }
+}
+
+/// Commits the statement of a `SortedProof` to the transcript.
+fn sorted_transcript_prefix(transcript: &mut ProofTranscript, commitments: &[RistrettoPoint]) {
+    transcript.commit(b"Sorted");
+    transcript.commit_u64(commitments.len() as u64);
+    for C in commitments.iter() {
+        transcript.commit(C.compress().as_bytes());
+    }
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(transcript: &mut ProofTranscript, A: &RistrettoPoint, B: &RistrettoPoint) {
+    transcript.commit(b"GreaterEqual");
+    transcript.commit(A.compress().as_bytes());
+    transcript.commit(B.compress().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+
+    fn prove_and_verify(a: u64, b: u64) -> Result<(), ProofError> {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+        let openings = [
+            (SecretValue::new(a), Blinding::random(&mut rng)),
+            (SecretValue::new(b), Blinding::random(&mut rng)),
+        ];
+        let A = pg.commit(Scalar::from_u64(a), *openings[0].1.as_scalar());
+        let B = pg.commit(Scalar::from_u64(b), *openings[1].1.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+        let proof = prove_greater_equal(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &A,
+            &B,
+            &openings,
+        )?;
+
+        let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+        proof.verify(generators.share(0), &mut transcript, &mut rng, n, &A, &B)
+    }
+
+    #[test]
+    fn greater_or_equal_values_verify() {
+        assert!(prove_and_verify(10, 3).is_ok());
+        assert!(prove_and_verify(10, 10).is_ok());
+        assert_eq!(prove_and_verify(3, 10), Err(ProofError::ValueOutOfRange));
+        // The difference does not fit in 16 bits.
+        assert_eq!(prove_and_verify(1 << 20, 0), Err(ProofError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn proof_does_not_verify_for_swapped_commitments() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+        let openings = [
+            (SecretValue::new(10), Blinding::random(&mut rng)),
+            (SecretValue::new(3), Blinding::random(&mut rng)),
+        ];
+        let A = pg.commit(Scalar::from_u64(10), *openings[0].1.as_scalar());
+        let B = pg.commit(Scalar::from_u64(3), *openings[1].1.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+        let proof = prove_greater_equal(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &A,
+            &B,
+            &openings,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &B, &A)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sorted_lists_verify() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        for values in [vec![3, 3, 8, 20], vec![5, 4, 8], vec![7]].iter() {
+            let openings: Vec<(SecretValue, Blinding)> = values
+                .iter()
+                .map(|&v| (SecretValue::new(v), Blinding::random(&mut rng)))
+                .collect();
+            let commitments: Vec<RistrettoPoint> = openings
+                .iter()
+                .map(|&(ref v, ref v_blinding)| pg.commit(v.to_scalar(), *v_blinding.as_scalar()))
+                .collect();
+            let is_sorted = values.windows(2).all(|pair| pair[0] <= pair[1]);
+
+            let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+            let proof = prove_sorted(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &commitments,
+                &openings,
+            );
+            assert_eq!(proof.is_ok(), is_sorted);
+            if !is_sorted {
+                continue;
+            }
+
+            let proof = proof.unwrap();
+            let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+            assert!(
+                proof
+                    .verify(generators.share(0), &mut transcript, &mut rng, n, &commitments)
+                    .is_ok()
+            );
+
+            // The reversed list is not sorted.
+            if commitments.len() > 1 {
+                let mut reversed = commitments.clone();
+                reversed.reverse();
+                let mut transcript = ProofTranscript::new(b"ComparisonTest").unwrap();
+                assert!(
+                    proof
+                        .verify(generators.share(0), &mut transcript, &mut rng, n, &reversed)
+                        .is_err()
+                );
+            }
+        }
+    }
+}
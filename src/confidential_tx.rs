@@ -0,0 +1,307 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `confidential_tx` module contains a helper proving that a
+//! confidential transaction balances.
+//!
+//! A transaction spends input commitments \\(I\_i\\) into output
+//! commitments \\(O\_j\\) and pays a public fee \\(f\\).  It balances
+//! when the excess \\( E = \sum\_i I\_i - \sum\_j O\_j - f B \\) is a
+//! commitment to zero, i.e. a multiple of \\(\widetilde{B}\\).  The
+//! `BalanceProof` contains a range proof for every output, so that no
+//! output is negative, and a Schnorr proof of knowledge of the discrete
+//! log of \\(E\\) with respect to \\(\widetilde{B}\\), all on one
+//! transcript.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let tx = ConfidentialTx { inputs, outputs, fee: 10 };
+//!
+//! let mut transcript = ProofTranscript::new(b"Transfer")?;
+//! let proof = tx.prove(generators.share(0), &mut transcript, &mut rng, 64,
+//!                      &input_blindings, &output_openings)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Transfer")?;
+//! tx.verify(&proof, generators.share(0), &mut transcript, &mut rng, 64)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// The public part of a confidential transaction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfidentialTx {
+    /// Commitments to the values spent
+    pub inputs: Vec<RistrettoPoint>,
+    /// Commitments to the values created
+    pub outputs: Vec<RistrettoPoint>,
+    /// The public fee
+    pub fee: u64,
+}
+
+/// A proof that a `ConfidentialTx` balances.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BalanceProof {
+    /// Range proofs for each of the outputs
+    output_proofs: Vec<RangeProof>,
+    /// Nonce commitment of the proof of knowledge of the excess blinding
+    R: RistrettoPoint,
+    /// Response of the proof of knowledge of the excess blinding
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    s: Scalar,
+}
+
+impl ConfidentialTx {
+    /// Proves that the transaction balances, given the blinding factors
+    /// of the inputs and the openings of the outputs, with `n`-bit range
+    /// proofs for the outputs.
+    ///
+    /// Returns `ProofError::WrongNumCommitments` if the number of
+    /// blindings or openings does not match the transaction,
+    /// `ProofError::InvalidOpening` if the openings do not match the
+    /// commitments, and `ProofError::ValueOutOfRange` if an output does
+    /// not fit in `n` bits or the transaction does not balance.  An
+    /// unsupported `n` is rejected first, as by
+    /// `RangeProof::generate_proof`.
+    pub fn prove<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        input_blindings: &[Blinding],
+        output_openings: &[(SecretValue, Blinding)],
+    ) -> Result<BalanceProof, ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        let pg = generators.pedersen_generators;
+        let fits = |x: u64| n == 64 || x < (1u64 << n);
+        if input_blindings.len() != self.inputs.len()
+            || output_openings.len() != self.outputs.len()
+        {
+            return Err(ProofError::WrongNumCommitments);
+        }
+        for (O, &(ref v, ref v_blinding)) in self.outputs.iter().zip(output_openings.iter()) {
+            if *O != pg.commit(v.to_scalar(), *v_blinding.as_scalar()) {
+                return Err(ProofError::InvalidOpening);
+            }
+            if !fits(v.value()) {
+                return Err(ProofError::ValueOutOfRange);
+            }
+        }
+
+        let excess_blinding = Blinding::new(
+            input_blindings
+                .iter()
+                .fold(Scalar::zero(), |acc, r| acc + r.as_scalar())
+                - output_openings
+                    .iter()
+                    .fold(Scalar::zero(), |acc, &(_, ref r)| acc + r.as_scalar()),
+        );
+        if self.excess(pg) != pg.B_blinding * excess_blinding.as_scalar() {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        self.transcript_prefix(transcript);
+
+        let output_proofs = output_openings
+            .iter()
+            .map(|&(ref v, ref v_blinding)| {
+                RangeProof::generate_proof(generators, transcript, rng, n, v, v_blinding)
+            })
+            .collect::<Result<_, _>>()?;
+
+        // Derive the nonce from the transcript and the excess blinding
+        // as well as from `rng`, as the range proofs do.
+        let mut nonces = transcript.build_rng(&[excess_blinding.as_scalar().as_bytes()], rng);
+        let k = Scalar::random(&mut nonces);
+        let R = pg.B_blinding * k;
+        transcript.commit(R.compress().as_bytes());
+        let c = transcript.challenge_scalar_labeled(b"excess");
+        let s = k + c * excess_blinding.as_scalar();
+
+        Ok(BalanceProof { output_proofs, R, s })
+    }
+
+    /// Verifies that the transaction balances, with `n`-bit range
+    /// proofs for the outputs.
+    ///
+    /// Returns `ProofError::WrongNumCommitments` if the number of range
+    /// proofs does not match the number of outputs.
+    pub fn verify<R: Rng>(
+        &self,
+        proof: &BalanceProof,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        if proof.output_proofs.len() != self.outputs.len() {
+            return Err(ProofError::WrongNumCommitments);
+        }
+        self.transcript_prefix(transcript);
+
+        for (range_proof, O) in proof.output_proofs.iter().zip(self.outputs.iter()) {
+            range_proof.verify(O, generators, transcript, rng, n)?;
+        }
+
+        let pg = generators.pedersen_generators;
+        transcript.commit(proof.R.compress().as_bytes());
+        let c = transcript.challenge_scalar_labeled(b"excess");
+        if pg.B_blinding * proof.s == proof.R + self.excess(pg) * c {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the excess \\( \sum\_i I\_i - \sum\_j O\_j - f B \\).
+    fn excess(&self, pg: &PedersenGenerators) -> RistrettoPoint {
+        let fee = pg.B * Scalar::from_u64(self.fee);
+        let inputs = self.inputs.iter().fold(-fee, |acc, I| acc + I);
+        self.outputs.iter().fold(inputs, |acc, O| acc - O)
+    }
+
+    /// Commits the transaction to the transcript.
+    fn transcript_prefix(&self, transcript: &mut ProofTranscript) {
+        transcript.commit(b"ConfidentialTx");
+        transcript.commit_u64(self.inputs.len() as u64);
+        for I in self.inputs.iter() {
+            transcript.commit(I.compress().as_bytes());
+        }
+        transcript.commit_u64(self.outputs.len() as u64);
+        for O in self.outputs.iter() {
+            transcript.commit(O.compress().as_bytes());
+        }
+        transcript.commit_u64(self.fee);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+
+    fn prove_and_verify(inputs: &[u64], outputs: &[u64], fee: u64) -> Result<(), ProofError> {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        let input_openings: Vec<(SecretValue, Blinding)> = inputs
+            .iter()
+            .map(|&v| (SecretValue::new(v), Blinding::random(&mut rng)))
+            .collect();
+        let output_openings: Vec<(SecretValue, Blinding)> = outputs
+            .iter()
+            .map(|&v| (SecretValue::new(v), Blinding::random(&mut rng)))
+            .collect();
+        let commit =
+            |&(ref v, ref r): &(SecretValue, Blinding)| pg.commit(v.to_scalar(), *r.as_scalar());
+        let tx = ConfidentialTx {
+            inputs: input_openings.iter().map(&commit).collect(),
+            outputs: output_openings.iter().map(&commit).collect(),
+            fee,
+        };
+        let input_blindings: Vec<Blinding> =
+            input_openings.iter().map(|&(_, ref r)| r.clone()).collect();
+
+        let mut transcript = ProofTranscript::new(b"ConfidentialTxTest").unwrap();
+        let proof = tx.prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &input_blindings,
+            &output_openings,
+        )?;
+
+        let mut transcript = ProofTranscript::new(b"ConfidentialTxTest").unwrap();
+        tx.verify(&proof, generators.share(0), &mut transcript, &mut rng, n)
+    }
+
+    #[test]
+    fn balanced_transactions_verify() {
+        assert!(prove_and_verify(&[100], &[90], 10).is_ok());
+        assert!(prove_and_verify(&[100, 50], &[70, 75, 0], 5).is_ok());
+    }
+
+    #[test]
+    fn unbalanced_transactions_are_rejected() {
+        assert_eq!(prove_and_verify(&[100], &[95], 10), Err(ProofError::ValueOutOfRange));
+        assert_eq!(prove_and_verify(&[100], &[90], 0), Err(ProofError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn unsupported_bitsizes_are_rejected() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 32, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+        let input_blinding = Blinding::random(&mut rng);
+        let output_opening = (SecretValue::new(100), Blinding::random(&mut rng));
+        let tx = ConfidentialTx {
+            inputs: vec![pg.commit(Scalar::from_u64(100), *input_blinding.as_scalar())],
+            outputs: vec![pg.commit(Scalar::from_u64(100), *output_opening.1.as_scalar())],
+            fee: 0,
+        };
+
+        for &n in [0, 65, 128].iter() {
+            let mut transcript = ProofTranscript::new(b"ConfidentialTxTest").unwrap();
+            let result = tx.prove(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &[input_blinding.clone()],
+                &[output_opening.clone()],
+            );
+            assert_eq!(result.unwrap_err(), ProofError::InvalidBitsize);
+        }
+    }
+
+    #[test]
+    fn proof_does_not_verify_with_a_different_fee() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        let input_blinding = Blinding::random(&mut rng);
+        let output_opening = (SecretValue::new(90), Blinding::random(&mut rng));
+        let mut tx = ConfidentialTx {
+            inputs: vec![pg.commit(Scalar::from_u64(100), *input_blinding.as_scalar())],
+            outputs: vec![pg.commit(Scalar::from_u64(90), *output_opening.1.as_scalar())],
+            fee: 10,
+        };
+
+        let mut transcript = ProofTranscript::new(b"ConfidentialTxTest").unwrap();
+        let proof = tx.prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &[input_blinding],
+            &[output_opening],
+        ).unwrap();
+
+        tx.fee = 11;
+        let mut transcript = ProofTranscript::new(b"ConfidentialTxTest").unwrap();
+        assert!(
+            tx.verify(&proof, generators.share(0), &mut transcript, &mut rng, n)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+#![deny(missing_docs)]
+
+//! The `cost` module estimates the cost of verifying range proofs,
+//! derived from the structure of the verification equation.
+//!
+//! This is intended for setting fees or weights for transactions
+//! carrying range proofs, so that the numbers track the
+//! implementation rather than being hardcoded elsewhere.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate ristretto_bulletproofs;
+//! # use ristretto_bulletproofs::cost::verification_cost;
+//! # fn main() {
+//! let cost = verification_cost(64, 1, 1);
+//! assert_eq!(cost.scalar_muls, 7 + 2 * 64 + 2 * 6);
+//! assert_eq!(cost.bytes, 32 * (9 + 2 * 6));
+//! # }
+//! ```
+
+/// Estimated cost of verifying a batch of range proofs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// Number of terms in the verification multiscalar multiplication
+    pub scalar_muls: usize,
+    /// Number of point additions needed to combine the terms
+    pub point_adds: usize,
+    /// Total size of the proof components, in bytes
+    pub bytes: usize,
+}
+
+/// Estimates the cost of verifying `batch_size` range proofs, each
+/// covering `m` values of `n` bits.
+///
+/// Proofs in a batch share the generator terms of the verification
+/// equation, so the per-proof cost decreases as the batch grows.
+///
+/// Panics if `n * m` is not a power of two, or if `batch_size` is zero.
+pub fn verification_cost(n: usize, m: usize, batch_size: usize) -> CostEstimate {
+    let nm = n * m;
+    assert!(nm.is_power_of_two(), "n * m must be a power of two");
+    assert!(batch_size > 0, "batch_size must be positive");
+    let lg_nm = nm.trailing_zeros() as usize;
+
+    // B, B_blinding, G and H are shared by all proofs in the batch.
+    let shared_terms = 2 + 2 * nm;
+    // A, S, T_1, T_2, the value commitments, and the IPP's L and R.
+    let proof_terms = 4 + m + 2 * lg_nm;
+    let scalar_muls = shared_terms + batch_size * proof_terms;
+
+    // A, S, T_1, T_2, L and R are points; t(x), its blinding, e_blinding,
+    // a and b are scalars.  Both are encoded in 32 bytes.
+    let proof_bytes = 32 * (4 + 2 * lg_nm + 5);
+
+    CostEstimate {
+        scalar_muls,
+        point_adds: scalar_muls - 1,
+        bytes: batch_size * proof_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_proof_cost_matches_verification_equation() {
+        // The verifier checks one multiscalar multiplication over
+        // 7 + 2n + 2 lg(n) points.
+        for &(n, lg_n) in [(8, 3), (16, 4), (32, 5), (64, 6)].iter() {
+            let cost = verification_cost(n, 1, 1);
+            assert_eq!(cost.scalar_muls, 7 + 2 * n + 2 * lg_n);
+            assert_eq!(cost.point_adds, cost.scalar_muls - 1);
+        }
+    }
+
+    #[test]
+    fn batching_amortizes_generator_terms() {
+        let one = verification_cost(64, 1, 1);
+        let ten = verification_cost(64, 1, 10);
+        assert!(ten.scalar_muls < 10 * one.scalar_muls);
+        assert_eq!(ten.bytes, 10 * one.bytes);
+    }
+}
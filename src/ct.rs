@@ -0,0 +1,136 @@
+#![deny(missing_docs)]
+
+//! The `ct` module contains constant-time comparisons for proofs and
+//! commitments, and constant-time powers of secret scalars.
+//!
+//! Every proof type implements `subtle::ConstantTimeEq`, and its
+//! `PartialEq` is built on it, so comparing two proofs takes the same
+//! time wherever they first differ.  Commitments are `RistrettoPoint`s,
+//! which implement `ConstantTimeEq` themselves.  The helpers here extend
+//! this to the slices of proofs and commitments that deduplication and
+//! consensus code compares: only the lengths, which are public, are
+//! compared in variable time.
+//!
+//! The crate computes the powers of public scalars, such as the
+//! challenges \\(y\\) and \\(x\\), with a plain iterator over `Scalar`s.
+//! Gadgets and protocols built on the crate that need the powers of a
+//! secret use `secret_exp_iter` instead, which takes and yields
+//! `SecretScalar`s, so that the powers stay redacted and, with the
+//! `zeroize` feature, are wiped when dropped.  Scalar multiplication is
+//! constant-time, so only the number of powers taken, which the caller
+//! chooses, affects the timing.  For a secret exponent \\(k\\),
+//! `secret_pow` computes every power up to a public bound and selects
+//! \\(x^k\\) with `ConditionallyAssignable`, where taking the
+//! \\(k\\)-th item of an iterator would take time linear in \\(k\\).
+//!
+//! # Example
+//!
+//! ```ascii
+//! if ct::contains(&seen_commitments, &V).unwrap_u8() == 1 {
+//!     return Err(Duplicate);
+//! }
+//!
+//! let x_k = ct::secret_pow(&x, &k, 64);
+//! ```
+
+use core::mem;
+
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConditionallyAssignable, ConstantTimeEq};
+
+use secret::{SecretScalar, SecretValue};
+
+/// Returns whether `a` and `b` have equal lengths and equal elements,
+/// comparing the elements in constant time.
+pub fn slices_eq<T: ConstantTimeEq>(a: &[T], b: &[T]) -> Choice {
+    // The lengths are public, so it is fine to exit early on them.
+    if a.len() != b.len() {
+        return Choice::from(0);
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(Choice::from(1), |acc, (a_i, b_i)| acc & a_i.ct_eq(b_i))
+}
+
+/// Returns whether `items` contains `item`, comparing it to every
+/// element in constant time.
+pub fn contains<T: ConstantTimeEq>(items: &[T], item: &T) -> Choice {
+    items
+        .iter()
+        .fold(Choice::from(0), |acc, item_i| acc | item_i.ct_eq(item))
+}
+
+/// An iterator over the powers \\(1, x, x^2, \ldots\\) of a secret \\(x\\).
+///
+/// This struct is created by the `secret_exp_iter` function.
+#[derive(Debug)]
+pub struct SecretScalarExp {
+    /// The base \\(x\\)
+    x: SecretScalar,
+    /// The next power to yield
+    next_exp_x: SecretScalar,
+}
+
+impl Iterator for SecretScalarExp {
+    type Item = SecretScalar;
+
+    fn next(&mut self) -> Option<SecretScalar> {
+        let next_exp_x = SecretScalar::new(self.next_exp_x.as_scalar() * self.x.as_scalar());
+        Some(mem::replace(&mut self.next_exp_x, next_exp_x))
+    }
+}
+
+/// Returns an iterator over the powers of the secret `x`.
+pub fn secret_exp_iter(x: &SecretScalar) -> SecretScalarExp {
+    SecretScalarExp {
+        x: x.clone(),
+        next_exp_x: SecretScalar::new(Scalar::one()),
+    }
+}
+
+/// Returns \\(x^k\\) for a secret exponent `k`, in time that depends on
+/// the public `bound` but not on `k`.
+///
+/// The result is zero if `k` is not less than `bound`.
+pub fn secret_pow(x: &Scalar, k: &SecretValue, bound: usize) -> SecretScalar {
+    let mut x_k = Scalar::zero();
+    let mut x_i = Scalar::one();
+    for i in 0..bound {
+        x_k.conditional_assign(&x_i, k.value().ct_eq(&(i as u64)));
+        x_i = x_i * x;
+    }
+    SecretScalar::new(x_k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT as B;
+
+    #[test]
+    fn commitments_compare_in_constant_time() {
+        let commitments: Vec<_> = (1..4).map(|i| B * Scalar::from_u64(i)).collect();
+        assert_eq!(slices_eq(&commitments, &commitments.clone()).unwrap_u8(), 1);
+        assert_eq!(slices_eq(&commitments, &commitments[..2]).unwrap_u8(), 0);
+        assert_eq!(slices_eq(&commitments[..2], &commitments[1..]).unwrap_u8(), 0);
+
+        assert_eq!(contains(&commitments, &(B + B)).unwrap_u8(), 1);
+        assert_eq!(contains(&commitments, &(B * Scalar::from_u64(4))).unwrap_u8(), 0);
+    }
+
+    #[test]
+    fn secret_powers_match_public_powers() {
+        let x = Scalar::from_u64(3);
+        let powers: Vec<Scalar> = secret_exp_iter(&SecretScalar::new(x))
+            .take(5)
+            .map(|x_i| *x_i.as_scalar())
+            .collect();
+        let expected: Vec<Scalar> =
+            [1, 3, 9, 27, 81].iter().map(|&p| Scalar::from_u64(p)).collect();
+        assert_eq!(powers, expected);
+
+        assert_eq!(secret_pow(&x, &SecretValue::new(4), 8).as_scalar(), &expected[4]);
+        assert_eq!(secret_pow(&x, &SecretValue::new(0), 8).as_scalar(), &Scalar::one());
+        assert_eq!(secret_pow(&x, &SecretValue::new(8), 8).as_scalar(), &Scalar::zero());
+    }
+}
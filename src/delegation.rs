@@ -0,0 +1,354 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `delegation` module lets a constrained prover, such as a phone
+//! or a hardware security module, hand the inner-product argument of a
+//! range proof to an untrusted helper.
+//!
+//! The device runs the range proof up to the inner-product argument:
+//! it commits to the bits of the value and to the polynomial \\(t(x)\\),
+//! and evaluates \\(\mathbf{l}(x)\\) and \\(\mathbf{r}(x)\\).  These vectors
+//! are blinded by the random vectors \\(\mathbf{s}\_L\\), \\(\mathbf{s}\_R\\)
+//! committed in \\(S\\); they are exactly what the linear-size protocol of
+//! the paper sends to the verifier in the clear, so the helper learns
+//! nothing about the value from them.
+//!
+//! The `DelegationRequest` is a plain value that serializes with serde:
+//! it holds the starting state of the transcript as a
+//! `TranscriptSnapshot`, the commitments of the proof so far and the two
+//! vectors.  The helper replays the transcript up to the inner-product
+//! argument, as a verifier would, and runs its \\(\lg n\\) folding rounds
+//! over the \\(2n\\) generators.
+//!
+//! Only those rounds move to the helper.  The device still computes
+//! \\(A\\), \\(S\\), \\(T\_1\\) and \\(T\_2\\), about \\(2n\\) point
+//! multiplications, and checks the assembled proof with a full
+//! verification, a multiscalar multiplication of size about \\(2n\\), so
+//! a faulty or malicious helper can make proving fail but cannot make
+//! the device output an invalid proof.  What it saves are the
+//! \\(4n\\) or so point multiplications of the folding rounds, which make
+//! up most of a prover's point arithmetic.  The proof is the one
+//! `generate_proof` would have made with the same randomness and
+//! verifies with the usual `Verifier`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let snapshot = TranscriptSnapshot::new(b"Payment")?;
+//! let (prover, request) =
+//!     DelegatedProver::start(generators.share(0), &snapshot, &mut rng, 64, &v, &v_blinding)?;
+//! let bytes = bincode::serialize(&request)?;
+//!
+//! // On the helper:
+//! let request: DelegationRequest = bincode::deserialize(&bytes)?;
+//! let response = request.prove(generators.share(0))?;
+//!
+//! // Back on the device:
+//! let proof = prover.finish(generators.share(0), response, &mut rng)?;
+//! ```
+
+use core::fmt;
+
+use rand::Rng;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use inner_product_proof::{InnerProductProof, ReducedWitness};
+use params::ProofParams;
+use range_proof::{RangeCommitments, RangeProof, Verifier};
+use range_proof_transcript::RangeProofTranscript;
+use secret::{Blinding, SecretValue};
+use snapshot::TranscriptSnapshot;
+use util;
+
+/// The device's half of a delegated range proof, waiting for the helper's
+/// inner-product argument.
+#[derive(Clone)]
+pub struct DelegatedProver {
+    /// The transcript as it was before the proof, to check the result
+    snapshot: TranscriptSnapshot,
+    /// The commitment to the value
+    V: RistrettoPoint,
+    /// The bitsize of the range
+    n: usize,
+    /// The commitments of the proof before the inner-product argument
+    commitments: RangeCommitments,
+}
+
+/// The work handed to the helper: the blinded vectors of the
+/// inner-product argument and what the helper needs to replay the
+/// transcript its challenges are drawn from.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DelegationRequest {
+    /// The transcript as it was before the proof
+    snapshot: TranscriptSnapshot,
+    /// The commitment to the value
+    V: RistrettoPoint,
+    /// The bitsize of the range
+    n: usize,
+    /// The commitments of the proof before the inner-product argument
+    commitments: RangeCommitments,
+    /// The blinded vector \\(\mathbf{l}(x)\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    a: Vec<Scalar>,
+    /// The blinded vector \\(\mathbf{r}(x)\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    b: Vec<Scalar>,
+}
+
+/// The helper's answer to a `DelegationRequest`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DelegationResponse {
+    /// The inner-product argument for the request's vectors
+    ipp_proof: InnerProductProof,
+}
+
+impl DelegatedProver {
+    /// Starts a proof that the value `v`, committed with `v_blinding`,
+    /// is in \\([0, 2^n)\\) over the transcript `snapshot` starts, returning
+    /// the device's state and the request for the helper.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not supported, and
+    /// `InvalidGeneratorsLength` if `generators` do not have exactly
+    /// \\(n\\) bases in each of `G` and `H`.
+    pub fn start<R: Rng>(
+        generators: GeneratorsView,
+        snapshot: &TranscriptSnapshot,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<(DelegatedProver, DelegationRequest), ProofError> {
+        let mut transcript = snapshot.transcript();
+        let (commitments, witness) = RangeProof::prove_reduced(
+            generators,
+            &mut transcript,
+            rng,
+            n,
+            v.value(),
+            v_blinding.as_scalar(),
+        )?;
+        let V = generators
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+
+        let request = DelegationRequest {
+            snapshot: snapshot.clone(),
+            V,
+            n,
+            commitments: commitments.clone(),
+            a: witness.a.to_vec(),
+            b: witness.b.to_vec(),
+        };
+        let prover = DelegatedProver {
+            snapshot: snapshot.clone(),
+            V,
+            n,
+            commitments,
+        };
+        Ok((prover, request))
+    }
+
+    /// Returns the commitment to the value being proven.
+    pub fn commitment(&self) -> &RistrettoPoint {
+        &self.V
+    }
+
+    /// Assembles the range proof from the helper's `response` and checks
+    /// it, so that only valid proofs leave the device.
+    ///
+    /// Returns an error if the helper's argument does not verify.
+    pub fn finish<R: Rng>(
+        self,
+        generators: GeneratorsView,
+        response: DelegationResponse,
+        rng: &mut R,
+    ) -> Result<RangeProof, ProofError> {
+        let DelegatedProver {
+            snapshot,
+            V,
+            n,
+            commitments,
+        } = self;
+        let proof = RangeProof::from_parts(commitments, response.ipp_proof);
+
+        Verifier::new(generators, n)?.verify(&proof, &V, &mut snapshot.transcript(), rng)?;
+        Ok(proof)
+    }
+}
+
+impl DelegationRequest {
+    /// Creates the inner-product argument for the request, on the helper.
+    ///
+    /// Returns `InvalidBitsize` if the request's bitsize is not supported,
+    /// `InvalidGeneratorsLength` if `generators` do not have exactly
+    /// \\(n\\) bases in each of `G` and `H`, and `FormatError` if the
+    /// vectors do not have \\(n\\) entries, since requests may come from
+    /// untrusted bytes.
+    pub fn prove(self, generators: GeneratorsView) -> Result<DelegationResponse, ProofError> {
+        let DelegationRequest {
+            snapshot,
+            V,
+            n,
+            commitments,
+            a,
+            b,
+        } = self;
+        ProofParams::single(n)?.check_share(&generators)?;
+        if a.len() != n || b.len() != n {
+            return Err(ProofError::FormatError);
+        }
+
+        // Replay the challenges the device drew, as the verifier will.
+        let mut transcript = snapshot.transcript();
+        let range_transcript = RangeProofTranscript::new(&mut transcript, n);
+        let (range_transcript, y, _) =
+            range_transcript.commit_bits(&V, &commitments.A, &commitments.S);
+        let (range_transcript, _) =
+            range_transcript.commit_poly(&commitments.T_1, &commitments.T_2);
+        let transcript = range_transcript.commit_evaluation_only(
+            &commitments.t_x,
+            &commitments.t_x_blinding,
+            &commitments.e_blinding,
+        );
+
+        let witness = ReducedWitness {
+            G_factors: vec![Scalar::one(); n],
+            H_factors: util::ScalarPowers::new(y.invert(), n).as_slice().to_vec(),
+            a: a.into(),
+            b: b.into(),
+        };
+        let ipp_proof = witness.prove(
+            transcript,
+            generators.pedersen_generators,
+            generators.G,
+            generators.H,
+        )?;
+        Ok(DelegationResponse { ipp_proof })
+    }
+}
+
+impl fmt::Debug for DelegatedProver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DelegatedProver")
+            .field("V", &self.V)
+            .field("n", &self.n)
+            .finish()
+    }
+}
+
+impl fmt::Debug for DelegationRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The vectors are blinded, but only the helper should see them.
+        f.debug_struct("DelegationRequest")
+            .field("n", &self.n)
+            .finish()
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+impl ::fuzzing::Arbitrary for DelegationResponse {
+    fn arbitrary(u: &mut ::fuzzing::Unstructured) -> Result<Self, ProofError> {
+        Ok(DelegationResponse {
+            ipp_proof: ::fuzzing::Arbitrary::arbitrary(u)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+
+    #[test]
+    fn delegated_proofs_verify_normally() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Blinding::random(&mut rng);
+
+        let snapshot = TranscriptSnapshot::new(b"DelegationTest").unwrap();
+        let (prover, request) = DelegatedProver::start(
+            generators.share(0),
+            &snapshot,
+            &mut rng,
+            n,
+            &SecretValue::new(123456),
+            &v_blinding,
+        ).unwrap();
+        let V = *prover.commitment();
+
+        // The request crosses to the helper as bytes.
+        let bytes = bincode::serialize(&request).unwrap();
+        let request: DelegationRequest = bincode::deserialize(&bytes).unwrap();
+        let response = request.prove(generators.share(0)).unwrap();
+        let proof = prover
+            .finish(generators.share(0), response, &mut rng)
+            .unwrap();
+
+        let mut transcript = ProofTranscript::new(b"DelegationTest").unwrap();
+        assert!(
+            Verifier::new(generators.share(0), n)
+                .unwrap()
+                .verify(&proof, &V, &mut transcript, &mut rng)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn wrong_helper_responses_are_rejected() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretValue::new(7);
+        let v_blinding = Blinding::random(&mut rng);
+
+        let snapshot = TranscriptSnapshot::new(b"DelegationTest").unwrap();
+        let (prover, mut request) = DelegatedProver::start(
+            generators.share(0),
+            &snapshot,
+            &mut rng,
+            n,
+            &v,
+            &v_blinding,
+        ).unwrap();
+
+        // Requests whose vectors do not match their bitsize are refused.
+        let mut truncated = request.clone();
+        truncated.a.pop();
+        assert_eq!(
+            truncated.prove(generators.share(0)).unwrap_err(),
+            ProofError::FormatError
+        );
+        request.n = 64;
+        assert_eq!(
+            request.prove(generators.share(0)).unwrap_err(),
+            ProofError::InvalidGeneratorsLength
+        );
+
+        // An argument for another request does not complete the proof.
+        let (_, other) = DelegatedProver::start(
+            generators.share(0),
+            &snapshot,
+            &mut rng,
+            n,
+            &v,
+            &v_blinding,
+        ).unwrap();
+        let response = other.prove(generators.share(0)).unwrap();
+        assert!(
+            prover
+                .finish(generators.share(0), response, &mut rng)
+                .is_err()
+        );
+    }
+}
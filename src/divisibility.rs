@@ -0,0 +1,220 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `divisibility` module contains a proof that a committed value
+//! is a multiple of a public constant, e.g. an amount in whole cents.
+//!
+//! If \\(V = v B + r \widetilde{B}\\) with \\(v = k q\\), then \\(V\\)
+//! is also a commitment \\(V = q (k B) + r \widetilde{B}\\) to the
+//! quotient \\(q\\) under the value base \\(k B\\).  The proof is a
+//! range proof for \\(q\\) under that base, which shows that
+//! \\(v = k q\\) with \\(0 \leq q < 2^n\\).
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"WholeCents")?;
+//! let proof = DivisibilityProof::prove(
+//!     generators.share(0), &mut transcript, &mut rng, 32, 100, &v, &v_blinding,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"WholeCents")?;
+//! proof.verify(generators.share(0), &mut transcript, &mut rng, 32, 100, &V)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// A proof that a committed value is a multiple of a public constant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DivisibilityProof {
+    /// Range proof for the quotient, under the value base \\(k B\\)
+    quotient_proof: RangeProof,
+}
+
+impl DivisibilityProof {
+    /// Proves that the value `v` committed with `v_blinding` is a
+    /// multiple of `k`, with an `n`-bit quotient.
+    ///
+    /// Returns `ProofError::InvalidStatement` if `k` is zero, and
+    /// `ProofError::ValueOutOfRange` if `v` is not a multiple of `k` or
+    /// the quotient does not fit in `n` bits.  An unsupported `n` is
+    /// rejected as by `RangeProof::generate_proof`.
+    pub fn prove<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        k: u64,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<DivisibilityProof, ProofError> {
+        if k == 0 {
+            return Err(ProofError::InvalidStatement);
+        }
+        if v.value() % k != 0 {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        let q = v.value() / k;
+        if n < 64 && q >= (1u64 << n) {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let V = generators
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+        transcript_prefix(transcript, k, &V);
+
+        let quotient_generators = quotient_generators(generators.pedersen_generators, k);
+        let quotient_proof = RangeProof::generate_proof(
+            quotient_view(generators, &quotient_generators),
+            transcript,
+            rng,
+            n,
+            &SecretValue::new(q),
+            v_blinding,
+        )?;
+
+        Ok(DivisibilityProof { quotient_proof })
+    }
+
+    /// Verifies that the value committed in `V` is a multiple of `k`,
+    /// with an `n`-bit quotient.
+    ///
+    /// Returns `ProofError::InvalidStatement` if `k` is zero.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        k: u64,
+        V: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        if k == 0 {
+            return Err(ProofError::InvalidStatement);
+        }
+        transcript_prefix(transcript, k, V);
+
+        let quotient_generators = quotient_generators(generators.pedersen_generators, k);
+        self.quotient_proof.verify(
+            V,
+            quotient_view(generators, &quotient_generators),
+            transcript,
+            rng,
+            n,
+        )
+    }
+}
+
+/// Returns the Pedersen generators with the value base \\(k B\\).
+fn quotient_generators(pg: &PedersenGenerators, k: u64) -> PedersenGenerators {
+    PedersenGenerators::new(pg.B * Scalar::from_u64(k), pg.B_blinding)
+}
+
+/// Returns the `generators` with the Pedersen generators replaced by
+/// `quotient_generators`.
+fn quotient_view<'a>(
+    generators: GeneratorsView<'a>,
+    quotient_generators: &'a PedersenGenerators,
+) -> GeneratorsView<'a> {
+    GeneratorsView {
+        pedersen_generators: quotient_generators,
+        G: generators.G,
+        H: generators.H,
+    }
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(transcript: &mut ProofTranscript, k: u64, V: &RistrettoPoint) {
+    transcript.commit(b"Divisibility");
+    transcript.commit_u64(k);
+    transcript.commit(V.compress().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+
+    fn prove_and_verify(k: u64, v: u64) -> Result<(), ProofError> {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretValue::new(v);
+        let v_blinding = Blinding::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"DivisibilityTest").unwrap();
+        let proof = DivisibilityProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            k,
+            &v,
+            &v_blinding,
+        )?;
+
+        let mut transcript = ProofTranscript::new(b"DivisibilityTest").unwrap();
+        proof.verify(generators.share(0), &mut transcript, &mut rng, n, k, &V)
+    }
+
+    #[test]
+    fn multiples_verify() {
+        assert!(prove_and_verify(100, 1500).is_ok());
+        assert!(prove_and_verify(7, 0).is_ok());
+        assert!(prove_and_verify(1, 65535).is_ok());
+    }
+
+    #[test]
+    fn non_multiples_are_rejected() {
+        assert_eq!(prove_and_verify(100, 1550), Err(ProofError::ValueOutOfRange));
+        assert_eq!(prove_and_verify(0, 0), Err(ProofError::InvalidStatement));
+        // The quotient does not fit in 16 bits.
+        assert_eq!(prove_and_verify(1, 1 << 16), Err(ProofError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn proof_does_not_verify_for_another_constant() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretValue::new(1500);
+        let v_blinding = Blinding::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"DivisibilityTest").unwrap();
+        let proof = DivisibilityProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            100,
+            &v,
+            &v_blinding,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"DivisibilityTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, 7, &V)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `elgamal` module contains ElGamal encryption of values over
+//! Ristretto, and a proof that a ciphertext encrypts the value of a
+//! Pedersen commitment.
+//!
+//! A value \\(v\\) is encrypted to the public key \\(P = x B\\) with a
+//! random \\(k\\) as \\((R, E) = (k B, v B + k P)\\).  The holder of
+//! \\(x\\) recovers \\(v B = E - x R\\), and from it small values by
+//! search.  An `EncryptionProof` shows that \\((R, E)\\) and a
+//! commitment \\(V = v B + r \widetilde{B}\\) hold the same \\(v\\),
+//! as a sigma proof of knowledge of \\((v, r, k)\\) with
+//! \\(V = v B + r \widetilde{B}\\), \\(R = k B\\) and \\(E = v B + k P\\).
+//!
+//! This lets a transaction carry its amounts encrypted to an auditor's
+//! view key, next to the commitments its range proofs are made for.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let k = SecretScalar::random(&mut rng);
+//! let ciphertext = ElGamalCiphertext::encrypt(&pg, &view_key, &v, &k);
+//!
+//! let mut transcript = ProofTranscript::new(b"Audit")?;
+//! let proof = EncryptionProof::prove(&pg, &mut transcript, &mut rng, &view_key, &v, &r, &k);
+//!
+//! let mut transcript = ProofTranscript::new(b"Audit")?;
+//! proof.verify(&pg, &mut transcript, &view_key, &V, &ciphertext)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar, SecretValue};
+use sigma::{SigmaProof, Statement};
+
+/// An ElGamal ciphertext \\((R, E) = (k B, v B + k P)\\) of a value
+/// \\(v\\) under the public key \\(P\\).
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ElGamalCiphertext {
+    /// The nonce commitment \\(R = k B\\)
+    pub R: RistrettoPoint,
+    /// The masked value \\(E = v B + k P\\)
+    pub E: RistrettoPoint,
+}
+
+impl ElGamalCiphertext {
+    /// Encrypts `value` to `public_key` with the nonce `k`, which must be
+    /// random and used only once.
+    pub fn encrypt(
+        pg: &PedersenGenerators,
+        public_key: &RistrettoPoint,
+        value: &SecretValue,
+        k: &SecretScalar,
+    ) -> ElGamalCiphertext {
+        ElGamalCiphertext {
+            R: pg.B * k.as_scalar(),
+            E: pg.B * value.to_scalar() + public_key * k.as_scalar(),
+        }
+    }
+
+    /// Decrypts the ciphertext with `secret_key`, returning \\(v B\\).
+    pub fn decrypt_point(&self, secret_key: &Scalar) -> RistrettoPoint {
+        self.E - self.R * secret_key
+    }
+
+    /// Decrypts the ciphertext with `secret_key`, returning the value if
+    /// it is less than `bound`.
+    ///
+    /// This searches the values one by one, so `bound` should be small.
+    pub fn decrypt(
+        &self,
+        pg: &PedersenGenerators,
+        secret_key: &Scalar,
+        bound: u64,
+    ) -> Option<u64> {
+        let target = self.decrypt_point(secret_key);
+        let mut point = RistrettoPoint::identity();
+        for v in 0..bound {
+            if point == target {
+                return Some(v);
+            }
+            point += pg.B;
+        }
+        None
+    }
+}
+
+/// A proof that an ElGamal ciphertext and a Pedersen commitment hold
+/// the same value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionProof {
+    /// Sigma proof of knowledge of the value, blinding factor and nonce
+    proof: SigmaProof,
+}
+
+impl EncryptionProof {
+    /// Proves that the encryption of `value` to `public_key` with the
+    /// nonce `k` holds the same value as its commitment with
+    /// `v_blinding`.
+    pub fn prove<R: Rng>(
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        public_key: &RistrettoPoint,
+        value: &SecretValue,
+        v_blinding: &Blinding,
+        k: &SecretScalar,
+    ) -> EncryptionProof {
+        let V = pg.commit(value.to_scalar(), *v_blinding.as_scalar());
+        let ciphertext = ElGamalCiphertext::encrypt(pg, public_key, value, k);
+
+        transcript.commit(b"ElGamalEncryption");
+        let statement = statement(pg, public_key, &V, &ciphertext);
+        let witness = [
+            SecretScalar::new(value.to_scalar()),
+            SecretScalar::new(*v_blinding.as_scalar()),
+            k.clone(),
+        ];
+        let proof = SigmaProof::prove(&statement, transcript, rng, &witness)
+            .expect("the witness matches the statement");
+        EncryptionProof { proof }
+    }
+
+    /// Verifies that `ciphertext`, under `public_key`, encrypts the value
+    /// committed in `V`.
+    pub fn verify(
+        &self,
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        public_key: &RistrettoPoint,
+        V: &RistrettoPoint,
+        ciphertext: &ElGamalCiphertext,
+    ) -> Result<(), ProofError> {
+        transcript.commit(b"ElGamalEncryption");
+        self.proof
+            .verify(&statement(pg, public_key, V, ciphertext), transcript)
+    }
+}
+
+/// Returns the statement \\(V = v B + r \widetilde{B} \wedge R = k B \wedge E = v B + k P\\)
+/// over the witness \\((v, r, k)\\).
+fn statement(
+    pg: &PedersenGenerators,
+    P: &RistrettoPoint,
+    V: &RistrettoPoint,
+    ciphertext: &ElGamalCiphertext,
+) -> Statement {
+    let O = RistrettoPoint::identity();
+    Statement::new(
+        vec![
+            vec![pg.B, pg.B_blinding, O],
+            vec![O, O, pg.B],
+            vec![pg.B, O, *P],
+        ],
+        vec![*V, ciphertext.R, ciphertext.E],
+    ).expect("the relations all have three bases")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn encryption_of_committed_value_verifies() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let secret_key = Scalar::random(&mut rng);
+        let public_key = pg.B * secret_key;
+
+        let v = SecretValue::new(42);
+        let r = Blinding::random(&mut rng);
+        let k = SecretScalar::random(&mut rng);
+        let V = pg.commit(v.to_scalar(), *r.as_scalar());
+        let ciphertext = ElGamalCiphertext::encrypt(&pg, &public_key, &v, &k);
+        assert_eq!(ciphertext.decrypt(&pg, &secret_key, 100), Some(42));
+        assert_eq!(ciphertext.decrypt(&pg, &secret_key, 10), None);
+
+        let mut transcript = ProofTranscript::new(b"ElGamalTest").unwrap();
+        let proof =
+            EncryptionProof::prove(&pg, &mut transcript, &mut rng, &public_key, &v, &r, &k);
+
+        let mut transcript = ProofTranscript::new(b"ElGamalTest").unwrap();
+        assert!(
+            proof
+                .verify(&pg, &mut transcript, &public_key, &V, &ciphertext)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn encryption_of_another_value_is_rejected() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let public_key = pg.B * Scalar::random(&mut rng);
+
+        let v = SecretValue::new(42);
+        let r = Blinding::random(&mut rng);
+        let k = SecretScalar::random(&mut rng);
+        let V = pg.commit(v.to_scalar(), *r.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"ElGamalTest").unwrap();
+        let proof =
+            EncryptionProof::prove(&pg, &mut transcript, &mut rng, &public_key, &v, &r, &k);
+
+        let other = ElGamalCiphertext::encrypt(&pg, &public_key, &SecretValue::new(43), &k);
+        let mut transcript = ProofTranscript::new(b"ElGamalTest").unwrap();
+        assert!(
+            proof
+                .verify(&pg, &mut transcript, &public_key, &V, &other)
+                .is_err()
+        );
+    }
+}
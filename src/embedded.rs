@@ -0,0 +1,376 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `embedded` module verifies small range proofs without heap
+//! allocation, for secure elements and microcontrollers.
+//!
+//! A `SmallRangeProof` holds a proof of a range of at most
+//! `MAX_BITSIZE` bits in fixed-size arrays, and `SmallGenerators` the
+//! generators for such proofs, which are the same as those of
+//! `Generators::new(domain, pedersen_generators, n, 1)`.  Verification replays
+//! the transcript into stack variables and sums the terms of the
+//! verification equation one scalar multiplication at a time, so it
+//! needs about 12KB of stack and touches no allocator; it is slower
+//! than `Verifier`, whose multiscalar multiplication allocates.
+//!
+//! The rest of the crate still links `alloc`, so a build for e.g.
+//! `thumbv7em-none-eabihf` uses `--no-default-features --features
+//! "u32_backend embedded"` with an allocator for the prover, or none if
+//! only this module is called.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let generators = SmallGenerators::new(b"MyApp", PedersenGenerators::default());
+//! let proof = SmallRangeProof::from_bytes(&bytes)?;
+//! let mut transcript = ProofTranscript::new(b"Device")?;
+//! proof.verify(&V, generators.share(32), &mut transcript, &mut rng)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+use subtle::{Choice, ConstantTimeEq};
+
+use ct;
+use errors::ProofError;
+use generators::{self, GeneratorsChain, GeneratorsView, PedersenGenerators};
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof;
+use range_proof_transcript::RangeProofTranscript;
+use util;
+
+/// The largest bitsize of the ranges of a `SmallRangeProof`.
+pub const MAX_BITSIZE: usize = 32;
+
+/// The largest number of rounds of the inner-product argument of a
+/// `SmallRangeProof`.
+const MAX_ROUNDS: usize = 5;
+
+/// The generators for range proofs of up to `MAX_BITSIZE` bits.
+#[derive(Clone)]
+pub struct SmallGenerators {
+    /// Bases for Pedersen commitments
+    pedersen_generators: PedersenGenerators,
+    /// Per-bit generators for the bit values
+    G: [RistrettoPoint; MAX_BITSIZE],
+    /// Per-bit generators for the bit blinding factors
+    H: [RistrettoPoint; MAX_BITSIZE],
+}
+
+impl SmallGenerators {
+    /// Derives the generators for the application `domain` from
+    /// `pedersen_generators`, as `Generators::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `domain` is empty.
+    pub fn new(domain: &[u8], pedersen_generators: PedersenGenerators) -> Self {
+        generators::check_domain(domain);
+        let G_chain = GeneratorsChain::with_domain(
+            domain,
+            pedersen_generators.B.compress().as_bytes(),
+        );
+        let H_chain = GeneratorsChain::with_domain(
+            domain,
+            pedersen_generators.B_blinding.compress().as_bytes(),
+        );
+        SmallGenerators::from_chains(pedersen_generators, G_chain, H_chain)
+    }
+
+    /// Derives the generators from `pedersen_generators` alone, as
+    /// `Generators::without_domain`.
+    pub fn without_domain(pedersen_generators: PedersenGenerators) -> Self {
+        let G_chain = GeneratorsChain::new(pedersen_generators.B.compress().as_bytes());
+        let H_chain = GeneratorsChain::new(pedersen_generators.B_blinding.compress().as_bytes());
+        SmallGenerators::from_chains(pedersen_generators, G_chain, H_chain)
+    }
+
+    fn from_chains(
+        pedersen_generators: PedersenGenerators,
+        G_chain: GeneratorsChain,
+        H_chain: GeneratorsChain,
+    ) -> Self {
+        let mut G = [RistrettoPoint::identity(); MAX_BITSIZE];
+        let mut H = [RistrettoPoint::identity(); MAX_BITSIZE];
+        for (G_i, P) in G.iter_mut().zip(G_chain) {
+            *G_i = P;
+        }
+        for (H_i, P) in H.iter_mut().zip(H_chain) {
+            *H_i = P;
+        }
+        SmallGenerators {
+            pedersen_generators,
+            G,
+            H,
+        }
+    }
+
+    /// Returns the generators for `n`-bit range proofs.
+    ///
+    /// Panics if `n` exceeds `MAX_BITSIZE`.
+    pub fn share(&self, n: usize) -> GeneratorsView {
+        assert!(n <= MAX_BITSIZE, "n must be at most MAX_BITSIZE");
+        GeneratorsView {
+            pedersen_generators: &self.pedersen_generators,
+            G: &self.G[..n],
+            H: &self.H[..n],
+        }
+    }
+}
+
+/// A range proof of at most `MAX_BITSIZE` bits, in fixed-size arrays.
+#[derive(Copy, Clone, Debug)]
+pub struct SmallRangeProof {
+    /// Commitment to the bits of the value
+    A: RistrettoPoint,
+    /// Commitment to the blinding factors
+    S: RistrettoPoint,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    T_1: RistrettoPoint,
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    T_2: RistrettoPoint,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    e_blinding: Scalar,
+    /// The number \\(\lg n\\) of rounds of the inner-product argument
+    rounds: usize,
+    /// The points \\(L\_j\\), in creation order, of which `rounds` are used
+    L: [RistrettoPoint; MAX_ROUNDS],
+    /// The points \\(R\_j\\), in creation order, of which `rounds` are used
+    R: [RistrettoPoint; MAX_ROUNDS],
+    /// The final scalar \\(a\\) of the inner-product argument
+    a: Scalar,
+    /// The final scalar \\(b\\) of the inner-product argument
+    b: Scalar,
+}
+
+impl SmallRangeProof {
+    /// Deserializes a proof in the encoding of `RangeProof::to_bytes`.
+    ///
+    /// Returns an error if the length is wrong or the range has more
+    /// than `MAX_BITSIZE` bits, or if a point or scalar is not a
+    /// canonical encoding.
+    pub fn from_bytes(slice: &[u8]) -> Result<SmallRangeProof, ProofError> {
+        if slice.len() < 9 * 32 || (slice.len() - 9 * 32) % 64 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let rounds = (slice.len() - 9 * 32) / 64;
+        if rounds > MAX_ROUNDS {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let chunk = |i: usize| &slice[i * 32..(i + 1) * 32];
+        let point = |i: usize| util::read_point(chunk(i));
+        let scalar = |i: usize| util::read_scalar(chunk(i));
+
+        let mut L = [RistrettoPoint::identity(); MAX_ROUNDS];
+        let mut R = [RistrettoPoint::identity(); MAX_ROUNDS];
+        for (j, (L_j, R_j)) in L.iter_mut().zip(R.iter_mut()).enumerate().take(rounds) {
+            *L_j = point(7 + 2 * j)?;
+            *R_j = point(8 + 2 * j)?;
+        }
+        Ok(SmallRangeProof {
+            A: point(0)?,
+            S: point(1)?,
+            T_1: point(2)?,
+            T_2: point(3)?,
+            t_x: scalar(4)?,
+            t_x_blinding: scalar(5)?,
+            e_blinding: scalar(6)?,
+            rounds,
+            L,
+            R,
+            a: scalar(7 + 2 * rounds)?,
+            b: scalar(8 + 2 * rounds)?,
+        })
+    }
+
+    /// Returns the bitsize \\(n\\) of the range.
+    pub fn n(&self) -> usize {
+        1 << self.rounds
+    }
+
+    /// Verifies the proof for the value commitment \\(V\\), with the
+    /// same result as `Verifier::verify`.
+    ///
+    /// Returns an error if `generators` are not for the proof's bitsize.
+    pub fn verify<R: Rng>(
+        &self,
+        V: &RistrettoPoint,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let n = self.n();
+        ProofParams::single(n)?.check_share(&generators)?;
+
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &self.A, &self.S);
+        let zz = z * z;
+        let minus_z = -z;
+        let (transcript, x) = transcript.commit_poly(&self.T_1, &self.T_2);
+        let (transcript, w) =
+            transcript.commit_evaluation(&self.t_x, &self.t_x_blinding, &self.e_blinding);
+
+        let mut u = [Scalar::zero(); MAX_ROUNDS];
+        let mut u_inv = [Scalar::zero(); MAX_ROUNDS];
+        for (j, (L_j, R_j)) in self.L.iter().zip(self.R.iter()).enumerate().take(self.rounds) {
+            transcript.commit(L_j.compress().as_bytes());
+            transcript.commit(R_j.compress().as_bytes());
+            u[j] = transcript.challenge_scalar_labeled(b"u");
+            u_inv[j] = u[j].invert();
+        }
+
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+
+        let mut check = &self.A + &(&self.S * &x);
+        check += &(V * &(c * zz));
+        check += &(&self.T_1 * &(c * x));
+        check += &(&self.T_2 * &(c * x * x));
+
+        let y_inv = y.invert();
+        let mut y_i = Scalar::one();
+        let mut y_inv_i = Scalar::one();
+        let mut two_i = Scalar::one();
+        let mut sum_of_powers_of_y = Scalar::zero();
+        for (i, (G_i, H_i)) in generators.G.iter().zip(generators.H.iter()).enumerate() {
+            let s_i = self.s(&u, &u_inv, i);
+            let s_i_inv = self.s(&u, &u_inv, n - 1 - i);
+            let g_i = minus_z - self.a * s_i;
+            let h_i = z + y_inv_i * (zz * two_i - self.b * s_i_inv);
+            check += &(G_i * &g_i);
+            check += &(H_i * &h_i);
+
+            sum_of_powers_of_y += y_i;
+            y_i *= y;
+            y_inv_i *= y_inv;
+            two_i = two_i + two_i;
+        }
+
+        let rounds = self.L.iter().zip(self.R.iter()).zip(u.iter().zip(u_inv.iter()));
+        for ((L_j, R_j), (u_j, u_inv_j)) in rounds.take(self.rounds) {
+            check += &(L_j * &(u_j * u_j));
+            check += &(R_j * &(u_inv_j * u_inv_j));
+        }
+
+        let sum_of_powers_of_2 = Scalar::from_u64((1u64 << n) - 1);
+        let delta = range_proof::delta_from_sums(&sum_of_powers_of_y, &sum_of_powers_of_2, &z);
+        let B_scalar = w * (self.t_x - self.a * self.b) + c * (delta - self.t_x);
+        let B_blinding_scalar = -self.e_blinding - c * self.t_x_blinding;
+        check += &(&generators.pedersen_generators.B * &B_scalar);
+        check += &(&generators.pedersen_generators.B_blinding * &B_blinding_scalar);
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Computes the inner-product verification scalar \\(s\_i\\), the
+    /// product over the rounds \\(j\\) of \\(u\_j\\) if bit \\(j - 1\\)
+    /// of \\(i\\) is set, and of \\(u\_j^{-1}\\) otherwise.
+    fn s(&self, u: &[Scalar], u_inv: &[Scalar], i: usize) -> Scalar {
+        let mut s_i = Scalar::one();
+        for bit in 0..self.rounds {
+            // u_{bit+1} is indexed by (rounds-1) - bit in creation order.
+            let j = (self.rounds - 1) - bit;
+            if (i >> bit) & 1 == 1 {
+                s_i *= u[j];
+            } else {
+                s_i *= u_inv[j];
+            }
+        }
+        s_i
+    }
+}
+
+impl ConstantTimeEq for SmallRangeProof {
+    fn ct_eq(&self, other: &SmallRangeProof) -> Choice {
+        // The number of rounds is public, so it is fine to exit early on it.
+        if self.rounds != other.rounds {
+            return Choice::from(0);
+        }
+        let rounds = self.rounds;
+        self.A.ct_eq(&other.A)
+            & self.S.ct_eq(&other.S)
+            & self.T_1.ct_eq(&other.T_1)
+            & self.T_2.ct_eq(&other.T_2)
+            & self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+            & self.a.ct_eq(&other.a)
+            & self.b.ct_eq(&other.b)
+            & ct::slices_eq(&self.L[..rounds], &other.L[..rounds])
+            & ct::slices_eq(&self.R[..rounds], &other.R[..rounds])
+    }
+}
+
+impl PartialEq for SmallRangeProof {
+    fn eq(&self, other: &SmallRangeProof) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for SmallRangeProof {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+    use range_proof::RangeProof;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn small_proofs_verify_without_allocating() {
+        let small_generators = SmallGenerators::new(b"Test", PedersenGenerators::default());
+        let mut rng = OsRng::new().unwrap();
+
+        for &n in [8, 16, 32].iter() {
+            let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+            assert_eq!(small_generators.share(n).G, generators.share(0).G);
+
+            let v_blinding = Scalar::random(&mut rng);
+            let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(5), v_blinding);
+            let mut transcript = ProofTranscript::new(b"EmbeddedTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(5),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+
+            let bytes = proof.to_bytes();
+            let small = SmallRangeProof::from_bytes(&bytes).unwrap();
+            assert_eq!(small.n(), n);
+            let mut transcript = ProofTranscript::new(b"EmbeddedTest").unwrap();
+            assert!(
+                small
+                    .verify(&V, small_generators.share(n), &mut transcript, &mut rng)
+                    .is_ok()
+            );
+
+            let wrong_V = V + generators.share(0).pedersen_generators.B;
+            let mut transcript = ProofTranscript::new(b"EmbeddedTest").unwrap();
+            assert!(
+                small
+                    .verify(&wrong_V, small_generators.share(n), &mut transcript, &mut rng)
+                    .is_err()
+            );
+
+            // The fixed-size buffers take only whole proofs.
+            assert!(SmallRangeProof::from_bytes(&bytes[..bytes.len() - 32]).is_err());
+            assert!(SmallRangeProof::from_bytes(&bytes[..8 * 32]).is_err());
+        }
+    }
+}
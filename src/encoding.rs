@@ -0,0 +1,188 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `encoding` module contains text encodings of proofs and
+//! commitments, for APIs and logs.
+//!
+//! `RangeProof` and `InnerProductProof` implement `Display` and
+//! `FromStr` as lowercase hex of their `to_bytes` encoding; parsing
+//! also accepts uppercase hex.  Commitments are encoded as hex of their
+//! 32-byte compressed form with `commitment_to_hex` and
+//! `commitment_from_hex`.
+//!
+//! With the `base64` feature, the same bytes can also be encoded in
+//! standard base64 with padding.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let text = proof.to_string();
+//! let proof: RangeProof = text.parse()?;
+//! let V = encoding::commitment_from_hex(&encoding::commitment_to_hex(&V))?;
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "base64")]
+use base64;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use errors::ProofError;
+use inner_product_proof::InnerProductProof;
+use range_proof::RangeProof;
+use util;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encodes `bytes` as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 * bytes.len());
+    for byte in bytes.iter() {
+        s.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        s.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+    }
+    s
+}
+
+/// Decodes hex, in either case, returning `FormatError` if `s` has an
+/// odd length or a character that is not a hex digit.
+pub fn from_hex(s: &str) -> Result<Vec<u8>, ProofError> {
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 {
+        return Err(ProofError::FormatError);
+    }
+    s.chunks(2)
+        .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Result<u8, ProofError> {
+    match c {
+        b'0'...b'9' => Ok(c - b'0'),
+        b'a'...b'f' => Ok(c - b'a' + 10),
+        b'A'...b'F' => Ok(c - b'A' + 10),
+        _ => Err(ProofError::FormatError),
+    }
+}
+
+fn write_hex(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    for byte in bytes.iter() {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+/// Encodes the commitment `V` as hex of its compressed form.
+pub fn commitment_to_hex(V: &RistrettoPoint) -> String {
+    to_hex(V.compress().as_bytes())
+}
+
+/// Decodes a commitment encoded with `commitment_to_hex`.
+pub fn commitment_from_hex(s: &str) -> Result<RistrettoPoint, ProofError> {
+    util::read_point(&from_hex(s)?)
+}
+
+/// Encodes the commitment `V` as base64 of its compressed form.
+#[cfg(feature = "base64")]
+pub fn commitment_to_base64(V: &RistrettoPoint) -> String {
+    base64::encode(V.compress().as_bytes())
+}
+
+/// Decodes a commitment encoded with `commitment_to_base64`.
+#[cfg(feature = "base64")]
+pub fn commitment_from_base64(s: &str) -> Result<RistrettoPoint, ProofError> {
+    util::read_point(&base64::decode(s).map_err(|_| ProofError::FormatError)?)
+}
+
+impl fmt::Display for RangeProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_hex(f, &self.to_bytes())
+    }
+}
+
+impl FromStr for RangeProof {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, ProofError> {
+        RangeProof::from_bytes(&from_hex(s)?)
+    }
+}
+
+impl fmt::Display for InnerProductProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_hex(f, &self.to_bytes())
+    }
+}
+
+impl FromStr for InnerProductProof {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, ProofError> {
+        InnerProductProof::from_bytes(&from_hex(s)?)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl RangeProof {
+    /// Encodes the proof as base64 of its `to_bytes` encoding.
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.to_bytes())
+    }
+
+    /// Decodes a proof encoded with `to_base64`.
+    pub fn from_base64(s: &str) -> Result<RangeProof, ProofError> {
+        RangeProof::from_bytes(&base64::decode(s).map_err(|_| ProofError::FormatError)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn proofs_and_commitments_round_trip_through_hex() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(200), v_blinding);
+        let mut transcript = ProofTranscript::new(b"EncodingTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(200),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let text = proof.to_string();
+        assert_eq!(text.len(), 2 * 32 * (9 + 2 * 3));
+        let parsed: RangeProof = text.to_uppercase().parse().unwrap();
+        assert_eq!(parsed.to_bytes(), proof.to_bytes());
+        assert!("not hex".parse::<RangeProof>().is_err());
+        assert!(format!("{}0", text).parse::<RangeProof>().is_err());
+        assert!(format!("{}00", text).parse::<RangeProof>().is_err());
+        assert!(text[..text.len() - 64].parse::<RangeProof>().is_err());
+
+        assert_eq!(commitment_from_hex(&commitment_to_hex(&V)), Ok(V));
+        assert!(commitment_from_hex(&text[..64]).is_ok());
+        assert!(commitment_from_hex(&text[..62]).is_err());
+        assert!(commitment_from_hex(&text[..66]).is_err());
+    }
+}
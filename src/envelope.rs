@@ -0,0 +1,239 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `envelope` module contains a versioned container for serialized
+//! range proofs, so that the format can change without breaking
+//! deployed verifiers.
+//!
+//! An envelope starts with a version byte, which selects the layout of
+//! the rest.  Decoding dispatches on it and rejects versions it does not
+//! know, so an old verifier fails cleanly on a proof of a newer format
+//! instead of misreading it.  Version 1 is:
+//!
+//! | bytes | field                                              |
+//! |-------|----------------------------------------------------|
+//! | 1     | the version, 1                                     |
+//! | 1     | the bitsize \\(n\\) of the ranges                  |
+//! | 4     | the number of proofs \\(m\\), little-endian        |
+//! | rest  | the \\(m\\) proofs in the encoding of `RangeProof::to_bytes` |
+//!
+//! Every proof of an \\(n\\)-bit range is \\(32 (9 + 2 \lg n)\\) bytes
+//! long, so the proofs need no length prefixes.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let bytes = ProofEnvelope::new(64, vec![proof])?.to_bytes();
+//! let envelope = ProofEnvelope::from_bytes(&bytes)?;
+//! assert_eq!(envelope.n(), 64);
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, LittleEndian};
+use subtle::{Choice, ConstantTimeEq};
+
+use ct;
+use errors::ProofError;
+use params::{Limits, ProofParams};
+use range_proof::RangeProof;
+use sizes;
+
+/// The version written by `ProofEnvelope::to_bytes`.
+pub const CURRENT_VERSION: u8 = 1;
+
+/// The length of the version 1 header.
+pub(crate) const V1_HEADER_LEN: usize = 6;
+
+/// Range proofs of one bitsize, with their parameters.
+#[derive(Clone, Debug)]
+pub struct ProofEnvelope {
+    /// The bitsize of the ranges
+    pub(crate) n: usize,
+    /// The proofs
+    pub(crate) proofs: Vec<RangeProof>,
+}
+
+impl ProofEnvelope {
+    /// Wraps `proofs` of `n`-bit ranges.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not a power of two of at most
+    /// 64 or if a proof was not made for `n` bits, and `TooLarge` if
+    /// there are more proofs than the version 1 header can count.
+    pub fn new(n: usize, proofs: Vec<RangeProof>) -> Result<Self, ProofError> {
+        ProofParams::single(n)?;
+        if proofs.iter().any(|p| p.to_bytes().len() != proof_len(n)) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if proofs.len() > u32::max_value() as usize {
+            return Err(ProofError::TooLarge);
+        }
+        Ok(ProofEnvelope { n, proofs })
+    }
+
+    /// Returns the bitsize of the ranges.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the proofs.
+    pub fn proofs(&self) -> &[RangeProof] {
+        &self.proofs
+    }
+
+    /// Returns the proofs, consuming the envelope.
+    pub fn into_proofs(self) -> Vec<RangeProof> {
+        self.proofs
+    }
+
+    /// Serializes the envelope in the format of `CURRENT_VERSION`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(V1_HEADER_LEN + self.proofs.len() * proof_len(self.n));
+        buf.push(CURRENT_VERSION);
+        buf.push(self.n as u8);
+        let mut m = [0u8; 4];
+        LittleEndian::write_u32(&mut m, self.proofs.len() as u32);
+        buf.extend_from_slice(&m);
+        for proof in self.proofs.iter() {
+            buf.extend_from_slice(&proof.to_bytes());
+        }
+        buf
+    }
+
+    /// Deserializes an envelope of any supported version.
+    ///
+    /// Returns an error if the version is unknown or the contents are
+    /// malformed.
+    pub fn from_bytes(slice: &[u8]) -> Result<ProofEnvelope, ProofError> {
+        ProofEnvelope::from_bytes_with_limits(slice, &Limits::default())
+    }
+
+    /// Deserializes an envelope as `from_bytes`, returning `TooLarge` if
+    /// its bitsize and number of proofs exceed `limits`.
+    pub fn from_bytes_with_limits(
+        slice: &[u8],
+        limits: &Limits,
+    ) -> Result<ProofEnvelope, ProofError> {
+        match slice.first() {
+            Some(&1) => ProofEnvelope::from_bytes_v1(slice, limits),
+            _ => Err(ProofError::FormatError),
+        }
+    }
+
+    fn from_bytes_v1(slice: &[u8], limits: &Limits) -> Result<ProofEnvelope, ProofError> {
+        if slice.len() < V1_HEADER_LEN {
+            return Err(ProofError::FormatError);
+        }
+        let n = slice[1] as usize;
+        let m = LittleEndian::read_u32(&slice[2..V1_HEADER_LEN]) as usize;
+        ProofParams::single(n)?;
+        limits.check(n, m)?;
+        let payload = &slice[V1_HEADER_LEN..];
+        let len = proof_len(n);
+        if payload.len() / len != m || payload.len() % len != 0 {
+            return Err(ProofError::FormatError);
+        }
+
+        let proofs = payload
+            .chunks(len)
+            .map(RangeProof::from_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ProofEnvelope { n, proofs })
+    }
+}
+
+/// Returns the length of the encoding of a proof of an `n`-bit range.
+fn proof_len(n: usize) -> usize {
+    sizes::range_proof_size(n)
+}
+
+impl ConstantTimeEq for ProofEnvelope {
+    fn ct_eq(&self, other: &ProofEnvelope) -> Choice {
+        // The bitsize is public, so it is fine to exit early on it.
+        if self.n != other.n {
+            return Choice::from(0);
+        }
+        ct::slices_eq(&self.proofs, &other.proofs)
+    }
+}
+
+impl PartialEq for ProofEnvelope {
+    fn eq(&self, other: &ProofEnvelope) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for ProofEnvelope {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    fn proofs(n: usize, count: u64) -> Vec<RangeProof> {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        (0..count)
+            .map(|v| {
+                let v_blinding = Scalar::random(&mut rng);
+                let mut transcript = ProofTranscript::new(b"EnvelopeTest").unwrap();
+                RangeProof::generate_proof(
+                    generators.share(0),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                    &SecretValue::new(v),
+                    &Blinding::new(v_blinding),
+                ).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn envelopes_round_trip() {
+        let envelope = ProofEnvelope::new(16, proofs(16, 3)).unwrap();
+        let bytes = envelope.to_bytes();
+        assert_eq!(bytes[0], CURRENT_VERSION);
+        assert_eq!(bytes[1], 16);
+        assert_eq!(&bytes[2..6], &[3, 0, 0, 0]);
+
+        let decoded = ProofEnvelope::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.n(), 16);
+        assert_eq!(decoded.proofs().len(), 3);
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(ProofEnvelope::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn unknown_versions_and_bad_lengths_are_rejected() {
+        let bytes = ProofEnvelope::new(8, proofs(8, 2)).unwrap().to_bytes();
+
+        let mut future = bytes.clone();
+        future[0] = 2;
+        assert!(ProofEnvelope::from_bytes(&future).is_err());
+        assert!(ProofEnvelope::from_bytes(&[]).is_err());
+
+        // A different bitsize changes the proof length.
+        let mut wrong_n = bytes.clone();
+        wrong_n[1] = 16;
+        assert!(ProofEnvelope::from_bytes(&wrong_n).is_err());
+
+        // The count must match the payload.
+        let mut wrong_m = bytes.clone();
+        wrong_m[2] = 3;
+        assert!(ProofEnvelope::from_bytes(&wrong_m).is_err());
+        assert!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 32]).is_err());
+
+        // Proofs of another bitsize cannot be wrapped either.
+        assert_eq!(ProofEnvelope::new(16, proofs(8, 1)).unwrap_err(), ProofError::InvalidBitsize);
+        assert_eq!(ProofEnvelope::new(24, vec![]).unwrap_err(), ProofError::InvalidBitsize);
+    }
+}
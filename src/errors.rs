@@ -0,0 +1,158 @@
+#![deny(missing_docs)]
+
+//! The `errors` module contains `ProofError`, the error type of range
+//! proofs, the inner-product argument, the generators, the sigma
+//! protocols, the statements built on them and their encodings.
+//!
+//! Errors of the constraint system proofs are the `R1CSError`s of the
+//! `r1cs` module, which convert into `ProofError::R1CS`, so code that
+//! combines both kinds of proofs can use `?` with a single error type.
+//! With the `std` feature both implement `std::error::Error`, and the
+//! cause of a `ProofError::R1CS` is the underlying `R1CSError`.
+
+use core::fmt;
+
+use r1cs::R1CSError;
+
+/// Represents an error in decoding or verifying a proof.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// The proof does not verify.
+    VerificationError,
+    /// An encoding has the wrong length or structure.
+    FormatError,
+    /// An encoded point does not decompress to a Ristretto point.
+    InvalidPoint,
+    /// An encoded scalar is not reduced modulo the group order.
+    InvalidScalar,
+    /// The bitsize is not a power of two of at most 64, or does not
+    /// match the proof.
+    InvalidBitsize,
+    /// The generators are too short for the proof, or were made for
+    /// proofs of another size.
+    InvalidGeneratorsLength,
+    /// The number of commitments does not match the number of proofs.
+    WrongNumCommitments,
+    /// A value to prove does not satisfy the statement, such as a
+    /// value that is not less than \\(2^n\\).
+    ValueOutOfRange,
+    /// An opening given to a prover does not match its commitment.
+    InvalidOpening,
+    /// The public part of a statement is malformed, such as a set that
+    /// is not sorted or a divisor of zero.
+    InvalidStatement,
+    /// The number of proofs is zero or larger than
+    /// `params::MAX_PROOFS`.
+    InvalidAggregation,
+    /// An encoding claims sizes beyond the configured `params::Limits`.
+    TooLarge,
+    /// An integrity hash does not match the data it covers.
+    IntegrityError,
+    /// A strict verifier rejected a proof with an identity point or a
+    /// zero challenge.
+    DegenerateProof,
+    /// The memory for a verification could not be allocated.
+    OutOfMemory,
+    /// A transcript was begun with an empty domain label.
+    EmptyLabel,
+    /// A constraint system proof failed.
+    R1CS(R1CSError),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProofError::R1CS(ref e) => write!(f, "constraint system proof failed: {}", e),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl ProofError {
+    /// Returns a short description of the error.
+    fn message(&self) -> &'static str {
+        match *self {
+            ProofError::VerificationError => "proof verification failed",
+            ProofError::FormatError => "invalid encoding",
+            ProofError::InvalidPoint => "invalid point encoding",
+            ProofError::InvalidScalar => "non-canonical scalar encoding",
+            ProofError::InvalidBitsize => "invalid bitsize",
+            ProofError::InvalidGeneratorsLength => "invalid generators length",
+            ProofError::WrongNumCommitments => "wrong number of commitments",
+            ProofError::ValueOutOfRange => "value out of range",
+            ProofError::InvalidOpening => "opening does not match the commitment",
+            ProofError::InvalidStatement => "invalid statement",
+            ProofError::InvalidAggregation => "invalid number of proofs",
+            ProofError::TooLarge => "claimed size exceeds the limits",
+            ProofError::IntegrityError => "integrity hash mismatch",
+            ProofError::DegenerateProof => "degenerate proof",
+            ProofError::OutOfMemory => "out of memory",
+            ProofError::EmptyLabel => "empty domain label",
+            ProofError::R1CS(_) => "constraint system proof failed",
+        }
+    }
+}
+
+impl From<R1CSError> for ProofError {
+    fn from(e: R1CSError) -> ProofError {
+        ProofError::R1CS(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for ProofError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            ProofError::R1CS(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for R1CSError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl R1CSError {
+    /// Returns a short description of the error.
+    fn message(&self) -> &'static str {
+        match *self {
+            R1CSError::InvalidGeneratorsLength => "invalid generators length",
+            R1CSError::InvalidBitsize => "invalid bitsize",
+            R1CSError::MissingAssignment => "missing variable assignment",
+            R1CSError::VerificationError => "proof verification failed",
+            R1CSError::NestedRandomizedConstraints => "nested randomized constraints",
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for R1CSError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn errors_chain_to_their_cause() {
+        let e = ProofError::from(R1CSError::MissingAssignment);
+        assert_eq!(
+            e.to_string(),
+            "constraint system proof failed: missing variable assignment"
+        );
+        assert_eq!(e.cause().unwrap().to_string(), "missing variable assignment");
+        assert!(ProofError::FormatError.cause().is_none());
+        assert_eq!(ProofError::VerificationError.to_string(), "proof verification failed");
+    }
+}
@@ -0,0 +1,668 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `ffi` module contains a C interface to range proofs and proof
+//! bundles, for use from languages that can call C functions.
+//!
+//! Generators, proofs and bundles are opaque handles, allocated by this
+//! library and released with the matching `_free` function.  Every
+//! function returns a `BpStatus`, and writes its results through out
+//! pointers only when it returns `BpStatus::Ok`.  Panics are caught at
+//! the boundary and reported as `BpStatus::Panic`.
+//!
+//! Values are `uint64_t`, blinding factors are 32-byte little-endian
+//! scalars, and commitments are 32-byte compressed Ristretto points.
+//! Proofs are encoded with `bincode`, or split into the fixed-layout
+//! `BpRangeProofParts`, which does not depend on the Rust layout of the
+//! proof types.  Randomness comes from the operating system.
+//!
+//! The declarations are in `include/bulletproofs.h`.  A shared library
+//! is built with
+//!
+//! ```ascii
+//! cargo rustc --release --features ffi -- --crate-type cdylib
+//! ```
+//!
+//! # Safety
+//!
+//! Pointers must be valid for the lengths given with them, or point
+//! to 32 bytes for scalars and points.  A pointer with a length of zero
+//! may be null.  Handles must come from this library and must not be
+//! used after they are freed.
+//!
+//! # Example
+//!
+//! ```ascii
+//! bp_generators *gens;
+//! bp_range_proof *proof;
+//! uint8_t V[32];
+//! bp_generators_new(domain, domain_len, 64, &gens);
+//! bp_commit(gens, value, blinding, V);
+//! bp_prove(gens, label, label_len, value, blinding, &proof);
+//! if (bp_verify(gens, label, label_len, proof, V) == BP_OK) { ... }
+//! bp_range_proof_free(proof);
+//! bp_generators_free(gens);
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use bincode;
+use rand::OsRng;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use generators::{Generators, PedersenGenerators};
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier};
+use secret::{Blinding, SecretValue};
+
+/// The result of a call through the C interface.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BpStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was null
+    NullPointer = 1,
+    /// An argument is outside the accepted range
+    InvalidArgument = 2,
+    /// Bytes could not be decoded as a point or a proof
+    InvalidEncoding = 3,
+    /// The proof is not valid for the statement
+    VerificationFailed = 4,
+    /// The output buffer is too small; the needed size was written
+    BufferTooSmall = 5,
+    /// The operating system random number generator is unavailable
+    RngUnavailable = 6,
+    /// The library panicked, which is a bug
+    Panic = 7,
+}
+
+/// Generators for `n`-bit range proofs.
+pub struct BpGenerators {
+    generators: Generators,
+    n: usize,
+}
+
+/// A range proof.
+pub struct BpRangeProof(RangeProof);
+
+/// A bundle of range proofs bound to one transcript.
+pub struct BpProofBundle(ProofBundle);
+
+/// Creates generators for `n`-bit range proofs for the application
+/// `domain`, with the default Pedersen generators.  `n` must be a power
+/// of two of at most 64, and `domain` must not be empty.
+#[no_mangle]
+pub unsafe extern "C" fn bp_generators_new(
+    domain: *const u8,
+    domain_len: usize,
+    n: usize,
+    out: *mut *mut BpGenerators,
+) -> BpStatus {
+    guard(|| {
+        let domain = read_label(domain, domain_len)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let params = ProofParams::new(n, 1).map_err(|_| BpStatus::InvalidArgument)?;
+        let generators = Generators::with_params(domain, PedersenGenerators::default(), &params);
+        *out = Box::into_raw(Box::new(BpGenerators { generators, n }));
+        Ok(())
+    })
+}
+
+/// Frees generators created by `bp_generators_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_generators_free(gens: *mut BpGenerators) {
+    if !gens.is_null() {
+        drop(Box::from_raw(gens));
+    }
+}
+
+/// Writes the commitment to `value` with the 32-byte `blinding` to the
+/// 32 bytes at `out`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_commit(
+    gens: *const BpGenerators,
+    value: u64,
+    blinding: *const u8,
+    out: *mut u8,
+) -> BpStatus {
+    guard(|| {
+        let gens = read_handle(gens)?;
+        let v_blinding = read_scalar(blinding)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let V = gens
+            .generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(value), v_blinding);
+        ptr::copy_nonoverlapping(V.compress().as_bytes().as_ptr(), out, 32);
+        Ok(())
+    })
+}
+
+/// Proves that `value`, committed with `blinding`, fits in the bitsize
+/// of `gens`, in a transcript labeled with `label`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_prove(
+    gens: *const BpGenerators,
+    label: *const u8,
+    label_len: usize,
+    value: u64,
+    blinding: *const u8,
+    out: *mut *mut BpRangeProof,
+) -> BpStatus {
+    guard(|| {
+        let gens = read_handle(gens)?;
+        let label = read_label(label, label_len)?;
+        let v_blinding = Blinding::new(read_scalar(blinding)?);
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        check_value(gens, value)?;
+        let mut rng = OsRng::new().map_err(|_| BpStatus::RngUnavailable)?;
+
+        let mut transcript = ProofTranscript::new(label).map_err(|_| BpStatus::InvalidArgument)?;
+        let proof = RangeProof::generate_proof(
+            gens.generators.share(0),
+            &mut transcript,
+            &mut rng,
+            gens.n,
+            &SecretValue::new(value),
+            &v_blinding,
+        ).map_err(|_| BpStatus::InvalidArgument)?;
+        *out = Box::into_raw(Box::new(BpRangeProof(proof)));
+        Ok(())
+    })
+}
+
+/// Verifies `proof` for the 32-byte compressed `commitment`, in a
+/// transcript labeled with `label`.
+///
+/// Returns `BP_VERIFICATION_FAILED` if the proof is invalid.
+#[no_mangle]
+pub unsafe extern "C" fn bp_verify(
+    gens: *const BpGenerators,
+    label: *const u8,
+    label_len: usize,
+    proof: *const BpRangeProof,
+    commitment: *const u8,
+) -> BpStatus {
+    guard(|| {
+        let gens = read_handle(gens)?;
+        let label = read_label(label, label_len)?;
+        let proof = read_handle(proof)?;
+        let V = read_point(commitment)?;
+        let mut rng = OsRng::new().map_err(|_| BpStatus::RngUnavailable)?;
+
+        let mut transcript = ProofTranscript::new(label).map_err(|_| BpStatus::InvalidArgument)?;
+        Verifier::new(gens.generators.share(0), gens.n)
+            .map_err(|_| BpStatus::InvalidArgument)?
+            .verify(&proof.0, &V, &mut transcript, &mut rng)
+            .map_err(|_| BpStatus::VerificationFailed)
+    })
+}
+
+/// Proves that each of the `count` values in `values`, committed with
+/// the corresponding 32 bytes of `blindings`, fits in the bitsize of
+/// `gens`, as a bundle in a transcript labeled with `label`.
+///
+/// The commitments are computed with `bp_commit`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_prove_many(
+    gens: *const BpGenerators,
+    label: *const u8,
+    label_len: usize,
+    values: *const u64,
+    blindings: *const u8,
+    count: usize,
+    out: *mut *mut BpProofBundle,
+) -> BpStatus {
+    guard(|| {
+        let gens = read_handle(gens)?;
+        let label = read_label(label, label_len)?;
+        if out.is_null() || (count > 0 && (values.is_null() || blindings.is_null())) {
+            return Err(BpStatus::NullPointer);
+        }
+        let mut statements = Vec::with_capacity(count);
+        for i in 0..count {
+            let value = *values.offset(i as isize);
+            check_value(gens, value)?;
+            let blinding = Blinding::new(read_scalar(blindings.offset(32 * i as isize))?);
+            statements.push((SecretValue::new(value), blinding));
+        }
+        let mut rng = OsRng::new().map_err(|_| BpStatus::RngUnavailable)?;
+
+        let mut transcript = ProofTranscript::new(label).map_err(|_| BpStatus::InvalidArgument)?;
+        let (bundle, _) = ProofBundle::prove_many(
+            gens.generators.share(0),
+            &mut transcript,
+            &mut rng,
+            gens.n,
+            &statements,
+        ).map_err(|_| BpStatus::InvalidArgument)?;
+        *out = Box::into_raw(Box::new(BpProofBundle(bundle)));
+        Ok(())
+    })
+}
+
+/// Verifies `bundle` for the `count` 32-byte compressed commitments in
+/// `commitments`, in a transcript labeled with `label`.
+///
+/// Returns `BP_VERIFICATION_FAILED` if the bundle is invalid.
+#[no_mangle]
+pub unsafe extern "C" fn bp_verify_many(
+    gens: *const BpGenerators,
+    label: *const u8,
+    label_len: usize,
+    bundle: *const BpProofBundle,
+    commitments: *const u8,
+    count: usize,
+) -> BpStatus {
+    guard(|| {
+        let gens = read_handle(gens)?;
+        let label = read_label(label, label_len)?;
+        let bundle = read_handle(bundle)?;
+        if count > 0 && commitments.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let mut points = Vec::with_capacity(count);
+        for i in 0..count {
+            points.push(read_point(commitments.offset(32 * i as isize))?);
+        }
+        let mut rng = OsRng::new().map_err(|_| BpStatus::RngUnavailable)?;
+
+        let mut transcript = ProofTranscript::new(label).map_err(|_| BpStatus::InvalidArgument)?;
+        bundle
+            .0
+            .verify(gens.generators.share(0), &mut transcript, &mut rng, gens.n, &points)
+            .map_err(|_| BpStatus::VerificationFailed)
+    })
+}
+
+/// Encodes `proof` into the `out_len` bytes at `out`, writing the
+/// length of the encoding to `written`.
+///
+/// If the buffer is too small, returns `BP_BUFFER_TOO_SMALL` and writes
+/// the needed length to `written`, so the call can be repeated.
+#[no_mangle]
+pub unsafe extern "C" fn bp_range_proof_to_bytes(
+    proof: *const BpRangeProof,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> BpStatus {
+    guard(|| {
+        let proof = read_handle(proof)?;
+        let bytes = bincode::serialize(&proof.0).map_err(|_| BpStatus::InvalidEncoding)?;
+        write_bytes(&bytes, out, out_len, written)
+    })
+}
+
+/// Decodes a proof from the `len` bytes at `bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_range_proof_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut BpRangeProof,
+) -> BpStatus {
+    guard(|| {
+        let bytes = read_bytes(bytes, len)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let proof = bincode::deserialize(bytes).map_err(|_| BpStatus::InvalidEncoding)?;
+        *out = Box::into_raw(Box::new(BpRangeProof(proof)));
+        Ok(())
+    })
+}
+
+/// Frees a proof created by `bp_prove` or `bp_range_proof_from_bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_range_proof_free(proof: *mut BpRangeProof) {
+    if !proof.is_null() {
+        drop(Box::from_raw(proof));
+    }
+}
+
+/// Encodes `bundle` like `bp_range_proof_to_bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_proof_bundle_to_bytes(
+    bundle: *const BpProofBundle,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> BpStatus {
+    guard(|| {
+        let bundle = read_handle(bundle)?;
+        let bytes = bincode::serialize(&bundle.0).map_err(|_| BpStatus::InvalidEncoding)?;
+        write_bytes(&bytes, out, out_len, written)
+    })
+}
+
+/// Decodes a bundle from the `len` bytes at `bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_proof_bundle_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut BpProofBundle,
+) -> BpStatus {
+    guard(|| {
+        let bytes = read_bytes(bytes, len)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let bundle = bincode::deserialize(bytes).map_err(|_| BpStatus::InvalidEncoding)?;
+        *out = Box::into_raw(Box::new(BpProofBundle(bundle)));
+        Ok(())
+    })
+}
+
+/// Frees a bundle created by `bp_prove_many` or
+/// `bp_proof_bundle_from_bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_proof_bundle_free(bundle: *mut BpProofBundle) {
+    if !bundle.is_null() {
+        drop(Box::from_raw(bundle));
+    }
+}
+
+/// The most rounds of the inner-product argument of a range proof,
+/// for \\(n = 64\\).
+pub const BP_MAX_ROUNDS: usize = 6;
+
+/// The components of a range proof, in a layout that C code can read
+/// and write without parsing the byte encoding.
+///
+/// Points are compressed and scalars are little-endian.  Only the first
+/// `rounds` entries of `L` and `R` are used; `rounds` is \\(\lg n\\).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BpRangeProofParts {
+    /// The number of rounds of the inner-product argument
+    pub rounds: usize,
+    /// Commitment to the bits of the value
+    pub A: [u8; 32],
+    /// Commitment to the blinding factors
+    pub S: [u8; 32],
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub T_1: [u8; 32],
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    pub T_2: [u8; 32],
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub t_x: [u8; 32],
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    pub t_x_blinding: [u8; 32],
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    pub e_blinding: [u8; 32],
+    /// The points \\(L\_j\\) of the inner-product argument
+    pub L: [[u8; 32]; BP_MAX_ROUNDS],
+    /// The points \\(R\_j\\) of the inner-product argument
+    pub R: [[u8; 32]; BP_MAX_ROUNDS],
+    /// The final scalar \\(a\\) of the inner-product argument
+    pub a: [u8; 32],
+    /// The final scalar \\(b\\) of the inner-product argument
+    pub b: [u8; 32],
+}
+
+impl BpRangeProofParts {
+    /// Splits `proof` into its components.
+    ///
+    /// Returns an error if the proof has more than `BP_MAX_ROUNDS`
+    /// rounds, which no range proof of at most 64 bits has.
+    pub fn from_proof(proof: &RangeProof) -> Result<BpRangeProofParts, BpStatus> {
+        let bytes = proof.to_bytes();
+        let rounds = (bytes.len() / 32 - 9) / 2;
+        if rounds > BP_MAX_ROUNDS {
+            return Err(BpStatus::InvalidArgument);
+        }
+        let element = |i: usize| {
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(&bytes[32 * i..32 * (i + 1)]);
+            buf
+        };
+
+        let mut parts = BpRangeProofParts {
+            rounds,
+            A: element(0),
+            S: element(1),
+            T_1: element(2),
+            T_2: element(3),
+            t_x: element(4),
+            t_x_blinding: element(5),
+            e_blinding: element(6),
+            L: [[0u8; 32]; BP_MAX_ROUNDS],
+            R: [[0u8; 32]; BP_MAX_ROUNDS],
+            a: element(7 + 2 * rounds),
+            b: element(8 + 2 * rounds),
+        };
+        {
+            let rounds = parts.L.iter_mut().zip(parts.R.iter_mut()).take(rounds);
+            for (j, (L, R)) in rounds.enumerate() {
+                *L = element(7 + 2 * j);
+                *R = element(8 + 2 * j);
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Assembles the proof from its components.
+    ///
+    /// Returns an error if `rounds` is out of range, or if a point or
+    /// scalar is not a canonical encoding.
+    pub fn to_proof(&self) -> Result<RangeProof, BpStatus> {
+        if self.rounds > BP_MAX_ROUNDS {
+            return Err(BpStatus::InvalidArgument);
+        }
+        let mut bytes = Vec::with_capacity(32 * (9 + 2 * self.rounds));
+        for element in [
+            &self.A,
+            &self.S,
+            &self.T_1,
+            &self.T_2,
+            &self.t_x,
+            &self.t_x_blinding,
+            &self.e_blinding,
+        ].iter()
+        {
+            bytes.extend_from_slice(&element[..]);
+        }
+        for (L, R) in self.L.iter().zip(self.R.iter()).take(self.rounds) {
+            bytes.extend_from_slice(L);
+            bytes.extend_from_slice(R);
+        }
+        bytes.extend_from_slice(&self.a);
+        bytes.extend_from_slice(&self.b);
+        RangeProof::from_bytes(&bytes).map_err(|_| BpStatus::InvalidEncoding)
+    }
+}
+
+/// Writes the components of `proof` to `out`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_range_proof_to_parts(
+    proof: *const BpRangeProof,
+    out: *mut BpRangeProofParts,
+) -> BpStatus {
+    guard(|| {
+        let proof = read_handle(proof)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        *out = BpRangeProofParts::from_proof(&proof.0)?;
+        Ok(())
+    })
+}
+
+/// Assembles a proof from the components at `parts`.
+#[no_mangle]
+pub unsafe extern "C" fn bp_range_proof_from_parts(
+    parts: *const BpRangeProofParts,
+    out: *mut *mut BpRangeProof,
+) -> BpStatus {
+    guard(|| {
+        let parts = read_handle(parts)?;
+        if out.is_null() {
+            return Err(BpStatus::NullPointer);
+        }
+        let proof = parts.to_proof()?;
+        *out = Box::into_raw(Box::new(BpRangeProof(proof)));
+        Ok(())
+    })
+}
+
+/// Runs `f`, turning its result and any panic into a status.
+fn guard<F: FnOnce() -> Result<(), BpStatus>>(f: F) -> BpStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => BpStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => BpStatus::Panic,
+    }
+}
+
+unsafe fn read_handle<'a, T>(handle: *const T) -> Result<&'a T, BpStatus> {
+    handle.as_ref().ok_or(BpStatus::NullPointer)
+}
+
+unsafe fn read_bytes<'a>(bytes: *const u8, len: usize) -> Result<&'a [u8], BpStatus> {
+    if len == 0 {
+        Ok(&[])
+    } else if bytes.is_null() {
+        Err(BpStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(bytes, len))
+    }
+}
+
+/// Reads a domain or transcript label, which must not be empty.
+unsafe fn read_label<'a>(label: *const u8, len: usize) -> Result<&'a [u8], BpStatus> {
+    if len == 0 {
+        return Err(BpStatus::InvalidArgument);
+    }
+    read_bytes(label, len)
+}
+
+unsafe fn read_array(bytes: *const u8) -> Result<[u8; 32], BpStatus> {
+    if bytes.is_null() {
+        return Err(BpStatus::NullPointer);
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(slice::from_raw_parts(bytes, 32));
+    Ok(buf)
+}
+
+unsafe fn read_scalar(bytes: *const u8) -> Result<Scalar, BpStatus> {
+    Ok(Scalar::from_bytes_mod_order(read_array(bytes)?))
+}
+
+unsafe fn read_point(bytes: *const u8) -> Result<RistrettoPoint, BpStatus> {
+    CompressedRistretto(read_array(bytes)?)
+        .decompress()
+        .ok_or(BpStatus::InvalidEncoding)
+}
+
+unsafe fn write_bytes(
+    bytes: &[u8],
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> Result<(), BpStatus> {
+    if written.is_null() {
+        return Err(BpStatus::NullPointer);
+    }
+    *written = bytes.len();
+    if out_len < bytes.len() {
+        return Err(BpStatus::BufferTooSmall);
+    }
+    if out.is_null() {
+        return Err(BpStatus::NullPointer);
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    Ok(())
+}
+
+/// Rejects values that do not fit in the bitsize of `gens`, for which
+/// the prover would make an invalid proof.
+fn check_value(gens: &BpGenerators, value: u64) -> Result<(), BpStatus> {
+    if gens.n < 64 && value >> gens.n != 0 {
+        Err(BpStatus::InvalidArgument)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prove_encode_decode_and_verify() {
+        unsafe {
+            let mut gens: *mut BpGenerators = ptr::null_mut();
+            let label = b"FfiTest";
+            assert_eq!(bp_generators_new(label.as_ptr(), label.len(), 16, &mut gens), BpStatus::Ok);
+            assert_eq!(
+                bp_generators_new(label.as_ptr(), label.len(), 12, &mut gens),
+                BpStatus::InvalidArgument
+            );
+            assert_eq!(
+                bp_generators_new(ptr::null(), 0, 16, &mut gens),
+                BpStatus::InvalidArgument
+            );
+
+            let blinding = [7u8; 32];
+            let mut V = [0u8; 32];
+            let status = bp_commit(gens, 1000, blinding.as_ptr(), V.as_mut_ptr());
+            assert_eq!(status, BpStatus::Ok);
+
+            let mut proof: *mut BpRangeProof = ptr::null_mut();
+            let (label_ptr, label_len) = (label.as_ptr(), label.len());
+            let status = bp_prove(gens, label_ptr, label_len, 1000, blinding.as_ptr(), &mut proof);
+            assert_eq!(status, BpStatus::Ok);
+
+            // Ask for the size first, then encode.
+            let mut len = 0;
+            let status = bp_range_proof_to_bytes(proof, ptr::null_mut(), 0, &mut len);
+            assert_eq!(status, BpStatus::BufferTooSmall);
+            let mut bytes = vec![0u8; len];
+            let status = bp_range_proof_to_bytes(proof, bytes.as_mut_ptr(), len, &mut len);
+            assert_eq!(status, BpStatus::Ok);
+            bp_range_proof_free(proof);
+
+            let mut decoded: *mut BpRangeProof = ptr::null_mut();
+            let status = bp_range_proof_from_bytes(bytes.as_ptr(), len, &mut decoded);
+            assert_eq!(status, BpStatus::Ok);
+            let status = bp_verify(gens, label_ptr, label_len, decoded, V.as_ptr());
+            assert_eq!(status, BpStatus::Ok);
+            let status = bp_verify(gens, b"Other".as_ptr(), 5, decoded, V.as_ptr());
+            assert_eq!(status, BpStatus::VerificationFailed);
+
+            // The components round trip through the fixed layout.
+            let mut parts: BpRangeProofParts = ::std::mem::zeroed();
+            assert_eq!(bp_range_proof_to_parts(decoded, &mut parts), BpStatus::Ok);
+            assert_eq!(parts.rounds, 4);
+            let mut rebuilt: *mut BpRangeProof = ptr::null_mut();
+            assert_eq!(bp_range_proof_from_parts(&parts, &mut rebuilt), BpStatus::Ok);
+            assert_eq!((*rebuilt).0.to_bytes(), bytes);
+            parts.rounds = BP_MAX_ROUNDS + 1;
+            let status = bp_range_proof_from_parts(&parts, &mut rebuilt);
+            assert_eq!(status, BpStatus::InvalidArgument);
+            bp_range_proof_free(rebuilt);
+            bp_range_proof_free(decoded);
+
+            // Values must fit in the bitsize.
+            let blinding = blinding.as_ptr();
+            let status = bp_prove(gens, label_ptr, label_len, 1 << 16, blinding, &mut proof);
+            assert_eq!(status, BpStatus::InvalidArgument);
+            bp_generators_free(gens);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `fuzzing` module contains `Arbitrary`, for building the proof
+//! types from a fuzzer's bytes, and its implementations.
+//!
+//! The values are structurally valid, so that they survive a round trip
+//! through any of the crate's encodings and reach the verifier, but
+//! semantically random: points are hashes of the fuzzer's bytes and
+//! scalars are reduced from them, so the proofs do not verify.  Range
+//! proofs have one of the supported bitsizes, and the proofs in a bundle
+//! or an envelope share one.
+//!
+//! The types covered are `RangeProof`, `InnerProductProof`,
+//! `ProofBundle`, `ProofEnvelope` and `DelegationResponse`, whose
+//! implementation lives in the `delegation` module.
+//!
+//! The trait has the shape of the `arbitrary` crate's, whose releases
+//! need a newer compiler than this crate's.  A fuzz target wraps its
+//! input in an `Unstructured` and builds values until it runs out:
+//!
+//! ```ascii
+//! let mut u = Unstructured::new(data);
+//! while let Ok(proof) = RangeProof::arbitrary(&mut u) {
+//!     let _ = verifier.verify(&proof, &V, &mut transcript, &mut rng);
+//! }
+//! ```
+
+use errors::ProofError;
+
+use sha2::{Digest, Sha512};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use envelope::ProofEnvelope;
+use inner_product_proof::InnerProductProof;
+use range_proof::{RangeCommitments, RangeProof};
+
+/// The largest number of proofs in an arbitrary bundle or envelope.
+const MAX_PROOFS: usize = 8;
+
+/// The fuzzer's bytes, consumed from the front.
+#[derive(Debug)]
+pub struct Unstructured<'a> {
+    /// The bytes not consumed yet
+    data: &'a [u8],
+}
+
+impl<'a> Unstructured<'a> {
+    /// Wraps the fuzzer's input `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Unstructured { data }
+    }
+
+    /// Returns the number of bytes not consumed yet.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if every byte has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Fills `buffer` with the next bytes, returning `FormatError` if
+    /// there are not enough of them.
+    pub fn fill_buffer(&mut self, buffer: &mut [u8]) -> Result<(), ProofError> {
+        if self.data.len() < buffer.len() {
+            return Err(ProofError::FormatError);
+        }
+        let (head, tail) = self.data.split_at(buffer.len());
+        buffer.copy_from_slice(head);
+        self.data = tail;
+        Ok(())
+    }
+
+    /// Returns a size for a container, read from the next byte.
+    pub fn container_size(&mut self) -> Result<usize, ProofError> {
+        let mut byte = [0u8; 1];
+        self.fill_buffer(&mut byte)?;
+        Ok(byte[0] as usize)
+    }
+}
+
+/// Types that can be built from a fuzzer's bytes.
+pub trait Arbitrary: Sized {
+    /// Builds a value from the next bytes of `u`, returning
+    /// `FormatError` if there are not enough of them.
+    fn arbitrary(u: &mut Unstructured) -> Result<Self, ProofError>;
+}
+
+fn point(u: &mut Unstructured) -> Result<RistrettoPoint, ProofError> {
+    let mut bytes = [0u8; 64];
+    u.fill_buffer(&mut bytes)?;
+    let mut hash = Sha512::default();
+    hash.input(&bytes[..]);
+    Ok(RistrettoPoint::from_hash(hash))
+}
+
+fn scalar(u: &mut Unstructured) -> Result<Scalar, ProofError> {
+    let mut bytes = [0u8; 32];
+    u.fill_buffer(&mut bytes)?;
+    Ok(Scalar::from_bytes_mod_order(bytes))
+}
+
+/// Picks one of the bitsizes 8, 16, 32 and 64.
+fn bitsize(u: &mut Unstructured) -> Result<usize, ProofError> {
+    let mut byte = [0u8; 1];
+    u.fill_buffer(&mut byte)?;
+    Ok(8 << (byte[0] % 4))
+}
+
+/// Builds an inner-product proof of `rounds` rounds.
+fn inner_product_proof(
+    u: &mut Unstructured,
+    rounds: usize,
+) -> Result<InnerProductProof, ProofError> {
+    let mut L_vec = Vec::with_capacity(rounds);
+    let mut R_vec = Vec::with_capacity(rounds);
+    for _ in 0..rounds {
+        L_vec.push(point(u)?);
+        R_vec.push(point(u)?);
+    }
+    Ok(InnerProductProof {
+        L_vec,
+        R_vec,
+        a: scalar(u)?,
+        b: scalar(u)?,
+    })
+}
+
+/// Builds a range proof of an `n`-bit range.
+fn range_proof(u: &mut Unstructured, n: usize) -> Result<RangeProof, ProofError> {
+    let commitments = RangeCommitments {
+        A: point(u)?,
+        S: point(u)?,
+        T_1: point(u)?,
+        T_2: point(u)?,
+        t_x: scalar(u)?,
+        t_x_blinding: scalar(u)?,
+        e_blinding: scalar(u)?,
+    };
+    let ipp_proof = inner_product_proof(u, n.trailing_zeros() as usize)?;
+    Ok(RangeProof::from_parts(commitments, ipp_proof))
+}
+
+/// Builds up to `MAX_PROOFS` range proofs of `n`-bit ranges.
+fn range_proofs(u: &mut Unstructured, n: usize) -> Result<Vec<RangeProof>, ProofError> {
+    let len = u.container_size()? % (MAX_PROOFS + 1);
+    (0..len).map(|_| range_proof(u, n)).collect()
+}
+
+impl Arbitrary for InnerProductProof {
+    fn arbitrary(u: &mut Unstructured) -> Result<Self, ProofError> {
+        let n = bitsize(u)?;
+        inner_product_proof(u, n.trailing_zeros() as usize)
+    }
+}
+
+impl Arbitrary for RangeProof {
+    fn arbitrary(u: &mut Unstructured) -> Result<Self, ProofError> {
+        let n = bitsize(u)?;
+        range_proof(u, n)
+    }
+}
+
+impl Arbitrary for ProofBundle {
+    fn arbitrary(u: &mut Unstructured) -> Result<Self, ProofError> {
+        let n = bitsize(u)?;
+        Ok(ProofBundle::from_proofs(range_proofs(u, n)?))
+    }
+}
+
+impl Arbitrary for ProofEnvelope {
+    fn arbitrary(u: &mut Unstructured) -> Result<Self, ProofError> {
+        let n = bitsize(u)?;
+        // The proofs are made for n bits, as `ProofEnvelope::new` checks.
+        let proofs = range_proofs(u, n)?;
+        Ok(ProofEnvelope { n, proofs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arbitrary_proofs_survive_encoding() {
+        let data: Vec<u8> = (0..1u32 << 17).map(|i| (i % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..8 {
+            let proof = RangeProof::arbitrary(&mut u).unwrap();
+            assert_eq!(RangeProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+
+            let envelope = ProofEnvelope::arbitrary(&mut u).unwrap();
+            let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+            assert_eq!(decoded.proofs(), envelope.proofs());
+        }
+
+        // Running out of bytes is an error, not a panic.
+        let mut u = Unstructured::new(&data[..100]);
+        assert_eq!(RangeProof::arbitrary(&mut u).unwrap_err(), ProofError::FormatError);
+    }
+}
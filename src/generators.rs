@@ -1,6 +1,17 @@
 //! The `generators` module contains API for producing a
 //! set of generators for a rangeproof.
 //!
+//! The per-bit generators are derived from an application domain
+//! label as well as the Pedersen generators, so that two protocols
+//! built on this crate never share generators, and a proof for one
+//! cannot verify in the other.  `Generators::without_domain` derives
+//! the generators from the Pedersen generators alone, as before domain
+//! labels, for deployments that must match existing proofs.
+//!
+//! A service that loads its generators from a cache file or a
+//! coordinator can pin their `integrity_hash` in its configuration and
+//! call `check_integrity` at startup, so that corrupted or substituted
+//! generators are rejected before any proof is made with them.
 //!
 //! # Example
 //!
@@ -8,7 +19,7 @@
 //! # extern crate ristretto_bulletproofs;
 //! # use ristretto_bulletproofs::{PedersenGenerators,Generators};
 //! # fn main() {
-//! let generators = Generators::new(PedersenGenerators::default(), 64,1);
+//! let generators = Generators::new(b"MyApp", PedersenGenerators::default(), 64,1);
 //! let view = generators.all();
 //! let G0 = view.G[0];
 //! let H0 = view.H[0];
@@ -21,26 +32,54 @@
 
 // XXX we should use Sha3 everywhere
 
-use curve25519_dalek::ristretto;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
+use byteorder::{ByteOrder, LittleEndian};
 use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+
+use errors::ProofError;
+use params::{Limits, ProofParams};
+use util;
+
+/// The length of the integrity hash of `Generators`.
+const INTEGRITY_HASH_LEN: usize = 32;
 
 /// The `GeneratorsChain` creates an arbitrary-long sequence of orthogonal generators.
 /// The sequence can be deterministically produced starting with an arbitrary point.
-struct GeneratorsChain {
+pub(crate) struct GeneratorsChain {
     next_point: RistrettoPoint,
 }
 
 impl GeneratorsChain {
     /// Creates a chain of generators, determined by the hash of `label`.
-    fn new(label: &[u8]) -> Self {
+    pub(crate) fn new(label: &[u8]) -> Self {
         let mut hash = Sha512::default();
         hash.input(b"GeneratorsChainInit");
         hash.input(label);
         let next_point = RistrettoPoint::from_hash(hash);
         GeneratorsChain { next_point }
     }
+
+    /// Creates a chain of generators, determined by the hash of the
+    /// application `domain` and `label`.
+    ///
+    /// The domain is length-prefixed, so that no pair of a domain and a
+    /// label collides with another, nor with a chain made by `new`.
+    pub(crate) fn with_domain(domain: &[u8], label: &[u8]) -> Self {
+        let mut domain_len = [0u8; 8];
+        LittleEndian::write_u64(&mut domain_len, domain.len() as u64);
+        let mut hash = Sha512::default();
+        hash.input(b"GeneratorsChainDomainInit");
+        hash.input(&domain_len);
+        hash.input(domain);
+        hash.input(label);
+        let next_point = RistrettoPoint::from_hash(hash);
+        GeneratorsChain { next_point }
+    }
 }
 
 impl Default for GeneratorsChain {
@@ -62,7 +101,7 @@ impl Iterator for GeneratorsChain {
 }
 
 /// `Generators` contains all the generators needed for aggregating `m` range proofs of `n` bits each.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Generators {
     /// Number of bits in a rangeproof
     pub n: usize,
@@ -77,6 +116,7 @@ pub struct Generators {
 }
 
 /// Represents a view into `Generators` relevant to a specific range proof.
+#[derive(Copy, Clone, Debug)]
 pub struct GeneratorsView<'a> {
     /// Bases for Pedersen commitments
     pub pedersen_generators: &'a PedersenGenerators,
@@ -87,7 +127,7 @@ pub struct GeneratorsView<'a> {
 }
 
 /// Represents a pair of base points for Pedersen commitments.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct PedersenGenerators {
     /// Base for the committed value
     pub B: RistrettoPoint,
@@ -105,10 +145,24 @@ impl PedersenGenerators {
 
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
     pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
-        ristretto::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
+        util::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
+    }
+}
+
+impl ConstantTimeEq for PedersenGenerators {
+    fn ct_eq(&self, other: &PedersenGenerators) -> Choice {
+        self.B.ct_eq(&other.B) & self.B_blinding.ct_eq(&other.B_blinding)
     }
 }
 
+impl PartialEq for PedersenGenerators {
+    fn eq(&self, other: &PedersenGenerators) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for PedersenGenerators {}
+
 impl Default for PedersenGenerators {
     fn default() -> Self {
         PedersenGenerators {
@@ -119,21 +173,59 @@ impl Default for PedersenGenerators {
 }
 
 impl Generators {
-    /// Creates generators for `m` range proofs of `n` bits each.
-    pub fn new(pedersen_generators: PedersenGenerators, n: usize, m: usize) -> Self {
-        let G = GeneratorsChain::new(pedersen_generators.B.compress().as_bytes())
-            .take(n * m)
-            .collect();
-        let H = GeneratorsChain::new(pedersen_generators.B_blinding.compress().as_bytes())
-            .take(n * m)
-            .collect();
+    /// Creates generators for `m` range proofs of `n` bits each, for the
+    /// application `domain`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `domain` is empty.  Use `without_domain` to derive
+    /// generators without a domain label.
+    pub fn new(domain: &[u8], pedersen_generators: PedersenGenerators, n: usize, m: usize) -> Self {
+        check_domain(domain);
+        let G = GeneratorsChain::with_domain(domain, pedersen_generators.B.compress().as_bytes());
+        let H = GeneratorsChain::with_domain(
+            domain,
+            pedersen_generators.B_blinding.compress().as_bytes(),
+        );
+        Generators::from_chains(pedersen_generators, n, m, G, H)
+    }
 
+    /// Creates generators for `m` range proofs of `n` bits each, derived
+    /// from the Pedersen generators alone.
+    ///
+    /// These are the generators of versions before domain labels.  Any
+    /// protocol using them can accept the proofs of any other that does,
+    /// so only use them to stay compatible with existing proofs.
+    pub fn without_domain(pedersen_generators: PedersenGenerators, n: usize, m: usize) -> Self {
+        let G = GeneratorsChain::new(pedersen_generators.B.compress().as_bytes());
+        let H = GeneratorsChain::new(pedersen_generators.B_blinding.compress().as_bytes());
+        Generators::from_chains(pedersen_generators, n, m, G, H)
+    }
+
+    /// Creates generators for the `m` proofs of `n` bits of `params`,
+    /// for the application `domain`, as `new`.
+    pub fn with_params(
+        domain: &[u8],
+        pedersen_generators: PedersenGenerators,
+        params: &ProofParams,
+    ) -> Self {
+        Generators::new(domain, pedersen_generators, params.n(), params.m())
+    }
+
+    /// Takes the first \(nm\) points of the chains `G` and `H`.
+    fn from_chains(
+        pedersen_generators: PedersenGenerators,
+        n: usize,
+        m: usize,
+        G: GeneratorsChain,
+        H: GeneratorsChain,
+    ) -> Self {
         Generators {
             n,
             m,
             pedersen_generators: pedersen_generators,
-            G,
-            H,
+            G: G.take(n * m).collect(),
+            H: H.take(n * m).collect(),
         }
     }
 
@@ -157,6 +249,113 @@ impl Generators {
             H: &self.H[lower..upper],
         }
     }
+
+    /// Serializes the generators, so that a coordinator can ship the
+    /// exact generator set to verifiers.
+    ///
+    /// The encoding is `n` and `m` as little-endian `u32`s, the points
+    /// \\(B\\) and \\(\tilde{B}\\), the \\(nm\\) points of `G` and
+    /// then of `H`, all compressed, and finally the `integrity_hash` of
+    /// everything before it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + 64 * (self.n * self.m + 1) + INTEGRITY_HASH_LEN);
+        let mut header = [0u8; 8];
+        LittleEndian::write_u32(&mut header[..4], self.n as u32);
+        LittleEndian::write_u32(&mut header[4..], self.m as u32);
+        buf.extend_from_slice(&header);
+        let pg = &self.pedersen_generators;
+        let points = [pg.B, pg.B_blinding];
+        for P in points.iter().chain(self.G.iter()).chain(self.H.iter()) {
+            buf.extend_from_slice(P.compress().as_bytes());
+        }
+        let hash = hash_encoding(&buf);
+        buf.extend_from_slice(&hash);
+        buf
+    }
+
+    /// Deserializes generators from the encoding of `to_bytes`.
+    ///
+    /// Returns an error if the length does not match `n` and `m`, if the
+    /// integrity hash does not match, or if a point is not a canonical
+    /// encoding.  The points are not rederived from \\(B\\) and
+    /// \\(\tilde{B}\\), so generators made with other chains survive the
+    /// round trip.
+    pub fn from_bytes(slice: &[u8]) -> Result<Generators, ProofError> {
+        Generators::from_bytes_with_limits(slice, &Limits::default())
+    }
+
+    /// Deserializes generators as `from_bytes`, returning `TooLarge` if
+    /// the encoding claims more than `limits` allow.
+    pub fn from_bytes_with_limits(slice: &[u8], limits: &Limits) -> Result<Generators, ProofError> {
+        if slice.len() < 8 + 64 + INTEGRITY_HASH_LEN {
+            return Err(ProofError::FormatError);
+        }
+        let (body, hash) = slice.split_at(slice.len() - INTEGRITY_HASH_LEN);
+        let n = LittleEndian::read_u32(&body[..4]) as usize;
+        let m = LittleEndian::read_u32(&body[4..8]) as usize;
+        limits.check(n, m)?;
+        let nm = n * m;
+        if (body.len() - 8) % 64 != 0 || Some((body.len() - 8) / 64) != nm.checked_add(1) {
+            return Err(ProofError::FormatError);
+        }
+        if hash_encoding(body)[..] != hash[..] {
+            return Err(ProofError::IntegrityError);
+        }
+
+        let mut points = body[8..].chunks(32).map(util::read_point);
+        let B = points.next().ok_or(ProofError::FormatError)??;
+        let B_blinding = points.next().ok_or(ProofError::FormatError)??;
+        let G = points.by_ref().take(nm).collect::<Result<Vec<_>, _>>()?;
+        let H = points.collect::<Result<Vec<_>, _>>()?;
+        Ok(Generators {
+            n,
+            m,
+            pedersen_generators: PedersenGenerators::new(B, B_blinding),
+            G,
+            H,
+        })
+    }
+
+    /// Returns a hash of the encoding of the generators, which verifiers
+    /// can compare to check they use identical parameters.
+    pub fn integrity_hash(&self) -> [u8; INTEGRITY_HASH_LEN] {
+        let bytes = self.to_bytes();
+        let mut hash = [0u8; INTEGRITY_HASH_LEN];
+        hash.copy_from_slice(&bytes[bytes.len() - INTEGRITY_HASH_LEN..]);
+        hash
+    }
+
+    /// Returns `IntegrityError` unless the `integrity_hash` of the
+    /// generators is the `expected` one, pinned by the caller.
+    ///
+    /// ```ascii
+    /// let generators = Generators::from_bytes(&cached)?;
+    /// generators.check_integrity(&config.generators_hash)?;
+    /// ```
+    pub fn check_integrity(&self, expected: &[u8; INTEGRITY_HASH_LEN]) -> Result<(), ProofError> {
+        if self.integrity_hash() != *expected {
+            return Err(ProofError::IntegrityError);
+        }
+        Ok(())
+    }
+}
+
+/// Panics if the application `domain` label is empty.
+pub(crate) fn check_domain(domain: &[u8]) {
+    if domain.is_empty() {
+        panic!("Domain label must not be empty; use without_domain to opt out");
+    }
+}
+
+/// Returns the first `INTEGRITY_HASH_LEN` bytes of a domain-separated
+/// SHA-512 hash of the encoding `body` of generators.
+fn hash_encoding(body: &[u8]) -> [u8; INTEGRITY_HASH_LEN] {
+    let mut hasher = Sha512::default();
+    hasher.input(b"Bulletproofs.Generators.IntegrityHash");
+    hasher.input(body);
+    let mut hash = [0u8; INTEGRITY_HASH_LEN];
+    hash.copy_from_slice(&hasher.result()[..INTEGRITY_HASH_LEN]);
+    hash
 }
 
 #[cfg(test)]
@@ -168,7 +367,7 @@ mod tests {
     fn rangeproof_generators() {
         let n = 2;
         let m = 3;
-        let gens = Generators::new(PedersenGenerators::default(), n, m);
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), n, m);
 
         // The concatenation of shares must be the full generator set
         assert_eq!(
@@ -190,4 +389,73 @@ mod tests {
             [gens.share(2).G[..].to_vec(), gens.share(2).H[..].to_vec()]
         );
     }
+
+    #[test]
+    fn generators_round_trip_through_bytes() {
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), 8, 2);
+        let mut bytes = gens.to_bytes();
+        assert_eq!(bytes.len(), 8 + 64 * 17 + INTEGRITY_HASH_LEN);
+        assert_eq!(Generators::from_bytes(&bytes), Ok(gens.clone()));
+        assert_eq!(&bytes[bytes.len() - 32..], &gens.integrity_hash()[..]);
+
+        // Any substituted point changes the hash.
+        bytes[8 + 64 * 5] ^= 1;
+        assert!(Generators::from_bytes(&bytes).is_err());
+        bytes[8 + 64 * 5] ^= 1;
+        assert!(Generators::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let other = Generators::new(b"Test", PedersenGenerators::default(), 8, 1);
+        assert!(other.integrity_hash() != gens.integrity_hash());
+    }
+
+    #[test]
+    fn pinned_hashes_detect_substituted_generators() {
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), 8, 2);
+        let pinned = gens.integrity_hash();
+        assert_eq!(gens.check_integrity(&pinned), Ok(()));
+
+        // Generators for another domain decode, but fail the pin.
+        let other = Generators::new(b"Other", PedersenGenerators::default(), 8, 2);
+        let substituted = Generators::from_bytes(&other.to_bytes()).unwrap();
+        assert_eq!(substituted.check_integrity(&pinned), Err(ProofError::IntegrityError));
+    }
+
+    #[test]
+    fn domains_separate_generators_and_proofs() {
+        use proof_transcript::ProofTranscript;
+        use rand::OsRng;
+        use range_proof::RangeProof;
+        use secret::{Blinding, SecretValue};
+
+        let pg = PedersenGenerators::default();
+        let gens_a = Generators::new(b"AppA", pg.clone(), 8, 1);
+        let gens_b = Generators::new(b"AppB", pg.clone(), 8, 1);
+        let legacy = Generators::without_domain(pg.clone(), 8, 1);
+        assert!(gens_a.all().G != gens_b.all().G);
+        assert!(gens_a.all().H != legacy.all().H);
+
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let V = pg.commit(Scalar::from_u64(3), v_blinding);
+        let mut transcript = ProofTranscript::new(b"Shared").unwrap();
+        let proof = RangeProof::generate_proof(
+            gens_a.share(0),
+            &mut transcript,
+            &mut rng,
+            8,
+            &SecretValue::new(3),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"Shared").unwrap();
+        assert!(proof.verify(&V, gens_a.share(0), &mut transcript, &mut rng, 8).is_ok());
+        let mut transcript = ProofTranscript::new(b"Shared").unwrap();
+        assert!(proof.verify(&V, gens_b.share(0), &mut transcript, &mut rng, 8).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_domains_are_rejected() {
+        Generators::new(b"", PedersenGenerators::default(), 8, 1);
+    }
 }
@@ -0,0 +1,223 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `group` module defines the prime-order group interface that the
+//! proofs need, as the `Group` and `ScalarField` traits, implemented for
+//! Ristretto.
+//!
+//! The traits cover what the range proof and the inner-product argument
+//! use: the scalar field operations, scalar multiplication and
+//! multiscalar multiplication, hashing to the group for generators, and
+//! canonical encodings with validating decodings.  A backend for another
+//! prime-order group, such as secp256k1 or decaf448, implements them for
+//! its point and scalar types.
+//!
+//! The proof code itself is not yet generic: `RangeProof`,
+//! `InnerProductProof` and `Generators` still use `RistrettoPoint` and
+//! `Scalar` directly, and are to be ported over these traits with
+//! Ristretto as the default, so that the encodings and serde formats of
+//! the existing types do not change.  Code written against the traits
+//! now runs on Ristretto unchanged once other groups are added.
+//!
+//! # Example
+//!
+//! ```ascii
+//! fn commit<G: Group>(v: G::Scalar, v_blinding: G::Scalar, B: G, B_blinding: G) -> G {
+//!     G::multiscalar_mul(&[v, v_blinding], &[B, B_blinding]).unwrap()
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::Rng;
+use sha2::{Digest, Sha512};
+
+use errors::ProofError;
+use util;
+
+/// The scalar field of a prime-order group.
+pub trait ScalarField:
+    Copy + Eq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    /// The length of the canonical encoding of a scalar.
+    const ENCODED_LEN: usize;
+
+    /// Returns the additive identity.
+    fn zero() -> Self;
+
+    /// Returns the multiplicative identity.
+    fn one() -> Self;
+
+    /// Returns the scalar for the integer `x`.
+    fn from_u64(x: u64) -> Self;
+
+    /// Returns the multiplicative inverse, or zero for zero.
+    fn invert(&self) -> Self;
+
+    /// Reduces 64 uniformly random bytes to a scalar, for challenges.
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self;
+
+    /// Draws a uniformly random scalar from `rng`.
+    fn random<R: Rng>(rng: &mut R) -> Self;
+
+    /// Returns the canonical encoding of the scalar.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Decodes a scalar, returning `FormatError` or `InvalidScalar` if
+    /// `bytes` is not a canonical encoding.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError>;
+}
+
+/// A prime-order group, written additively.
+pub trait Group: Copy + Eq + Add<Output = Self> + Sub<Output = Self> + Neg<Output = Self> {
+    /// The scalar field of the group.
+    type Scalar: ScalarField;
+
+    /// The length of the canonical encoding of a point.
+    const ENCODED_LEN: usize;
+
+    /// Returns the identity element.
+    fn identity() -> Self;
+
+    /// Returns the point `s * self`.
+    fn scalar_mul(&self, s: &Self::Scalar) -> Self;
+
+    /// Computes \\( \sum\_i s\_i P\_i \\) in constant time.
+    ///
+    /// Returns `FormatError` if the slices have different lengths.
+    fn multiscalar_mul(scalars: &[Self::Scalar], points: &[Self]) -> Result<Self, ProofError>;
+
+    /// Computes \\( \sum\_i s\_i P\_i \\) in variable time, for public
+    /// scalars only.
+    ///
+    /// Returns `FormatError` if the slices have different lengths.
+    fn vartime_multiscalar_mul(
+        scalars: &[Self::Scalar],
+        points: &[Self],
+    ) -> Result<Self, ProofError>;
+
+    /// Hashes `bytes` to a point with no known discrete logarithm, for
+    /// generators.
+    fn hash_from_bytes(bytes: &[u8]) -> Self;
+
+    /// Returns the canonical encoding of the point.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Decodes a point, returning `FormatError` or `InvalidPoint` if
+    /// `bytes` is not a canonical encoding of a group element.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError>;
+}
+
+impl ScalarField for Scalar {
+    const ENCODED_LEN: usize = 32;
+
+    fn zero() -> Self {
+        Scalar::zero()
+    }
+
+    fn one() -> Self {
+        Scalar::one()
+    }
+
+    fn from_u64(x: u64) -> Self {
+        Scalar::from_u64(x)
+    }
+
+    fn invert(&self) -> Self {
+        Scalar::invert(self)
+    }
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        Scalar::from_bytes_mod_order_wide(bytes)
+    }
+
+    fn random<R: Rng>(rng: &mut R) -> Self {
+        Scalar::random(rng)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        util::read_scalar(bytes)
+    }
+}
+
+impl Group for RistrettoPoint {
+    type Scalar = Scalar;
+
+    const ENCODED_LEN: usize = 32;
+
+    fn identity() -> Self {
+        <RistrettoPoint as Identity>::identity()
+    }
+
+    fn scalar_mul(&self, s: &Scalar) -> Self {
+        self * s
+    }
+
+    fn multiscalar_mul(scalars: &[Scalar], points: &[Self]) -> Result<Self, ProofError> {
+        if scalars.len() != points.len() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(util::multiscalar_mul(scalars, points))
+    }
+
+    fn vartime_multiscalar_mul(scalars: &[Scalar], points: &[Self]) -> Result<Self, ProofError> {
+        util::vartime_multiscalar_mul(scalars, points)
+    }
+
+    fn hash_from_bytes(bytes: &[u8]) -> Self {
+        let mut hash = Sha512::default();
+        hash.input(bytes);
+        RistrettoPoint::from_hash(hash)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.compress().as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        util::read_point(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::PedersenGenerators;
+
+    fn commit<G: Group>(v: G::Scalar, v_blinding: G::Scalar, B: G, B_blinding: G) -> G {
+        G::multiscalar_mul(&[v, v_blinding], &[B, B_blinding]).unwrap()
+    }
+
+    #[test]
+    fn ristretto_implements_the_group_interface() {
+        let pg = PedersenGenerators::default();
+        let v = <Scalar as ScalarField>::from_u64(7);
+        let v_blinding = <Scalar as ScalarField>::from_u64(11);
+        let V = commit(v, v_blinding, pg.B, pg.B_blinding);
+        assert_eq!(V, pg.commit(v, v_blinding));
+        assert_eq!(
+            <RistrettoPoint as Group>::vartime_multiscalar_mul(&[v], &[pg.B, pg.B_blinding]),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(
+            V - pg.B.scalar_mul(&v) - pg.B_blinding.scalar_mul(&v_blinding),
+            <RistrettoPoint as Group>::identity()
+        );
+
+        let bytes = Group::to_bytes(&V);
+        assert_eq!(bytes.len(), <RistrettoPoint as Group>::ENCODED_LEN);
+        assert_eq!(<RistrettoPoint as Group>::from_bytes(&bytes), Ok(V));
+        assert!(<Scalar as ScalarField>::from_bytes(&[0xff; 32]).is_err());
+        assert_eq!(v * ScalarField::invert(&v), <Scalar as ScalarField>::one());
+    }
+}
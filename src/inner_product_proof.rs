@@ -2,20 +2,38 @@
 
 #![doc(include = "../docs/inner-product-protocol.md")]
 
-use std::iter;
-use std::borrow::Borrow;
+use core::iter;
+use core::borrow::Borrow;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
 use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::ristretto;
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+
+use subtle::{Choice, ConstantTimeEq};
 
+use errors::ProofError;
+use generators::PedersenGenerators;
 use proof_transcript::ProofTranscript;
+use secret::SecretScalars;
+
+use util;
 
+use backend::{DefaultBackend, MultiscalarMul};
+
+use profiling;
+
+/// An inner-product argument, the final part of a range proof or an
+/// R1CS proof.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct InnerProductProof {
     pub(crate) L_vec: Vec<RistrettoPoint>,
     pub(crate) R_vec: Vec<RistrettoPoint>,
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
     pub(crate) a: Scalar,
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
     pub(crate) b: Scalar,
 }
 
@@ -28,6 +46,10 @@ impl InnerProductProof {
     /// The `verifier` is passed in as a parameter so that the
     /// challenges depend on the *entire* transcript (including parent
     /// protocols).
+    ///
+    /// Returns `InvalidBitsize` if `a_vec` and `b_vec` do not have the
+    /// same power-of-two length, and `InvalidGeneratorsLength` if
+    /// `G_vec` and `H_vec` do not have that length too.
     pub fn create<I>(
         verifier: &mut ProofTranscript,
         Q: &RistrettoPoint,
@@ -36,32 +58,61 @@ impl InnerProductProof {
         mut H_vec: Vec<RistrettoPoint>,
         mut a_vec: Vec<Scalar>,
         mut b_vec: Vec<Scalar>,
-    ) -> InnerProductProof
+    ) -> Result<InnerProductProof, ProofError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Scalar>,
+    {
+        InnerProductProof::create_in_place(
+            &DefaultBackend,
+            verifier,
+            Q,
+            Hprime_factors,
+            &mut G_vec[..],
+            &mut H_vec[..],
+            &mut a_vec[..],
+            &mut b_vec[..],
+        )
+    }
+
+    /// Create an inner-product proof, using the input slices as
+    /// working memory.
+    ///
+    /// The contents of `G`, `H`, `a`, `b` are overwritten.  Returns the
+    /// errors of `create`.
+    pub(crate) fn create_in_place<I, M>(
+        backend: &M,
+        verifier: &mut ProofTranscript,
+        Q: &RistrettoPoint,
+        Hprime_factors: I,
+        mut G: &mut [RistrettoPoint],
+        mut H: &mut [RistrettoPoint],
+        mut a: &mut [Scalar],
+        mut b: &mut [Scalar],
+    ) -> Result<InnerProductProof, ProofError>
     where
         I: IntoIterator,
         I::Item: Borrow<Scalar>,
+        M: MultiscalarMul,
     {
-        // Create slices G, H, a, b backed by their respective
-        // vectors.  This lets us reslice as we compress the lengths
-        // of the vectors in the main loop below.
-        let mut G = &mut G_vec[..];
-        let mut H = &mut H_vec[..];
-        let mut a = &mut a_vec[..];
-        let mut b = &mut b_vec[..];
-
-        let mut n = G.len();
-
-        // All of the input vectors must have the same length.
-        assert_eq!(G.len(), n);
-        assert_eq!(H.len(), n);
-        assert_eq!(a.len(), n);
-        assert_eq!(b.len(), n);
+        // The slices G, H, a, b are resliced as we compress their
+        // lengths in the main loop below.
+        let mut n = a.len();
+
+        // All of the input vectors must have the same power-of-two length.
+        if !n.is_power_of_two() || b.len() != n {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if G.len() != n || H.len() != n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
 
         // XXX save these scalar mults by unrolling them into the
         // first iteration of the loop below
         for (H_i, h_i) in H.iter_mut().zip(Hprime_factors.into_iter()) {
             *H_i = (&*H_i) * h_i.borrow();
         }
+        profiling::record_scalar_muls(n);
 
         let lg_n = n.next_power_of_two().trailing_zeros() as usize;
         let mut L_vec = Vec::with_capacity(lg_n);
@@ -77,15 +128,17 @@ impl InnerProductProof {
             let c_L = inner_product(&a_L, &b_R);
             let c_R = inner_product(&a_R, &b_L);
 
-            let L = ristretto::vartime::multiscalar_mul(
-                a_L.iter().chain(b_R.iter()).chain(iter::once(&c_L)),
-                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
-            );
+            let L_scalars: Vec<Scalar> =
+                a_L.iter().chain(b_R.iter()).chain(iter::once(&c_L)).cloned().collect();
+            let L_points: Vec<RistrettoPoint> =
+                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)).cloned().collect();
+            let L = backend.vartime_multiscalar_mul(&L_scalars, &L_points)?;
 
-            let R = ristretto::vartime::multiscalar_mul(
-                a_R.iter().chain(b_L.iter()).chain(iter::once(&c_R)),
-                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
-            );
+            let R_scalars: Vec<Scalar> =
+                a_R.iter().chain(b_L.iter()).chain(iter::once(&c_R)).cloned().collect();
+            let R_points: Vec<RistrettoPoint> =
+                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)).cloned().collect();
+            let R = backend.vartime_multiscalar_mul(&R_scalars, &R_points)?;
 
             L_vec.push(L);
             R_vec.push(R);
@@ -93,15 +146,15 @@ impl InnerProductProof {
             verifier.commit(L.compress().as_bytes());
             verifier.commit(R.compress().as_bytes());
 
-            let u = verifier.challenge_scalar();
+            let u = verifier.challenge_scalar_labeled(b"u");
             let u_inv = u.invert();
 
             for i in 0..n {
                 a_L[i] = a_L[i] * u + u_inv * a_R[i];
                 b_L[i] = b_L[i] * u_inv + u * b_R[i];
-                G_L[i] = ristretto::vartime::multiscalar_mul(&[u_inv, u], &[G_L[i], G_R[i]]);
-                H_L[i] = ristretto::vartime::multiscalar_mul(&[u, u_inv], &[H_L[i], H_R[i]]);
             }
+            fold_points(u_inv, u, G_L, G_R);
+            fold_points(u, u_inv, H_L, H_R);
 
             a = a_L;
             b = b_L;
@@ -109,24 +162,40 @@ impl InnerProductProof {
             H = H_L;
         }
 
-        return InnerProductProof {
+        Ok(InnerProductProof {
             L_vec: L_vec,
             R_vec: R_vec,
             a: a[0],
             b: b[0],
-        };
+        })
     }
 
-    /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
-    /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
-    pub(crate) fn verification_scalars(
+    /// Checks that the proof is an argument over vectors of length `n`:
+    /// that `n` is a power of two below \\(2\^{32}\\), and that there are
+    /// \\(\lg n\\) points \\(L\_j\\) and as many \\(R\_j\\).
+    ///
+    /// Returns `FormatError` otherwise.  The verification scalars are
+    /// only computed after this check, so that no caller can forget it.
+    pub(crate) fn check_rounds(&self, n: usize) -> Result<(), ProofError> {
+        let lg_n = n.trailing_zeros() as usize;
+        if !n.is_power_of_two()
+            || lg_n >= 32
+            || self.L_vec.len() != lg_n
+            || self.R_vec.len() != lg_n
+        {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+
+    /// Recomputes the challenges \\(u\_k, \ldots, u\_1\\) in creation order
+    /// from the transcript, along with their inverses and the inverse
+    /// of their product.
+    pub(crate) fn challenges(
         &self,
         transcript: &mut ProofTranscript,
-    ) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
+    ) -> (Vec<Scalar>, Vec<Scalar>, Scalar) {
         let lg_n = self.L_vec.len();
-        let n = 1 << lg_n;
-
-        // 1. Recompute x_k,...,x_1 based on the proof transcript
 
         let mut challenges = Vec::with_capacity(lg_n);
         for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
@@ -134,13 +203,32 @@ impl InnerProductProof {
             transcript.commit(L.compress().as_bytes());
             transcript.commit(R.compress().as_bytes());
 
-            challenges.push(transcript.challenge_scalar());
+            challenges.push(transcript.challenge_scalar_labeled(b"u"));
         }
 
+        let mut challenges_inv = challenges.clone();
+        let allinv = util::batch_invert(&mut challenges_inv);
+
+        (challenges, challenges_inv, allinv)
+    }
+
+    /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
+    /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
+    ///
+    /// Returns `FormatError` if the proof is not an argument over
+    /// vectors of length `n`, as `check_rounds` does.
+    pub(crate) fn verification_scalars(
+        &self,
+        transcript: &mut ProofTranscript,
+        n: usize,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
+        self.check_rounds(n)?;
+        let lg_n = self.L_vec.len();
+
+        // 1. Recompute x_k,...,x_1 based on the proof transcript
         // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1
 
-        let mut challenges_inv = challenges.clone();
-        let allinv = Scalar::batch_invert(&mut challenges_inv);
+        let (mut challenges, mut challenges_inv, allinv) = self.challenges(transcript);
 
         // 3. Compute u_i^2 and (1/u_i)^2
 
@@ -165,13 +253,34 @@ impl InnerProductProof {
             s.push(s[i - k] * u_lg_i_sq);
         }
 
-        (challenges_sq, challenges_inv_sq, s)
+        Ok((challenges_sq, challenges_inv_sq, s))
+    }
+
+    /// Like `verification_scalars`, but returns the \\(s\_i\\) as an
+    /// iterator computing each value on demand, instead of a vector.
+    ///
+    /// The iterator holds only the \\(2 \lg n\\) challenges, at the cost of
+    /// \\(O(\lg n)\\) multiplications per \\(s\_i\\) instead of one.  It is
+    /// only lazy: a caller that collects it, or hands it to a multiscalar
+    /// multiplication that does, still holds all \\(n\\) of them.
+    pub(crate) fn verification_scalars_lazy(
+        &self,
+        transcript: &mut ProofTranscript,
+        n: usize,
+    ) -> Result<(Vec<Scalar>, Vec<Scalar>, LazyS), ProofError> {
+        self.check_rounds(n)?;
+        let (challenges, challenges_inv, _) = self.challenges(transcript);
+        Ok(LazyS::with_squares(challenges, challenges_inv))
     }
 
     /// This method is for testing that proof generation work,
     /// but for efficiency the actual protocols would use `verification_scalars`
     /// method to combine inner product verification with other checks
     /// in a single multiscalar multiplication.
+    ///
+    /// Returns `InvalidGeneratorsLength` unless `G`, `H` and
+    /// `Hprime_factors` have the same number \\(n\\) of elements, and
+    /// `FormatError` unless the proof has \\(\lg n\\) rounds.
     #[allow(dead_code)]
     pub fn verify<I>(
         &self,
@@ -181,12 +290,23 @@ impl InnerProductProof {
         Q: &RistrettoPoint,
         G: &[RistrettoPoint],
         H: &[RistrettoPoint],
-    ) -> Result<(), ()>
+    ) -> Result<(), ProofError>
     where
         I: IntoIterator,
         I::Item: Borrow<Scalar>,
     {
-        let (u_sq, u_inv_sq, s) = self.verification_scalars(transcript);
+        let n = G.len();
+        if H.len() != n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        // The factors may be an endless iterator such as `exp_iter`.
+        let Hprime_factors: Vec<Scalar> =
+            Hprime_factors.into_iter().take(n).map(|h_i| *h_i.borrow()).collect();
+        if Hprime_factors.len() != n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let (u_sq, u_inv_sq, s) = self.verification_scalars(transcript, n)?;
 
         let a_times_s = s.iter().map(|s_i| self.a * s_i);
 
@@ -194,14 +314,14 @@ impl InnerProductProof {
         let inv_s = s.iter().rev();
 
         let h_times_b_div_s = Hprime_factors
-            .into_iter()
+            .iter()
             .zip(inv_s)
-            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
+            .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i);
 
         let neg_u_sq = u_sq.iter().map(|ui| -ui);
         let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
 
-        let expect_P = ristretto::vartime::multiscalar_mul(
+        let expect_P = util::vartime_multiscalar_mul(
             iter::once(self.a * self.b)
                 .chain(a_times_s)
                 .chain(h_times_b_div_s)
@@ -212,16 +332,403 @@ impl InnerProductProof {
                 .chain(H.iter())
                 .chain(self.L_vec.iter())
                 .chain(self.R_vec.iter()),
-        );
+        )?;
+
+        if expect_P.ct_eq(P).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the points \\(L\_j\\), in creation order.
+    pub fn L_vec(&self) -> &[RistrettoPoint] {
+        &self.L_vec
+    }
+
+    /// Returns the points \\(R\_j\\), in creation order.
+    pub fn R_vec(&self) -> &[RistrettoPoint] {
+        &self.R_vec
+    }
+
+    /// Returns the final scalar \\(a\\).
+    pub fn a(&self) -> &Scalar {
+        &self.a
+    }
+
+    /// Returns the final scalar \\(b\\).
+    pub fn b(&self) -> &Scalar {
+        &self.b
+    }
+
+    /// Serializes the proof as the 32-byte encodings of
+    /// \\(L\_0, R\_0, \ldots, L\_{k-1}, R\_{k-1}, a, b\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity((2 * self.L_vec.len() + 2) * 32);
+        for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            buf.extend_from_slice(L.compress().as_bytes());
+            buf.extend_from_slice(R.compress().as_bytes());
+        }
+        buf.extend_from_slice(self.a.as_bytes());
+        buf.extend_from_slice(self.b.as_bytes());
+        buf
+    }
+
+    /// Serializes the proof into `buf`, which must have the length of
+    /// its `to_bytes`.
+    pub(crate) fn write_bytes(&self, buf: &mut [u8]) {
+        let (points, scalars) = buf.split_at_mut(64 * self.L_vec.len());
+        for (pair, (L, R)) in points
+            .chunks_mut(64)
+            .zip(self.L_vec.iter().zip(self.R_vec.iter()))
+        {
+            pair[..32].copy_from_slice(L.compress().as_bytes());
+            pair[32..].copy_from_slice(R.compress().as_bytes());
+        }
+        scalars[..32].copy_from_slice(self.a.as_bytes());
+        scalars[32..].copy_from_slice(self.b.as_bytes());
+    }
+
+    /// Deserializes a proof serialized with `to_bytes`.
+    ///
+    /// Returns an error if the length is wrong, or if a point or scalar
+    /// is not a canonical encoding.
+    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
+        if slice.len() % 64 != 0 || slice.is_empty() || slice.len() > 64 * 33 {
+            return Err(ProofError::FormatError);
+        }
+        let lg_n = slice.len() / 64 - 1;
+        let mut L_vec = Vec::with_capacity(lg_n);
+        let mut R_vec = Vec::with_capacity(lg_n);
+        for pair in slice[..64 * lg_n].chunks(64) {
+            L_vec.push(util::read_point(&pair[..32])?);
+            R_vec.push(util::read_point(&pair[32..])?);
+        }
+        let a = util::read_scalar(&slice[64 * lg_n..64 * lg_n + 32])?;
+        let b = util::read_scalar(&slice[64 * lg_n + 32..])?;
+        Ok(InnerProductProof { L_vec, R_vec, a, b })
+    }
+}
+
+/// The witness of a protocol whose final step is an inner product
+/// argument: vectors \\(\mathbf{a}\\), \\(\mathbf{b}\\) for the bases
+/// \\(G\_i \cdot \texttt{G\\_factors}\_i\\) and \\(H\_i \cdot \texttt{H\\_factors}\_i\\).
+#[derive(Clone)]
+pub(crate) struct ReducedWitness {
+    /// Factors for the generators \\(\mathbf{G}\\)
+    pub(crate) G_factors: Vec<Scalar>,
+    /// Factors for the generators \\(\mathbf{H}\\)
+    pub(crate) H_factors: Vec<Scalar>,
+    /// The vector \\(\mathbf{a}\\)
+    pub(crate) a: SecretScalars,
+    /// The vector \\(\mathbf{b}\\)
+    pub(crate) b: SecretScalars,
+}
+
+impl ReducedWitness {
+    /// Appends the witness `other`, which uses the generators following
+    /// this one's, weighting its inner product by `rho`.
+    ///
+    /// The combined inner product is \\(c\_1 + \rho c\_2\\).  `rho` must be
+    /// a challenge drawn after both statements are committed, so that a
+    /// prover cannot trade an error in one inner product for the other.
+    pub(crate) fn concat(mut self, other: ReducedWitness, rho: &Scalar) -> ReducedWitness {
+        let rho_inv = rho.invert();
+        self.G_factors.extend(other.G_factors);
+        self.H_factors
+            .extend(other.H_factors.iter().map(|h_i| h_i * rho_inv));
+        self.a.extend(other.a.iter().cloned());
+        self.b.extend(other.b.iter().map(|b_i| b_i * rho));
+        self
+    }
+
+    /// Pads the vectors with zeros to length `n`.
+    pub(crate) fn pad(&mut self, n: usize) {
+        self.G_factors.resize(n, Scalar::one());
+        self.H_factors.resize(n, Scalar::one());
+        self.a.resize(n, Scalar::zero());
+        self.b.resize(n, Scalar::zero());
+    }
+
+    /// Draws the challenge \\(w\\) and creates the inner product argument
+    /// over the generators `G`, `H`, with \\(Q = w B\\).
+    ///
+    /// Returns the errors of `InnerProductProof::create`.
+    pub(crate) fn prove(
+        self,
+        transcript: &mut ProofTranscript,
+        pedersen_generators: &PedersenGenerators,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<InnerProductProof, ProofError> {
+        let w = transcript.challenge_scalar_labeled(b"w");
+        let Q = w * pedersen_generators.B;
+
+        let ReducedWitness {
+            G_factors,
+            H_factors,
+            mut a,
+            mut b,
+        } = self;
+        let mut G: Vec<RistrettoPoint> = G.iter()
+            .zip(G_factors.iter())
+            .map(|(G_i, g_i)| if *g_i == Scalar::one() { *G_i } else { G_i * g_i })
+            .collect();
+        let mut H = H.to_vec();
+        InnerProductProof::create_in_place(
+            &DefaultBackend,
+            transcript,
+            &Q,
+            H_factors,
+            &mut G,
+            &mut H,
+            &mut a,
+            &mut b,
+        )
+    }
+}
+
+/// The scalars of the verification equation of a protocol whose final
+/// step is an inner product argument, except those of the argument.
+///
+/// The argument shows that the vectors committed with the bases
+/// \\(G\_i \cdot \texttt{G\\_factors}\_i\\) and \\(H\_i \cdot \texttt{H\\_factors}\_i\\)
+/// have the inner product `t_x`.
+pub(crate) struct ReducedStatement {
+    /// The claimed inner product
+    pub(crate) t_x: Scalar,
+    /// Scalar for the Pedersen base \\(B\\)
+    pub(crate) B: Scalar,
+    /// Scalar for the Pedersen blinding base \\(\widetilde{B}\\)
+    pub(crate) B_blinding: Scalar,
+    /// Scalars for the generators \\(\mathbf{G}\\)
+    pub(crate) G: Vec<Scalar>,
+    /// Scalars for the generators \\(\mathbf{H}\\)
+    pub(crate) H: Vec<Scalar>,
+    /// Factors for the generators \\(\mathbf{G}\\) in the argument
+    pub(crate) G_factors: Vec<Scalar>,
+    /// Factors for the generators \\(\mathbf{H}\\) in the argument
+    pub(crate) H_factors: Vec<Scalar>,
+    /// Scalars for `proof_points`
+    pub(crate) proof_scalars: Vec<Scalar>,
+    /// The protocol's commitments, other than the argument's
+    pub(crate) proof_points: Vec<RistrettoPoint>,
+}
+
+impl ReducedStatement {
+    /// Appends the statement `other`, which uses the generators
+    /// following this one's, weighting its inner product by `rho`, as
+    /// in `ReducedWitness::concat`.
+    pub(crate) fn concat(mut self, other: ReducedStatement, rho: &Scalar) -> ReducedStatement {
+        let rho_inv = rho.invert();
+        self.t_x = self.t_x + rho * other.t_x;
+        self.B = self.B + other.B;
+        self.B_blinding = self.B_blinding + other.B_blinding;
+        self.G.extend(other.G);
+        self.H.extend(other.H);
+        self.G_factors.extend(other.G_factors);
+        self.H_factors
+            .extend(other.H_factors.iter().map(|h_i| h_i * rho_inv));
+        self.proof_scalars.extend(other.proof_scalars);
+        self.proof_points.extend(other.proof_points);
+        self
+    }
+
+    /// Pads the statement with zero entries to length `n`.
+    pub(crate) fn pad(&mut self, n: usize) {
+        self.G.resize(n, Scalar::zero());
+        self.H.resize(n, Scalar::zero());
+        self.G_factors.resize(n, Scalar::one());
+        self.H_factors.resize(n, Scalar::one());
+    }
+
+    /// Draws the challenge \\(w\\) and checks the statement together with
+    /// the inner product argument `proof` over the generators `G`, `H`.
+    pub(crate) fn verify(
+        self,
+        proof: &InnerProductProof,
+        transcript: &mut ProofTranscript,
+        pedersen_generators: &PedersenGenerators,
+        G: &[RistrettoPoint],
+        H: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        let n = self.G.len();
+        if G.len() != n || H.len() != n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let w = transcript.challenge_scalar_labeled(b"w");
+        let (u_sq, u_inv_sq, s) = proof.verification_scalars(transcript, n)?;
+        let a = proof.a;
+        let b = proof.b;
+
+        let g = self.G
+            .iter()
+            .zip(self.G_factors.iter())
+            .zip(s.iter())
+            .map(|((g_i, g_factor), s_i)| g_i - a * s_i * g_factor);
+        // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
+        let h = self.H
+            .iter()
+            .zip(self.H_factors.iter())
+            .zip(s.iter().rev())
+            .map(|((h_i, h_factor), s_i_inv)| h_i - b * s_i_inv * h_factor);
+
+        let mega_check = util::vartime_multiscalar_mul(
+            iter::once(self.B + w * (self.t_x - a * b))
+                .chain(iter::once(self.B_blinding))
+                .chain(g)
+                .chain(h)
+                .chain(self.proof_scalars.iter().cloned())
+                .chain(u_sq.into_iter())
+                .chain(u_inv_sq.into_iter()),
+            iter::once(&pedersen_generators.B)
+                .chain(iter::once(&pedersen_generators.B_blinding))
+                .chain(G.iter())
+                .chain(H.iter())
+                .chain(self.proof_points.iter())
+                .chain(proof.L_vec.iter())
+                .chain(proof.R_vec.iter()),
+        )?;
 
-        if expect_P == *P {
+        if mega_check.is_identity() {
             Ok(())
         } else {
-            Err(())
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// An iterator over the verification scalars \\(s\_i\\), computed on demand.
+///
+/// Each \\(s\_i\\) is the product over the rounds \\(j\\) of \\(u\_j\\) if
+/// bit \\(j - 1\\) of \\(i\\) is set, and of \\(u\_j^{-1}\\) otherwise.
+#[derive(Clone)]
+pub(crate) struct LazyS {
+    /// The challenges in creation order \\(u\_k, \ldots, u\_1\\)
+    challenges: Vec<Scalar>,
+    /// Their inverses, in the same order
+    challenges_inv: Vec<Scalar>,
+    /// Index of the next \\(s\_i\\) from the front
+    front: usize,
+    /// One past the index of the next \\(s\_i\\) from the back
+    back: usize,
+}
+
+impl LazyS {
+    /// Returns the squares of `challenges` and `challenges_inv`, in
+    /// creation order, and the iterator over the \\(s\_i\\) for them.
+    pub(crate) fn with_squares(
+        challenges: Vec<Scalar>,
+        challenges_inv: Vec<Scalar>,
+    ) -> (Vec<Scalar>, Vec<Scalar>, LazyS) {
+        let challenges_sq = challenges.iter().map(|u| u * u).collect();
+        let challenges_inv_sq = challenges_inv.iter().map(|u| u * u).collect();
+        let back = 1 << challenges.len();
+        let s = LazyS {
+            challenges,
+            challenges_inv,
+            front: 0,
+            back,
+        };
+        (challenges_sq, challenges_inv_sq, s)
+    }
+
+    fn s(&self, i: usize) -> Scalar {
+        let lg_n = self.challenges.len();
+        let mut s_i = Scalar::one();
+        for bit in 0..lg_n {
+            // u_{bit+1} is indexed by (lg_n-1) - bit in creation order.
+            let j = (lg_n - 1) - bit;
+            if (i >> bit) & 1 == 1 {
+                s_i *= self.challenges[j];
+            } else {
+                s_i *= self.challenges_inv[j];
+            }
+        }
+        s_i
+    }
+}
+
+impl Iterator for LazyS {
+    type Item = Scalar;
+
+    fn next(&mut self) -> Option<Scalar> {
+        if self.front == self.back {
+            return None;
+        }
+        let s_i = self.s(self.front);
+        self.front += 1;
+        Some(s_i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for LazyS {
+    fn next_back(&mut self) -> Option<Scalar> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.s(self.back))
+    }
+}
+
+impl ConstantTimeEq for InnerProductProof {
+    fn ct_eq(&self, other: &InnerProductProof) -> Choice {
+        // The number of rounds is public, so it is fine to exit early on it.
+        if self.L_vec.len() != other.L_vec.len() || self.R_vec.len() != other.R_vec.len() {
+            return Choice::from(0);
         }
+        let mut result = self.a.ct_eq(&other.a) & self.b.ct_eq(&other.b);
+        for (L, other_L) in self.L_vec.iter().zip(other.L_vec.iter()) {
+            result = result & L.ct_eq(other_L);
+        }
+        for (R, other_R) in self.R_vec.iter().zip(other.R_vec.iter()) {
+            result = result & R.ct_eq(other_R);
+        }
+        result
+    }
+}
+
+impl PartialEq for InnerProductProof {
+    fn eq(&self, other: &InnerProductProof) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for InnerProductProof {}
+
+/// Folds the generators for the next round of the argument,
+/// setting \\(P\_{L,i} \gets x\_L P\_{L,i} + x\_R P\_{R,i}\\).
+#[cfg(not(feature = "parallel"))]
+fn fold_points(x_L: Scalar, x_R: Scalar, P_L: &mut [RistrettoPoint], P_R: &[RistrettoPoint]) {
+    for (P_L_i, P_R_i) in P_L.iter_mut().zip(P_R.iter()) {
+        *P_L_i = util::vartime_multiscalar_mul(&[x_L, x_R], &[*P_L_i, *P_R_i])
+            .expect("two scalars and two points");
     }
 }
 
+/// Folds the generators for the next round of the argument,
+/// setting \\(P\_{L,i} \gets x\_L P\_{L,i} + x\_R P\_{R,i}\\).
+///
+/// Each entry is computed independently, so the result is identical
+/// to the serial version.
+#[cfg(feature = "parallel")]
+fn fold_points(x_L: Scalar, x_R: Scalar, P_L: &mut [RistrettoPoint], P_R: &[RistrettoPoint]) {
+    use rayon::prelude::*;
+
+    P_L.par_iter_mut()
+        .zip(P_R.par_iter())
+        .for_each(|(P_L_i, P_R_i)| {
+            *P_L_i = util::vartime_multiscalar_mul(&[x_L, x_R], &[*P_L_i, *P_R_i])
+            .expect("two scalars and two points");
+        });
+}
 
 /// Computes an inner product of two vectors
 /// \\[
@@ -252,7 +759,7 @@ mod tests {
         let mut rng = OsRng::new().unwrap();
 
         use generators::{PedersenGenerators,Generators};
-        let gens = Generators::new(PedersenGenerators::default(), n, 1);
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
         let G = gens.share(0).G.to_vec();
         let H = gens.share(0).H.to_vec();
 
@@ -276,12 +783,12 @@ mod tests {
         // a.iter() has Item=&Scalar, need Item=Scalar to chain with b_prime
         let a_prime = a.iter().cloned();
 
-        let P = ristretto::vartime::multiscalar_mul(
+        let P = util::vartime_multiscalar_mul(
             a_prime.chain(b_prime).chain(iter::once(c)),
             G.iter().chain(H.iter()).chain(iter::once(&Q)),
-        );
+        ).unwrap();
 
-        let mut verifier = ProofTranscript::new(b"innerproducttest");
+        let mut verifier = ProofTranscript::new(b"innerproducttest").unwrap();
         let proof = InnerProductProof::create(
             &mut verifier,
             &Q,
@@ -290,14 +797,132 @@ mod tests {
             H.clone(),
             a.clone(),
             b.clone(),
-        );
+        ).unwrap();
 
-        let mut verifier = ProofTranscript::new(b"innerproducttest");
+        let mut verifier = ProofTranscript::new(b"innerproducttest").unwrap();
         assert!(
             proof
                 .verify(&mut verifier, util::exp_iter(y_inv), &P, &Q, &G, &H)
                 .is_ok()
         );
+
+        let mut verifier = ProofTranscript::new(b"innerproducttest").unwrap();
+        assert_eq!(
+            proof.verify(&mut verifier, util::exp_iter(y_inv), &P, &Q, &G[1..], &H),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
+    #[test]
+    fn lazy_s_matches_s_vector() {
+        let mut rng = OsRng::new().unwrap();
+        let n = 16;
+
+        use generators::{PedersenGenerators,Generators};
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let Q = RistrettoPoint::hash_from_bytes::<Sha512>(b"test point");
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+        let proof = InnerProductProof::create(
+            &mut transcript,
+            &Q,
+            util::exp_iter(Scalar::one()),
+            gens.share(0).G.to_vec(),
+            gens.share(0).H.to_vec(),
+            a,
+            b,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+        let (u_sq, u_inv_sq, s) = proof.verification_scalars(&mut transcript, n).unwrap();
+        let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+        let (lazy_u_sq, lazy_u_inv_sq, lazy_s) =
+            proof.verification_scalars_lazy(&mut transcript, n).unwrap();
+
+        assert_eq!(u_sq, lazy_u_sq);
+        assert_eq!(u_inv_sq, lazy_u_inv_sq);
+        assert_eq!(s, lazy_s.clone().collect::<Vec<_>>());
+        assert_eq!(
+            s.iter().rev().cloned().collect::<Vec<_>>(),
+            lazy_s.rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn mismatched_rounds_are_rejected() {
+        let mut rng = OsRng::new().unwrap();
+        let n = 8;
+
+        use generators::{PedersenGenerators,Generators};
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let Q = RistrettoPoint::hash_from_bytes::<Sha512>(b"test point");
+        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+        let proof = InnerProductProof::create(
+            &mut transcript,
+            &Q,
+            util::exp_iter(Scalar::one()),
+            gens.share(0).G.to_vec(),
+            gens.share(0).H.to_vec(),
+            a,
+            b,
+        ).unwrap();
+        assert!(proof.check_rounds(n).is_ok());
+
+        let mut short_R = proof.clone();
+        short_R.R_vec.pop();
+        let mut long_L = proof.clone();
+        long_L.L_vec.push(Q);
+        for bad in [short_R, long_L].iter() {
+            let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+            assert_eq!(
+                bad.verification_scalars(&mut transcript, n).err(),
+                Some(ProofError::FormatError)
+            );
+            let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+            assert!(bad.verification_scalars_lazy(&mut transcript, n).is_err());
+        }
+        assert_eq!(proof.check_rounds(n / 2), Err(ProofError::FormatError));
+        assert_eq!(proof.check_rounds(n + 1), Err(ProofError::FormatError));
+    }
+
+    #[test]
+    fn malformed_vectors_are_rejected() {
+        let n = 4;
+
+        use generators::{PedersenGenerators,Generators};
+        let gens = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let Q = RistrettoPoint::hash_from_bytes::<Sha512>(b"test point");
+        let G = gens.share(0).G.to_vec();
+        let H = gens.share(0).H.to_vec();
+        let ones = vec![Scalar::one(); n];
+        let create = |G: &[RistrettoPoint], H: &[RistrettoPoint], a: &[Scalar], b: &[Scalar]| {
+            let mut transcript = ProofTranscript::new(b"innerproducttest").unwrap();
+            InnerProductProof::create(
+                &mut transcript,
+                &Q,
+                util::exp_iter(Scalar::one()),
+                G.to_vec(),
+                H.to_vec(),
+                a.to_vec(),
+                b.to_vec(),
+            ).err()
+        };
+
+        assert_eq!(create(&G, &H, &ones, &ones), None);
+        // Vectors that are empty, not a power of two, or of different lengths
+        let invalid_bitsize = Some(ProofError::InvalidBitsize);
+        assert_eq!(create(&[], &[], &[], &[]), invalid_bitsize);
+        assert_eq!(create(&G[..3], &H[..3], &ones[..3], &ones[..3]), invalid_bitsize);
+        assert_eq!(create(&G, &H, &ones, &ones[..2]), invalid_bitsize);
+        // Generators that do not match the vectors
+        let invalid_generators = Some(ProofError::InvalidGeneratorsLength);
+        assert_eq!(create(&G[..2], &H, &ones, &ones), invalid_generators);
+        assert_eq!(create(&G, &H[..2], &ones, &ones), invalid_generators);
     }
 
     #[test]
@@ -0,0 +1,368 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `issuance` module supports range proofs over commitments whose
+//! blinding factor is contributed by several parties, as in blind
+//! issuance.
+//!
+//! The user picks a `BlindingShare` \\(r\_U\\) and sends the issuer
+//! only its commitment \\(R\_U = r\_U \widetilde{B}\\), in a `ShareRequest`
+//! with a proof of knowledge of \\(r\_U\\).  The proof keeps the user
+//! from smuggling a value into the commitment: a request for
+//! \\(\delta B + r\_U \widetilde{B}\\) would otherwise be issued as a
+//! commitment to \\(v + \delta\\).  The issuer picks its own
+//! share \\(r\_I\\) and issues
+//! \\(V = v B + r\_I \widetilde{B} + r\_U \widetilde{B}\\), sending
+//! \\(V\\), \\(v\\) and \\(r\_I\\) to the user.  The user then combines the
+//! shares into the opening \\((v, r\_I + r\_U)\\) of \\(V\\), which checks
+//! that the shares really open \\(V\\) before any proof is made, and
+//! proves ranges over \\(V\\).  Neither party alone chooses the
+//! blinding factor.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let user_share = BlindingShare::random(&mut rng);
+//! let mut transcript = ProofTranscript::new(b"Issuance")?;
+//! let request = user_share.request(&pg, &mut transcript, &mut rng);
+//!
+//! let issuer_share = BlindingShare::random(&mut rng);
+//! let mut transcript = ProofTranscript::new(b"Issuance")?;
+//! let V = issuance::issue(&pg, 500, &request, &mut transcript, &issuer_share)?;
+//!
+//! let opening = CombinedOpening::combine(&pg, &V, 500, &[user_share, issuer_share])?;
+//! let mut transcript = ProofTranscript::new(b"Issued")?;
+//! let proof = opening.prove_range(generators.share(0), &mut transcript, &mut rng, 32)?;
+//! ```
+
+use core::fmt;
+
+use rand::Rng;
+use subtle::{Choice, ConstantTimeEq};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretScalar, SecretValue};
+use sigma::{SigmaProof, Statement};
+
+/// One party's share of the blinding factor of a commitment.
+#[derive(Clone)]
+pub struct BlindingShare {
+    /// The share \\(r\_i\\)
+    share: Blinding,
+}
+
+impl BlindingShare {
+    /// Picks a random share.
+    pub fn random<R: Rng>(rng: &mut R) -> BlindingShare {
+        BlindingShare {
+            share: Blinding::random(rng),
+        }
+    }
+
+    /// Wraps a share received from another party.
+    pub fn from_scalar(share: Scalar) -> BlindingShare {
+        BlindingShare {
+            share: Blinding::new(share),
+        }
+    }
+
+    /// Returns the share, to send to the owner of the commitment.
+    pub fn as_scalar(&self) -> &Scalar {
+        self.share.as_scalar()
+    }
+
+    /// Returns the commitment \\(r\_i \widetilde{B}\\) to the share, which
+    /// can be sent to other parties without revealing it.
+    pub fn commitment(&self, pg: &PedersenGenerators) -> RistrettoPoint {
+        pg.B_blinding * self.share.as_scalar()
+    }
+
+    /// Requests the issuance of a commitment blinded with this share,
+    /// proving knowledge of the share on the issuance `transcript`.
+    pub fn request<R: Rng>(
+        &self,
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> ShareRequest {
+        let commitment = self.commitment(pg);
+        let statement = Statement::dlog(&pg.B_blinding, &commitment);
+        let witness = [SecretScalar::new(*self.share.as_scalar())];
+        let proof = SigmaProof::prove(&statement, transcript, rng, &witness)
+            .expect("the witness matches the statement");
+        ShareRequest { commitment, proof }
+    }
+}
+
+/// A user's request for issuance: the commitment \\(R\_U = r\_U
+/// \widetilde{B}\\) to its blinding share, and a proof of knowledge of
+/// \\(r\_U\\).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShareRequest {
+    /// The commitment \\(R\_U\\) to the share
+    commitment: RistrettoPoint,
+    /// The proof of knowledge of the discrete log of \\(R\_U\\) to the
+    /// base \\(\widetilde{B}\\)
+    proof: SigmaProof,
+}
+
+impl ShareRequest {
+    /// Returns the commitment to the user's share.
+    pub fn commitment(&self) -> &RistrettoPoint {
+        &self.commitment
+    }
+}
+
+impl fmt::Debug for BlindingShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BlindingShare(..)")
+    }
+}
+
+impl ConstantTimeEq for BlindingShare {
+    fn ct_eq(&self, other: &BlindingShare) -> Choice {
+        self.as_scalar().ct_eq(other.as_scalar())
+    }
+}
+
+impl PartialEq for BlindingShare {
+    fn eq(&self, other: &BlindingShare) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for BlindingShare {}
+
+/// Issues a commitment to `value`, blinded with `issuer_share` and the
+/// share committed in the `request`.
+///
+/// Returns an error if the request's proof of knowledge of the share
+/// does not verify on the `transcript`, since the commitment could then
+/// hide a value of the user's choosing.
+pub fn issue(
+    pg: &PedersenGenerators,
+    value: u64,
+    request: &ShareRequest,
+    transcript: &mut ProofTranscript,
+    issuer_share: &BlindingShare,
+) -> Result<RistrettoPoint, ProofError> {
+    let statement = Statement::dlog(&pg.B_blinding, &request.commitment);
+    request.proof.verify(&statement, transcript)?;
+    Ok(pg.commit(Scalar::from_u64(value), *issuer_share.as_scalar()) + request.commitment)
+}
+
+/// The opening of a commitment, combined from the blinding shares of
+/// all parties.
+#[derive(Clone)]
+pub struct CombinedOpening {
+    /// The commitment
+    V: RistrettoPoint,
+    /// The committed value
+    value: SecretValue,
+    /// The sum of the blinding shares
+    blinding: Blinding,
+}
+
+impl CombinedOpening {
+    /// Combines the blinding `shares` of the commitment `V` to `value`.
+    ///
+    /// Returns `ProofError::InvalidOpening` if the value and the sum of
+    /// the shares do not open `V`, e.g. because a share is missing or
+    /// was tampered with.
+    pub fn combine(
+        pg: &PedersenGenerators,
+        V: &RistrettoPoint,
+        value: u64,
+        shares: &[BlindingShare],
+    ) -> Result<CombinedOpening, ProofError> {
+        let blinding = shares
+            .iter()
+            .fold(Scalar::zero(), |acc, share| acc + share.as_scalar());
+        let blinding = Blinding::new(blinding);
+        let value = SecretValue::new(value);
+        if pg.commit(value.to_scalar(), *blinding.as_scalar()) != *V {
+            return Err(ProofError::InvalidOpening);
+        }
+        Ok(CombinedOpening {
+            V: *V,
+            value,
+            blinding,
+        })
+    }
+
+    /// Returns the commitment.
+    pub fn commitment(&self) -> &RistrettoPoint {
+        &self.V
+    }
+
+    /// Returns the committed value.
+    pub fn value(&self) -> u64 {
+        self.value.value()
+    }
+
+    /// Returns the combined blinding factor.
+    pub fn blinding(&self) -> &Scalar {
+        self.blinding.as_scalar()
+    }
+
+    /// Proves that the committed value is in \\([0, 2^n)\\), for
+    /// verification with `RangeProof::verify` against the commitment.
+    ///
+    /// Returns the errors of `RangeProof::try_generate_proof`, such as
+    /// `ProofError::ValueOutOfRange` if the value does not fit in `n`
+    /// bits.
+    pub fn prove_range<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+    ) -> Result<RangeProof, ProofError> {
+        RangeProof::try_generate_proof(
+            generators,
+            transcript,
+            rng,
+            n,
+            &self.value,
+            &self.blinding,
+        )
+    }
+}
+
+impl fmt::Debug for CombinedOpening {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Only the commitment is public.
+        f.debug_struct("CombinedOpening").field("V", &self.V).finish()
+    }
+}
+
+impl ConstantTimeEq for CombinedOpening {
+    fn ct_eq(&self, other: &CombinedOpening) -> Choice {
+        self.V.ct_eq(&other.V)
+            & self.value.value().ct_eq(&other.value.value())
+            & self.blinding.as_scalar().ct_eq(other.blinding.as_scalar())
+    }
+}
+
+impl PartialEq for CombinedOpening {
+    fn eq(&self, other: &CombinedOpening) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for CombinedOpening {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+
+    fn transcript() -> ProofTranscript {
+        ProofTranscript::new(b"IssuanceTest").unwrap()
+    }
+
+    #[test]
+    fn issued_commitments_are_range_proven() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let pg = generators.share(0).pedersen_generators;
+        let mut rng = OsRng::new().unwrap();
+
+        let user_share = BlindingShare::random(&mut rng);
+        let request = user_share.request(pg, &mut transcript(), &mut rng);
+        let issuer_share = BlindingShare::random(&mut rng);
+        let V = issue(pg, 500, &request, &mut transcript(), &issuer_share).unwrap();
+
+        // The issuer's share is sent to the user as a scalar.
+        let received = BlindingShare::from_scalar(*issuer_share.as_scalar());
+        let opening =
+            CombinedOpening::combine(pg, &V, 500, &[user_share.clone(), received]).unwrap();
+
+        let proof = opening
+            .prove_range(generators.share(0), &mut transcript(), &mut rng, n)
+            .unwrap();
+        assert!(
+            proof
+                .verify(&V, generators.share(0), &mut transcript(), &mut rng, n)
+                .is_ok()
+        );
+
+        // Out of range values are rejected before proving.
+        let V = issue(pg, 1 << 20, &request, &mut transcript(), &issuer_share).unwrap();
+        let opening =
+            CombinedOpening::combine(pg, &V, 1 << 20, &[user_share, issuer_share]).unwrap();
+        assert!(
+            opening
+                .prove_range(generators.share(0), &mut transcript(), &mut rng, n)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn wrong_shares_are_rejected() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+
+        let user_share = BlindingShare::random(&mut rng);
+        let request = user_share.request(&pg, &mut transcript(), &mut rng);
+        let issuer_share = BlindingShare::random(&mut rng);
+        let V = issue(&pg, 500, &request, &mut transcript(), &issuer_share).unwrap();
+
+        assert!(CombinedOpening::combine(&pg, &V, 500, &[user_share.clone()]).is_err());
+        let shares = [user_share.clone(), issuer_share.clone()];
+        assert_eq!(
+            CombinedOpening::combine(&pg, &V, 501, &shares).err(),
+            Some(ProofError::InvalidOpening)
+        );
+        let tampered = BlindingShare::from_scalar(issuer_share.as_scalar() + Scalar::one());
+        assert!(CombinedOpening::combine(&pg, &V, 500, &[user_share, tampered]).is_err());
+    }
+
+    #[test]
+    fn requests_hiding_a_value_are_rejected() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+
+        let user_share = BlindingShare::random(&mut rng);
+        let issuer_share = BlindingShare::random(&mut rng);
+
+        // A commitment to 1000 + r_U B~, with the proof of an honest share.
+        let mut request = user_share.request(&pg, &mut transcript(), &mut rng);
+        request.commitment = pg.commit(Scalar::from_u64(1000), *user_share.as_scalar());
+        assert_eq!(
+            issue(&pg, 500, &request, &mut transcript(), &issuer_share),
+            Err(ProofError::VerificationError)
+        );
+
+        // A proof bound to another transcript.
+        let mut other = ProofTranscript::new(b"Other").unwrap();
+        let request = user_share.request(&pg, &mut other, &mut rng);
+        assert_eq!(
+            issue(&pg, 500, &request, &mut transcript(), &issuer_share),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn secrets_are_redacted_from_debug_output() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let share = BlindingShare::random(&mut rng);
+        let zero = BlindingShare::from_scalar(Scalar::zero());
+        let request = share.request(&pg, &mut transcript(), &mut rng);
+        let V = issue(&pg, 500, &request, &mut transcript(), &zero).unwrap();
+        let opening = CombinedOpening::combine(&pg, &V, 500, &[share.clone()]).unwrap();
+
+        assert_eq!(format!("{:?}", share), "BlindingShare(..)");
+        assert!(!format!("{:?}", opening).contains("blinding"));
+        assert_eq!(opening, opening.clone());
+        assert_ne!(share, BlindingShare::random(&mut rng));
+    }
+}
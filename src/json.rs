@@ -0,0 +1,220 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `json` module contains mirrors of the proof types whose points
+//! and scalars are base64 strings, for web services that pass proofs
+//! around as JSON.
+//!
+//! The mirrors derive `Serialize` and `Deserialize`, so any serde data
+//! format can carry them, and convert to the proof types with `TryFrom`,
+//! which checks every point and scalar and returns a `ProofError`, and
+//! back with `From`.  Strings are standard base64 with padding of the
+//! 32-byte encodings.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let json = serde_json::to_string(&RangeProofJson::from(&proof))?;
+//! let dto: RangeProofJson = serde_json::from_str(&json)?;
+//! let proof = RangeProof::try_from(dto)?;
+//! ```
+
+use core::convert::TryFrom;
+
+use base64;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use errors::ProofError;
+use inner_product_proof::InnerProductProof;
+use range_proof::{RangeCommitments, RangeProof};
+use util;
+
+/// A `RangeProof` with base64 fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RangeProofJson {
+    /// Commitment to the bits of the value
+    pub A: String,
+    /// Commitment to the blinding factors
+    pub S: String,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub T_1: String,
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    pub T_2: String,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub t_x: String,
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    pub t_x_blinding: String,
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    pub e_blinding: String,
+    /// Proof data for the inner-product argument
+    pub ipp_proof: InnerProductProofJson,
+}
+
+/// An `InnerProductProof` with base64 fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InnerProductProofJson {
+    /// The points \\(L\_j\\), in creation order
+    pub L_vec: Vec<String>,
+    /// The points \\(R\_j\\), in creation order
+    pub R_vec: Vec<String>,
+    /// The final scalar \\(a\\)
+    pub a: String,
+    /// The final scalar \\(b\\)
+    pub b: String,
+}
+
+/// A `ProofBundle` with base64 fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofBundleJson {
+    /// The range proofs, in the order of their statements
+    pub proofs: Vec<RangeProofJson>,
+}
+
+impl<'a> From<&'a RangeProof> for RangeProofJson {
+    fn from(proof: &'a RangeProof) -> Self {
+        let (c, ipp_proof) = proof.parts();
+        RangeProofJson {
+            A: point_to_base64(&c.A),
+            S: point_to_base64(&c.S),
+            T_1: point_to_base64(&c.T_1),
+            T_2: point_to_base64(&c.T_2),
+            t_x: base64::encode(c.t_x.as_bytes()),
+            t_x_blinding: base64::encode(c.t_x_blinding.as_bytes()),
+            e_blinding: base64::encode(c.e_blinding.as_bytes()),
+            ipp_proof: InnerProductProofJson::from(ipp_proof),
+        }
+    }
+}
+
+impl TryFrom<RangeProofJson> for RangeProof {
+    type Error = ProofError;
+
+    fn try_from(dto: RangeProofJson) -> Result<Self, ProofError> {
+        let commitments = RangeCommitments {
+            A: point_from_base64(&dto.A)?,
+            S: point_from_base64(&dto.S)?,
+            T_1: point_from_base64(&dto.T_1)?,
+            T_2: point_from_base64(&dto.T_2)?,
+            t_x: scalar_from_base64(&dto.t_x)?,
+            t_x_blinding: scalar_from_base64(&dto.t_x_blinding)?,
+            e_blinding: scalar_from_base64(&dto.e_blinding)?,
+        };
+        let ipp_proof = InnerProductProof::try_from(dto.ipp_proof)?;
+        Ok(RangeProof::from_parts(commitments, ipp_proof))
+    }
+}
+
+impl<'a> From<&'a InnerProductProof> for InnerProductProofJson {
+    fn from(proof: &'a InnerProductProof) -> Self {
+        InnerProductProofJson {
+            L_vec: proof.L_vec.iter().map(point_to_base64).collect(),
+            R_vec: proof.R_vec.iter().map(point_to_base64).collect(),
+            a: base64::encode(proof.a.as_bytes()),
+            b: base64::encode(proof.b.as_bytes()),
+        }
+    }
+}
+
+impl TryFrom<InnerProductProofJson> for InnerProductProof {
+    type Error = ProofError;
+
+    fn try_from(dto: InnerProductProofJson) -> Result<Self, ProofError> {
+        if dto.L_vec.len() != dto.R_vec.len() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(InnerProductProof {
+            L_vec: dto.L_vec
+                .iter()
+                .map(|s| point_from_base64(s))
+                .collect::<Result<_, _>>()?,
+            R_vec: dto.R_vec
+                .iter()
+                .map(|s| point_from_base64(s))
+                .collect::<Result<_, _>>()?,
+            a: scalar_from_base64(&dto.a)?,
+            b: scalar_from_base64(&dto.b)?,
+        })
+    }
+}
+
+impl<'a> From<&'a ProofBundle> for ProofBundleJson {
+    fn from(bundle: &'a ProofBundle) -> Self {
+        ProofBundleJson {
+            proofs: bundle.proofs().iter().map(RangeProofJson::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<ProofBundleJson> for ProofBundle {
+    type Error = ProofError;
+
+    fn try_from(dto: ProofBundleJson) -> Result<Self, ProofError> {
+        let proofs = dto.proofs
+            .into_iter()
+            .map(RangeProof::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(ProofBundle::from_proofs(proofs))
+    }
+}
+
+fn point_to_base64(P: &RistrettoPoint) -> String {
+    base64::encode(P.compress().as_bytes())
+}
+
+fn point_from_base64(s: &str) -> Result<RistrettoPoint, ProofError> {
+    util::read_point(&base64::decode(s).map_err(|_| ProofError::FormatError)?)
+}
+
+fn scalar_from_base64(s: &str) -> Result<Scalar, ProofError> {
+    util::read_scalar(&base64::decode(s).map_err(|_| ProofError::FormatError)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn proofs_convert_to_and_from_json_mirrors() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"JsonTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(42),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let dto = RangeProofJson::from(&proof);
+        assert_eq!(dto.A.len(), 44);
+        assert_eq!(dto.ipp_proof.L_vec.len(), 3);
+        let decoded = RangeProof::try_from(dto.clone()).unwrap();
+        assert_eq!(RangeProofJson::from(&decoded), dto);
+
+        let mut bad = dto.clone();
+        bad.t_x = "not base64!".to_string();
+        assert!(RangeProof::try_from(bad).is_err());
+        // Valid base64 of 31 bytes, and of a scalar that is not reduced
+        let mut bad = dto.clone();
+        bad.A = base64::encode(&[0u8; 31]);
+        assert!(RangeProof::try_from(bad).is_err());
+        let mut bad = dto.clone();
+        bad.e_blinding = base64::encode(&[0xffu8; 32]);
+        assert!(RangeProof::try_from(bad).is_err());
+        // L and R of different lengths
+        let mut bad = dto;
+        bad.ipp_proof.R_vec.pop();
+        assert!(RangeProof::try_from(bad).is_err());
+    }
+}
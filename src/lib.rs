@@ -1,13 +1,24 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), feature(alloc))]
 #![cfg_attr(feature = "bench", feature(test))]
 #![feature(nll)]
 #![feature(test)]
 #![feature(external_doc)]
+#![feature(try_from)]
+#![feature(const_fn)]
+#![feature(try_reserve)]
 #![doc(include = "../README.md")]
 #![doc(html_logo_url = "https://doc.dalek.rs/assets/dalek-logo-clear.png")]
 
 //! Note that docs will only build on nightly Rust until
 //! [RFC 1990 stabilizes](https://github.com/rust-lang/rust/issues/44732).
 
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+
 extern crate byteorder;
 extern crate curve25519_dalek;
 extern crate rand;
@@ -15,25 +26,130 @@ extern crate sha2;
 extern crate subtle;
 extern crate tiny_keccak;
 
+#[cfg(feature = "parallel")]
+extern crate rayon;
+
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
 #[cfg(test)]
 extern crate test;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "wasm", feature = "ffi"))]
 extern crate bincode;
 
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+
+#[cfg(feature = "base64")]
+extern crate base64;
+
+#[cfg(feature = "test-vectors")]
+extern crate serde_json;
+
+#[cfg(feature = "proptest-strategies")]
+#[macro_use]
+extern crate proptest;
+
+#[cfg(feature = "mlock")]
+extern crate libc;
+
 mod util;
+mod errors;
+
+pub mod profiling;
+pub mod poly;
+pub mod bits;
+pub mod ct;
+pub mod backend;
+pub mod group;
+pub mod cost;
+pub mod r1cs;
 
 #[doc(include = "../docs/notes.md")]
 mod notes {}
 
 mod proof_transcript;
+mod range_proof_transcript;
 mod generators;
 mod range_proof;
+pub mod one_of_many;
+pub mod non_membership;
+pub mod solvency;
+pub mod confidential_tx;
+pub mod assets;
+pub mod commitment_equality;
+pub mod divisibility;
+pub mod comparison;
+pub mod sigma;
+pub mod nullifier;
+pub mod polynomial_commitment;
+pub mod vector_commitment;
+pub mod elgamal;
+pub mod issuance;
+pub mod bundle;
+pub mod multi_range;
+pub mod sum_equality;
+pub mod delegation;
+pub mod encoding;
+pub mod envelope;
+pub mod sizes;
+pub mod view;
+pub mod snapshot;
+pub mod params;
+mod scratch;
+mod secret;
+mod session;
 mod inner_product_proof;
+mod batch;
+mod accumulator;
+mod queue;
+mod pipeline;
+#[cfg(feature = "std")]
+mod streaming;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "proto")]
+pub mod proto;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "proptest-strategies")]
+pub mod strategies;
+
+#[cfg(feature = "embedded")]
+pub mod embedded;
+
+#[cfg(feature = "mlock")]
+pub mod locked;
 
+pub use errors::ProofError;
 pub use proof_transcript::ProofTranscript;
-pub use range_proof::RangeProof;
+pub use range_proof::{RangeProof, VerificationReport, Verifier};
+pub use inner_product_proof::InnerProductProof;
+pub use scratch::ProverScratch;
+pub use secret::{Blinding, SecretScalar, SecretValue};
+#[cfg(feature = "zeroize")]
+pub use secret::{Zeroize, ZeroizeOnDrop};
+pub use session::ProofSession;
+pub use batch::BatchVerifier;
+pub use accumulator::VerificationAccumulator;
+pub use queue::PendingVerificationQueue;
+pub use pipeline::ProvingPipeline;
 pub use generators::{PedersenGenerators, Generators, GeneratorsView};
+pub use params::ProofParams;
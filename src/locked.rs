@@ -0,0 +1,175 @@
+#![deny(missing_docs)]
+
+//! The `locked` module contains `SecretBuffer`, a heap cell in
+//! page-locked memory, for secrets that must never reach swap.
+//!
+//! With the `mlock` feature, `SecretValue` and `Blinding`, and so the
+//! blinding factors the prover draws for each proof, keep their
+//! contents in a `SecretBuffer`.  Each buffer has pages of its own,
+//! locked with `mlock(2)` for as long as it lives, so that unlocking
+//! one buffer never unlocks another's secret; on drop the pages are
+//! overwritten with zeros before they are unlocked and freed.  A page
+//! per secret is wasteful, but custody deployments hold few secrets at
+//! a time.
+//!
+//! Locking fails once a process exceeds `RLIMIT_MEMLOCK`.  Proving must
+//! not fail for that, so a buffer whose pages cannot be locked keeps its
+//! secret in them unlocked, and is still wiped on drop.  Such a buffer
+//! reports `is_locked() == false`, and `unlocked_buffers` counts how
+//! many there have been, so that a deployment can alert on the count or
+//! raise the limit.  The feature is only available on Unix.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let v_blinding = Blinding::random(&mut rng);
+//! // The scalar lives in a locked page until v_blinding is dropped.
+//! let V = pg.commit(Scalar::from_u64(v), *v_blinding.as_scalar());
+//! ```
+
+use std::borrow::{Borrow, BorrowMut};
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic;
+use std::sync::atomic::AtomicUsize;
+
+use libc;
+
+/// The number of buffers whose pages could not be locked.
+static UNLOCKED_BUFFERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of `SecretBuffer`s created since the process
+/// started whose pages could not be locked.
+pub fn unlocked_buffers() -> usize {
+    UNLOCKED_BUFFERS.load(atomic::Ordering::Relaxed)
+}
+
+/// A value of type `T` in page-locked memory that is wiped when the
+/// buffer is dropped.
+pub struct SecretBuffer<T: Copy> {
+    /// The start of the pages, holding the value
+    ptr: *mut T,
+    /// The length of the pages
+    len: usize,
+    /// Whether the pages are locked
+    locked: bool,
+}
+
+impl<T: Copy> SecretBuffer<T> {
+    /// Moves `value` into freshly allocated pages, locked if possible.
+    ///
+    /// If the pages cannot be locked, the value is kept in them
+    /// unlocked, `is_locked` returns false and `unlocked_buffers` counts
+    /// the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pages cannot be allocated, as `Box::new` would.
+    pub fn new(value: T) -> Self {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let len = (mem::size_of::<T>() + page - 1) / page * page;
+        let mut raw: *mut libc::c_void = ptr::null_mut();
+        let locked;
+        unsafe {
+            if libc::posix_memalign(&mut raw, page, len) != 0 {
+                panic!("could not allocate memory for a secret");
+            }
+            locked = libc::mlock(raw, len) == 0;
+            ptr::write(raw as *mut T, value);
+        }
+        if !locked {
+            UNLOCKED_BUFFERS.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+        SecretBuffer {
+            ptr: raw as *mut T,
+            len,
+            locked,
+        }
+    }
+
+    /// Returns whether the value is in locked pages.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl<T: Copy> Drop for SecretBuffer<T> {
+    fn drop(&mut self) {
+        let bytes = self.ptr as *mut u8;
+        unsafe {
+            for i in 0..self.len {
+                ptr::write_volatile(bytes.offset(i as isize), 0);
+            }
+            atomic::compiler_fence(atomic::Ordering::SeqCst);
+            if self.locked {
+                libc::munlock(self.ptr as *const libc::c_void, self.len);
+            }
+            libc::free(self.ptr as *mut libc::c_void);
+        }
+    }
+}
+
+impl<T: Copy> Deref for SecretBuffer<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T: Copy> DerefMut for SecretBuffer<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T: Copy> Borrow<T> for SecretBuffer<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: Copy> BorrowMut<T> for SecretBuffer<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: Copy> Clone for SecretBuffer<T> {
+    fn clone(&self) -> Self {
+        SecretBuffer::new(**self)
+    }
+}
+
+impl<T: Copy> fmt::Debug for SecretBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretBuffer { .. }")
+    }
+}
+
+// The buffer owns its pages exclusively, like a `Box<T>`.
+unsafe impl<T: Copy + Send> Send for SecretBuffer<T> {}
+unsafe impl<T: Copy + Sync> Sync for SecretBuffer<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn buffers_hold_their_values_in_locked_pages() {
+        let mut buffer = SecretBuffer::new(Scalar::from_u64(7));
+        assert_eq!(*buffer, Scalar::from_u64(7));
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        assert_eq!(buffer.ptr as usize % page, 0);
+        assert!(buffer.is_locked() || unlocked_buffers() > 0);
+
+        let copy = buffer.clone();
+        *buffer = Scalar::one();
+        assert_eq!(*copy, Scalar::from_u64(7));
+        assert_eq!(*buffer, Scalar::one());
+        assert_eq!(format!("{:?}", copy), "SecretBuffer { .. }");
+    }
+}
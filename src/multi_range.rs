@@ -0,0 +1,220 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `multi_range` module contains a proof that one committed value
+//! satisfies several range constraints at once, e.g. \\(v < 2^{64}\\)
+//! and \\(v \geq 10^6\\).
+//!
+//! Each bound shifts the commitment \\(V = v B + r \widetilde{B}\\) to a
+//! commitment the verifier can compute itself: a lower bound \\(a\\)
+//! gives \\(V - a B\\), a commitment to \\(v - a\\), and an upper bound
+//! \\(b\\) gives \\(b B - V\\), a commitment to \\(b - v\\) with the
+//! blinding factor \\(-r\\).  The proof is a `ProofBundle` showing that
+//! \\(V\\) and every shifted commitment hold values in \\([0, 2^n)\\).
+//! Since \\(v\\) itself is in range, \\(v - a\\) can only be in range
+//! if \\(v \geq a\\), and likewise for upper bounds.
+//!
+//! The bounds are committed to the transcript, so the proof only
+//! verifies for the bounds it was made for.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let bounds = [Bound::AtLeast(1_000_000)];
+//! let mut transcript = ProofTranscript::new(b"Deposit")?;
+//! let proof = MultiRangeProof::prove(
+//!     generators.share(0), &mut transcript, &mut rng, 64, &v, &v_blinding, &bounds,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Deposit")?;
+//! proof.verify(generators.share(0), &mut transcript, &mut rng, 64, &V, &bounds)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretValue};
+
+/// A bound on a committed value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The value is at least the given value.
+    AtLeast(u64),
+    /// The value is at most the given value.
+    AtMost(u64),
+}
+
+/// A proof that a committed value is in \\([0, 2^n)\\) and satisfies
+/// a list of bounds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultiRangeProof {
+    /// Range proofs for the value and each shifted value
+    bundle: ProofBundle,
+}
+
+impl MultiRangeProof {
+    /// Proves that the value `v`, committed with `v_blinding`, is in
+    /// \\([0, 2^n)\\) and satisfies all the `bounds`.
+    ///
+    /// Returns `ProofError::ValueOutOfRange` if `v` does not fit in `n`
+    /// bits or violates a bound, and the errors of
+    /// `ProofBundle::prove_many` for an unsupported `n`.
+    pub fn prove<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+        bounds: &[Bound],
+    ) -> Result<MultiRangeProof, ProofError> {
+        if n < 64 && v.value() >= (1u64 << n) {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let mut statements = vec![(v.clone(), v_blinding.clone())];
+        for bound in bounds.iter() {
+            statements.push(match *bound {
+                Bound::AtLeast(a) if v.value() >= a => {
+                    (SecretValue::new(v.value() - a), v_blinding.clone())
+                }
+                Bound::AtMost(b) if v.value() <= b => (
+                    SecretValue::new(b - v.value()),
+                    Blinding::new(-v_blinding.as_scalar()),
+                ),
+                _ => return Err(ProofError::ValueOutOfRange),
+            });
+        }
+
+        commit_bounds(transcript, bounds);
+        let (bundle, _) = ProofBundle::prove_many(generators, transcript, rng, n, &statements)?;
+        Ok(MultiRangeProof { bundle })
+    }
+
+    /// Verifies that the value committed in `V` is in \\([0, 2^n)\\)
+    /// and satisfies all the `bounds`.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        V: &RistrettoPoint,
+        bounds: &[Bound],
+    ) -> Result<(), ProofError> {
+        let commitments = shifted_commitments(generators.pedersen_generators, V, bounds);
+
+        commit_bounds(transcript, bounds);
+        self.bundle.verify(generators, transcript, rng, n, &commitments)
+    }
+}
+
+/// Returns \\(V\\) followed by its shift for each of the `bounds`.
+fn shifted_commitments(
+    pg: &PedersenGenerators,
+    V: &RistrettoPoint,
+    bounds: &[Bound],
+) -> Vec<RistrettoPoint> {
+    let mut commitments = vec![*V];
+    for bound in bounds.iter() {
+        commitments.push(match *bound {
+            Bound::AtLeast(a) => V - pg.B * Scalar::from_u64(a),
+            Bound::AtMost(b) => pg.B * Scalar::from_u64(b) - V,
+        });
+    }
+    commitments
+}
+
+/// Commits the `bounds` to the transcript.
+fn commit_bounds(transcript: &mut ProofTranscript, bounds: &[Bound]) {
+    transcript.commit(b"MultiRange");
+    transcript.commit_u64(bounds.len() as u64);
+    for bound in bounds.iter() {
+        let (kind, value): (&[u8], u64) = match *bound {
+            Bound::AtLeast(a) => (b"AtLeast", a),
+            Bound::AtMost(b) => (b"AtMost", b),
+        };
+        transcript.commit(kind);
+        transcript.commit_u64(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+
+    #[test]
+    fn value_within_all_bounds_verifies() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretValue::new(1_500_000);
+        let v_blinding = Blinding::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+        let bounds = [Bound::AtLeast(1_000_000), Bound::AtMost(2_000_000)];
+
+        let mut transcript = ProofTranscript::new(b"MultiRangeTest").unwrap();
+        let proof = MultiRangeProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &v,
+            &v_blinding,
+            &bounds,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"MultiRangeTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &V, &bounds)
+                .is_ok()
+        );
+
+        // The proof does not verify for tighter bounds.
+        let tighter = [Bound::AtLeast(1_600_000), Bound::AtMost(2_000_000)];
+        let mut transcript = ProofTranscript::new(b"MultiRangeTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &V, &tighter)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn violated_bounds_are_rejected() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Blinding::random(&mut rng);
+
+        for bounds in [[Bound::AtLeast(1_000_000)], [Bound::AtMost(10)]].iter() {
+            let mut transcript = ProofTranscript::new(b"MultiRangeTest").unwrap();
+            assert!(
+                MultiRangeProof::prove(
+                    generators.share(0),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                    &SecretValue::new(500),
+                    &v_blinding,
+                    bounds,
+                ).is_err()
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `non_membership` module contains a proof that a committed value
+//! is not in a sorted public set, for blacklisting use cases.
+//!
+//! For a strictly increasing set \\(s\_0 < \cdots < s\_{k-1}\\), the
+//! prover picks the gap containing its value \\(v\\) and proves with
+//! range proofs that \\(v - s\_{g-1} - 1\\) and \\(s\_g - 1 - v\\) are
+//! both in \\([0, 2^n)\\).  The first and last gaps are unbounded on
+//! one side and need only one range proof.
+//!
+//! The proof reveals which gap contains the value, i.e. between which
+//! two adjacent set elements it lies, but nothing more about it.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let set = [3, 17, 42];
+//! let v = SecretValue::new(20);
+//! let V = generators.share(0).pedersen_generators.commit(v.to_scalar(), *v_blinding.as_scalar());
+//!
+//! let mut transcript = ProofTranscript::new(b"Blacklist")?;
+//! let proof = NonMembershipProof::prove(
+//!     generators.share(0), &mut transcript, &mut rng, 64, &set, &v, &v_blinding,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Blacklist")?;
+//! proof.verify(generators.share(0), &mut transcript, &mut rng, 64, &set, &V)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// A proof that a committed value is not in a sorted public set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NonMembershipProof {
+    /// The index of the gap containing the value: the value lies
+    /// between `set[gap - 1]` and `set[gap]`.
+    gap: usize,
+    /// Proof that the value is above `set[gap - 1]`, unless `gap == 0`
+    lower: Option<RangeProof>,
+    /// Proof that the value is below `set[gap]`, unless `gap == set.len()`
+    upper: Option<RangeProof>,
+}
+
+impl NonMembershipProof {
+    /// Proves that the value `v` committed with `v_blinding` is not in
+    /// the strictly increasing `set`, with `n`-bit range proofs.
+    ///
+    /// Returns `ProofError::InvalidStatement` if the set is not strictly
+    /// increasing, and `ProofError::ValueOutOfRange` if `v` is in the
+    /// set or its distance to one of its neighbours in the set does not
+    /// fit in `n` bits.  An unsupported `n` is rejected first, as by
+    /// `RangeProof::generate_proof`.
+    pub fn prove<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        set: &[u64],
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<NonMembershipProof, ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        if !is_strictly_increasing(set) {
+            return Err(ProofError::InvalidStatement);
+        }
+        if set.contains(&v.value()) {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        let fits = |x: u64| n == 64 || x < (1u64 << n);
+
+        let gap = set.iter().take_while(|&&s| s < v.value()).count();
+        let V = generators
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+        transcript_prefix(transcript, set, gap, &V);
+
+        let lower = if gap > 0 {
+            let delta = v.value() - set[gap - 1] - 1;
+            if !fits(delta) {
+                return Err(ProofError::ValueOutOfRange);
+            }
+            Some(RangeProof::generate_proof(
+                generators,
+                transcript,
+                rng,
+                n,
+                &SecretValue::new(delta),
+                v_blinding,
+            )?)
+        } else {
+            None
+        };
+
+        let upper = if gap < set.len() {
+            let delta = set[gap] - 1 - v.value();
+            if !fits(delta) {
+                return Err(ProofError::ValueOutOfRange);
+            }
+            Some(RangeProof::generate_proof(
+                generators,
+                transcript,
+                rng,
+                n,
+                &SecretValue::new(delta),
+                &Blinding::new(-v_blinding.as_scalar()),
+            )?)
+        } else {
+            None
+        };
+
+        Ok(NonMembershipProof { gap, lower, upper })
+    }
+
+    /// Verifies that the value committed in `V` is not in the strictly
+    /// increasing `set`.
+    ///
+    /// Returns `ProofError::InvalidStatement` if the set is not strictly
+    /// increasing, and `ProofError::FormatError` if the proof does not
+    /// match its shape.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        set: &[u64],
+        V: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        if !is_strictly_increasing(set) {
+            return Err(ProofError::InvalidStatement);
+        }
+        if self.gap > set.len() {
+            return Err(ProofError::FormatError);
+        }
+        transcript_prefix(transcript, set, self.gap, V);
+
+        let B = generators.pedersen_generators.B;
+
+        match (self.gap > 0, &self.lower) {
+            (true, &Some(ref lower)) => {
+                // V - (s_{g-1} + 1) B commits to v - s_{g-1} - 1.
+                let V_lower = V - B * (Scalar::from_u64(set[self.gap - 1]) + Scalar::one());
+                lower.verify(&V_lower, generators, transcript, rng, n)?;
+            }
+            (false, &None) => {}
+            _ => return Err(ProofError::FormatError),
+        }
+
+        match (self.gap < set.len(), &self.upper) {
+            (true, &Some(ref upper)) => {
+                // (s_g - 1) B - V commits to s_g - 1 - v.
+                let V_upper = B * (Scalar::from_u64(set[self.gap]) - Scalar::one()) - V;
+                upper.verify(&V_upper, generators, transcript, rng, n)?;
+            }
+            (false, &None) => {}
+            _ => return Err(ProofError::FormatError),
+        }
+
+        Ok(())
+    }
+}
+
+fn is_strictly_increasing(set: &[u64]) -> bool {
+    set.windows(2).all(|w| w[0] < w[1])
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(
+    transcript: &mut ProofTranscript,
+    set: &[u64],
+    gap: usize,
+    V: &RistrettoPoint,
+) {
+    transcript.commit(b"NonMembership");
+    transcript.commit_u64(set.len() as u64);
+    for &s in set.iter() {
+        transcript.commit_u64(s);
+    }
+    transcript.commit_u64(gap as u64);
+    transcript.commit(V.compress().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+
+    fn prove_and_verify(set: &[u64], v: u64) -> Result<(), ProofError> {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = SecretValue::new(v);
+        let v_blinding = Blinding::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(v.to_scalar(), *v_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"NonMembershipTest").unwrap();
+        let proof = NonMembershipProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            set,
+            &v,
+            &v_blinding,
+        )?;
+
+        let mut transcript = ProofTranscript::new(b"NonMembershipTest").unwrap();
+        proof.verify(generators.share(0), &mut transcript, &mut rng, n, set, &V)
+    }
+
+    #[test]
+    fn values_outside_the_set_verify() {
+        let set = [3, 17, 42];
+        for &v in [0, 2, 4, 16, 18, 41, 43, 1000].iter() {
+            assert!(prove_and_verify(&set, v).is_ok());
+        }
+    }
+
+    #[test]
+    fn values_in_the_set_are_rejected() {
+        let set = [3, 17, 42];
+        for &v in set.iter() {
+            assert_eq!(prove_and_verify(&set, v), Err(ProofError::ValueOutOfRange));
+        }
+        // Not sorted
+        assert_eq!(prove_and_verify(&[17, 3], 10), Err(ProofError::InvalidStatement));
+        // Distance does not fit in 16 bits
+        assert_eq!(prove_and_verify(&[3], 1 << 20), Err(ProofError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn unsupported_bitsizes_are_rejected() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 16, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Blinding::random(&mut rng);
+        for &n in [0, 65, 128].iter() {
+            let mut transcript = ProofTranscript::new(b"NonMembershipTest").unwrap();
+            let result = NonMembershipProof::prove(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &[3, 17, 42],
+                &SecretValue::new(20),
+                &v_blinding,
+            );
+            assert_eq!(result, Err(ProofError::InvalidBitsize));
+        }
+    }
+
+    #[test]
+    fn proof_does_not_verify_for_a_member() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Blinding::random(&mut rng);
+        let set = [3, 17, 42];
+
+        let mut transcript = ProofTranscript::new(b"NonMembershipTest").unwrap();
+        let proof = NonMembershipProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &set,
+            &SecretValue::new(20),
+            &v_blinding,
+        ).unwrap();
+
+        // The same blinding, but a commitment to a member of the set
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(17), *v_blinding.as_scalar());
+        let mut transcript = ProofTranscript::new(b"NonMembershipTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &set, &V)
+                .is_err()
+        );
+    }
+}
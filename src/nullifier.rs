@@ -0,0 +1,196 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `nullifier` module contains a linkable membership proof: a
+//! proof that the prover can open one member of a public list of coins,
+//! which also outputs a deterministic nullifier for that coin, following
+//! the linkable one-of-many construction of Lelantus.
+//!
+//! A coin is a Pedersen commitment \\(C = s B + r \widetilde{B}\\) to a
+//! secret serial number \\(s\\).  Its nullifier is \\(I = s B\\).  The
+//! proof shows that \\(C\_l - I\\) is a commitment to zero for some
+//! member \\(C\_l\\) of the list, with the logarithmic one-of-many proof
+//! of the `one_of_many` module, and that the prover knows the discrete
+//! logarithm \\(s\\) of \\(I\\) to the base \\(B\\).  The second part
+//! ties the nullifier to the opening: by the binding of the commitment,
+//! \\(s B\\) is the only point of that form leaving a commitment to zero,
+//! so each coin has exactly one nullifier.
+//!
+//! The proof reveals neither the member nor its opening, since \\(C\\)
+//! hides \\(s\\), but two proofs for the same coin output the same
+//! nullifier, so double-spends are detectable by keeping a set of seen
+//! nullifiers.  For a list padded to \\(N = 2^n\\) members, the proof
+//! consists of \\(4n + 1\\) points and \\(3n + 2\\) scalars.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Spend")?;
+//! let (proof, I) =
+//!     LinkableMembershipProof::prove(&pg, &mut transcript, &mut rng, &coins, l, &s, &r)?;
+//! assert!(seen_nullifiers.insert(I.compress()));
+//!
+//! let mut transcript = ProofTranscript::new(b"Spend")?;
+//! proof.verify(&pg, &mut transcript, &coins, &I)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use one_of_many::OneOfManyProof;
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar};
+use sigma::{SigmaProof, Statement};
+
+/// Returns the nullifier \\(s B\\) of the coins with serial number `s`.
+pub fn nullifier(pg: &PedersenGenerators, s: &SecretScalar) -> RistrettoPoint {
+    pg.B * s.as_scalar()
+}
+
+/// A proof of knowledge of the opening of one member of a list of
+/// coins, linked to the coin's nullifier.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LinkableMembershipProof {
+    /// Proof of knowledge of the serial number behind the nullifier
+    serial: SigmaProof,
+    /// Proof that the nullifier and one of the coins differ by a
+    /// commitment to zero
+    membership: OneOfManyProof,
+}
+
+impl LinkableMembershipProof {
+    /// Proves knowledge of the opening of `coins[index]`, with serial
+    /// number `s` and blinding factor `blinding`, returning the proof
+    /// and the nullifier of the coin.
+    ///
+    /// Returns `ProofError::InvalidStatement` if `index` is out of
+    /// range.  The proof only verifies if `coins[index]` is
+    /// \\(s B + r \widetilde{B}\\) for the `blinding` \\(r\\).
+    pub fn prove<R: Rng>(
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        coins: &[RistrettoPoint],
+        index: usize,
+        s: &SecretScalar,
+        blinding: &Blinding,
+    ) -> Result<(LinkableMembershipProof, RistrettoPoint), ProofError> {
+        if index >= coins.len() {
+            return Err(ProofError::InvalidStatement);
+        }
+        let I = nullifier(pg, s);
+
+        transcript.commit(b"LinkableMembership");
+        let serial =
+            SigmaProof::prove(&Statement::dlog(&pg.B, &I), transcript, rng, &[s.clone()])?;
+        // I - C_l = -r B~ is the commitment to zero the one-of-many proof opens.
+        let membership = OneOfManyProof::prove(
+            pg,
+            transcript,
+            rng,
+            &I,
+            coins,
+            index,
+            &Blinding::new(-blinding.as_scalar()),
+        )?;
+
+        Ok((LinkableMembershipProof { serial, membership }, I))
+    }
+
+    /// Verifies that the prover can open one of the `coins`, whose
+    /// nullifier is `I`.
+    pub fn verify(
+        &self,
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        coins: &[RistrettoPoint],
+        I: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        transcript.commit(b"LinkableMembership");
+        self.serial.verify(&Statement::dlog(&pg.B, I), transcript)?;
+        self.membership.verify(pg, transcript, I, coins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::OsRng;
+
+    #[test]
+    fn nullifiers_link_proofs() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let serials: Vec<SecretScalar> = (0..5).map(|_| SecretScalar::random(&mut rng)).collect();
+        let blindings: Vec<Blinding> = (0..5).map(|_| Blinding::random(&mut rng)).collect();
+        let coins: Vec<RistrettoPoint> = serials
+            .iter()
+            .zip(blindings.iter())
+            .map(|(s, r)| pg.commit(*s.as_scalar(), *r.as_scalar()))
+            .collect();
+
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        let (proof, I) = LinkableMembershipProof::prove(
+            &pg,
+            &mut transcript,
+            &mut rng,
+            &coins,
+            2,
+            &serials[2],
+            &blindings[2],
+        ).unwrap();
+        assert_eq!(I, nullifier(&pg, &serials[2]));
+
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        assert!(proof.verify(&pg, &mut transcript, &coins, &I).is_ok());
+
+        // Another nullifier does not verify.
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        let other = nullifier(&pg, &serials[1]);
+        assert!(proof.verify(&pg, &mut transcript, &coins, &other).is_err());
+
+        // A second proof for the same coin has the same nullifier.
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        let (_, I_again) = LinkableMembershipProof::prove(
+            &pg,
+            &mut transcript,
+            &mut rng,
+            &coins,
+            2,
+            &serials[2],
+            &blindings[2],
+        ).unwrap();
+        assert_eq!(I, I_again);
+    }
+
+    #[test]
+    fn non_member_is_rejected() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let coins: Vec<RistrettoPoint> = (0..3)
+            .map(|_| pg.commit(Scalar::random(&mut rng), Scalar::random(&mut rng)))
+            .collect();
+        let s = SecretScalar::random(&mut rng);
+        let r = Blinding::random(&mut rng);
+
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        let (proof, I) =
+            LinkableMembershipProof::prove(&pg, &mut transcript, &mut rng, &coins, 0, &s, &r)
+                .unwrap();
+
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        assert!(proof.verify(&pg, &mut transcript, &coins, &I).is_err());
+
+        // An index past the coins is rejected.
+        let mut transcript = ProofTranscript::new(b"NullifierTest").unwrap();
+        assert_eq!(
+            LinkableMembershipProof::prove(&pg, &mut transcript, &mut rng, &coins, 3, &s, &r)
+                .unwrap_err(),
+            ProofError::InvalidStatement
+        );
+    }
+}
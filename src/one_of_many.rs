@@ -0,0 +1,400 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `one_of_many` module contains a proof that a commitment opens
+//! to the same value as one member of a public list of commitments,
+//! without revealing which one, following Groth and Kohlweiss's
+//! one-out-of-many proof.
+//!
+//! For a commitment \\(V\\) and a list \\(W\_0, \ldots, W\_{N-1}\\),
+//! the prover shows that it knows an index \\(l\\) and a blinding factor
+//! \\(r\\) with \\(V - W\_l = r \widetilde{B}\\).  To prove that \\(V\\)
+//! commits to one of a list of public values, use `value_set` to turn
+//! them into commitments with zero blinding.
+//!
+//! The list is padded to a power of two \\(N = 2^n\\) by repeating its
+//! last element, and the proof consists of \\(4n\\) points and
+//! \\(3n + 1\\) scalars.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let set = one_of_many::value_set(&gens, &[10, 20, 30, 40]);
+//! let V = gens.commit(Scalar::from_u64(30), v_blinding);
+//!
+//! let mut transcript = ProofTranscript::new(b"MembershipTest")?;
+//! let proof = OneOfManyProof::prove(&gens, &mut transcript, &mut rng, &V, &set, 2, &v_blinding)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"MembershipTest")?;
+//! assert!(proof.verify(&gens, &mut transcript, &V, &set).is_ok());
+//! ```
+
+use core::cmp;
+use core::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use proof_transcript::ProofTranscript;
+use secret::Blinding;
+use util;
+
+/// A proof that a commitment opens to the same value as one member of
+/// a list of commitments.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OneOfManyProof {
+    /// Commitments to the bits \\(l\_j\\) of the index
+    C_l: Vec<RistrettoPoint>,
+    /// Commitments to the masks \\(a\_j\\)
+    C_a: Vec<RistrettoPoint>,
+    /// Commitments to the products \\(l\_j a\_j\\)
+    C_b: Vec<RistrettoPoint>,
+    /// Commitments to the low-degree coefficients of the selection polynomial
+    C_d: Vec<RistrettoPoint>,
+    /// Masked bits \\(f\_j = l\_j x + a\_j\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    f: Vec<Scalar>,
+    /// Blinding factors opening \\(x C\_{l,j} + C\_{a,j}\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    z_a: Vec<Scalar>,
+    /// Blinding factors opening \\((x - f\_j) C\_{l,j} + C\_{b,j}\\)
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    z_b: Vec<Scalar>,
+    /// Blinding factor opening the selection equation
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    z_d: Scalar,
+}
+
+/// Returns commitments with zero blinding to the public `values`, for
+/// proving that a commitment opens to one of them.
+pub fn value_set(gens: &PedersenGenerators, values: &[u64]) -> Vec<RistrettoPoint> {
+    values.iter().map(|&v| gens.B * Scalar::from_u64(v)).collect()
+}
+
+impl OneOfManyProof {
+    /// Proves that `V - set[l]` is a commitment to zero with blinding
+    /// factor `blinding`, i.e. that \\(V\\) opens to the same value as
+    /// `set[l]`, with `blinding` the difference of their blinding factors.
+    ///
+    /// Returns `ProofError::InvalidStatement` if `l` is not an index
+    /// into `set`.
+    pub fn prove<R: Rng>(
+        gens: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        V: &RistrettoPoint,
+        set: &[RistrettoPoint],
+        l: usize,
+        blinding: &Blinding,
+    ) -> Result<OneOfManyProof, ProofError> {
+        if l >= set.len() {
+            return Err(ProofError::InvalidStatement);
+        }
+        let C = differences(V, set);
+        let N = C.len();
+        let n = N.trailing_zeros() as usize;
+
+        transcript_prefix(transcript, &C);
+
+        // Derive the nonces from the transcript and the index and
+        // blinding as well as from `rng`, as the range proofs do.
+        let mut l_bytes = [0u8; 8];
+        LittleEndian::write_u64(&mut l_bytes, l as u64);
+        let mut nonces = transcript.build_rng(&[&l_bytes, blinding.as_scalar().as_bytes()], rng);
+        let rng = &mut nonces;
+
+        let l_bits: Vec<Scalar> = (0..n).map(|j| Scalar::from_u64(((l >> j) & 1) as u64)).collect();
+        let r: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let s: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let t: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let rho: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+
+        let C_l: Vec<RistrettoPoint> = (0..n).map(|j| gens.commit(l_bits[j], r[j])).collect();
+        let C_a: Vec<RistrettoPoint> = (0..n).map(|j| gens.commit(a[j], s[j])).collect();
+        let C_b: Vec<RistrettoPoint> =
+            (0..n).map(|j| gens.commit(l_bits[j] * a[j], t[j])).collect();
+
+        // p[i][k] is the coefficient of x^k in prod_j f_{j, i_j}(x), where
+        // f_{j,1}(x) = l_j x + a_j and f_{j,0}(x) = (1 - l_j) x - a_j.
+        let p: Vec<Vec<Scalar>> = (0..N)
+            .map(|i| {
+                let mut coeffs = vec![Scalar::one()];
+                for j in 0..n {
+                    let (c_0, c_1) = if (i >> j) & 1 == 1 {
+                        (a[j], l_bits[j])
+                    } else {
+                        (-a[j], Scalar::one() - l_bits[j])
+                    };
+                    let mut next = vec![Scalar::zero(); coeffs.len() + 1];
+                    for (k, coeff) in coeffs.iter().enumerate() {
+                        next[k] += c_0 * coeff;
+                        next[k + 1] += c_1 * coeff;
+                    }
+                    coeffs = next;
+                }
+                coeffs
+            })
+            .collect();
+
+        let C_d: Vec<RistrettoPoint> = (0..n)
+            .map(|k| {
+                util::multiscalar_mul(
+                    p.iter().map(|p_i| p_i[k]).chain(iter::once(rho[k])),
+                    C.iter().chain(iter::once(&gens.B_blinding)),
+                )
+            })
+            .collect();
+
+        for P in C_l.iter().chain(C_a.iter()).chain(C_b.iter()).chain(C_d.iter()) {
+            transcript.commit(P.compress().as_bytes());
+        }
+        let x = transcript.challenge_scalar_labeled(b"x");
+
+        let f: Vec<Scalar> = (0..n).map(|j| l_bits[j] * x + a[j]).collect();
+        let z_a: Vec<Scalar> = (0..n).map(|j| r[j] * x + s[j]).collect();
+        let z_b: Vec<Scalar> = (0..n).map(|j| r[j] * (x - f[j]) + t[j]).collect();
+        let x_powers = util::ScalarPowers::new(x, n + 1);
+        let z_d = blinding.as_scalar() * x_powers.as_slice()[n]
+            - rho
+                .iter()
+                .zip(x_powers.as_slice().iter())
+                .fold(Scalar::zero(), |acc, (rho_k, x_k)| acc + rho_k * x_k);
+
+        Ok(OneOfManyProof {
+            C_l,
+            C_a,
+            C_b,
+            C_d,
+            f,
+            z_a,
+            z_b,
+            z_d,
+        })
+    }
+
+    /// Verifies that \\(V\\) opens to the same value as one member of `set`.
+    ///
+    /// Returns `ProofError::InvalidStatement` if the set is empty, and
+    /// `ProofError::FormatError` if the proof does not have one entry
+    /// per bit of the padded set size.
+    pub fn verify(
+        &self,
+        gens: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        V: &RistrettoPoint,
+        set: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        if set.is_empty() {
+            return Err(ProofError::InvalidStatement);
+        }
+        let C = differences(V, set);
+        let N = C.len();
+        let n = N.trailing_zeros() as usize;
+        if self.C_l.len() != n
+            || self.C_a.len() != n
+            || self.C_b.len() != n
+            || self.C_d.len() != n
+            || self.f.len() != n
+            || self.z_a.len() != n
+            || self.z_b.len() != n
+        {
+            return Err(ProofError::FormatError);
+        }
+
+        transcript_prefix(transcript, &C);
+        for P in self.C_l
+            .iter()
+            .chain(self.C_a.iter())
+            .chain(self.C_b.iter())
+            .chain(self.C_d.iter())
+        {
+            transcript.commit(P.compress().as_bytes());
+        }
+        let x = transcript.challenge_scalar_labeled(b"x");
+
+        for j in 0..n {
+            // x C_l + C_a = Com(f; z_a)
+            let check_a = x * self.C_l[j] + self.C_a[j] - gens.commit(self.f[j], self.z_a[j]);
+            // (x - f) C_l + C_b = Com(0; z_b)
+            let check_b = (x - self.f[j]) * self.C_l[j] + self.C_b[j]
+                - gens.commit(Scalar::zero(), self.z_b[j]);
+            if !check_a.is_identity() || !check_b.is_identity() {
+                return Err(ProofError::VerificationError);
+            }
+        }
+
+        // sum_i (prod_j f_{j, i_j}) C_i - sum_k x^k C_d = Com(0; z_d)
+        let selectors = (0..N).map(|i| {
+            (0..n).fold(Scalar::one(), |acc, j| {
+                if (i >> j) & 1 == 1 {
+                    acc * self.f[j]
+                } else {
+                    acc * (x - self.f[j])
+                }
+            })
+        });
+        let x_powers = util::ScalarPowers::new(x, n);
+        let check_d = util::vartime_multiscalar_mul(
+            selectors
+                .chain(x_powers.as_slice().iter().map(|x_k| -x_k))
+                .chain(iter::once(-self.z_d)),
+            C.iter()
+                .chain(self.C_d.iter())
+                .chain(iter::once(&gens.B_blinding)),
+        )?;
+
+        if check_d.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// Computes the commitments \\(V - W\_i\\) to zero, padded to a power of
+/// two of at least two elements by repeating the last one.
+fn differences(V: &RistrettoPoint, set: &[RistrettoPoint]) -> Vec<RistrettoPoint> {
+    let N = cmp::max(set.len(), 2).next_power_of_two();
+    let last = set[set.len() - 1];
+    set.iter()
+        .chain(iter::repeat(&last))
+        .take(N)
+        .map(|W| V - W)
+        .collect()
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(transcript: &mut ProofTranscript, C: &[RistrettoPoint]) {
+    transcript.commit(b"OneOfMany");
+    transcript.commit_u64(C.len() as u64);
+    for C_i in C.iter() {
+        transcript.commit(C_i.compress().as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn membership_in_public_values() {
+        let gens = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let set = value_set(&gens, &[10, 20, 30, 40, 50]);
+
+        let v_blinding = Blinding::random(&mut rng);
+        let V = gens.commit(Scalar::from_u64(40), *v_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        let proof = OneOfManyProof::prove(
+            &gens,
+            &mut transcript,
+            &mut rng,
+            &V,
+            &set,
+            3,
+            &v_blinding,
+        ).unwrap();
+        assert_eq!(proof.C_l.len(), 3);
+
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        assert!(proof.verify(&gens, &mut transcript, &V, &set).is_ok());
+
+        // A commitment to another value
+        let W = gens.commit(Scalar::from_u64(41), *v_blinding.as_scalar());
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        assert!(proof.verify(&gens, &mut transcript, &W, &set).is_err());
+
+        // A value outside the set cannot be proven for any index.
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        let proof = OneOfManyProof::prove(
+            &gens,
+            &mut transcript,
+            &mut rng,
+            &W,
+            &set,
+            3,
+            &v_blinding,
+        ).unwrap();
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        assert!(proof.verify(&gens, &mut transcript, &W, &set).is_err());
+    }
+
+    #[test]
+    fn nonces_depend_on_the_witness() {
+        use rand::ChaChaRng;
+
+        let gens = PedersenGenerators::default();
+        let set = value_set(&gens, &[10, 20, 30, 40]);
+        let V = gens.commit(Scalar::from_u64(20), Scalar::one());
+        let prove = |l: usize| {
+            let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+            let mut rng = ChaChaRng::new_unseeded();
+            let blinding = Blinding::new(Scalar::one());
+            OneOfManyProof::prove(&gens, &mut transcript, &mut rng, &V, &set, l, &blinding)
+                .unwrap()
+        };
+
+        // The same RNG output gives different nonces for different indices.
+        assert!(prove(0).C_a != prove(1).C_a);
+        assert_eq!(prove(1).C_a, prove(1).C_a);
+    }
+
+    #[test]
+    fn membership_in_public_commitments() {
+        let gens = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+
+        let blindings: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let set: Vec<RistrettoPoint> = blindings
+            .iter()
+            .enumerate()
+            .map(|(i, w_blinding)| gens.commit(Scalar::from_u64(i as u64), *w_blinding))
+            .collect();
+
+        // The index must point into the set.
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        let V = gens.commit(Scalar::one(), Scalar::zero());
+        let blinding = Blinding::new(Scalar::zero());
+        assert_eq!(
+            OneOfManyProof::prove(&gens, &mut transcript, &mut rng, &V, &set, 4, &blinding)
+                .unwrap_err(),
+            ProofError::InvalidStatement
+        );
+        assert_eq!(
+            OneOfManyProof::prove(&gens, &mut transcript, &mut rng, &V, &[], 0, &blinding)
+                .unwrap_err(),
+            ProofError::InvalidStatement
+        );
+
+        // Re-randomize the commitment to 1, and prove it opens to the same value.
+        let v_blinding = Scalar::random(&mut rng);
+        let V = gens.commit(Scalar::one(), v_blinding);
+        let blinding = Blinding::new(v_blinding - blindings[1]);
+
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        let proof = OneOfManyProof::prove(
+            &gens,
+            &mut transcript,
+            &mut rng,
+            &V,
+            &set,
+            1,
+            &blinding,
+        ).unwrap();
+        let mut transcript = ProofTranscript::new(b"OneOfManyTest").unwrap();
+        assert!(proof.verify(&gens, &mut transcript, &V, &set).is_ok());
+    }
+}
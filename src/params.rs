@@ -0,0 +1,192 @@
+#![deny(missing_docs)]
+
+//! The `params` module contains `ProofParams`, the validated bitsize and
+//! number of proofs generators and verifiers are set up for.
+//!
+//! `ProofParams::new` checks once that the bitsize is a power of two of
+//! at most `MAX_BITSIZE` and that the number of proofs is between one
+//! and `MAX_PROOFS`.  `Generators::with_params` then makes generators of
+//! exactly that capacity, and `check_share` checks that a share of
+//! generators matches the bitsize.  Every entry point that takes a
+//! bitsize, from `RangeProof::generate_proof` and `Verifier::new` to
+//! the decoders of envelopes and streams, goes through these checks, so
+//! that code past the front door never needs to recheck them.
+//!
+//! Decoders of encodings that claim their own sizes, such as
+//! `Generators::from_bytes_with_limits`, check the claimed \\(nm\\)
+//! against `Limits` before hashing or allocating anything, and return
+//! `TooLarge` if it exceeds them.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let params = ProofParams::new(n, m)?;
+//! let generators = Generators::with_params(b"MyApp", PedersenGenerators::default(), &params);
+//! let verifier = Verifier::with_params(generators.share(0), &params)?;
+//! ```
+
+use errors::ProofError;
+use generators::{Generators, GeneratorsView};
+
+/// The largest supported bitsize of a range.
+pub const MAX_BITSIZE: usize = 64;
+
+/// The largest number of proofs a set of generators is made for.
+pub const MAX_PROOFS: usize = 256;
+
+/// The largest \\(nm\\) decoders accept by default.
+pub const DEFAULT_MAX_NM: usize = MAX_BITSIZE * MAX_PROOFS;
+
+/// Limits on the sizes claimed by the encodings decoders accept.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The largest accepted product of the bitsize and number of proofs
+    max_nm: usize,
+}
+
+impl Limits {
+    /// Creates limits accepting at most `max_nm` generators or bits in
+    /// total.
+    pub fn new(max_nm: usize) -> Self {
+        Limits { max_nm }
+    }
+
+    /// Returns the largest accepted \\(nm\\).
+    pub fn max_nm(&self) -> usize {
+        self.max_nm
+    }
+
+    /// Returns `TooLarge` if \\(nm\\) exceeds the limits.
+    pub fn check(&self, n: usize, m: usize) -> Result<(), ProofError> {
+        match n.checked_mul(m) {
+            Some(nm) if nm <= self.max_nm => Ok(()),
+            _ => Err(ProofError::TooLarge),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::new(DEFAULT_MAX_NM)
+    }
+}
+
+/// A validated bitsize `n` and number of proofs `m`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProofParams {
+    /// The bitsize of the ranges
+    n: usize,
+    /// The number of proofs
+    m: usize,
+}
+
+impl ProofParams {
+    /// Checks and returns the parameters for `m` proofs of `n`-bit ranges.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not a power of two of at most
+    /// `MAX_BITSIZE`, and `InvalidAggregation` if `m` is zero or larger
+    /// than `MAX_PROOFS`.
+    pub fn new(n: usize, m: usize) -> Result<ProofParams, ProofError> {
+        if !n.is_power_of_two() || n > MAX_BITSIZE {
+            return Err(ProofError::InvalidBitsize);
+        }
+        if m == 0 || m > MAX_PROOFS {
+            return Err(ProofError::InvalidAggregation);
+        }
+        Ok(ProofParams { n, m })
+    }
+
+    /// Checks and returns the parameters for proofs of `n`-bit ranges
+    /// made one at a time.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not a power of two of at most
+    /// `MAX_BITSIZE`.
+    pub fn single(n: usize) -> Result<ProofParams, ProofError> {
+        ProofParams::new(n, 1)
+    }
+
+    /// Returns the bitsize \\(n\\) of the ranges.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the number \\(m\\) of proofs.
+    pub fn m(&self) -> usize {
+        self.m
+    }
+
+    /// Returns the number \\(\lg n\\) of rounds of the inner-product
+    /// argument of each proof.
+    pub fn rounds(&self) -> usize {
+        self.n.trailing_zeros() as usize
+    }
+
+    /// Checks that `generators` were made for these parameters.
+    pub fn check_generators(&self, generators: &Generators) -> Result<(), ProofError> {
+        if generators.n != self.n || generators.m != self.m {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        Ok(())
+    }
+
+    /// Checks that a share of generators has exactly \\(n\\) bases in
+    /// each of `G` and `H`.
+    pub fn check_share(&self, generators: &GeneratorsView) -> Result<(), ProofError> {
+        if generators.G.len() != self.n || generators.H.len() != self.n {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::PedersenGenerators;
+
+    #[test]
+    fn params_are_checked_once() {
+        assert_eq!(ProofParams::new(24, 1), Err(ProofError::InvalidBitsize));
+        assert_eq!(ProofParams::new(128, 1), Err(ProofError::InvalidBitsize));
+        assert_eq!(ProofParams::new(0, 1), Err(ProofError::InvalidBitsize));
+        assert_eq!(ProofParams::new(8, 0), Err(ProofError::InvalidAggregation));
+        assert_eq!(
+            ProofParams::new(8, MAX_PROOFS + 1),
+            Err(ProofError::InvalidAggregation)
+        );
+
+        let params = ProofParams::new(16, 2).unwrap();
+        assert_eq!((params.n(), params.m(), params.rounds()), (16, 2, 4));
+        let generators = Generators::with_params(b"Test", PedersenGenerators::default(), &params);
+        assert!(params.check_generators(&generators).is_ok());
+        let other = Generators::new(b"Test", PedersenGenerators::default(), 16, 1);
+        assert_eq!(
+            params.check_generators(&other),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+
+        let single = ProofParams::single(16).unwrap();
+        assert!(single.check_share(&other.share(0)).is_ok());
+        assert_eq!(
+            ProofParams::single(8).unwrap().check_share(&other.share(0)),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(ProofParams::single(24), Err(ProofError::InvalidBitsize));
+    }
+
+    #[test]
+    fn limits_reject_claimed_sizes() {
+        let limits = Limits::default();
+        assert!(limits.check(MAX_BITSIZE, MAX_PROOFS).is_ok());
+        assert_eq!(limits.check(MAX_BITSIZE, MAX_PROOFS + 1), Err(ProofError::TooLarge));
+        assert_eq!(limits.check(usize::max_value(), 2), Err(ProofError::TooLarge));
+
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 8, 2);
+        let bytes = generators.to_bytes();
+        assert!(Generators::from_bytes_with_limits(&bytes, &Limits::new(16)).is_ok());
+        assert_eq!(
+            Generators::from_bytes_with_limits(&bytes, &Limits::new(15)),
+            Err(ProofError::TooLarge)
+        );
+    }
+}
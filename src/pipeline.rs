@@ -0,0 +1,245 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `pipeline` module contains a throughput-oriented prover for
+//! workloads that create many range proofs with the same parameters.
+//!
+//! Jobs are queued with `ProvingPipeline::push` and proven in batches
+//! by `ProvingPipeline::prove`.  Every batch shares the generators and
+//! reuses one `ProverScratch`, so no per-proof allocation is needed
+//! beyond the proof itself.  With the `parallel` feature, batches are
+//! proven on the rayon thread pool.
+//!
+//! Each job gets its own RNG, seeded from the caller's RNG in job
+//! order before any proving starts, so the proofs do not depend on how
+//! the batches are scheduled and are the same with and without the
+//! `parallel` feature.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut pipeline = ProvingPipeline::new(generators.share(0), 64, b"Withdrawal")?;
+//! for (v, v_blinding) in withdrawals {
+//!     pipeline.push(v, v_blinding);
+//! }
+//! for (V, proof) in pipeline.prove(&mut rng) {
+//!     // publish V and proof
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::{ChaChaRng, Rng, SeedableRng};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use scratch::ProverScratch;
+use secret::{Blinding, SecretValue};
+
+/// The number of jobs proven with one `ProverScratch` by default.
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// A queued proving job.
+struct Job {
+    v: SecretValue,
+    v_blinding: Blinding,
+    seed: [u32; 8],
+}
+
+/// `ProvingPipeline` queues range proof jobs and proves them in batches.
+pub struct ProvingPipeline<'a> {
+    generators: GeneratorsView<'a>,
+    n: usize,
+    /// A fresh transcript with the pipeline's label, forked for each job
+    transcript: ProofTranscript,
+    batch_size: usize,
+    jobs: Vec<(SecretValue, Blinding)>,
+}
+
+impl<'a> ProvingPipeline<'a> {
+    /// Creates an empty pipeline for `n`-bit range proofs against
+    /// `generators`, each in a fresh transcript labeled with `label`.
+    ///
+    /// Returns `EmptyLabel` if `label` is empty, and the errors of
+    /// `RangeProof::generate_proof` if `n` or the generators are not
+    /// supported, so that proving the jobs cannot fail.
+    pub fn new(
+        generators: GeneratorsView<'a>,
+        n: usize,
+        label: &[u8],
+    ) -> Result<Self, ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        Ok(ProvingPipeline {
+            generators,
+            n,
+            transcript: ProofTranscript::new(label)?,
+            batch_size: DEFAULT_BATCH_SIZE,
+            jobs: Vec::new(),
+        })
+    }
+
+    /// Sets the number of jobs proven with one `ProverScratch`.
+    ///
+    /// With the `parallel` feature this is also the unit of work handed
+    /// to each thread.  Panics if `batch_size` is zero.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch_size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Queues a proof that the value `v` committed with `v_blinding`
+    /// is in range, taking ownership of both.
+    pub fn push(&mut self, v: SecretValue, v_blinding: Blinding) {
+        self.jobs.push((v, v_blinding));
+    }
+
+    /// Returns the number of queued jobs.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Returns `true` if no jobs are queued.
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Proves all the queued jobs, returning the value commitment and
+    /// the proof for each, in the order the jobs were pushed.
+    ///
+    /// The queue is empty afterwards, so the pipeline can be refilled.
+    pub fn prove<R: Rng>(&mut self, rng: &mut R) -> Vec<(RistrettoPoint, RangeProof)> {
+        let jobs: Vec<Job> = self.jobs
+            .drain(..)
+            .map(|(v, v_blinding)| Job {
+                v,
+                v_blinding,
+                seed: rng.gen(),
+            })
+            .collect();
+
+        self.prove_jobs(&jobs)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn prove_jobs(&self, jobs: &[Job]) -> Vec<(RistrettoPoint, RangeProof)> {
+        jobs.chunks(self.batch_size)
+            .flat_map(|batch| self.prove_batch(batch))
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn prove_jobs(&self, jobs: &[Job]) -> Vec<(RistrettoPoint, RangeProof)> {
+        use rayon::prelude::*;
+
+        let batches: Vec<Vec<_>> = jobs.par_chunks(self.batch_size)
+            .map(|batch| self.prove_batch(batch))
+            .collect();
+        batches.into_iter().flat_map(|batch| batch).collect()
+    }
+
+    fn prove_batch(&self, batch: &[Job]) -> Vec<(RistrettoPoint, RangeProof)> {
+        let mut scratch = ProverScratch::new();
+        batch
+            .iter()
+            .map(|job| {
+                let mut rng = ChaChaRng::from_seed(&job.seed[..]);
+                let mut transcript = self.transcript.fork();
+                let V = self.generators
+                    .pedersen_generators
+                    .commit(job.v.to_scalar(), *job.v_blinding.as_scalar());
+                let proof = RangeProof::generate_proof_with_scratch(
+                    self.generators,
+                    &mut transcript,
+                    &mut rng,
+                    &mut scratch,
+                    self.n,
+                    &job.v,
+                    &job.v_blinding,
+                ).expect("the parameters were checked by ProvingPipeline::new");
+                (V, proof)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use bincode;
+    use rand::OsRng;
+
+    #[test]
+    fn pipeline_proofs_verify_in_order() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut pipeline = ProvingPipeline::new(generators.share(0), n, b"PipelineTest")
+            .unwrap()
+            .with_batch_size(2);
+        for v in 0..5u64 {
+            pipeline.push(SecretValue::new(v), Blinding::random(&mut rng));
+        }
+        assert_eq!(pipeline.len(), 5);
+
+        let proofs = pipeline.prove(&mut rng);
+        assert!(pipeline.is_empty());
+        assert_eq!(proofs.len(), 5);
+
+        for &(V, ref proof) in proofs.iter() {
+            let mut transcript = ProofTranscript::new(b"PipelineTest").unwrap();
+            assert!(
+                proof
+                    .verify(&V, generators.share(0), &mut transcript, &mut rng, n)
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn batch_size_does_not_change_proofs() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let blindings: Vec<Scalar> = (0..3u64).map(|i| Scalar::from_u64(i + 100)).collect();
+
+        let prove_with = |batch_size| {
+            let mut pipeline = ProvingPipeline::new(generators.share(0), n, b"PipelineTest")
+                .unwrap()
+                .with_batch_size(batch_size);
+            for (v, v_blinding) in blindings.iter().enumerate() {
+                pipeline.push(SecretValue::new(v as u64), Blinding::new(*v_blinding));
+            }
+            let mut rng = ChaChaRng::from_seed(&[7u32; 8]);
+            pipeline.prove(&mut rng)
+        };
+
+        let one: Vec<_> = prove_with(1).into_iter().map(|(_, p)| p).collect();
+        let three: Vec<_> = prove_with(3).into_iter().map(|(_, p)| p).collect();
+        assert_eq!(
+            bincode::serialize(&one).unwrap(),
+            bincode::serialize(&three).unwrap()
+        );
+    }
+
+    #[test]
+    fn unsupported_parameters_are_rejected_upfront() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 16, 1);
+        assert_eq!(
+            ProvingPipeline::new(generators.share(0), 12, b"PipelineTest").err(),
+            Some(ProofError::InvalidBitsize)
+        );
+        assert_eq!(
+            ProvingPipeline::new(generators.share(0), 8, b"PipelineTest").err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+    }
+}
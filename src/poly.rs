@@ -0,0 +1,235 @@
+#![deny(missing_docs)]
+#![allow(non_snake_case)]
+
+//! The `poly` module contains the vector-polynomial arithmetic used to
+//! build the polynomials \\(l(x)\\), \\(r(x)\\) and
+//! \\(t(x) = {\langle l(x), r(x) \rangle}\\) in the range proof and
+//! the constraint system proof.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate curve25519_dalek;
+//! # extern crate ristretto_bulletproofs;
+//! # use curve25519_dalek::scalar::Scalar;
+//! # use ristretto_bulletproofs::poly::VecPoly1;
+//! # fn main() {
+//! let one = Scalar::one();
+//! let two = Scalar::from_u64(2);
+//!
+//! // l(x) = (1, 2) + (2, 1) x and r(x) = (2, 2) + (1, 1) x
+//! let l = VecPoly1(vec![one, two], vec![two, one]);
+//! let r = VecPoly1(vec![two, two], vec![one, one]);
+//!
+//! let t = l.inner_product(&r);
+//! let x = Scalar::from_u64(5);
+//! assert_eq!(t.eval(x), l.eval(x).iter().zip(r.eval(x).iter()).fold(
+//!     Scalar::zero(),
+//!     |acc, (l_i, r_i)| acc + l_i * r_i,
+//! ));
+//! # }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use inner_product_proof::inner_product;
+use util::add_vec;
+
+/// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
+#[derive(Clone, Debug)]
+pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
+
+/// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
+#[derive(Clone, Debug)]
+pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
+
+/// Represents a degree-3 vector polynomial
+/// \\(\mathbf{a} + \mathbf{b} \cdot x + \mathbf{c} \cdot x^2 + \mathbf{d} \cdot x^3 \\).
+#[derive(Clone, Debug)]
+pub struct VecPoly3(
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+    pub Vec<Scalar>,
+);
+
+/// Represents a degree-6 scalar polynomial, without the zero-th degree
+/// \\(a \cdot x + b \cdot x^2 + c \cdot x^3 + d \cdot x^4 + e \cdot x^5 + f \cdot x^6\\)
+#[derive(Clone, Debug)]
+pub struct Poly6 {
+    /// Coefficient of \\(x\\)
+    pub t1: Scalar,
+    /// Coefficient of \\(x^2\\)
+    pub t2: Scalar,
+    /// Coefficient of \\(x^3\\)
+    pub t3: Scalar,
+    /// Coefficient of \\(x^4\\)
+    pub t4: Scalar,
+    /// Coefficient of \\(x^5\\)
+    pub t5: Scalar,
+    /// Coefficient of \\(x^6\\)
+    pub t6: Scalar,
+}
+
+impl VecPoly1 {
+    /// Returns the zero polynomial with vectors of length `n`.
+    pub fn zero(n: usize) -> Self {
+        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
+    }
+
+    /// Computes the inner product \\({\langle l(x), r(x) \rangle}\\)
+    /// of two vector polynomials.
+    ///
+    /// Panics if the lengths of the vectors do not match.
+    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
+        // Uses Karatsuba's method
+        let l = self;
+        let r = rhs;
+
+        let t0 = inner_product(&l.0, &r.0);
+        let t2 = inner_product(&l.1, &r.1);
+
+        let l0_plus_l1 = add_vec(&l.0, &l.1);
+        let r0_plus_r1 = add_vec(&r.0, &r.1);
+
+        let t1 = inner_product(&l0_plus_l1, &r0_plus_r1) - t0 - t2;
+
+        Poly2(t0, t1, t2)
+    }
+
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+        let n = self.0.len();
+        let mut out = vec![Scalar::zero(); n];
+        self.eval_into(x, &mut out);
+        out
+    }
+
+    /// Evaluates the polynomial at `x`, writing the result into `out`.
+    pub fn eval_into(&self, x: Scalar, out: &mut [Scalar]) {
+        for i in 0..self.0.len() {
+            out[i] = self.0[i] + self.1[i] * x;
+        }
+    }
+}
+
+impl Poly2 {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Scalar {
+        self.0 + x * (self.1 + x * self.2)
+    }
+}
+
+impl VecPoly3 {
+    /// Returns the zero polynomial with vectors of length `n`.
+    pub fn zero(n: usize) -> Self {
+        VecPoly3(
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+            vec![Scalar::zero(); n],
+        )
+    }
+
+    /// Computes the inner product \\({\langle l(x), r(x) \rangle}\\)
+    /// of two vector polynomials, assuming that the zero-th degree
+    /// coefficient of `self` is zero, as is the case for the
+    /// constraint system proof.
+    ///
+    /// Panics if the lengths of the vectors do not match.
+    pub fn special_inner_product(&self, rhs: &VecPoly3) -> Poly6 {
+        let l = self;
+        let r = rhs;
+
+        let t1 = inner_product(&l.1, &r.0);
+        let t2 = inner_product(&l.1, &r.1) + inner_product(&l.2, &r.0);
+        let t3 = inner_product(&l.2, &r.1) + inner_product(&l.3, &r.0) + inner_product(&l.1, &r.2);
+        let t4 = inner_product(&l.1, &r.3) + inner_product(&l.3, &r.1) + inner_product(&l.2, &r.2);
+        let t5 = inner_product(&l.2, &r.3) + inner_product(&l.3, &r.2);
+        let t6 = inner_product(&l.3, &r.3);
+
+        Poly6 {
+            t1,
+            t2,
+            t3,
+            t4,
+            t5,
+            t6,
+        }
+    }
+
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+        let n = self.0.len();
+        let mut out = vec![Scalar::zero(); n];
+        for i in 0..n {
+            out[i] = self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i]));
+        }
+        out
+    }
+}
+
+impl Poly6 {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: Scalar) -> Scalar {
+        x * (self.t1 + x * (self.t2 + x * (self.t3 + x * (self.t4 + x * (self.t5 + x * self.t6)))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn inner_product_matches_evaluations() {
+        let mut rng = OsRng::new().unwrap();
+        let n = 8;
+        let random_vec = |rng: &mut OsRng| -> Vec<Scalar> {
+            (0..n).map(|_| Scalar::random(rng)).collect()
+        };
+
+        let l = VecPoly1(random_vec(&mut rng), random_vec(&mut rng));
+        let r = VecPoly1(random_vec(&mut rng), random_vec(&mut rng));
+        let t = l.inner_product(&r);
+
+        let x = Scalar::random(&mut rng);
+        assert_eq!(t.eval(x), inner_product(&l.eval(x), &r.eval(x)));
+    }
+
+    #[test]
+    fn special_inner_product_matches_evaluations() {
+        let mut rng = OsRng::new().unwrap();
+        let n = 4;
+        let random_vec = |rng: &mut OsRng| -> Vec<Scalar> {
+            (0..n).map(|_| Scalar::random(rng)).collect()
+        };
+
+        let l = VecPoly3(
+            vec![Scalar::zero(); n],
+            random_vec(&mut rng),
+            random_vec(&mut rng),
+            random_vec(&mut rng),
+        );
+        let r = VecPoly3(
+            random_vec(&mut rng),
+            random_vec(&mut rng),
+            random_vec(&mut rng),
+            random_vec(&mut rng),
+        );
+        let t = l.special_inner_product(&r);
+
+        let x = Scalar::random(&mut rng);
+        assert_eq!(t.eval(x), inner_product(&l.eval(x), &r.eval(x)));
+    }
+
+    #[test]
+    fn poly2_eval() {
+        let p = Poly2(Scalar::from_u64(1), Scalar::from_u64(2), Scalar::from_u64(3));
+        // 1 + 2*2 + 3*4 = 17
+        assert_eq!(p.eval(Scalar::from_u64(2)), Scalar::from_u64(17));
+    }
+}
@@ -0,0 +1,395 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `polynomial_commitment` module contains a polynomial commitment
+//! scheme built on the inner product argument.
+//!
+//! A polynomial \\(p(X) = \sum\_i c\_i X^i\\) with at most \\(n\\)
+//! coefficients is committed as \\(C = \langle \mathbf{c}, \mathbf{G} \rangle + r \widetilde{B}\\).
+//! To open it at a point \\(x\\), the prover shows that
+//! \\(\langle \mathbf{c}, (1, x, \ldots, x^{n-1}) \rangle = p(x)\\) with
+//! an inner product argument over \\(\mathbf{G}\\) and \\(\mathbf{H}\\),
+//! where the second vector is public.
+//!
+//! The inner product argument is not zero-knowledge by itself, so the
+//! prover first commits to a random polynomial \\(d\\) and the
+//! evaluation \\(d(x)\\), and runs the argument for
+//! \\(c + \alpha d\\) with a challenge \\(\alpha\\).  The opening then
+//! reveals nothing about \\(p\\) beyond \\(p(x)\\).
+//!
+//! The number of coefficients \\(n\\) must be a power of two no larger
+//! than the number of generators, and an opening has
+//! \\(2 \lg n + 1\\) points and four scalars.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let C = polynomial_commitment::commit(generators.share(0), &coefficients, &blinding)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Evaluation")?;
+//! let (y, proof) = OpeningProof::open(
+//!     generators.share(0), &mut transcript, &mut rng, 64, &coefficients, &blinding, &x,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Evaluation")?;
+//! proof.verify(generators.share(0), &mut transcript, 64, &C, &x, &y)?;
+//! ```
+
+use core::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use inner_product_proof::{inner_product, InnerProductProof};
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar, SecretScalars};
+use util;
+
+/// Commits to the polynomial with the given `coefficients`, lowest
+/// degree first, with the blinding factor `blinding`.
+///
+/// Returns `ProofError::InvalidGeneratorsLength` if there are more
+/// coefficients than generators.
+pub fn commit(
+    generators: GeneratorsView,
+    coefficients: &[SecretScalar],
+    blinding: &Blinding,
+) -> Result<RistrettoPoint, ProofError> {
+    commit_scalars(generators, &SecretScalars::from(coefficients), blinding.as_scalar())
+}
+
+/// Commits to the `coefficients` with the blinding factor `blinding`,
+/// as `commit`.
+pub(crate) fn commit_scalars(
+    generators: GeneratorsView,
+    coefficients: &[Scalar],
+    blinding: &Scalar,
+) -> Result<RistrettoPoint, ProofError> {
+    if coefficients.len() > generators.G.len() {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    Ok(util::multiscalar_mul(
+        iter::once(blinding).chain(coefficients.iter()),
+        iter::once(&generators.pedersen_generators.B_blinding)
+            .chain(generators.G.iter()),
+    ))
+}
+
+/// Evaluates the polynomial with the given `coefficients`, lowest
+/// degree first, at `x`.
+pub fn evaluate(coefficients: &[Scalar], x: &Scalar) -> Scalar {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, c_i| acc * x + c_i)
+}
+
+/// A proof that a committed polynomial evaluates to a given value at a
+/// given point.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OpeningProof {
+    /// Commitment to the masking polynomial \\(d\\)
+    D: RistrettoPoint,
+    /// Inner product of the masking vector with the public vector, i.e.
+    /// the evaluation \\(d(x)\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    d_x: Scalar,
+    /// Blinding factor of the commitment to \\(c + \alpha d\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    blinding: Scalar,
+    /// Proof data for the inner-product argument
+    ipp_proof: InnerProductProof,
+}
+
+impl OpeningProof {
+    /// Opens the commitment to the polynomial with the given
+    /// `coefficients` and `blinding` at `x`, for polynomials with at
+    /// most `n` coefficients, returning the evaluation and the proof.
+    ///
+    /// Returns `ProofError::InvalidBitsize` if `n` is not a power of
+    /// two, `ProofError::InvalidGeneratorsLength` if it exceeds the
+    /// number of generators, and `ProofError::ValueOutOfRange` if there
+    /// are more than `n` coefficients.
+    pub fn open<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        coefficients: &[SecretScalar],
+        blinding: &Blinding,
+        x: &Scalar,
+    ) -> Result<(Scalar, OpeningProof), ProofError> {
+        check_size(&generators, n)?;
+        if coefficients.len() > n {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let c = SecretScalars::from(coefficients);
+        let blinding = blinding.as_scalar();
+        let C = commit_scalars(generators, &c, blinding)?;
+        let y = evaluate(&c, x);
+        transcript_prefix(transcript, n, &C, x, &y);
+
+        let x_powers = util::ScalarPowers::new(*x, n).as_slice().to_vec();
+        let proof = OpeningProof::create(generators, transcript, rng, &c, blinding, x_powers)?;
+        Ok((y, proof))
+    }
+
+    /// Verifies that the polynomial committed in `C`, with at most `n`
+    /// coefficients, evaluates to `y` at `x`.
+    pub fn verify(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        n: usize,
+        C: &RistrettoPoint,
+        x: &Scalar,
+        y: &Scalar,
+    ) -> Result<(), ProofError> {
+        check_size(&generators, n)?;
+        transcript_prefix(transcript, n, C, x, y);
+
+        let x_powers = util::ScalarPowers::new(*x, n).as_slice().to_vec();
+        self.verify_inner_product(generators, transcript, C, &x_powers, y)
+    }
+
+    /// Proves that the vector `c` committed with `blinding` has the inner
+    /// product \\(\langle \mathbf{c}, \mathbf{b} \rangle\\) with the public
+    /// vector `b`, padding `c` with zeros to the length of `b`.
+    ///
+    /// The length of `b` must be a power of two covered by the
+    /// generators, and at least the length of `c`.
+    pub(crate) fn create<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        c: &[Scalar],
+        blinding: &Scalar,
+        b: Vec<Scalar>,
+    ) -> Result<OpeningProof, ProofError> {
+        let n = b.len();
+        check_size(&generators, n)?;
+        if c.len() > n {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        let mut c = SecretScalars::from(c.to_vec());
+        c.resize(n, Scalar::zero());
+
+        // Commit to a random masking vector and its inner product, with
+        // nonces derived from the transcript and the witness as well as
+        // from `rng`, as the range proofs do.
+        let witness: Vec<&[u8]> = c
+            .iter()
+            .chain(iter::once(blinding))
+            .map(|x| &x.as_bytes()[..])
+            .collect();
+        let mut nonces = transcript.build_rng(&witness, rng);
+        let d: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut nonces)).collect();
+        let d_blinding = Scalar::random(&mut nonces);
+        let D = commit_scalars(generators, &d, &d_blinding)?;
+        let d_x = inner_product(&d, &b);
+
+        transcript.commit(D.compress().as_bytes());
+        transcript.commit(d_x.as_bytes());
+        let alpha = transcript.challenge_scalar_labeled(b"alpha");
+
+        let a: Vec<Scalar> = c.iter().zip(d.iter()).map(|(c_i, d_i)| c_i + alpha * d_i).collect();
+        let blinding = blinding + alpha * d_blinding;
+
+        transcript.commit(blinding.as_bytes());
+        let w = transcript.challenge_scalar_labeled(b"w");
+        let Q = w * generators.pedersen_generators.B;
+
+        let ipp_proof = InnerProductProof::create(
+            transcript,
+            &Q,
+            iter::repeat(Scalar::one()).take(n),
+            generators.G[..n].to_vec(),
+            generators.H[..n].to_vec(),
+            a,
+            b,
+        )?;
+
+        Ok(OpeningProof {
+            D,
+            d_x,
+            blinding,
+            ipp_proof,
+        })
+    }
+
+    /// Verifies that the vector committed in `C` has the inner product
+    /// `y` with the public vector `b`.
+    pub(crate) fn verify_inner_product(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        C: &RistrettoPoint,
+        b: &[Scalar],
+        y: &Scalar,
+    ) -> Result<(), ProofError> {
+        let n = b.len();
+        check_size(&generators, n)?;
+
+        transcript.commit(self.D.compress().as_bytes());
+        transcript.commit(self.d_x.as_bytes());
+        let alpha = transcript.challenge_scalar_labeled(b"alpha");
+
+        transcript.commit(self.blinding.as_bytes());
+        let w = transcript.challenge_scalar_labeled(b"w");
+
+        let (u_sq, u_inv_sq, s) = self.ipp_proof.verification_scalars(transcript, n)?;
+        let ipp_a = self.ipp_proof.a;
+        let ipp_b = self.ipp_proof.b;
+
+        // C + alpha D - blinding B_blinding + <b, H> + (y + alpha d_x) Q
+        // must equal the inner product argument's <a s, G> + <b s^-1, H> + a b Q
+        // - sum(u_j^2 L_j + u_j^-2 R_j).
+        let g = s.iter().map(|s_i| -(ipp_a * s_i));
+        // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
+        let h = b.iter()
+            .zip(s.iter().rev())
+            .map(|(b_i, s_i_inv)| b_i - ipp_b * s_i_inv);
+
+        let mega_check = util::vartime_multiscalar_mul(
+            iter::once(Scalar::one())
+                .chain(iter::once(alpha))
+                .chain(iter::once(-self.blinding))
+                .chain(iter::once(w * (y + alpha * self.d_x - ipp_a * ipp_b)))
+                .chain(g)
+                .chain(h)
+                .chain(u_sq.into_iter())
+                .chain(u_inv_sq.into_iter()),
+            iter::once(C)
+                .chain(iter::once(&self.D))
+                .chain(iter::once(&generators.pedersen_generators.B_blinding))
+                .chain(iter::once(&generators.pedersen_generators.B))
+                .chain(generators.G[..n].iter())
+                .chain(generators.H[..n].iter())
+                .chain(self.ipp_proof.L_vec.iter())
+                .chain(self.ipp_proof.R_vec.iter()),
+        )?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// Checks that `n` is a power of two covered by the generators.
+fn check_size(generators: &GeneratorsView, n: usize) -> Result<(), ProofError> {
+    if !n.is_power_of_two() {
+        Err(ProofError::InvalidBitsize)
+    } else if n > generators.G.len() {
+        Err(ProofError::InvalidGeneratorsLength)
+    } else {
+        Ok(())
+    }
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(
+    transcript: &mut ProofTranscript,
+    n: usize,
+    C: &RistrettoPoint,
+    x: &Scalar,
+    y: &Scalar,
+) {
+    transcript.commit(b"PolynomialCommitment");
+    transcript.commit_u64(n as u64);
+    transcript.commit(C.compress().as_bytes());
+    transcript.commit(x.as_bytes());
+    transcript.commit(y.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+
+    #[test]
+    fn openings_verify() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        // p(X) = 3 + 2X + X^4
+        let coefficients: Vec<SecretScalar> = [3, 2, 0, 0, 1]
+            .iter()
+            .map(|&c| SecretScalar::new(Scalar::from_u64(c)))
+            .collect();
+        let blinding = Blinding::random(&mut rng);
+        let C = commit(generators.share(0), &coefficients, &blinding).unwrap();
+        let x = Scalar::from_u64(2);
+
+        let mut transcript = ProofTranscript::new(b"PolynomialTest").unwrap();
+        let (y, proof) = OpeningProof::open(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &coefficients,
+            &blinding,
+            &x,
+        ).unwrap();
+        assert_eq!(y, Scalar::from_u64(23));
+
+        let mut transcript = ProofTranscript::new(b"PolynomialTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, n, &C, &x, &y)
+                .is_ok()
+        );
+
+        // Another evaluation, or another point, does not verify.
+        let mut transcript = ProofTranscript::new(b"PolynomialTest").unwrap();
+        let wrong_y = y + Scalar::one();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, n, &C, &x, &wrong_y)
+                .is_err()
+        );
+        let mut transcript = ProofTranscript::new(b"PolynomialTest").unwrap();
+        let wrong_x = Scalar::from_u64(3);
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, n, &C, &wrong_x, &y)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sizes_are_checked() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 8, 1);
+        let mut rng = OsRng::new().unwrap();
+        let coefficients = vec![SecretScalar::new(Scalar::one()); 5];
+        let blinding = Blinding::random(&mut rng);
+        let x = Scalar::from_u64(2);
+
+        for &n in [4, 6, 16].iter() {
+            let mut transcript = ProofTranscript::new(b"PolynomialTest").unwrap();
+            assert!(
+                OpeningProof::open(
+                    generators.share(0),
+                    &mut transcript,
+                    &mut rng,
+                    n,
+                    &coefficients,
+                    &blinding,
+                    &x,
+                ).is_err()
+            );
+        }
+    }
+}
@@ -0,0 +1,124 @@
+#![deny(missing_docs)]
+
+//! The `profiling` module counts the expensive operations performed
+//! while proving and verifying.
+//!
+//! Counting is only performed when the crate is built with the
+//! `profiling` feature; otherwise the recording hooks compile to
+//! nothing and `measure` always reports zero counts.
+//!
+//! Counts are kept per thread.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let (result, counts) = profiling::measure(|| {
+//!     proof.verify(&V, generators.share(0), &mut transcript, &mut rng, n)
+//! });
+//! println!("verification used {} scalar multiplications", counts.scalar_muls);
+//! ```
+
+#[cfg(feature = "profiling")]
+use core::cell::Cell;
+
+/// Numbers of operations performed during a measured computation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    /// Scalar-point multiplications, counting each term of a
+    /// multiscalar multiplication separately
+    pub scalar_muls: u64,
+    /// Point additions performed outside of multiscalar multiplications
+    pub point_adds: u64,
+    /// Messages committed to a `ProofTranscript`
+    pub transcript_commits: u64,
+    /// Challenges extracted from a `ProofTranscript`
+    pub transcript_challenges: u64,
+}
+
+#[cfg(feature = "profiling")]
+thread_local! {
+    static COUNTS: Cell<OpCounts> = Cell::new(OpCounts::default());
+}
+
+#[cfg(feature = "profiling")]
+fn update<F: FnOnce(&mut OpCounts)>(f: F) {
+    COUNTS.with(|counts| {
+        let mut c = counts.get();
+        f(&mut c);
+        counts.set(c);
+    });
+}
+
+/// Runs `f` and returns its result together with the operations it performed.
+pub fn measure<T, F: FnOnce() -> T>(f: F) -> (T, OpCounts) {
+    let before = current();
+    let result = f();
+    let after = current();
+    let counts = OpCounts {
+        scalar_muls: after.scalar_muls - before.scalar_muls,
+        point_adds: after.point_adds - before.point_adds,
+        transcript_commits: after.transcript_commits - before.transcript_commits,
+        transcript_challenges: after.transcript_challenges - before.transcript_challenges,
+    };
+    (result, counts)
+}
+
+#[cfg(feature = "profiling")]
+fn current() -> OpCounts {
+    COUNTS.with(|counts| counts.get())
+}
+
+#[cfg(not(feature = "profiling"))]
+fn current() -> OpCounts {
+    OpCounts::default()
+}
+
+/// Records `n` scalar-point multiplications.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn record_scalar_muls(n: usize) {
+    #[cfg(feature = "profiling")]
+    update(|c| c.scalar_muls += n as u64);
+}
+
+/// Records `n` point additions.
+#[inline]
+#[allow(unused_variables)]
+pub(crate) fn record_point_adds(n: usize) {
+    #[cfg(feature = "profiling")]
+    update(|c| c.point_adds += n as u64);
+}
+
+/// Records a transcript commitment.
+#[inline]
+pub(crate) fn record_transcript_commit() {
+    #[cfg(feature = "profiling")]
+    update(|c| c.transcript_commits += 1);
+}
+
+/// Records a transcript challenge.
+#[inline]
+pub(crate) fn record_transcript_challenge() {
+    #[cfg(feature = "profiling")]
+    update(|c| c.transcript_challenges += 1);
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+    use proof_transcript::ProofTranscript;
+
+    #[test]
+    fn measure_counts_transcript_operations() {
+        let (_, counts) = measure(|| {
+            let mut transcript = ProofTranscript::new(b"ProfilingTest").unwrap();
+            transcript.commit(b"message");
+            transcript.challenge_scalar()
+        });
+
+        // The label and the message are both committed.
+        assert_eq!(counts.transcript_commits, 2);
+        assert_eq!(counts.transcript_challenges, 1);
+        assert_eq!(counts.scalar_muls, 0);
+    }
+}
@@ -4,7 +4,10 @@
 //! implementation of non-interactive proofs as if they were
 //! interactive, using the Fiat-Shamir transform.
 
+use core::fmt;
+
 use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
 
 // XXX This uses experiment fork of tiny_keccak with half-duplex
 // support that we require in this implementation.
@@ -14,6 +17,34 @@ use tiny_keccak::Keccak;
 
 use byteorder::{ByteOrder, LittleEndian};
 
+use errors::ProofError;
+use profiling;
+
+/// The length of the longest length prefix written by `length_prefix`.
+pub(crate) const MAX_LENGTH_PREFIX: usize = 10;
+
+/// The `u16` prefix of messages of 65535 bytes or more, which is
+/// followed by their length as a little-endian `u64`.
+pub(crate) const LONG_MESSAGE: u16 = 0xffff;
+
+/// Writes the prefix of a message of `len` bytes to `prefix`, returning
+/// the length of the prefix.
+///
+/// The prefix is the length as a little-endian `u16` for messages
+/// shorter than 65535 bytes, and `LONG_MESSAGE` followed by the length
+/// as a little-endian `u64` otherwise, so that no prefix of one length
+/// is the start of the prefix of another.
+pub(crate) fn length_prefix(len: usize, prefix: &mut [u8; MAX_LENGTH_PREFIX]) -> usize {
+    if len < LONG_MESSAGE as usize {
+        LittleEndian::write_u16(&mut prefix[..2], len as u16);
+        2
+    } else {
+        LittleEndian::write_u16(&mut prefix[..2], LONG_MESSAGE);
+        LittleEndian::write_u64(&mut prefix[2..], len as u64);
+        MAX_LENGTH_PREFIX
+    }
+}
+
 /// The `ProofTranscript` struct represents a transcript of messages
 /// between a prover and verifier engaged in a public-coin argument.
 ///
@@ -25,12 +56,27 @@ use byteorder::{ByteOrder, LittleEndian};
 /// object, send it (what should be) the same messages, and request
 /// (what should be) the same challenge values.
 ///
-/// To create a `ProofTranscript` object, use `ProofTranscript::new()`
-/// at the outermost protocol layer.  A `&mut` reference to this
+/// To create a `ProofTranscript` object, use `ProofTranscript::new()?`
+/// with the application domain label at the outermost protocol layer.  A `&mut` reference to this
 /// object can then be passed to any sub-protocols, making it easy to
 /// ensure that their challenge values are bound to the *entire* proof
 /// transcript, not just the sub-protocol.
 ///
+/// A `ProofTranscript` is not `Clone`: a copy of its state would let a
+/// caller make two proofs from the same transcript state, which weakens
+/// the domain separation between them.  Every proof moves the
+/// transcript on to a new state, so sub-protocols sharing a `&mut`
+/// reference never see the same state twice.
+///
+/// ```compile_fail
+/// # extern crate ristretto_bulletproofs;
+/// # use ristretto_bulletproofs::ProofTranscript;
+/// # fn main() {
+/// let transcript = ProofTranscript::new(b"Example").unwrap();
+/// let copy = transcript.clone();
+/// # }
+/// ```
+///
 /// Internally, the `ProofTranscript` is supposed to use Keccak to
 /// absorb incoming messages and to squeeze challenges.  The
 /// construction currently used is ad-hoc, has no security analysis,
@@ -47,7 +93,7 @@ use byteorder::{ByteOrder, LittleEndian};
 /// use curve25519_dalek::constants;
 /// let B = &constants::RISTRETTO_BASEPOINT_TABLE;
 ///
-/// let mut transcript = ProofTranscript::new(b"MyProofName: Don't copypaste this");
+/// let mut transcript = ProofTranscript::new(b"MyProofName: Don't copypaste this").unwrap();
 ///
 /// // Send "some message" to the verifier
 /// transcript.commit(b"some message");
@@ -60,15 +106,44 @@ use byteorder::{ByteOrder, LittleEndian};
 /// transcript.commit(P.compress().as_bytes());
 /// # }
 /// ```
-#[derive(Clone)]
 pub struct ProofTranscript {
     hash: Keccak,
 }
 
+impl fmt::Debug for ProofTranscript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The sponge state determines every later challenge, so keep it
+        // out of logs.
+        f.write_str("ProofTranscript { .. }")
+    }
+}
+
 impl ProofTranscript {
     /// Begin a new, empty proof transcript, using the given `label`
     /// for domain separation.
-    pub fn new(label: &[u8]) -> Self {
+    ///
+    /// The label should name the application and the protocol, so that
+    /// the challenges of two protocols never coincide.
+    ///
+    /// Returns `EmptyLabel` if `label` is empty.  Use `without_domain`
+    /// to begin a transcript without a label.
+    pub fn new(label: &[u8]) -> Result<Self, ProofError> {
+        if label.is_empty() {
+            return Err(ProofError::EmptyLabel);
+        }
+        Ok(ProofTranscript::labeled(label))
+    }
+
+    /// Begin a new, empty proof transcript with no domain label.
+    ///
+    /// The challenges of such a transcript are shared by every protocol
+    /// using one, so only use it to stay compatible with existing proofs.
+    pub fn without_domain() -> Self {
+        ProofTranscript::labeled(b"")
+    }
+
+    /// Begins a transcript with `label`, which the caller has checked.
+    pub(crate) fn labeled(label: &[u8]) -> Self {
         let mut ro = ProofTranscript {
             hash: Keccak::new_shake128(),
         };
@@ -78,25 +153,31 @@ impl ProofTranscript {
         ro
     }
 
+    /// Copies the transcript, for the crate's own checks that replay a
+    /// proof from the state it was made in.
+    pub(crate) fn fork(&self) -> ProofTranscript {
+        ProofTranscript {
+            hash: self.hash.clone(),
+        }
+    }
+
     /// Commit a `message` to the proof transcript.
     ///
     /// # Note
     ///
-    /// Each message must be shorter than 64Kb (65536 bytes).
+    /// Each message is prefixed with its length as by `length_prefix`,
+    /// so messages of any length can be committed.
     pub fn commit(&mut self, message: &[u8]) {
-        let len = message.len();
-        if len > (u16::max_value() as usize) {
-            panic!("Committed message must be less than 64Kb!");
-        }
+        profiling::record_transcript_commit();
 
-        let mut len_prefix = [0u8; 2];
-        LittleEndian::write_u16(&mut len_prefix, len as u16);
+        let mut len_prefix = [0u8; MAX_LENGTH_PREFIX];
+        let prefix_len = length_prefix(message.len(), &mut len_prefix);
 
         // XXX we rely on tiny_keccak experimental support for half-duplex mode and
         // correct switching from absorbing to squeezing and back.
         // Review this after this PR is merged or updated:
         // https://github.com/debris/tiny-keccak/pull/24
-        self.hash.absorb(&len_prefix);
+        self.hash.absorb(&len_prefix[..prefix_len]);
         self.hash.absorb(message);
     }
 
@@ -113,6 +194,8 @@ impl ProofTranscript {
 
     /// Extracts an arbitrary-sized challenge byte slice.
     pub fn challenge_bytes(&mut self, mut output: &mut [u8]) {
+        profiling::record_transcript_challenge();
+
         // XXX we rely on tiny_keccak experimental support for half-duplex mode and
         // correct switching from absorbing to squeezing and back.
         // Review this after this PR is merged or updated:
@@ -130,6 +213,41 @@ impl ProofTranscript {
         Scalar::from_bytes_mod_order_wide(&buf)
     }
 
+    /// Extracts a challenge scalar bound to a protocol phase `label`.
+    ///
+    /// The label is committed to the transcript before the challenge
+    /// is extracted, so challenges derived in different phases of a
+    /// protocol are domain-separated even if the rest of the
+    /// transcript is identical.
+    pub fn challenge_scalar_labeled(&mut self, label: &[u8]) -> Scalar {
+        self.commit(label);
+        self.challenge_scalar()
+    }
+
+    /// Forks the transcript into a `TranscriptRng` for the prover's
+    /// nonces, bound to the current state, to the `witness` messages
+    /// and to 32 bytes from `rng`.
+    ///
+    /// The nonces are then as unpredictable as the best of the three: a
+    /// repeated or weak `rng` output cannot make two proofs share
+    /// nonces unless their statements and witnesses are also the same.
+    /// The fork does not change the transcript and is not counted by
+    /// `profiling`.
+    pub(crate) fn build_rng<R: Rng>(&self, witness: &[&[u8]], rng: &mut R) -> TranscriptRng {
+        let mut random_bytes = [0u8; 32];
+        rng.fill_bytes(&mut random_bytes);
+
+        let mut nonces = TranscriptRng {
+            hash: self.hash.clone(),
+        };
+        nonces.absorb(b"TranscriptRng");
+        for message in witness.iter() {
+            nonces.absorb(message);
+        }
+        nonces.absorb(&random_bytes);
+        nonces
+    }
+
     /// Pad separates the prior operations by padding
     /// the rest of the block with zeroes and applying a permutation.
     /// Each incoming message is length-prefixed anyway, but padding
@@ -139,6 +257,40 @@ impl ProofTranscript {
     }
 }
 
+/// A generator of prover nonces forked from a `ProofTranscript` by
+/// `build_rng`.
+pub(crate) struct TranscriptRng {
+    hash: Keccak,
+}
+
+impl TranscriptRng {
+    /// Absorbs the length-prefixed `message`, as `ProofTranscript::commit`.
+    fn absorb(&mut self, message: &[u8]) {
+        let mut len_prefix = [0u8; MAX_LENGTH_PREFIX];
+        let prefix_len = length_prefix(message.len(), &mut len_prefix);
+        self.hash.absorb(&len_prefix[..prefix_len]);
+        self.hash.absorb(message);
+    }
+}
+
+impl Rng for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        LittleEndian::read_u32(&buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        LittleEndian::read_u64(&buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.hash.squeeze(dest);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate hex;
@@ -147,7 +299,7 @@ mod tests {
     #[test]
     fn challenges_must_be_random() {
         {
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"test");
             {
                 let mut ch = [0u8; 32];
@@ -168,7 +320,7 @@ mod tests {
                 );
             }
 
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"test");
             {
                 let mut ch = [0u8; 16];
@@ -180,7 +332,7 @@ mod tests {
                 assert_eq!(hex::encode(ch), "add523844517c2320fc23ca72423b0ee");
             }
 
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"test");
             {
                 let mut ch = [0u8; 16];
@@ -198,7 +350,7 @@ mod tests {
     #[test]
     fn messages_are_disambiguated_by_length_prefix() {
         {
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"msg1msg2");
             {
                 let mut ch = [0u8; 8];
@@ -207,7 +359,7 @@ mod tests {
             }
         }
         {
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"msg1");
             ro.commit(b"msg2");
             {
@@ -217,7 +369,7 @@ mod tests {
             }
         }
         {
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"msg");
             ro.commit(b"1msg2");
             {
@@ -227,7 +379,7 @@ mod tests {
             }
         }
         {
-            let mut ro = ProofTranscript::new(b"TestProtocol");
+            let mut ro = ProofTranscript::new(b"TestProtocol").unwrap();
             ro.commit(b"ms");
             ro.commit(b"g1ms");
             ro.commit(b"g2");
@@ -238,4 +390,71 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn domain_labels_are_required() {
+        assert_eq!(ProofTranscript::new(b"").err(), Some(ProofError::EmptyLabel));
+        let mut labeled = ProofTranscript::new(b"TestProtocol").unwrap();
+        let mut unlabeled = ProofTranscript::without_domain();
+        assert!(labeled.challenge_scalar() != unlabeled.challenge_scalar());
+    }
+
+    #[test]
+    fn challenges_are_separated_by_phase_label() {
+        let mut ro1 = ProofTranscript::new(b"TestProtocol").unwrap();
+        let mut ro2 = ro1.fork();
+        let mut ro3 = ro1.fork();
+        ro1.commit(b"test");
+        ro2.commit(b"test");
+        ro3.commit(b"test");
+
+        let y = ro1.challenge_scalar_labeled(b"y");
+        let z = ro2.challenge_scalar_labeled(b"z");
+        let unlabeled = ro3.challenge_scalar();
+
+        assert!(y != z);
+        assert!(y != unlabeled);
+        assert!(z != unlabeled);
+    }
+
+    #[test]
+    fn long_messages_are_committed() {
+        let long = vec![7u8; 70000];
+        let mut prefix = [0u8; MAX_LENGTH_PREFIX];
+        assert_eq!(length_prefix(65534, &mut prefix), 2);
+        assert_eq!(length_prefix(65535, &mut prefix), MAX_LENGTH_PREFIX);
+        assert_eq!(length_prefix(long.len(), &mut prefix), MAX_LENGTH_PREFIX);
+
+        let mut ro1 = ProofTranscript::new(b"TestProtocol").unwrap();
+        let mut ro2 = ro1.fork();
+        ro1.commit(&long);
+        ro2.commit(&long[..65535]);
+        ro2.commit(&long[65535..]);
+        assert!(ro1.challenge_scalar() != ro2.challenge_scalar());
+    }
+
+    #[test]
+    fn nonces_depend_on_transcript_witness_and_rng() {
+        use rand::ChaChaRng;
+
+        let mut transcript = ProofTranscript::new(b"TestProtocol").unwrap();
+        transcript.commit(b"statement");
+        let mut expected = transcript.fork();
+        let nonce = |transcript: &ProofTranscript, witness: &[u8], rng: &mut ChaChaRng| {
+            Scalar::random(&mut transcript.build_rng(&[witness], rng))
+        };
+
+        // The same RNG output gives different nonces for different witnesses.
+        let n_1 = nonce(&transcript, b"witness 1", &mut ChaChaRng::new_unseeded());
+        let n_2 = nonce(&transcript, b"witness 2", &mut ChaChaRng::new_unseeded());
+        assert!(n_1 != n_2);
+        assert_eq!(n_1, nonce(&transcript, b"witness 1", &mut ChaChaRng::new_unseeded()));
+
+        let mut rng = ChaChaRng::new_unseeded();
+        let n_3 = nonce(&transcript, b"witness 1", &mut rng);
+        assert!(n_3 != nonce(&transcript, b"witness 1", &mut rng));
+
+        // Forking leaves the transcript unchanged.
+        assert_eq!(transcript.challenge_scalar(), expected.challenge_scalar());
+    }
 }
@@ -0,0 +1,261 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `proto` module encodes range proofs as Protocol Buffers
+//! messages, following the schema in `proto/bulletproofs.proto`, so that
+//! services in other languages can parse them with generated code.
+//!
+//! The encoder writes the fields in ascending order, as generated
+//! encoders do.  The decoder accepts fields in any order, skips unknown
+//! fields, and lets a repeated singular field override earlier ones, as
+//! the protobuf specification requires.  It rejects points that do not
+//! decompress, non-canonical scalars, and proofs with missing fields.
+//!
+//! The crate has no multi-party (dealer and party) protocol, so the
+//! schema only covers the proofs themselves.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let bytes = proto::encode_range_proof(&proof);
+//! let proof = proto::decode_range_proof(&bytes)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use errors::ProofError;
+use inner_product_proof::InnerProductProof;
+use range_proof::{RangeCommitments, RangeProof};
+use util;
+
+/// The wire type of varints.
+const VARINT: u64 = 0;
+/// The wire type of 64-bit fixed-size fields.
+const FIXED64: u64 = 1;
+/// The wire type of length-delimited fields.
+const LENGTH_DELIMITED: u64 = 2;
+/// The wire type of 32-bit fixed-size fields.
+const FIXED32: u64 = 5;
+
+/// Encodes `proof` as a `RangeProof` message.
+pub fn encode_range_proof(proof: &RangeProof) -> Vec<u8> {
+    let (c, ipp_proof) = proof.parts();
+    let mut out = Vec::new();
+    for (field, P) in [c.A, c.S, c.T_1, c.T_2].iter().enumerate() {
+        put_bytes(&mut out, field as u64 + 1, P.compress().as_bytes());
+    }
+    for (field, s) in [c.t_x, c.t_x_blinding, c.e_blinding].iter().enumerate() {
+        put_bytes(&mut out, field as u64 + 5, s.as_bytes());
+    }
+    put_bytes(&mut out, 8, &encode_inner_product_proof(ipp_proof));
+    out
+}
+
+/// Decodes a `RangeProof` message.
+///
+/// Returns `InvalidPoint` or `InvalidScalar` for a point or scalar that
+/// does not decode, and `FormatError` if the message is otherwise
+/// malformed or a field is missing.
+pub fn decode_range_proof(bytes: &[u8]) -> Result<RangeProof, ProofError> {
+    let mut points = [None; 4];
+    let mut scalars = [None; 3];
+    let mut ipp_proof = None;
+
+    let mut reader = Reader { bytes };
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1...4 => points[field as usize - 1] = Some(util::read_point(value)?),
+            5...7 => scalars[field as usize - 5] = Some(util::read_scalar(value)?),
+            8 => ipp_proof = Some(decode_inner_product_proof(value)?),
+            _ => {}
+        }
+    }
+
+    let commitments = RangeCommitments {
+        A: points[0].ok_or(ProofError::FormatError)?,
+        S: points[1].ok_or(ProofError::FormatError)?,
+        T_1: points[2].ok_or(ProofError::FormatError)?,
+        T_2: points[3].ok_or(ProofError::FormatError)?,
+        t_x: scalars[0].ok_or(ProofError::FormatError)?,
+        t_x_blinding: scalars[1].ok_or(ProofError::FormatError)?,
+        e_blinding: scalars[2].ok_or(ProofError::FormatError)?,
+    };
+    Ok(RangeProof::from_parts(commitments, ipp_proof.ok_or(ProofError::FormatError)?))
+}
+
+/// Encodes `proof` as an `InnerProductProof` message.
+fn encode_inner_product_proof(proof: &InnerProductProof) -> Vec<u8> {
+    let mut out = Vec::new();
+    for L in proof.L_vec.iter() {
+        put_bytes(&mut out, 1, L.compress().as_bytes());
+    }
+    for R in proof.R_vec.iter() {
+        put_bytes(&mut out, 2, R.compress().as_bytes());
+    }
+    put_bytes(&mut out, 3, proof.a.as_bytes());
+    put_bytes(&mut out, 4, proof.b.as_bytes());
+    out
+}
+
+/// Decodes an `InnerProductProof` message.
+fn decode_inner_product_proof(bytes: &[u8]) -> Result<InnerProductProof, ProofError> {
+    let mut L_vec = Vec::new();
+    let mut R_vec = Vec::new();
+    let mut a = None;
+    let mut b = None;
+
+    let mut reader = Reader { bytes };
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => L_vec.push(util::read_point(value)?),
+            2 => R_vec.push(util::read_point(value)?),
+            3 => a = Some(util::read_scalar(value)?),
+            4 => b = Some(util::read_scalar(value)?),
+            _ => {}
+        }
+    }
+
+    if L_vec.len() != R_vec.len() {
+        return Err(ProofError::FormatError);
+    }
+    Ok(InnerProductProof {
+        L_vec,
+        R_vec,
+        a: a.ok_or(ProofError::FormatError)?,
+        b: b.ok_or(ProofError::FormatError)?,
+    })
+}
+
+/// Appends the varint encoding of `value` to `out`.
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Appends the length-delimited field `field` holding `bytes` to `out`.
+fn put_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, field << 3 | LENGTH_DELIMITED);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Reads the fields of a message.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the next length-delimited field, skipping fields of other
+    /// wire types, and returns its number and contents.
+    fn next_field(&mut self) -> Result<Option<(u64, &'a [u8])>, ProofError> {
+        while !self.bytes.is_empty() {
+            let key = self.varint()?;
+            let field = key >> 3;
+            if field == 0 {
+                return Err(ProofError::FormatError);
+            }
+            match key & 7 {
+                VARINT => {
+                    self.varint()?;
+                }
+                FIXED64 => {
+                    self.take(8)?;
+                }
+                LENGTH_DELIMITED => {
+                    let len = self.varint()?;
+                    if len > self.bytes.len() as u64 {
+                        return Err(ProofError::FormatError);
+                    }
+                    return Ok(Some((field, self.take(len as usize)?)));
+                }
+                FIXED32 => {
+                    self.take(4)?;
+                }
+                _ => return Err(ProofError::FormatError),
+            }
+            // All fields of the schema are length-delimited.
+            if field <= 8 {
+                return Err(ProofError::FormatError);
+            }
+        }
+        Ok(None)
+    }
+
+    fn varint(&mut self) -> Result<u64, ProofError> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let byte = *self.take(1)?.first().ok_or(ProofError::FormatError)?;
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ProofError::FormatError)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProofError> {
+        if len > self.bytes.len() {
+            return Err(ProofError::FormatError);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use range_proof::Verifier;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn unknown_fields_are_skipped_and_missing_ones_rejected() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let V = generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(300), v_blinding);
+
+        let mut transcript = ProofTranscript::new(b"ProtoTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(300),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let mut bytes = encode_range_proof(&proof);
+
+        // Unknown fields are skipped: here a varint field 9.
+        bytes.extend_from_slice(&[9 << 3, 1]);
+        let decoded = decode_range_proof(&bytes).unwrap();
+        assert_eq!(encode_range_proof(&decoded), &bytes[..bytes.len() - 2]);
+
+        let mut transcript = ProofTranscript::new(b"ProtoTest").unwrap();
+        assert!(
+            Verifier::new(generators.share(0), n)
+                .unwrap()
+                .verify(&decoded, &V, &mut transcript, &mut rng)
+                .is_ok()
+        );
+
+        // Truncated messages are rejected, as are messages without the
+        // point A: its key, its length and its 32 bytes.
+        assert!(decode_range_proof(&bytes[..bytes.len() / 2]).is_err());
+        assert_eq!(&bytes[..2], &[1 << 3 | 2, 32]);
+        assert!(decode_range_proof(&bytes[34..]).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `queue` module contains a queue of range proofs awaiting
+//! verification, for node software that receives proofs faster than
+//! it needs their results, as in a mempool.
+//!
+//! Pushing a proof only stores it and hashes it: a proof identical to
+//! one already pending, for the same commitment, is not stored again
+//! and gets the same ticket.  `verify` then shuffles the pending proofs
+//! into batches, so a sender cannot choose which proofs share a batch,
+//! and checks each batch with a single multiscalar multiplication.
+//! Only the proofs of a failed batch are verified one by one.  The
+//! result for every ticket is reported back.
+//!
+//! All proofs are verified in a fresh `ProofTranscript` with the
+//! queue's label.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let verifier = Verifier::new(generators.share(0), n)?;
+//! let mut queue = PendingVerificationQueue::new(verifier, b"Tx")?;
+//! let ticket = queue.push(proof, V);
+//! // ... more proofs ...
+//! for (ticket, result) in queue.verify(&mut rng) {
+//!     // accept or drop the transaction waiting on the ticket
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use sha2::{Digest, Sha512};
+
+use backend::{DefaultBackend, MultiscalarMul};
+use batch;
+use errors::ProofError;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier};
+
+/// The number of proofs verified in one batch by default.
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// A proof awaiting verification.
+struct Entry {
+    ticket: usize,
+    proof: RangeProof,
+    V: RistrettoPoint,
+}
+
+/// `PendingVerificationQueue` stores range proofs and verifies them in
+/// randomized batches on demand.
+pub struct PendingVerificationQueue<'a, M = DefaultBackend> {
+    verifier: Verifier<'a, M>,
+    /// A fresh transcript with the queue's label, forked for each proof
+    transcript: ProofTranscript,
+    batch_size: usize,
+    next_ticket: usize,
+    entries: Vec<Entry>,
+    /// Hashes of the pending proofs with their tickets, sorted by hash
+    digests: Vec<([u8; 32], usize)>,
+}
+
+impl<'a, M: MultiscalarMul> PendingVerificationQueue<'a, M> {
+    /// Creates an empty queue, verifying with `verifier` in transcripts
+    /// labeled with `label`.
+    ///
+    /// Returns `EmptyLabel` if `label` is empty.
+    pub fn new(verifier: Verifier<'a, M>, label: &[u8]) -> Result<Self, ProofError> {
+        Ok(PendingVerificationQueue {
+            verifier,
+            transcript: ProofTranscript::new(label)?,
+            batch_size: DEFAULT_BATCH_SIZE,
+            next_ticket: 0,
+            entries: Vec::new(),
+            digests: Vec::new(),
+        })
+    }
+
+    /// Sets the number of proofs verified in one batch.
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch_size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Queues the proof `proof` for the commitment `V`, returning the
+    /// ticket its result will be reported under.
+    ///
+    /// If the same proof for the same commitment is already pending,
+    /// returns its ticket instead.
+    pub fn push(&mut self, proof: RangeProof, V: RistrettoPoint) -> usize {
+        let digest = digest(&proof, &V);
+        match self.digests.binary_search_by(|probe| probe.0.cmp(&digest)) {
+            Ok(i) => self.digests[i].1,
+            Err(i) => {
+                let ticket = self.next_ticket;
+                self.next_ticket += 1;
+                self.digests.insert(i, (digest, ticket));
+                self.entries.push(Entry { ticket, proof, V });
+                ticket
+            }
+        }
+    }
+
+    /// Returns the number of distinct pending proofs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no proofs are pending.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verifies all the pending proofs, returning the result for each
+    /// ticket, in the order the tickets were issued.
+    ///
+    /// The queue is empty afterwards, so it can be refilled.
+    pub fn verify<R: Rng>(&mut self, rng: &mut R) -> Vec<(usize, Result<(), ProofError>)> {
+        let mut entries: Vec<Entry> = self.entries.drain(..).collect();
+        self.digests.clear();
+        rng.shuffle(&mut entries);
+
+        let mut results = Vec::with_capacity(entries.len());
+        for chunk in entries.chunks(self.batch_size) {
+            let batch_result = batch::verify_batch(
+                &self.verifier,
+                chunk
+                    .iter()
+                    .map(|entry| (&entry.proof, &entry.V, None, self.transcript.fork())),
+                rng,
+            );
+            for entry in chunk.iter() {
+                let result = if batch_result.is_ok() {
+                    Ok(())
+                } else {
+                    let mut transcript = self.transcript.fork();
+                    self.verifier
+                        .verify(&entry.proof, &entry.V, &mut transcript, rng)
+                };
+                results.push((entry.ticket, result));
+            }
+        }
+
+        results.sort_by_key(|&(ticket, _)| ticket);
+        results
+    }
+}
+
+/// Returns a hash identifying the proof `proof` for the commitment `V`.
+fn digest(proof: &RangeProof, V: &RistrettoPoint) -> [u8; 32] {
+    let mut hash = Sha512::default();
+    hash.input(b"Bulletproofs.PendingProof");
+    hash.input(V.compress().as_bytes());
+    proof.hash_into(&mut hash);
+
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&hash.result()[..32]);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn queue_deduplicates_and_reports_each_proof() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut proofs = Vec::new();
+        for v in 0..5u64 {
+            let v_blinding = Scalar::random(&mut rng);
+            let V = generators
+                .share(0)
+                .pedersen_generators
+                .commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"QueueTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+            proofs.push((proof, V));
+        }
+
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let mut queue = PendingVerificationQueue::new(verifier, b"QueueTest")
+            .unwrap()
+            .with_batch_size(2);
+        let mut tickets = Vec::new();
+        for &(ref proof, V) in proofs.iter() {
+            tickets.push(queue.push(proof.clone(), V));
+        }
+        assert_eq!(tickets, vec![0, 1, 2, 3, 4]);
+
+        // Resubmitting a pending proof returns its ticket.
+        assert_eq!(queue.push(proofs[3].0.clone(), proofs[3].1), 3);
+        // The same proof for another commitment is a new, invalid proof.
+        let bad = queue.push(proofs[3].0.clone(), proofs[0].1);
+        assert_eq!(bad, 5);
+        assert_eq!(queue.len(), 6);
+
+        let results = queue.verify(&mut rng);
+        assert!(queue.is_empty());
+        assert_eq!(results.len(), 6);
+        for &(ticket, result) in results.iter() {
+            assert_eq!(result.is_ok(), ticket != bad);
+        }
+
+        // The queue can be refilled, and accepts proofs seen before.
+        assert_eq!(queue.push(proofs[0].0.clone(), proofs[0].1), 6);
+        assert_eq!(queue.verify(&mut rng), vec![(6, Ok(()))]);
+    }
+}
@@ -0,0 +1,72 @@
+//! The `ConstraintSystem` trait shared by the prover and the verifier.
+
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+use super::{LinearCombination, R1CSError, Variable};
+
+/// A callback building randomized constraints, run once all the other
+/// gates have been committed to.
+pub type RandomizedConstraints =
+    Box<FnMut(&mut ConstraintSystem) -> Result<(), R1CSError>>;
+
+/// The interface for a constraint system, abstracting over the prover
+/// and verifier's roles.
+///
+/// Statements to be proved are written as functions generic over a
+/// `ConstraintSystem`, so that the prover and the verifier build
+/// exactly the same constraints.  The prover knows the values of all
+/// variables; the verifier knows only their structure.
+pub trait ConstraintSystem {
+    /// Allocates a multiplication gate with the inputs `left` and
+    /// `right`, and returns its left input, right input and output
+    /// variables.
+    ///
+    /// The inputs are constrained to equal `left` and `right`, and the
+    /// output is constrained to equal their product.
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable);
+
+    /// Allocates a multiplication gate whose inputs are not linear
+    /// combinations of existing variables, and returns its left input,
+    /// right input and output variables.
+    ///
+    /// The prover must supply the `assignment` of the two inputs; the
+    /// verifier passes `None`.  Only the output is constrained, to
+    /// equal the product of the inputs.
+    fn allocate(
+        &mut self,
+        assignment: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError>;
+
+    /// Enforces that the linear combination `lc` is zero.
+    fn constrain(&mut self, lc: LinearCombination);
+
+    /// Returns a challenge scalar bound to `label`, derived from the
+    /// transcript.
+    ///
+    /// Outside of randomized constraints, the challenge depends only on
+    /// the value commitments made so far, so constraints built from it
+    /// may only involve committed variables and linear combinations of
+    /// them.  Inside randomized constraints, the challenge is also bound
+    /// to all the gates allocated outside of them.
+    fn challenge_scalar(&mut self, label: &[u8]) -> Scalar;
+
+    /// Defers `callback` to a second phase, run after the prover has
+    /// committed to all the gates allocated outside of randomized
+    /// constraints.
+    ///
+    /// The callback receives the constraint system, and may draw
+    /// challenges with `challenge_scalar`, allocate new gates and add
+    /// constraints using them.  Returns an error if called from inside
+    /// randomized constraints.
+    fn specify_randomized_constraints(
+        &mut self,
+        callback: RandomizedConstraints,
+    ) -> Result<(), R1CSError>;
+}
@@ -0,0 +1,501 @@
+//! The `gadgets` module contains reusable building blocks for
+//! statements in a `ConstraintSystem`.
+//!
+//! Every gadget is generic over the `ConstraintSystem`, so the prover
+//! and the verifier call it identically.  Gadgets that allocate new
+//! variables take an `Option` assignment, which the prover must supply
+//! and the verifier passes as `None`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! // Proves that the committed value is a 16-bit integer.
+//! fn u16_gadget<CS: ConstraintSystem>(
+//!     cs: &mut CS,
+//!     v: Variable,
+//!     v_assignment: Option<u64>,
+//! ) -> Result<(), R1CSError> {
+//!     gadgets::range(cs, v.into(), v_assignment, 16)
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use super::{ConstraintSystem, LinearCombination, R1CSError, Variable};
+
+/// Allocates a variable constrained to be \\(0\\) or \\(1\\), with the
+/// prover's `assignment`.
+///
+/// Uses one multiplication gate.
+pub fn boolean<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    assignment: Option<bool>,
+) -> Result<Variable, R1CSError> {
+    let (a, b, o) = cs.allocate(assignment.map(|bit| {
+        let bit = Scalar::from_u64(bit as u64);
+        (bit, Scalar::one() - bit)
+    }))?;
+
+    // a * b = 0 and a + b = 1 imply that a is 0 or 1.
+    cs.constrain(o.into());
+    cs.constrain(a + b - Scalar::one());
+
+    Ok(a)
+}
+
+/// Constrains the linear combination `v` to be \\(0\\) or \\(1\\).
+///
+/// Uses one multiplication gate.
+pub fn constrain_boolean<CS: ConstraintSystem + ?Sized>(cs: &mut CS, v: LinearCombination) {
+    let one_minus_v = LinearCombination::from(Scalar::one()) - v.clone();
+    let (_, _, o) = cs.multiply(v, one_minus_v);
+    cs.constrain(o.into());
+}
+
+/// Constrains the linear combinations `a` and `b` to be equal.
+pub fn equal<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    a: LinearCombination,
+    b: LinearCombination,
+) {
+    cs.constrain(a - b);
+}
+
+/// Returns a linear combination equal to `a` if `condition` is
+/// \\(1\\) and to `b` if it is \\(0\\).
+///
+/// The `condition` must already be constrained to be boolean, e.g. by
+/// allocating it with `boolean`.  Uses one multiplication gate.
+pub fn conditional_select<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    condition: Variable,
+    a: LinearCombination,
+    b: LinearCombination,
+) -> LinearCombination {
+    // b + condition * (a - b)
+    let (_, _, o) = cs.multiply(condition.into(), a - b.clone());
+    b + o
+}
+
+/// Returns the linear combination \\( \sum\_i 2^i \cdot \texttt{bits}\_i \\),
+/// packing little-endian `bits` into an integer.
+///
+/// The bits must already be constrained to be boolean.
+pub fn pack(bits: &[Variable]) -> LinearCombination {
+    let mut exp_2 = Scalar::one();
+    bits.iter()
+        .map(|&bit| {
+            let term = (bit, exp_2);
+            exp_2 = exp_2 + exp_2;
+            term
+        })
+        .collect()
+}
+
+/// Decomposes the linear combination `v` into `n` boolean variables
+/// in little-endian order, using the prover's `assignment` of `v`.
+///
+/// This constrains `v` to be in the range \\([0, 2^n)\\).  Uses `n`
+/// multiplication gates.  Panics if `n > 64`.
+pub fn unpack<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    v: LinearCombination,
+    assignment: Option<u64>,
+    n: usize,
+) -> Result<Vec<Variable>, R1CSError> {
+    assert!(n <= 64, "cannot decompose a u64 into more than 64 bits");
+
+    let mut bits = Vec::with_capacity(n);
+    for i in 0..n {
+        let bit = assignment.map(|value| (value >> i) & 1 == 1);
+        bits.push(boolean(cs, bit)?);
+    }
+
+    let packed = pack(&bits);
+    equal(cs, v, packed);
+
+    Ok(bits)
+}
+
+/// Constrains the linear combination `v` to be in the range
+/// \\([0, 2^n)\\), using the prover's `assignment` of `v`.
+///
+/// Uses `n` multiplication gates.  Panics if `n > 64`.
+pub fn range<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    v: LinearCombination,
+    assignment: Option<u64>,
+    n: usize,
+) -> Result<(), R1CSError> {
+    unpack(cs, v, assignment, n).map(|_| ())
+}
+
+/// Constrains the linear combination `v` to be \\(0\\) or in the range
+/// \\([\texttt{min}, \texttt{min} + 2^n)\\), using the prover's
+/// `assignment` of `v`, without revealing which.
+///
+/// Allocates a bit \\(b\\), and constrains \\((1 - b) \cdot v = 0\\) and
+/// \\(b \cdot (v - \texttt{min})\\) to \\([0, 2^n)\\): either \\(b = 0\\)
+/// and \\(v = 0\\), or \\(b = 1\\) and \\(v - \texttt{min}\\) is in
+/// range.  Uses \\(n + 3\\) multiplication gates.  Panics if `n > 64`.
+pub fn zero_or_range<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    v: LinearCombination,
+    assignment: Option<u64>,
+    min: u64,
+    n: usize,
+) -> Result<(), R1CSError> {
+    let nonzero = boolean(cs, assignment.map(|value| value != 0))?;
+    let is_zero = LinearCombination::from(Scalar::one()) - nonzero;
+    let (_, _, o) = cs.multiply(is_zero, v.clone());
+    cs.constrain(o.into());
+
+    let offset = v - Scalar::from_u64(min);
+    let selected = conditional_select(cs, nonzero, offset, Scalar::zero().into());
+    // A nonzero value below `min` wraps to a value whose bits do not
+    // pack to `v - min`, so the proof does not verify.
+    let selected_assignment = assignment.map(|value| match value {
+        0 => 0,
+        value => value.wrapping_sub(min),
+    });
+    range(cs, selected, selected_assignment, n)
+}
+
+/// Constrains the values `y` to be a permutation of the values `x`.
+///
+/// Draws a challenge \\(z\\) and checks that
+/// \\( \prod\_i (x\_i - z) = \prod\_i (y\_i - z) \\): the two polynomials
+/// in \\(z\\) are equal exactly when their roots are the same multiset.
+/// The check is built as randomized constraints, so that the challenge
+/// is bound to `x` and `y`, which must not themselves be allocated in
+/// randomized constraints.  Uses \\(2(k - 1)\\) multiplication gates
+/// for lists of length \\(k\\).
+///
+/// Panics if `x` and `y` have different lengths.
+pub fn shuffle<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    x: &[Variable],
+    y: &[Variable],
+) -> Result<(), R1CSError> {
+    assert_eq!(x.len(), y.len(), "shuffled lists must have the same length");
+    if x.is_empty() {
+        return Ok(());
+    }
+
+    let x = x.to_vec();
+    let y = y.to_vec();
+    cs.specify_randomized_constraints(Box::new(
+        move |cs: &mut ConstraintSystem| -> Result<(), R1CSError> {
+            let z = cs.challenge_scalar(b"shuffle z");
+            let x_product = product_of_differences(cs, &x, z);
+            let y_product = product_of_differences(cs, &y, z);
+            equal(cs, x_product, y_product);
+            Ok(())
+        },
+    ))
+}
+
+/// Constrains the linear combination `v` to be one of the values in
+/// the public `table`.
+///
+/// Checks that \\( \prod\_i (v - t\_i) = 0 \\).  Uses \\(k - 1\\)
+/// multiplication gates for a table of \\(k\\) values, so it suits
+/// small tables.  Panics if `table` is empty.
+pub fn lookup<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    v: LinearCombination,
+    table: &[Scalar],
+) {
+    let product = product_of_shifts(cs, v, table);
+    cs.constrain(product);
+}
+
+/// Constrains the pair of linear combinations `input` and `output` to
+/// be one of the rows of the public `table`, e.g. of an S-box.
+///
+/// Draws a challenge \\(z\\) and checks that
+/// \\( \prod\_i (\texttt{input} + z \cdot \texttt{output} - (a\_i + z b\_i)) = 0 \\)
+/// for the rows \\((a\_i, b\_i)\\): a pair outside the table makes the
+/// product a nonzero polynomial in \\(z\\).  Like `shuffle`, the check
+/// is built as randomized constraints, so `input` and `output` must
+/// not involve variables allocated in randomized constraints.  Uses
+/// \\(k - 1\\) multiplication gates for a table of \\(k\\) rows.
+///
+/// Panics if `table` is empty.
+pub fn lookup_pair<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    input: LinearCombination,
+    output: LinearCombination,
+    table: &[(Scalar, Scalar)],
+) -> Result<(), R1CSError> {
+    assert!(!table.is_empty(), "lookup table must not be empty");
+
+    let table = table.to_vec();
+    cs.specify_randomized_constraints(Box::new(
+        move |cs: &mut ConstraintSystem| -> Result<(), R1CSError> {
+            let z = cs.challenge_scalar(b"lookup z");
+            let combined: Vec<Scalar> = table.iter().map(|&(a, b)| a + z * b).collect();
+            let product = product_of_shifts(cs, input.clone() + output.clone() * z, &combined);
+            cs.constrain(product);
+            Ok(())
+        },
+    ))
+}
+
+/// Returns a linear combination equal to \\( \prod\_i (v - t\_i) \\).
+fn product_of_shifts<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    v: LinearCombination,
+    table: &[Scalar],
+) -> LinearCombination {
+    assert!(!table.is_empty(), "lookup table must not be empty");
+
+    let mut product = v.clone() - table[0];
+    for &t in table[1..].iter() {
+        let (_, _, o) = cs.multiply(product, v.clone() - t);
+        product = o.into();
+    }
+    product
+}
+
+/// Returns a linear combination equal to \\( \prod\_i (v\_i - z) \\).
+fn product_of_differences<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    values: &[Variable],
+    z: Scalar,
+) -> LinearCombination {
+    let mut product = values[0] - z;
+    for &v in values[1..].iter() {
+        let (_, _, o) = cs.multiply(product, v - z);
+        product = o.into();
+    }
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use r1cs::{Prover, Verifier};
+    use rand::OsRng;
+    use secret::{Blinding, SecretScalar};
+
+    /// A statement about the committed values, which receives the
+    /// values themselves when run by the prover.
+    type Statement =
+        fn(&mut ConstraintSystem, &[Variable], Option<&[u64]>) -> Result<(), R1CSError>;
+
+    /// Runs `statement` with the committed `values` as prover and
+    /// verifier, and returns whether the proof verifies.
+    fn check(values: &[u64], statement: Statement) -> Result<(), R1CSError> {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 64, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"GadgetTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let mut commitments = Vec::new();
+        let mut vars = Vec::new();
+        for &v in values.iter() {
+            let v_blinding = Blinding::random(&mut rng);
+            let (V, var) = prover.commit(&SecretScalar::new(Scalar::from_u64(v)), &v_blinding);
+            commitments.push(V);
+            vars.push(var);
+        }
+        statement(&mut prover, &vars, Some(values))?;
+        let proof = prover.prove(&mut rng)?;
+
+        let mut transcript = ProofTranscript::new(b"GadgetTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let vars: Vec<Variable> = commitments.iter().map(|V| verifier.commit(*V)).collect();
+        statement(&mut verifier, &vars, None)?;
+        verifier.verify(&proof, &mut rng)
+    }
+
+    #[test]
+    fn boolean_gadget() {
+        fn allocated(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let bit = boolean(cs, values.map(|v| v[0] == 1))?;
+            equal(cs, bit.into(), vars[0].into());
+            Ok(())
+        }
+        assert!(check(&[0], allocated).is_ok());
+        assert!(check(&[1], allocated).is_ok());
+
+        fn constrained(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            constrain_boolean(cs, vars[0].into());
+            Ok(())
+        }
+        assert!(check(&[1], constrained).is_ok());
+        assert!(check(&[2], constrained).is_err());
+    }
+
+    #[test]
+    fn equal_gadget() {
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            equal(cs, vars[0] + vars[1], vars[2].into());
+            Ok(())
+        }
+        assert!(check(&[2, 3, 5], statement).is_ok());
+        assert!(check(&[2, 3, 6], statement).is_err());
+    }
+
+    #[test]
+    fn conditional_select_gadget() {
+        // vars: condition, a, b, expected
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            constrain_boolean(cs, vars[0].into());
+            let selected = conditional_select(cs, vars[0], vars[1].into(), vars[2].into());
+            equal(cs, selected, vars[3].into());
+            Ok(())
+        }
+        assert!(check(&[1, 10, 20, 10], statement).is_ok());
+        assert!(check(&[0, 10, 20, 20], statement).is_ok());
+        assert!(check(&[0, 10, 20, 10], statement).is_err());
+    }
+
+    #[test]
+    fn pack_unpack_gadgets() {
+        // vars: value, then its low 4 bits
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let bits = unpack(cs, vars[0].into(), values.map(|v| v[0]), 4)?;
+            for (bit, var) in bits.iter().zip(vars[1..].iter()) {
+                equal(cs, (*bit).into(), (*var).into());
+            }
+            equal(cs, pack(&bits), vars[0].into());
+            Ok(())
+        }
+        assert!(check(&[0b1101, 1, 0, 1, 1], statement).is_ok());
+        assert!(check(&[0b1101, 1, 1, 0, 1], statement).is_err());
+    }
+
+    #[test]
+    fn range_gadget() {
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            range(cs, vars[0].into(), values.map(|v| v[0]), 8)
+        }
+        assert!(check(&[0], statement).is_ok());
+        assert!(check(&[255], statement).is_ok());
+        assert!(check(&[256], statement).is_err());
+    }
+
+    #[test]
+    fn zero_or_range_gadget() {
+        // Zero, or a value in [100, 116)
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            zero_or_range(cs, vars[0].into(), values.map(|v| v[0]), 100, 4)
+        }
+        assert!(check(&[0], statement).is_ok());
+        assert!(check(&[100], statement).is_ok());
+        assert!(check(&[115], statement).is_ok());
+        assert!(check(&[1], statement).is_err());
+        assert!(check(&[99], statement).is_err());
+        assert!(check(&[116], statement).is_err());
+    }
+
+    #[test]
+    fn shuffle_gadget() {
+        // vars: three inputs, then three outputs
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            shuffle(cs, &vars[..3], &vars[3..])
+        }
+        assert!(check(&[3, 7, 9, 9, 3, 7], statement).is_ok());
+        assert!(check(&[3, 7, 7, 7, 3, 7], statement).is_ok());
+        assert!(check(&[3, 7, 9, 9, 3, 8], statement).is_err());
+        assert!(check(&[3, 7, 7, 7, 3, 3], statement).is_err());
+    }
+
+    #[test]
+    fn lookup_gadget() {
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let fee_tiers: Vec<Scalar> = [10, 25, 50, 100]
+                .iter()
+                .map(|&t| Scalar::from_u64(t))
+                .collect();
+            lookup(cs, vars[0].into(), &fee_tiers);
+            Ok(())
+        }
+        assert!(check(&[10], statement).is_ok());
+        assert!(check(&[100], statement).is_ok());
+        assert!(check(&[30], statement).is_err());
+    }
+
+    #[test]
+    fn lookup_pair_gadget() {
+        // vars: a 2-bit input and its image under a small S-box
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            _: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let sbox: Vec<(Scalar, Scalar)> = [(0, 2), (1, 0), (2, 3), (3, 1)]
+                .iter()
+                .map(|&(a, b)| (Scalar::from_u64(a), Scalar::from_u64(b)))
+                .collect();
+            lookup_pair(cs, vars[0].into(), vars[1].into(), &sbox)
+        }
+        assert!(check(&[0, 2], statement).is_ok());
+        assert!(check(&[3, 1], statement).is_ok());
+        // Both values are in the table, but not in the same row.
+        assert!(check(&[0, 3], statement).is_err());
+    }
+
+    #[test]
+    fn shuffle_of_allocated_variables() {
+        // Shuffles the bits of the committed values, which are not
+        // committed variables themselves.
+        fn statement(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let x = unpack(cs, vars[0].into(), values.map(|v| v[0]), 4)?;
+            let y = unpack(cs, vars[1].into(), values.map(|v| v[1]), 4)?;
+            shuffle(cs, &x, &y)
+        }
+        assert!(check(&[0b0011, 0b1010], statement).is_ok());
+        assert!(check(&[0b0111, 0b1101], statement).is_ok());
+        assert!(check(&[0b0011, 0b1110], statement).is_err());
+    }
+}
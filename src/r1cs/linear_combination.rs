@@ -0,0 +1,140 @@
+//! Variables and linear combinations of variables.
+
+use core::iter::FromIterator;
+use core::ops::{Add, Mul, Neg, Sub};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+/// Represents a variable in a constraint system.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Variable {
+    /// Represents an external input specified by a commitment.
+    Committed(usize),
+    /// Represents the left input of a multiplication gate.
+    MultiplierLeft(usize),
+    /// Represents the right input of a multiplication gate.
+    MultiplierRight(usize),
+    /// Represents the output of a multiplication gate.
+    MultiplierOutput(usize),
+    /// Represents the constant 1.
+    One(),
+}
+
+/// Represents a linear combination of `Variable`s, i.e. a sum
+/// \\( \sum\_i c\_i v\_i \\) with scalar coefficients \\(c\_i\\).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinearCombination {
+    pub(crate) terms: Vec<(Variable, Scalar)>,
+}
+
+impl LinearCombination {
+    /// Returns the terms of the linear combination.
+    pub fn terms(&self) -> &[(Variable, Scalar)] {
+        &self.terms
+    }
+}
+
+impl From<Variable> for LinearCombination {
+    fn from(v: Variable) -> LinearCombination {
+        LinearCombination {
+            terms: vec![(v, Scalar::one())],
+        }
+    }
+}
+
+impl From<Scalar> for LinearCombination {
+    fn from(s: Scalar) -> LinearCombination {
+        LinearCombination {
+            terms: vec![(Variable::One(), s)],
+        }
+    }
+}
+
+impl FromIterator<(Variable, Scalar)> for LinearCombination {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (Variable, Scalar)>,
+    {
+        LinearCombination {
+            terms: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<L: Into<LinearCombination>> Add<L> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn add(mut self, rhs: L) -> LinearCombination {
+        self.terms.extend(rhs.into().terms.into_iter());
+        self
+    }
+}
+
+impl<L: Into<LinearCombination>> Sub<L> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn sub(mut self, rhs: L) -> LinearCombination {
+        self.terms
+            .extend(rhs.into().terms.into_iter().map(|(var, coeff)| (var, -coeff)));
+        self
+    }
+}
+
+impl Mul<Scalar> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn mul(mut self, rhs: Scalar) -> LinearCombination {
+        for &mut (_, ref mut coeff) in self.terms.iter_mut() {
+            *coeff = *coeff * rhs;
+        }
+        self
+    }
+}
+
+impl Neg for LinearCombination {
+    type Output = LinearCombination;
+
+    fn neg(mut self) -> LinearCombination {
+        for &mut (_, ref mut coeff) in self.terms.iter_mut() {
+            *coeff = -*coeff;
+        }
+        self
+    }
+}
+
+impl<L: Into<LinearCombination>> Add<L> for Variable {
+    type Output = LinearCombination;
+
+    fn add(self, rhs: L) -> LinearCombination {
+        LinearCombination::from(self) + rhs
+    }
+}
+
+impl<L: Into<LinearCombination>> Sub<L> for Variable {
+    type Output = LinearCombination;
+
+    fn sub(self, rhs: L) -> LinearCombination {
+        LinearCombination::from(self) - rhs
+    }
+}
+
+impl Mul<Scalar> for Variable {
+    type Output = LinearCombination;
+
+    fn mul(self, rhs: Scalar) -> LinearCombination {
+        LinearCombination {
+            terms: vec![(self, rhs)],
+        }
+    }
+}
+
+impl Neg for Variable {
+    type Output = LinearCombination;
+
+    fn neg(self) -> LinearCombination {
+        -LinearCombination::from(self)
+    }
+}
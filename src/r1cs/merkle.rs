@@ -0,0 +1,132 @@
+//! The `merkle` module contains a gadget proving that a leaf belongs
+//! to a Merkle tree with a given root, without revealing the leaf or
+//! its position.
+//!
+//! Nodes are hashed with `MiMC`, so the native `root` function and the
+//! `membership` gadget agree on every tree.  The authentication path
+//! lists, from the leaf upwards, each sibling and whether the current
+//! node is the right child.
+//!
+//! # Example
+//!
+//! ```ascii
+//! fn membership_gadget<CS: ConstraintSystem>(
+//!     cs: &mut CS,
+//!     mimc: &MiMC,
+//!     leaf: Variable,
+//!     root: Scalar,
+//!     path: Option<&[(Scalar, bool)]>,
+//! ) -> Result<(), R1CSError> {
+//!     merkle::membership(cs, mimc, leaf.into(), root.into(), path, DEPTH)
+//! }
+//! ```
+
+use curve25519_dalek::scalar::Scalar;
+
+use super::gadgets;
+use super::mimc::MiMC;
+use super::{ConstraintSystem, LinearCombination, R1CSError};
+
+/// Computes the root of the Merkle tree containing `leaf` with the
+/// authentication `path`.
+pub fn root(mimc: &MiMC, leaf: &Scalar, path: &[(Scalar, bool)]) -> Scalar {
+    path.iter().fold(*leaf, |node, &(sibling, is_right)| {
+        if is_right {
+            mimc.hash(&sibling, &node)
+        } else {
+            mimc.hash(&node, &sibling)
+        }
+    })
+}
+
+/// Constrains `leaf` to belong to the Merkle tree of the given `depth`
+/// with root `root`, using the prover's authentication `path`.
+///
+/// Uses \\(d (3 R + 3)\\) multiplication gates for a tree of depth
+/// \\(d\\), where \\(R\\) is the number of MiMC rounds.  Panics if the
+/// `path` does not have `depth` entries.
+pub fn membership<CS: ConstraintSystem + ?Sized>(
+    cs: &mut CS,
+    mimc: &MiMC,
+    leaf: LinearCombination,
+    root: LinearCombination,
+    path: Option<&[(Scalar, bool)]>,
+    depth: usize,
+) -> Result<(), R1CSError> {
+    if let Some(path) = path {
+        assert_eq!(path.len(), depth, "the path length must equal the depth");
+    }
+
+    let mut node = leaf;
+    for level in 0..depth {
+        let assignment = path.map(|path| path[level]);
+
+        // The sibling is a free witness; the gate's other wires are unused.
+        let (sibling, _, _) =
+            cs.allocate(assignment.map(|(sibling, _)| (sibling, Scalar::one())))?;
+        let is_right = gadgets::boolean(cs, assignment.map(|(_, is_right)| is_right))?;
+
+        let left = gadgets::conditional_select(cs, is_right, sibling.into(), node.clone());
+        let right = node + sibling - left.clone();
+        node = mimc.hash_gadget(cs, left, right);
+    }
+
+    gadgets::equal(cs, node, root);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use r1cs::{Prover, Variable, Verifier};
+    use rand::OsRng;
+    use secret::{Blinding, SecretScalar};
+
+    const DEPTH: usize = 2;
+
+    fn prove_and_verify(
+        leaf: Scalar,
+        path: &[(Scalar, bool)],
+        root: Scalar,
+    ) -> Result<(), R1CSError> {
+        let mimc = MiMC::new();
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 1024, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"MerkleTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let (V, leaf_var) = prover.commit(&SecretScalar::new(leaf), &Blinding::random(&mut rng));
+        membership(&mut prover, &mimc, leaf_var.into(), root.into(), Some(path), DEPTH)?;
+        let proof = prover.prove(&mut rng)?;
+
+        let mut transcript = ProofTranscript::new(b"MerkleTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let leaf_var: Variable = verifier.commit(V);
+        membership(&mut verifier, &mimc, leaf_var.into(), root.into(), None, DEPTH)?;
+        verifier.verify(&proof, &mut rng)
+    }
+
+    #[test]
+    fn leaf_in_tree_verifies() {
+        let mimc = MiMC::new();
+        let leaves: Vec<Scalar> = (0..4u64).map(|i| Scalar::from_u64(100 + i)).collect();
+        let level_1 = [
+            mimc.hash(&leaves[0], &leaves[1]),
+            mimc.hash(&leaves[2], &leaves[3]),
+        ];
+        let tree_root = mimc.hash(&level_1[0], &level_1[1]);
+
+        // Leaf 2 is the left child of level_1[1], which is a right child.
+        let path = [(leaves[3], false), (level_1[0], true)];
+        assert_eq!(root(&mimc, &leaves[2], &path), tree_root);
+        assert!(prove_and_verify(leaves[2], &path, tree_root).is_ok());
+
+        // A leaf that is not in the tree
+        assert!(prove_and_verify(Scalar::from_u64(7), &path, tree_root).is_err());
+        // A path with the wrong directions
+        let wrong_path = [(leaves[3], true), (level_1[0], true)];
+        assert!(prove_and_verify(leaves[2], &wrong_path, tree_root).is_err());
+    }
+}
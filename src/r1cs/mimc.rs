@@ -0,0 +1,157 @@
+//! The `mimc` module contains the MiMC hash function, both as a
+//! native function and as a gadget producing the same outputs inside
+//! a `ConstraintSystem`.
+//!
+//! MiMC is built from the permutation \\( x \mapsto x^5 \\) of the
+//! scalar field, which costs only three multiplication gates per
+//! round, instead of the tens of thousands of gates that SHA-2 would
+//! need.
+//!
+//! The block cipher \\(E\_k\\) applies \\(R\\) rounds of
+//! \\( x \mapsto (x + k + c\_i)^5 \\) followed by a final key addition,
+//! with round constants \\(c\_i\\) derived by hashing.  Two field
+//! elements are compressed with the Miyaguchi–Preneel construction
+//! \\( H(l, r) = E\_l(r) + l + r \\).
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate curve25519_dalek;
+//! # extern crate ristretto_bulletproofs;
+//! # use curve25519_dalek::scalar::Scalar;
+//! # use ristretto_bulletproofs::r1cs::mimc::MiMC;
+//! # fn main() {
+//! let mimc = MiMC::new();
+//! let h = mimc.hash(&Scalar::from_u64(1), &Scalar::from_u64(2));
+//! assert!(h != mimc.hash(&Scalar::from_u64(2), &Scalar::from_u64(1)));
+//! # }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::scalar::Scalar;
+use sha2::Sha512;
+
+use super::{ConstraintSystem, LinearCombination};
+
+/// The number of rounds, \\( \lceil \log\_5 \ell \rceil \\) for the
+/// group order \\(\ell\\).
+///
+/// \\(5\\) does not divide \\(\ell - 1\\), so \\( x \mapsto x^5 \\) is
+/// a permutation.
+pub const MIMC_ROUNDS: usize = 110;
+
+/// The MiMC hash function, holding its round constants.
+#[derive(Clone, Debug)]
+pub struct MiMC {
+    round_constants: Vec<Scalar>,
+}
+
+impl MiMC {
+    /// Derives the round constants.
+    pub fn new() -> Self {
+        let round_constants = (0..MIMC_ROUNDS)
+            .map(|i| {
+                let mut input = [0u8; 25];
+                input[..17].copy_from_slice(b"Bulletproofs.MiMC");
+                LittleEndian::write_u64(&mut input[17..], i as u64);
+                Scalar::hash_from_bytes::<Sha512>(&input)
+            })
+            .collect();
+
+        MiMC { round_constants }
+    }
+
+    /// Computes the hash \\( H(l, r) \\) of `left` and `right`.
+    pub fn hash(&self, left: &Scalar, right: &Scalar) -> Scalar {
+        let mut x = *right;
+        for c in self.round_constants.iter() {
+            let t = x + left + c;
+            let t2 = t * t;
+            x = t2 * t2 * t;
+        }
+        x + left + left + right
+    }
+
+    /// Returns a linear combination equal to the hash \\( H(l, r) \\) of
+    /// the linear combinations `left` and `right`.
+    ///
+    /// Uses \\(3 R\\) multiplication gates.
+    pub fn hash_gadget<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> LinearCombination {
+        let mut x = right.clone();
+        for c in self.round_constants.iter() {
+            let t = x + left.clone() + *c;
+            let (_, _, t2) = cs.multiply(t.clone(), t.clone());
+            let (_, _, t4) = cs.multiply(t2.into(), t2.into());
+            let (_, _, t5) = cs.multiply(t4.into(), t);
+            x = t5.into();
+        }
+        x + left.clone() + left + right
+    }
+}
+
+impl Default for MiMC {
+    fn default() -> Self {
+        MiMC::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use r1cs::{Prover, R1CSError, Verifier};
+    use rand::OsRng;
+    use secret::{Blinding, SecretScalar};
+
+    /// Proves that the committed `left` and `right` hash to `output`.
+    fn prove_and_verify(left: Scalar, right: Scalar, output: Scalar) -> Result<(), R1CSError> {
+        let mimc = MiMC::new();
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 512, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"MiMCTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let (L, l) = prover.commit(&SecretScalar::new(left), &Blinding::random(&mut rng));
+        let (R, r) = prover.commit(&SecretScalar::new(right), &Blinding::random(&mut rng));
+        let hash = mimc.hash_gadget(&mut prover, l.into(), r.into());
+        prover.constrain(hash - output);
+        let proof = prover.prove(&mut rng)?;
+
+        let mut transcript = ProofTranscript::new(b"MiMCTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let l = verifier.commit(L);
+        let r = verifier.commit(R);
+        let hash = mimc.hash_gadget(&mut verifier, l.into(), r.into());
+        verifier.constrain(hash - output);
+        verifier.verify(&proof, &mut rng)
+    }
+
+    #[test]
+    fn gadget_matches_native_hash() {
+        let mimc = MiMC::new();
+        let left = Scalar::from_u64(12345);
+        let right = Scalar::from_u64(67890);
+        let output = mimc.hash(&left, &right);
+
+        assert!(prove_and_verify(left, right, output).is_ok());
+        assert!(prove_and_verify(right, left, output).is_err());
+        assert!(prove_and_verify(left, right, output + Scalar::one()).is_err());
+    }
+
+    #[test]
+    fn round_constants_are_distinct() {
+        let mimc = MiMC::new();
+        for (i, c) in mimc.round_constants.iter().enumerate() {
+            assert!(mimc.round_constants[..i].iter().all(|d| d != c));
+        }
+    }
+}
@@ -0,0 +1,414 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `r1cs` module contains an API for proving statements expressed
+//! as arithmetic circuits, following section 5 of the Bulletproofs
+//! paper.
+//!
+//! A statement is a set of multiplication gates \\(a\_L \cdot a\_R = a\_O\\)
+//! and linear constraints over the gate wires and a list of committed
+//! values.  Statements are written once, as a function generic over a
+//! `ConstraintSystem`, and run by both the `Prover`, which assigns all
+//! the variables, and the `Verifier`, which only knows the commitments.
+//!
+//! The proof reuses the inner product argument and the generators of
+//! the range proof.  The number of multiplication gates is rounded up
+//! to a power of two, and the generators must have at least that many
+//! elements.
+//!
+//! Constraints that need challenges bound to the gates, e.g. to check
+//! a permutation of allocated variables, are built in a second phase
+//! with `ConstraintSystem::specify_randomized_constraints`.  The prover
+//! commits to the first-phase gates before the challenges are drawn,
+//! and to the second-phase gates separately afterwards.
+//!
+//! A committed value can be range-proven on the same transcript with
+//! `Prover::commit_with_range_proof`, linking the range proof to the
+//! variable without a separate equality proof.  Alternatively,
+//! `Prover::prove_with_range_proof` proves the constraints and one
+//! range proof with a single inner product argument, as a `JointProof`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! // Proves that the committed values satisfy a * b = c.
+//! fn mul_gadget<CS: ConstraintSystem>(cs: &mut CS, a: Variable, b: Variable, c: Variable) {
+//!     let (_, _, o) = cs.multiply(a.into(), b.into());
+//!     cs.constrain(o - c);
+//! }
+//!
+//! let mut transcript = ProofTranscript::new(b"R1CSExample")?;
+//! let mut prover = Prover::new(generators.all(), &mut transcript);
+//! // The values are `SecretScalar`s holding 3, 4 and 12.
+//! let (A, a) = prover.commit(&a_value, &Blinding::random(&mut rng));
+//! let (B, b) = prover.commit(&b_value, &Blinding::random(&mut rng));
+//! let (C, c) = prover.commit(&c_value, &Blinding::random(&mut rng));
+//! mul_gadget(&mut prover, a, b, c);
+//! let proof = prover.prove(&mut rng)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"R1CSExample")?;
+//! let mut verifier = Verifier::new(generators.all(), &mut transcript);
+//! let a = verifier.commit(A);
+//! let b = verifier.commit(B);
+//! let c = verifier.commit(C);
+//! mul_gadget(&mut verifier, a, b, c);
+//! verifier.verify(&proof, &mut rng)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use params::ProofParams;
+
+mod constraint_system;
+pub mod gadgets;
+mod linear_combination;
+pub mod merkle;
+pub mod mimc;
+mod proof;
+mod prover;
+pub mod uint;
+mod verifier;
+
+pub use self::constraint_system::{ConstraintSystem, RandomizedConstraints};
+pub use self::linear_combination::{LinearCombination, Variable};
+pub use self::proof::{JointProof, R1CSProof};
+pub use self::prover::Prover;
+pub use self::verifier::Verifier;
+
+/// Represents an error in proof creation or verification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum R1CSError {
+    /// The generators are too short for the number of multiplication
+    /// gates, rounded up to a power of two.
+    InvalidGeneratorsLength,
+    /// The bitsize of a linked range proof is not a power of two of at
+    /// most 64.
+    InvalidBitsize,
+    /// The prover allocated a multiplication gate without an assignment.
+    MissingAssignment,
+    /// The proof does not verify.
+    VerificationError,
+    /// Randomized constraints were specified from inside randomized
+    /// constraints.
+    NestedRandomizedConstraints,
+}
+
+/// Returns the first `n` of the `generators`, used for range proofs
+/// linked to the constraint system proof.
+fn range_generators<'a>(
+    generators: &GeneratorsView<'a>,
+    n: usize,
+) -> Result<GeneratorsView<'a>, R1CSError> {
+    check_range_bitsize(n)?;
+    if generators.G.len() < n {
+        return Err(R1CSError::InvalidGeneratorsLength);
+    }
+    Ok(GeneratorsView {
+        pedersen_generators: generators.pedersen_generators,
+        G: &generators.G[..n],
+        H: &generators.H[..n],
+    })
+}
+
+/// Checks the bitsize `n` of a range proof linked to the constraint
+/// system proof.
+fn check_range_bitsize(n: usize) -> Result<(), R1CSError> {
+    ProofParams::single(n).map(|_| ()).map_err(range_error)
+}
+
+/// Maps the parameter errors of a linked range proof.
+fn range_error(e: ProofError) -> R1CSError {
+    match e {
+        ProofError::InvalidBitsize => R1CSError::InvalidBitsize,
+        _ => R1CSError::InvalidGeneratorsLength,
+    }
+}
+
+/// Flattens the `constraints` into the vectors
+/// \\(\mathbf{w}\_L, \mathbf{w}\_R, \mathbf{w}\_O, \mathbf{w}\_V\\) and the
+/// scalar \\(w\_c\\), weighting the \\(q\\)-th constraint by \\(z^{q+1}\\).
+///
+/// The constraints \\(\sum\_q z^{q+1} \mathrm{lc}\_q = 0\\) then read
+/// \\( \langle \mathbf{w}\_L, \mathbf{a}\_L \rangle + \langle \mathbf{w}\_R, \mathbf{a}\_R \rangle
+/// + \langle \mathbf{w}\_O, \mathbf{a}\_O \rangle = \langle \mathbf{w}\_V, \mathbf{v} \rangle + w\_c \\).
+fn flattened_constraints(
+    constraints: &[LinearCombination],
+    z: &Scalar,
+    n: usize,
+    m: usize,
+) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Scalar) {
+    let mut wL = vec![Scalar::zero(); n];
+    let mut wR = vec![Scalar::zero(); n];
+    let mut wO = vec![Scalar::zero(); n];
+    let mut wV = vec![Scalar::zero(); m];
+    let mut wc = Scalar::zero();
+
+    let mut exp_z = *z;
+    for lc in constraints.iter() {
+        for &(var, coeff) in lc.terms.iter() {
+            match var {
+                Variable::MultiplierLeft(i) => wL[i] += exp_z * coeff,
+                Variable::MultiplierRight(i) => wR[i] += exp_z * coeff,
+                Variable::MultiplierOutput(i) => wO[i] += exp_z * coeff,
+                Variable::Committed(i) => wV[i] -= exp_z * coeff,
+                Variable::One() => wc -= exp_z * coeff,
+            }
+        }
+        exp_z = exp_z * z;
+    }
+
+    (wL, wR, wO, wV, wc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretScalar, SecretValue};
+
+    fn secret(v: u64) -> SecretScalar {
+        SecretScalar::new(Scalar::from_u64(v))
+    }
+
+    /// Constrains the committed values to satisfy `a * b = c` and
+    /// `a + b = d`.
+    fn example_gadget<CS: ConstraintSystem>(
+        cs: &mut CS,
+        a: Variable,
+        b: Variable,
+        c: Variable,
+        d: Variable,
+    ) {
+        let (_, _, o) = cs.multiply(a.into(), b.into());
+        cs.constrain(o - c);
+        cs.constrain(a + b - d);
+    }
+
+    /// Proves the example gadget for the committed `values`, and
+    /// verifies it against the commitments in the order `order`.
+    fn prove_and_verify(values: [u64; 4], order: [usize; 4]) -> Result<(), R1CSError> {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 1, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let mut commitments = Vec::new();
+        let mut vars = Vec::new();
+        for &v in values.iter() {
+            let (V, var) = prover.commit(&secret(v), &Blinding::random(&mut rng));
+            commitments.push(V);
+            vars.push(var);
+        }
+        example_gadget(&mut prover, vars[0], vars[1], vars[2], vars[3]);
+        let proof = prover.prove(&mut rng)?;
+
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let vars: Vec<Variable> = order.iter().map(|&j| verifier.commit(commitments[j])).collect();
+        example_gadget(&mut verifier, vars[0], vars[1], vars[2], vars[3]);
+        verifier.verify(&proof, &mut rng)
+    }
+
+    #[test]
+    fn satisfied_constraints_verify() {
+        assert_eq!(prove_and_verify([3, 4, 12, 7], [0, 1, 2, 3]), Ok(()));
+        assert_eq!(prove_and_verify([0, 5, 0, 5], [0, 1, 2, 3]), Ok(()));
+    }
+
+    #[test]
+    fn unsatisfied_constraints_fail() {
+        assert_eq!(
+            prove_and_verify([3, 4, 13, 7], [0, 1, 2, 3]),
+            Err(R1CSError::VerificationError)
+        );
+        assert_eq!(
+            prove_and_verify([3, 4, 12, 8], [0, 1, 2, 3]),
+            Err(R1CSError::VerificationError)
+        );
+        // Valid values, but the verifier swaps two commitments.
+        assert_eq!(
+            prove_and_verify([3, 4, 12, 7], [0, 1, 3, 2]),
+            Err(R1CSError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn gates_are_padded_to_a_power_of_two() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 4, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        // Three gates computing x^4, padded to four.
+        fn gadget<CS: ConstraintSystem>(cs: &mut CS, x: Variable, y: Variable) {
+            let (_, _, x2) = cs.multiply(x.into(), x.into());
+            let (_, _, x4) = cs.multiply(x2.into(), x2.into());
+            let (_, _, x4_again) = cs.multiply(x4.into(), Scalar::one().into());
+            cs.constrain(x4_again - y);
+        }
+
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let (X, x) = prover.commit(&secret(3), &Blinding::random(&mut rng));
+        let (Y, y) = prover.commit(&secret(81), &Blinding::random(&mut rng));
+        gadget(&mut prover, x, y);
+        let proof = prover.prove(&mut rng).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let x = verifier.commit(X);
+        let y = verifier.commit(Y);
+        gadget(&mut verifier, x, y);
+        assert_eq!(verifier.verify(&proof, &mut rng), Ok(()));
+
+        // Too few generators for the padded gates.
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 2, 1);
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let (_, x) = prover.commit(&secret(3), &Blinding::random(&mut rng));
+        let (_, y) = prover.commit(&secret(81), &Blinding::random(&mut rng));
+        gadget(&mut prover, x, y);
+        assert_eq!(
+            prover.prove(&mut rng).unwrap_err(),
+            R1CSError::InvalidGeneratorsLength
+        );
+    }
+
+    #[test]
+    fn range_proofs_link_to_committed_variables() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 16, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        // Proves that a and b are 16-bit values with a + b = 100.
+        let a_blinding = Blinding::random(&mut rng);
+        let b_blinding = Blinding::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let (A, a, a_proof) = prover
+            .commit_with_range_proof(&mut rng, 16, &SecretValue::new(40), &a_blinding)
+            .unwrap();
+        let (B, b, b_proof) = prover
+            .commit_with_range_proof(&mut rng, 16, &SecretValue::new(60), &b_blinding)
+            .unwrap();
+        prover.constrain(a + b - Scalar::from_u64(100));
+        let proof = prover.prove(&mut rng).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let a = verifier
+            .commit_with_range_proof(&mut rng, 16, A, &a_proof)
+            .unwrap();
+        let b = verifier
+            .commit_with_range_proof(&mut rng, 16, B, &b_proof)
+            .unwrap();
+        verifier.constrain(a + b - Scalar::from_u64(100));
+        assert_eq!(verifier.verify(&proof, &mut rng), Ok(()));
+
+        // The range proofs are bound to their position in the transcript.
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        assert_eq!(
+            verifier.commit_with_range_proof(&mut rng, 16, B, &b_proof),
+            Err(R1CSError::VerificationError)
+        );
+
+        // Linked range proofs need a supported bitsize.
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        assert_eq!(
+            prover
+                .commit_with_range_proof(&mut rng, 12, &SecretValue::new(40), &a_blinding)
+                .map(|_| ()),
+            Err(R1CSError::InvalidBitsize)
+        );
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        assert_eq!(
+            verifier.commit_with_range_proof(&mut rng, 12, A, &a_proof),
+            Err(R1CSError::InvalidBitsize)
+        );
+    }
+
+    #[test]
+    fn joint_proofs_share_one_inner_product_argument() {
+        // Four gates computing x^4, and a 16-bit range proof.
+        fn gadget<CS: ConstraintSystem>(cs: &mut CS, x: Variable, y: Variable) {
+            let (_, _, x2) = cs.multiply(x.into(), x.into());
+            let (_, _, x4) = cs.multiply(x2.into(), x2.into());
+            let (_, _, x4_again) = cs.multiply(x4.into(), Scalar::one().into());
+            let (_, _, x4_once_more) = cs.multiply(x4_again.into(), Scalar::one().into());
+            cs.constrain(x4_once_more - y);
+        }
+
+        fn prove_and_verify(v: u64, V_offset: u64) -> Result<JointProof, R1CSError> {
+            let generators = Generators::new(b"Test", PedersenGenerators::default(), 32, 1);
+            let mut rng = OsRng::new().unwrap();
+
+            let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+            let mut prover = Prover::new(generators.all(), &mut transcript);
+            let (X, x) = prover.commit(&secret(3), &Blinding::random(&mut rng));
+            let (Y, y) = prover.commit(&secret(81), &Blinding::random(&mut rng));
+            gadget(&mut prover, x, y);
+            let v_blinding = Blinding::random(&mut rng);
+            let (proof, V) =
+                prover.prove_with_range_proof(&mut rng, 16, &SecretValue::new(v), &v_blinding)?;
+            let V = V + generators.all().pedersen_generators.B * Scalar::from_u64(V_offset);
+
+            let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+            let mut verifier = Verifier::new(generators.all(), &mut transcript);
+            let x = verifier.commit(X);
+            let y = verifier.commit(Y);
+            gadget(&mut verifier, x, y);
+            verifier.verify_with_range_proof(&proof, &mut rng, 16, &V)?;
+            Ok(proof)
+        }
+
+        let proof = prove_and_verify(1000, 0).unwrap();
+        // 4 + 16 generators, padded to 32, instead of 2 + 4 rounds.
+        assert_eq!(proof.ipp_proof.L_vec.len(), 5);
+
+        assert_eq!(
+            prove_and_verify(1 << 16, 0).unwrap_err(),
+            R1CSError::VerificationError
+        );
+        assert_eq!(
+            prove_and_verify(1000, 1).unwrap_err(),
+            R1CSError::VerificationError
+        );
+    }
+
+    #[test]
+    fn prover_requires_assignments() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 1, 1);
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        assert_eq!(prover.allocate(None), Err(R1CSError::MissingAssignment));
+    }
+
+    #[test]
+    fn randomized_constraints_cannot_be_nested() {
+        fn nested(cs: &mut ConstraintSystem) -> Result<(), R1CSError> {
+            fn empty(_: &mut ConstraintSystem) -> Result<(), R1CSError> {
+                Ok(())
+            }
+            cs.specify_randomized_constraints(Box::new(empty))
+        }
+
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 1, 1);
+        let mut rng = OsRng::new().unwrap();
+        let mut transcript = ProofTranscript::new(b"R1CSTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        prover
+            .specify_randomized_constraints(Box::new(nested))
+            .unwrap();
+        assert_eq!(
+            prover.prove(&mut rng).unwrap_err(),
+            R1CSError::NestedRandomizedConstraints
+        );
+    }
+}
@@ -0,0 +1,208 @@
+#![allow(non_snake_case)]
+
+//! The `R1CSProof` and `JointProof` structs.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConstantTimeEq};
+
+use inner_product_proof::InnerProductProof;
+use range_proof::RangeCommitments;
+
+/// A proof of some statement specified by a `ConstraintSystem`.
+///
+/// Statements are specified by writing gadget functions which add
+/// constraints to a `ConstraintSystem` implementation.  To construct
+/// an `R1CSProof`, a prover constructs a `Prover`, then passes it to
+/// gadget functions to build the constraint system, then consumes the
+/// constraint system using `Prover::prove` to produce an
+/// `R1CSProof`.  To verify an `R1CSProof`, a verifier constructs a
+/// `Verifier`, passes it to the same sequence of gadget functions,
+/// then uses `Verifier::verify` to verify the proof.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct R1CSProof {
+    /// Commitment to the values of input wires in the first phase
+    pub(crate) A_I1: RistrettoPoint,
+    /// Commitment to the values of output wires in the first phase
+    pub(crate) A_O1: RistrettoPoint,
+    /// Commitment to the blinding factors in the first phase
+    pub(crate) S1: RistrettoPoint,
+    /// Commitment to the values of input wires in the second phase
+    pub(crate) A_I2: RistrettoPoint,
+    /// Commitment to the values of output wires in the second phase
+    pub(crate) A_O2: RistrettoPoint,
+    /// Commitment to the blinding factors in the second phase
+    pub(crate) S2: RistrettoPoint,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(crate) T_1: RistrettoPoint,
+    /// Commitment to the \\(t_3\\) coefficient of \\( t(x) \\)
+    pub(crate) T_3: RistrettoPoint,
+    /// Commitment to the \\(t_4\\) coefficient of \\( t(x) \\)
+    pub(crate) T_4: RistrettoPoint,
+    /// Commitment to the \\(t_5\\) coefficient of \\( t(x) \\)
+    pub(crate) T_5: RistrettoPoint,
+    /// Commitment to the \\(t_6\\) coefficient of \\( t(x) \\)
+    pub(crate) T_6: RistrettoPoint,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\( t(x) \\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the
+    /// inner-product arguments
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) e_blinding: Scalar,
+    /// Proof data for the inner-product argument.
+    pub(crate) ipp_proof: InnerProductProof,
+}
+
+impl R1CSProof {
+    /// Assembles a proof from the `commitments` and the `ipp_proof`.
+    pub(crate) fn from_parts(commitments: R1CSCommitments, ipp_proof: InnerProductProof) -> Self {
+        R1CSProof {
+            A_I1: commitments.A_I1,
+            A_O1: commitments.A_O1,
+            S1: commitments.S1,
+            A_I2: commitments.A_I2,
+            A_O2: commitments.A_O2,
+            S2: commitments.S2,
+            T_1: commitments.T_1,
+            T_3: commitments.T_3,
+            T_4: commitments.T_4,
+            T_5: commitments.T_5,
+            T_6: commitments.T_6,
+            t_x: commitments.t_x,
+            t_x_blinding: commitments.t_x_blinding,
+            e_blinding: commitments.e_blinding,
+            ipp_proof,
+        }
+    }
+
+    /// Returns the commitments and evaluations of the proof.
+    pub(crate) fn commitments(&self) -> R1CSCommitments {
+        R1CSCommitments {
+            A_I1: self.A_I1,
+            A_O1: self.A_O1,
+            S1: self.S1,
+            A_I2: self.A_I2,
+            A_O2: self.A_O2,
+            S2: self.S2,
+            T_1: self.T_1,
+            T_3: self.T_3,
+            T_4: self.T_4,
+            T_5: self.T_5,
+            T_6: self.T_6,
+            t_x: self.t_x,
+            t_x_blinding: self.t_x_blinding,
+            e_blinding: self.e_blinding,
+        }
+    }
+}
+
+/// The commitments and evaluations of a constraint system proof,
+/// without its inner-product argument.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct R1CSCommitments {
+    /// Commitment to the values of input wires in the first phase
+    pub(crate) A_I1: RistrettoPoint,
+    /// Commitment to the values of output wires in the first phase
+    pub(crate) A_O1: RistrettoPoint,
+    /// Commitment to the blinding factors in the first phase
+    pub(crate) S1: RistrettoPoint,
+    /// Commitment to the values of input wires in the second phase
+    pub(crate) A_I2: RistrettoPoint,
+    /// Commitment to the values of output wires in the second phase
+    pub(crate) A_O2: RistrettoPoint,
+    /// Commitment to the blinding factors in the second phase
+    pub(crate) S2: RistrettoPoint,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(crate) T_1: RistrettoPoint,
+    /// Commitment to the \\(t_3\\) coefficient of \\( t(x) \\)
+    pub(crate) T_3: RistrettoPoint,
+    /// Commitment to the \\(t_4\\) coefficient of \\( t(x) \\)
+    pub(crate) T_4: RistrettoPoint,
+    /// Commitment to the \\(t_5\\) coefficient of \\( t(x) \\)
+    pub(crate) T_5: RistrettoPoint,
+    /// Commitment to the \\(t_6\\) coefficient of \\( t(x) \\)
+    pub(crate) T_6: RistrettoPoint,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\( t(x) \\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the
+    /// inner-product arguments
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) e_blinding: Scalar,
+}
+
+/// A constraint system proof and a range proof sharing a single
+/// inner-product argument.
+///
+/// The proof is created by `Prover::prove_with_range_proof` and
+/// verified by `Verifier::verify_with_range_proof`.  The two final
+/// inner-product statements are combined with a random weight into one
+/// argument over consecutive generators, which has
+/// \(\lg(n\_c + n)\) rounds, rounded up, instead of
+/// \(\lg n\_c + \lg n\) for an `R1CSProof` with \(n\_c\) padded gates
+/// and an \(n\)-bit `RangeProof`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JointProof {
+    /// The constraint system proof, without its inner-product argument
+    pub(crate) circuit: R1CSCommitments,
+    /// The range proof, without its inner-product argument
+    pub(crate) range: RangeCommitments,
+    /// Proof data for the combined inner-product argument
+    pub(crate) ipp_proof: InnerProductProof,
+}
+
+impl ConstantTimeEq for R1CSCommitments {
+    fn ct_eq(&self, other: &R1CSCommitments) -> Choice {
+        self.A_I1.ct_eq(&other.A_I1)
+            & self.A_O1.ct_eq(&other.A_O1)
+            & self.S1.ct_eq(&other.S1)
+            & self.A_I2.ct_eq(&other.A_I2)
+            & self.A_O2.ct_eq(&other.A_O2)
+            & self.S2.ct_eq(&other.S2)
+            & self.T_1.ct_eq(&other.T_1)
+            & self.T_3.ct_eq(&other.T_3)
+            & self.T_4.ct_eq(&other.T_4)
+            & self.T_5.ct_eq(&other.T_5)
+            & self.T_6.ct_eq(&other.T_6)
+            & self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+    }
+}
+
+impl ConstantTimeEq for R1CSProof {
+    fn ct_eq(&self, other: &R1CSProof) -> Choice {
+        self.commitments().ct_eq(&other.commitments()) & self.ipp_proof.ct_eq(&other.ipp_proof)
+    }
+}
+
+impl PartialEq for R1CSProof {
+    fn eq(&self, other: &R1CSProof) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for R1CSProof {}
+
+impl ConstantTimeEq for JointProof {
+    fn ct_eq(&self, other: &JointProof) -> Choice {
+        self.circuit.ct_eq(&other.circuit)
+            & self.range.ct_eq(&other.range)
+            & self.ipp_proof.ct_eq(&other.ipp_proof)
+    }
+}
+
+impl PartialEq for JointProof {
+    fn eq(&self, other: &JointProof) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for JointProof {}
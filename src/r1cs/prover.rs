@@ -0,0 +1,527 @@
+#![allow(non_snake_case)]
+
+//! The prover side of the constraint system proof.
+
+use core::{fmt, iter, mem};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use generators::GeneratorsView;
+use inner_product_proof::{inner_product, ReducedWitness};
+use poly::VecPoly3;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretScalar, SecretScalars, SecretValue};
+use util;
+
+use super::proof::R1CSCommitments;
+use super::{
+    check_range_bitsize, flattened_constraints, range_error, range_generators, ConstraintSystem,
+    JointProof, LinearCombination, R1CSError, R1CSProof, RandomizedConstraints, Variable,
+};
+
+/// A `ConstraintSystem` implementation for use by the prover.
+///
+/// The prover commits high-level values and their blinding factors
+/// with `commit`, builds the constraints through the
+/// `ConstraintSystem` methods, keeping track of the assignments of
+/// all variables, and finally creates the proof with `prove`.
+pub struct Prover<'a> {
+    generators: GeneratorsView<'a>,
+    transcript: &'a mut ProofTranscript,
+    /// The constraints accumulated so far
+    constraints: Vec<LinearCombination>,
+    /// Stores assignments to the "left" of multiplication gates
+    a_L: SecretScalars,
+    /// Stores assignments to the "right" of multiplication gates
+    a_R: SecretScalars,
+    /// Stores assignments to the "output" of multiplication gates
+    a_O: SecretScalars,
+    /// High-level witness data (value openings to V commitments)
+    v: SecretScalars,
+    /// High-level witness data (blinding openings to V commitments)
+    v_blinding: SecretScalars,
+    /// The randomized constraints, built after the first phase
+    deferred_constraints: Vec<RandomizedConstraints>,
+    /// Whether the randomized constraints are being built
+    in_second_phase: bool,
+}
+
+impl<'a> Prover<'a> {
+    /// Constructs a new `Prover`, proving against `generators` and
+    /// binding the proof to `transcript`.
+    pub fn new(generators: GeneratorsView<'a>, transcript: &'a mut ProofTranscript) -> Self {
+        transcript.commit(b"R1CS");
+
+        Prover {
+            generators,
+            transcript,
+            constraints: Vec::new(),
+            a_L: SecretScalars::default(),
+            a_R: SecretScalars::default(),
+            a_O: SecretScalars::default(),
+            v: SecretScalars::default(),
+            v_blinding: SecretScalars::default(),
+            deferred_constraints: Vec::new(),
+            in_second_phase: false,
+        }
+    }
+
+    /// Commits to the value `v` with the blinding factor `v_blinding`,
+    /// returning the commitment and a variable for the value.
+    ///
+    /// The commitment is added to the transcript, and must be sent to
+    /// the verifier along with the proof.
+    pub fn commit(
+        &mut self,
+        v: &SecretScalar,
+        v_blinding: &Blinding,
+    ) -> (RistrettoPoint, Variable) {
+        let (v, v_blinding) = (*v.as_scalar(), *v_blinding.as_scalar());
+        let i = self.v.len();
+        self.v.push(v);
+        self.v_blinding.push(v_blinding);
+
+        let V = self.generators.pedersen_generators.commit(v, v_blinding);
+        self.transcript.commit(V.compress().as_bytes());
+
+        (V, Variable::Committed(i))
+    }
+
+    /// Commits to the `n`-bit value `v` with the blinding factor
+    /// `v_blinding`, and proves that it is in range, returning the
+    /// commitment, a variable for the value, and the range proof.
+    ///
+    /// The range proof is made for the same commitment, on the same
+    /// transcript and with the same generators as the constraint system
+    /// proof, so both proofs necessarily talk about the same opening.
+    /// The verifier must call `Verifier::commit_with_range_proof` at the
+    /// same point.
+    pub fn commit_with_range_proof<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<(RistrettoPoint, Variable, RangeProof), R1CSError> {
+        let range_generators = range_generators(&self.generators, n)?;
+        let range_proof =
+            RangeProof::generate_proof(range_generators, self.transcript, rng, n, v, v_blinding)
+                .map_err(range_error)?;
+        let (V, var) = self.commit(&SecretScalar::new(v.to_scalar()), v_blinding);
+
+        Ok((V, var, range_proof))
+    }
+
+    /// Evaluates the linear combination `lc` with the current assignments.
+    fn eval(&self, lc: &LinearCombination) -> Scalar {
+        lc.terms
+            .iter()
+            .fold(Scalar::zero(), |acc, &(var, coeff)| {
+                acc + coeff * match var {
+                    Variable::MultiplierLeft(i) => self.a_L[i],
+                    Variable::MultiplierRight(i) => self.a_R[i],
+                    Variable::MultiplierOutput(i) => self.a_O[i],
+                    Variable::Committed(i) => self.v[i],
+                    Variable::One() => Scalar::one(),
+                }
+            })
+    }
+
+    /// Consumes the `Prover` to create a proof of the constraints.
+    ///
+    /// The gates allocated so far form the first phase and are
+    /// committed to before the randomized constraints are built, which
+    /// allocate the second-phase gates.
+    ///
+    /// Returns an error if the generators are too short for the number
+    /// of multiplication gates, rounded up to a power of two.
+    pub fn prove<R: Rng>(self, rng: &mut R) -> Result<R1CSProof, R1CSError> {
+        let (transcript, generators, commitments, witness) = self.reduce(rng)?;
+        let padded_n = witness.a.len();
+        let ipp_proof = witness.prove(
+            transcript,
+            generators.pedersen_generators,
+            &generators.G[..padded_n],
+            &generators.H[..padded_n],
+        ).map_err(|_| R1CSError::InvalidGeneratorsLength)?;
+
+        Ok(R1CSProof::from_parts(commitments, ipp_proof))
+    }
+
+    /// Consumes the `Prover` to create a proof of the constraints and
+    /// an `n`-bit range proof for the value `v` committed with
+    /// `v_blinding`, sharing one inner-product argument.
+    ///
+    /// Returns the proof and the commitment to `v`.  The range proof
+    /// uses the generators following the constraint system's, so the
+    /// generators must cover the number of multiplication gates,
+    /// rounded up to a power of two, plus `n`, all rounded up to a
+    /// power of two.
+    ///
+    /// To constrain `v` in the circuit as well, commit it with `commit`
+    /// using the same blinding factor.
+    pub fn prove_with_range_proof<R: Rng>(
+        self,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<(JointProof, RistrettoPoint), R1CSError> {
+        check_range_bitsize(n)?;
+        let (transcript, generators, circuit, circuit_witness) = self.reduce(rng)?;
+        let offset = circuit_witness.a.len();
+        let joint_n = (offset + n).next_power_of_two();
+        if generators.G.len() < joint_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        let pedersen_generators = generators.pedersen_generators;
+
+        transcript.commit(b"JointRangeProof");
+        let range_generators = GeneratorsView {
+            pedersen_generators,
+            G: &generators.G[offset..offset + n],
+            H: &generators.H[offset..offset + n],
+        };
+        let (range, range_witness) = RangeProof::prove_reduced(
+            range_generators,
+            transcript,
+            rng,
+            n,
+            v.value(),
+            v_blinding.as_scalar(),
+        ).map_err(range_error)?;
+
+        // Combine the two inner product statements with a random weight.
+        let rho = transcript.challenge_scalar_labeled(b"rho");
+        let mut witness = circuit_witness.concat(range_witness, &rho);
+        witness.pad(joint_n);
+        let ipp_proof = witness.prove(
+            transcript,
+            pedersen_generators,
+            &generators.G[..joint_n],
+            &generators.H[..joint_n],
+        ).map_err(|_| R1CSError::InvalidGeneratorsLength)?;
+
+        let V = pedersen_generators.commit(v.to_scalar(), *v_blinding.as_scalar());
+        let proof = JointProof {
+            circuit,
+            range,
+            ipp_proof,
+        };
+        Ok((proof, V))
+    }
+
+    /// Runs the proof protocol up to, but not including, the
+    /// inner-product argument, returning the transcript, the
+    /// generators, the commitments and the witness for the argument.
+    fn reduce<R: Rng>(
+        mut self,
+        rng: &mut R,
+    ) -> Result<
+        (
+            &'a mut ProofTranscript,
+            GeneratorsView<'a>,
+            R1CSCommitments,
+            ReducedWitness,
+        ),
+        R1CSError,
+    > {
+        let n1 = self.a_L.len();
+        let m = self.v.len();
+        if self.generators.G.len() < n1 {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        self.transcript.commit_u64(m as u64);
+        self.transcript.commit_u64(n1 as u64);
+
+        let B_blinding = self.generators.pedersen_generators.B_blinding;
+
+        // Commit to the first-phase gates
+        let i_blinding1 = Scalar::random(rng);
+        let o_blinding1 = Scalar::random(rng);
+        let s_blinding1 = Scalar::random(rng);
+        let mut s_L: Vec<Scalar> = (0..n1).map(|_| Scalar::random(rng)).collect();
+        let mut s_R: Vec<Scalar> = (0..n1).map(|_| Scalar::random(rng)).collect();
+
+        let (A_I1, A_O1, S1) = phase_commitments(
+            &B_blinding,
+            &self.generators.G[..n1],
+            &self.generators.H[..n1],
+            (&i_blinding1, &self.a_L[..], &self.a_R[..]),
+            (&o_blinding1, &self.a_O[..]),
+            (&s_blinding1, &s_L[..], &s_R[..]),
+        );
+
+        self.transcript.commit(A_I1.compress().as_bytes());
+        self.transcript.commit(A_O1.compress().as_bytes());
+        self.transcript.commit(S1.compress().as_bytes());
+
+        // Build the randomized constraints, which may use challenges
+        // bound to the first-phase gates.
+        self.in_second_phase = true;
+        let deferred_constraints = mem::replace(&mut self.deferred_constraints, Vec::new());
+        for mut callback in deferred_constraints.into_iter() {
+            callback(&mut self)?;
+        }
+
+        let Prover {
+            generators,
+            transcript,
+            constraints,
+            mut a_L,
+            mut a_R,
+            mut a_O,
+            v,
+            v_blinding,
+            ..
+        } = self;
+
+        let n = a_L.len();
+        let padded_n = n.next_power_of_two();
+        if generators.G.len() < padded_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        let G = &generators.G[..padded_n];
+        let H = &generators.H[..padded_n];
+
+        transcript.commit_u64((n - n1) as u64);
+
+        // Pad the gates with zeros, which trivially satisfy a_L * a_R = a_O.
+        a_L.resize(padded_n, Scalar::zero());
+        a_R.resize(padded_n, Scalar::zero());
+        a_O.resize(padded_n, Scalar::zero());
+
+        // Commit to the second-phase and padding gates, if any
+        let (i_blinding2, o_blinding2, s_blinding2) = if padded_n > n1 {
+            (Scalar::random(rng), Scalar::random(rng), Scalar::random(rng))
+        } else {
+            (Scalar::zero(), Scalar::zero(), Scalar::zero())
+        };
+        s_L.extend((n1..padded_n).map(|_| Scalar::random(rng)));
+        s_R.extend((n1..padded_n).map(|_| Scalar::random(rng)));
+
+        let (A_I2, A_O2, S2) = phase_commitments(
+            &B_blinding,
+            &G[n1..],
+            &H[n1..],
+            (&i_blinding2, &a_L[n1..], &a_R[n1..]),
+            (&o_blinding2, &a_O[n1..]),
+            (&s_blinding2, &s_L[n1..], &s_R[n1..]),
+        );
+
+        transcript.commit(A_I2.compress().as_bytes());
+        transcript.commit(A_O2.compress().as_bytes());
+        transcript.commit(S2.compress().as_bytes());
+        let y = transcript.challenge_scalar_labeled(b"y");
+        let z = transcript.challenge_scalar_labeled(b"z");
+
+        let (wL, wR, wO, wV, _wc) = flattened_constraints(&constraints, &z, padded_n, m);
+
+        let y_powers = util::ScalarPowers::new(y, padded_n);
+        let y_inv_powers = util::ScalarPowers::new(y.invert(), padded_n);
+
+        let mut l_poly = VecPoly3::zero(padded_n);
+        let mut r_poly = VecPoly3::zero(padded_n);
+        for i in 0..padded_n {
+            let exp_y = y_powers.as_slice()[i];
+            let exp_y_inv = y_inv_powers.as_slice()[i];
+
+            // l_poly.0 = 0
+            l_poly.1[i] = a_L[i] + exp_y_inv * wR[i];
+            l_poly.2[i] = a_O[i];
+            l_poly.3[i] = s_L[i];
+            r_poly.0[i] = wO[i] - exp_y;
+            r_poly.1[i] = exp_y * a_R[i] + wL[i];
+            // r_poly.2 = 0
+            r_poly.3[i] = exp_y * s_R[i];
+        }
+
+        let t_poly = l_poly.special_inner_product(&r_poly);
+
+        let t_1_blinding = Scalar::random(rng);
+        let t_3_blinding = Scalar::random(rng);
+        let t_4_blinding = Scalar::random(rng);
+        let t_5_blinding = Scalar::random(rng);
+        let t_6_blinding = Scalar::random(rng);
+
+        let pedersen_generators = generators.pedersen_generators;
+        let T_1 = pedersen_generators.commit(t_poly.t1, t_1_blinding);
+        let T_3 = pedersen_generators.commit(t_poly.t3, t_3_blinding);
+        let T_4 = pedersen_generators.commit(t_poly.t4, t_4_blinding);
+        let T_5 = pedersen_generators.commit(t_poly.t5, t_5_blinding);
+        let T_6 = pedersen_generators.commit(t_poly.t6, t_6_blinding);
+
+        transcript.commit(T_1.compress().as_bytes());
+        transcript.commit(T_3.compress().as_bytes());
+        transcript.commit(T_4.compress().as_bytes());
+        transcript.commit(T_5.compress().as_bytes());
+        transcript.commit(T_6.compress().as_bytes());
+        let u = transcript.challenge_scalar_labeled(b"u");
+        let x = transcript.challenge_scalar_labeled(b"x");
+
+        // t_2 is committed to implicitly by the value commitments, with
+        // blinding factor <wV, v_blinding>.
+        let t_2_blinding = inner_product(&wV, &v_blinding);
+
+        let t_x = t_poly.eval(x);
+        let t_x_blinding = x * (t_1_blinding
+            + x * (t_2_blinding
+                + x * (t_3_blinding + x * (t_4_blinding + x * (t_5_blinding + x * t_6_blinding)))));
+
+        // The second-phase commitments are weighted by u, so that they
+        // cannot change the first-phase gates.
+        let i_blinding = i_blinding1 + u * i_blinding2;
+        let o_blinding = o_blinding1 + u * o_blinding2;
+        let s_blinding = s_blinding1 + u * s_blinding2;
+        let e_blinding = x * (i_blinding + x * (o_blinding + x * s_blinding));
+
+        transcript.commit(t_x.as_bytes());
+        transcript.commit(t_x_blinding.as_bytes());
+        transcript.commit(e_blinding.as_bytes());
+
+        // Scale the second-phase generators by u to match.
+        let u_or_one = |i: usize| if i < n1 { Scalar::one() } else { u };
+        let witness = ReducedWitness {
+            G_factors: (0..padded_n).map(u_or_one).collect(),
+            H_factors: y_inv_powers
+                .as_slice()
+                .iter()
+                .enumerate()
+                .map(|(i, exp_y_inv)| exp_y_inv * u_or_one(i))
+                .collect(),
+            a: l_poly.eval(x).into(),
+            b: r_poly.eval(x).into(),
+        };
+
+        let commitments = R1CSCommitments {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1,
+            T_3,
+            T_4,
+            T_5,
+            T_6,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+        };
+        Ok((transcript, generators, commitments, witness))
+    }
+}
+
+/// Computes the commitments to the inputs, the outputs and the blinding
+/// factors of the gates of one phase.
+fn phase_commitments(
+    B_blinding: &RistrettoPoint,
+    G: &[RistrettoPoint],
+    H: &[RistrettoPoint],
+    (i_blinding, a_L, a_R): (&Scalar, &[Scalar], &[Scalar]),
+    (o_blinding, a_O): (&Scalar, &[Scalar]),
+    (s_blinding, s_L, s_R): (&Scalar, &[Scalar], &[Scalar]),
+) -> (RistrettoPoint, RistrettoPoint, RistrettoPoint) {
+    // A_I = <a_L, G> + <a_R, H> + i_blinding * B_blinding
+    let A_I = util::multiscalar_mul(
+        iter::once(i_blinding).chain(a_L.iter()).chain(a_R.iter()),
+        iter::once(B_blinding).chain(G.iter()).chain(H.iter()),
+    );
+    // A_O = <a_O, G> + o_blinding * B_blinding
+    let A_O = util::multiscalar_mul(
+        iter::once(o_blinding).chain(a_O.iter()),
+        iter::once(B_blinding).chain(G.iter()),
+    );
+    // S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
+    let S = util::multiscalar_mul(
+        iter::once(s_blinding).chain(s_L.iter()).chain(s_R.iter()),
+        iter::once(B_blinding).chain(G.iter()).chain(H.iter()),
+    );
+    (A_I, A_O, S)
+}
+
+impl<'a> fmt::Debug for Prover<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The assignments and openings are the witness; only the shape
+        // of the circuit is public.
+        f.debug_struct("Prover")
+            .field("commitments", &self.v.len())
+            .field("multipliers", &self.a_L.len())
+            .field("constraints", &self.constraints.len())
+            .field("in_second_phase", &self.in_second_phase)
+            .finish()
+    }
+}
+
+impl<'a> ConstraintSystem for Prover<'a> {
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let l = self.eval(&left);
+        let r = self.eval(&right);
+
+        let i = self.a_L.len();
+        self.a_L.push(l);
+        self.a_R.push(r);
+        self.a_O.push(l * r);
+
+        let (l_var, r_var, o_var) = (
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        );
+
+        self.constrain(left - l_var);
+        self.constrain(right - r_var);
+
+        (l_var, r_var, o_var)
+    }
+
+    fn allocate(
+        &mut self,
+        assignment: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let (l, r) = assignment.ok_or(R1CSError::MissingAssignment)?;
+
+        let i = self.a_L.len();
+        self.a_L.push(l);
+        self.a_R.push(r);
+        self.a_O.push(l * r);
+
+        Ok((
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        ))
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.constraints.push(lc);
+    }
+
+    fn challenge_scalar(&mut self, label: &[u8]) -> Scalar {
+        self.transcript.challenge_scalar_labeled(label)
+    }
+
+    fn specify_randomized_constraints(
+        &mut self,
+        callback: RandomizedConstraints,
+    ) -> Result<(), R1CSError> {
+        if self.in_second_phase {
+            return Err(R1CSError::NestedRandomizedConstraints);
+        }
+        self.deferred_constraints.push(callback);
+        Ok(())
+    }
+}
@@ -0,0 +1,462 @@
+//! The `uint` module contains gadgets for 32- and 64-bit unsigned
+//! integer arithmetic with the semantics of machine integers.
+//!
+//! Field arithmetic never overflows, so a circuit adding two amounts
+//! with plain linear combinations accepts sums that no machine integer
+//! could hold.  A `UInt` is a linear combination constrained to
+//! \\([0, 2^n)\\), and every operation on it range-constrains its
+//! output and makes the overflow explicit: the wrapping operations
+//! return the carry, borrow or high half of the result, and the checked
+//! operations constrain it to zero, so that the proof fails on
+//! overflow.
+//!
+//! Each operation takes \\(n\\) or \\(2n\\) multiplication gates for
+//! its range constraints.
+//!
+//! # Example
+//!
+//! ```ascii
+//! // Proves that the committed balance minus the committed amount is
+//! // the committed new balance, without underflow.
+//! let balance = UInt::u64(cs, balance_var.into(), balance_assignment)?;
+//! let amount = UInt::u64(cs, amount_var.into(), amount_assignment)?;
+//! let new_balance = balance.checked_sub(cs, &amount)?;
+//! gadgets::equal(cs, new_balance.lc(), new_balance_var.into());
+//! ```
+
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use super::gadgets;
+use super::{ConstraintSystem, LinearCombination, R1CSError, Variable};
+
+/// An unsigned integer of `n` bits in a constraint system.
+#[derive(Clone)]
+pub struct UInt {
+    /// The value, constrained to \\([0, 2^n)\\)
+    lc: LinearCombination,
+    /// The prover's assignment of the value
+    assignment: Option<u64>,
+    /// The number of bits
+    n: usize,
+}
+
+impl UInt {
+    /// Constrains the linear combination `lc` to be an `n`-bit integer,
+    /// using the prover's `assignment` of it.
+    ///
+    /// Uses `n` multiplication gates.  Panics if `n` is zero or more
+    /// than 64.
+    pub fn constrain<CS: ConstraintSystem + ?Sized>(
+        cs: &mut CS,
+        lc: LinearCombination,
+        assignment: Option<u64>,
+        n: usize,
+    ) -> Result<UInt, R1CSError> {
+        assert!(n > 0 && n <= 64, "integers must have between 1 and 64 bits");
+        gadgets::range(cs, lc.clone(), assignment, n)?;
+        Ok(UInt { lc, assignment, n })
+    }
+
+    /// Allocates a new `n`-bit integer with the prover's `assignment`.
+    ///
+    /// Uses `n` multiplication gates.  Panics if `n` is zero or more
+    /// than 64.
+    pub fn allocate<CS: ConstraintSystem + ?Sized>(
+        cs: &mut CS,
+        assignment: Option<u64>,
+        n: usize,
+    ) -> Result<UInt, R1CSError> {
+        assert!(n > 0 && n <= 64, "integers must have between 1 and 64 bits");
+        let mut bits = Vec::with_capacity(n);
+        for i in 0..n {
+            bits.push(gadgets::boolean(cs, assignment.map(|value| (value >> i) & 1 == 1))?);
+        }
+        Ok(UInt {
+            lc: gadgets::pack(&bits),
+            assignment,
+            n,
+        })
+    }
+
+    /// Constrains `lc` to be a 32-bit integer.
+    pub fn u32<CS: ConstraintSystem + ?Sized>(
+        cs: &mut CS,
+        lc: LinearCombination,
+        assignment: Option<u32>,
+    ) -> Result<UInt, R1CSError> {
+        UInt::constrain(cs, lc, assignment.map(|value| value as u64), 32)
+    }
+
+    /// Constrains `lc` to be a 64-bit integer.
+    pub fn u64<CS: ConstraintSystem + ?Sized>(
+        cs: &mut CS,
+        lc: LinearCombination,
+        assignment: Option<u64>,
+    ) -> Result<UInt, R1CSError> {
+        UInt::constrain(cs, lc, assignment, 64)
+    }
+
+    /// Returns the linear combination equal to the integer.
+    pub fn lc(&self) -> LinearCombination {
+        self.lc.clone()
+    }
+
+    /// Returns the prover's assignment of the integer.
+    pub fn assignment(&self) -> Option<u64> {
+        self.assignment
+    }
+
+    /// Returns the number of bits of the integer.
+    pub fn bits(&self) -> usize {
+        self.n
+    }
+
+    /// Returns \\(a + b \bmod 2^n\\) and the carry, a boolean variable
+    /// which is \\(1\\) if the sum overflowed.
+    ///
+    /// Uses \\(n + 1\\) multiplication gates.  Panics if the integers
+    /// have different sizes.
+    pub fn wrapping_add<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<(UInt, Variable), R1CSError> {
+        let n = self.same_size(other);
+        let (sum, carry) = match (self.assignment, other.assignment) {
+            (Some(a), Some(b)) => {
+                // Below 64 bits the sum cannot overflow a u64.
+                let (sum, carry) = a.overflowing_add(b);
+                if n == 64 {
+                    (Some(sum), Some(carry))
+                } else {
+                    (Some(truncate(sum, n)), Some(sum >> n == 1))
+                }
+            }
+            _ => (None, None),
+        };
+
+        // a + b = sum + carry * 2^n
+        let carry_var = gadgets::boolean(cs, carry)?;
+        let lc = self.lc() + other.lc() - carry_var * power_of_two(n);
+        Ok((UInt::constrain(cs, lc, sum, n)?, carry_var))
+    }
+
+    /// Returns \\(a + b\\), constraining the sum not to overflow.
+    pub fn checked_add<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<UInt, R1CSError> {
+        let (sum, carry) = self.wrapping_add(cs, other)?;
+        cs.constrain(carry.into());
+        Ok(sum)
+    }
+
+    /// Returns \\(a - b \bmod 2^n\\) and the borrow, a boolean variable
+    /// which is \\(1\\) if the difference underflowed.
+    ///
+    /// Uses \\(n + 1\\) multiplication gates.  Panics if the integers
+    /// have different sizes.
+    pub fn wrapping_sub<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<(UInt, Variable), R1CSError> {
+        let n = self.same_size(other);
+        let (difference, borrow) = match (self.assignment, other.assignment) {
+            (Some(a), Some(b)) => {
+                let (difference, borrow) = a.overflowing_sub(b);
+                (Some(truncate(difference, n)), Some(borrow))
+            }
+            _ => (None, None),
+        };
+
+        // a - b = difference - borrow * 2^n
+        let borrow_var = gadgets::boolean(cs, borrow)?;
+        let lc = self.lc() - other.lc() + borrow_var * power_of_two(n);
+        Ok((UInt::constrain(cs, lc, difference, n)?, borrow_var))
+    }
+
+    /// Returns \\(a - b\\), constraining the difference not to
+    /// underflow.
+    pub fn checked_sub<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<UInt, R1CSError> {
+        let (difference, borrow) = self.wrapping_sub(cs, other)?;
+        cs.constrain(borrow.into());
+        Ok(difference)
+    }
+
+    /// Returns the low and high \\(n\\) bits of the \\(2n\\)-bit product
+    /// \\(a b\\), i.e. \\(a b \bmod 2^n\\) and the overflow.
+    ///
+    /// Uses \\(2n + 1\\) multiplication gates.  Panics if the integers
+    /// have different sizes.
+    pub fn wrapping_mul<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<(UInt, UInt), R1CSError> {
+        let n = self.same_size(other);
+        let (low, high) = match (self.assignment, other.assignment) {
+            (Some(a), Some(b)) => {
+                let (low, high) = widening_mul(a, b);
+                if n == 64 {
+                    (Some(low), Some(high))
+                } else {
+                    (Some(truncate(low, n)), Some((high << (64 - n)) | (low >> n)))
+                }
+            }
+            _ => (None, None),
+        };
+
+        // a * b = low + high * 2^n, where both halves are n-bit
+        // integers, so the product cannot wrap around the field.
+        let (_, _, product) = cs.multiply(self.lc(), other.lc());
+        let high = UInt::allocate(cs, high, n)?;
+        let low = UInt::constrain(cs, product - high.lc() * power_of_two(n), low, n)?;
+        Ok((low, high))
+    }
+
+    /// Returns \\(a b\\), constraining the product not to overflow.
+    pub fn checked_mul<CS: ConstraintSystem + ?Sized>(
+        &self,
+        cs: &mut CS,
+        other: &UInt,
+    ) -> Result<UInt, R1CSError> {
+        let (low, high) = self.wrapping_mul(cs, other)?;
+        cs.constrain(high.lc());
+        Ok(low)
+    }
+
+    /// Returns the size of both integers, panicking if they differ.
+    fn same_size(&self, other: &UInt) -> usize {
+        assert_eq!(self.n, other.n, "integers must have the same size");
+        self.n
+    }
+}
+
+/// Returns the low `n` bits of `value`.
+fn truncate(value: u64, n: usize) -> u64 {
+    if n == 64 {
+        value
+    } else {
+        value & ((1u64 << n) - 1)
+    }
+}
+
+/// Returns \\(2^n\\) as a scalar.
+fn power_of_two(n: usize) -> Scalar {
+    let mut exp_2 = Scalar::one();
+    for _ in 0..n {
+        exp_2 = exp_2 + exp_2;
+    }
+    exp_2
+}
+
+/// Returns the low and high 64 bits of the product of `a` and `b`.
+fn widening_mul(a: u64, b: u64) -> (u64, u64) {
+    let (a_lo, a_hi) = (a & 0xffff_ffff, a >> 32);
+    let (b_lo, b_hi) = (b & 0xffff_ffff, b >> 32);
+
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let hi_hi = a_hi * b_hi;
+
+    // The middle terms overlap both halves; each partial sum fits in
+    // 64 bits.
+    let middle = (lo_lo >> 32) + (lo_hi & 0xffff_ffff) + (hi_lo & 0xffff_ffff);
+    let low = (middle << 32) | (lo_lo & 0xffff_ffff);
+    let high = hi_hi + (lo_hi >> 32) + (hi_lo >> 32) + (middle >> 32);
+    (low, high)
+}
+
+impl fmt::Debug for UInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The assignment is the prover's witness.
+        f.debug_struct("UInt")
+            .field("lc", &self.lc)
+            .field("n", &self.n)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use r1cs::{Prover, Verifier};
+    use rand::OsRng;
+    use secret::{Blinding, SecretScalar};
+
+    /// A statement about the committed values, which receives the
+    /// values themselves when run by the prover.
+    type Statement =
+        fn(&mut ConstraintSystem, &[Variable], Option<&[u64]>) -> Result<(), R1CSError>;
+
+    /// Runs `statement` with the committed `values` as prover and
+    /// verifier, and returns whether the proof verifies.
+    fn check(values: &[u64], statement: Statement) -> Result<(), R1CSError> {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 512, 1);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"UIntTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+        let mut commitments = Vec::new();
+        let mut vars = Vec::new();
+        for &v in values.iter() {
+            let v_blinding = Blinding::random(&mut rng);
+            let (V, var) = prover.commit(&SecretScalar::new(Scalar::from_u64(v)), &v_blinding);
+            commitments.push(V);
+            vars.push(var);
+        }
+        statement(&mut prover, &vars, Some(values))?;
+        let proof = prover.prove(&mut rng)?;
+
+        let mut transcript = ProofTranscript::new(b"UIntTest").unwrap();
+        let mut verifier = Verifier::new(generators.all(), &mut transcript);
+        let vars: Vec<Variable> = commitments.iter().map(|V| verifier.commit(*V)).collect();
+        statement(&mut verifier, &vars, None)?;
+        verifier.verify(&proof, &mut rng)
+    }
+
+    /// Constrains the first two committed values to be 32-bit integers.
+    fn u32_operands(
+        cs: &mut ConstraintSystem,
+        vars: &[Variable],
+        values: Option<&[u64]>,
+    ) -> Result<(UInt, UInt), R1CSError> {
+        let a = UInt::u32(cs, vars[0].into(), values.map(|v| v[0] as u32))?;
+        let b = UInt::u32(cs, vars[1].into(), values.map(|v| v[1] as u32))?;
+        Ok((a, b))
+    }
+
+    #[test]
+    fn add_gadgets() {
+        // vars: a, b, sum, carry
+        fn wrapping(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let (a, b) = u32_operands(cs, vars, values)?;
+            let (sum, carry) = a.wrapping_add(cs, &b)?;
+            gadgets::equal(cs, sum.lc(), vars[2].into());
+            gadgets::equal(cs, carry.into(), vars[3].into());
+            Ok(())
+        }
+        assert!(check(&[5, 7, 12, 0], wrapping).is_ok());
+        assert!(check(&[0xffff_ffff, 2, 1, 0], wrapping).is_err());
+        assert!(check(&[0xffff_ffff, 2, 1, 1], wrapping).is_ok());
+        assert!(check(&[0x1_0000_0000, 2, 2, 1], wrapping).is_err());
+
+        // vars: a, b, sum
+        fn checked(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let (a, b) = u32_operands(cs, vars, values)?;
+            let sum = a.checked_add(cs, &b)?;
+            gadgets::equal(cs, sum.lc(), vars[2].into());
+            Ok(())
+        }
+        assert!(check(&[5, 7, 12], checked).is_ok());
+        assert!(check(&[0xffff_ffff, 2, 0x1_0000_0001], checked).is_err());
+    }
+
+    #[test]
+    fn sub_gadgets() {
+        // vars: a, b, difference, borrow
+        fn wrapping(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let (a, b) = u32_operands(cs, vars, values)?;
+            let (difference, borrow) = a.wrapping_sub(cs, &b)?;
+            gadgets::equal(cs, difference.lc(), vars[2].into());
+            gadgets::equal(cs, borrow.into(), vars[3].into());
+            Ok(())
+        }
+        assert!(check(&[7, 5, 2, 0], wrapping).is_ok());
+        assert!(check(&[5, 7, 0xffff_fffe, 1], wrapping).is_ok());
+
+        // vars: a, b, difference
+        fn checked(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let (a, b) = u32_operands(cs, vars, values)?;
+            let difference = a.checked_sub(cs, &b)?;
+            gadgets::equal(cs, difference.lc(), vars[2].into());
+            Ok(())
+        }
+        assert!(check(&[7, 7, 0], checked).is_ok());
+        assert!(check(&[5, 7, 0xffff_fffe], checked).is_err());
+    }
+
+    #[test]
+    fn mul_gadgets() {
+        // vars: a, b, low, high
+        fn wrapping(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let a = UInt::u64(cs, vars[0].into(), values.map(|v| v[0]))?;
+            let b = UInt::u64(cs, vars[1].into(), values.map(|v| v[1]))?;
+            let (low, high) = a.wrapping_mul(cs, &b)?;
+            gadgets::equal(cs, low.lc(), vars[2].into());
+            gadgets::equal(cs, high.lc(), vars[3].into());
+            Ok(())
+        }
+        assert!(check(&[1 << 63, 6, 0, 3], wrapping).is_ok());
+        assert!(check(&[1 << 63, 6, 0, 2], wrapping).is_err());
+
+        // vars: a, b, product
+        fn checked(
+            cs: &mut ConstraintSystem,
+            vars: &[Variable],
+            values: Option<&[u64]>,
+        ) -> Result<(), R1CSError> {
+            let (a, b) = u32_operands(cs, vars, values)?;
+            let product = a.checked_mul(cs, &b)?;
+            gadgets::equal(cs, product.lc(), vars[2].into());
+            Ok(())
+        }
+        assert!(check(&[0xffff, 0x1_0001, 0xffff_ffff], checked).is_ok());
+        assert!(check(&[0x1_0000, 0x1_0000, 0], checked).is_err());
+    }
+
+    #[test]
+    fn widening_mul_matches_the_product() {
+        assert_eq!(widening_mul(3, 5), (15, 0));
+        assert_eq!(widening_mul(1 << 63, 6), (0, 3));
+        assert_eq!(widening_mul(u64::max_value(), u64::max_value()), (1, u64::max_value() - 1));
+    }
+    #[test]
+    fn debug_output_redacts_assignments() {
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 64, 1);
+        let mut rng = OsRng::new().unwrap();
+        let mut transcript = ProofTranscript::new(b"UIntTest").unwrap();
+        let mut prover = Prover::new(generators.all(), &mut transcript);
+
+        let v = 305_419_896;
+        let v_blinding = Blinding::random(&mut rng);
+        let (_, var) = prover.commit(&SecretScalar::new(Scalar::from_u64(v)), &v_blinding);
+        let uint = UInt::u32(&mut prover, var.into(), Some(v as u32)).unwrap();
+        assert!(!format!("{:?}", uint).contains("305419896"));
+        assert!(format!("{:?}", prover).contains("commitments: 1, multipliers: 32"));
+    }
+}
@@ -0,0 +1,324 @@
+#![allow(non_snake_case)]
+
+//! The verifier side of the constraint system proof.
+
+use core::{iter, mem};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use generators::GeneratorsView;
+use inner_product_proof::ReducedStatement;
+use proof_transcript::ProofTranscript;
+use range_proof::{RangeProof, Verifier as RangeVerifier};
+use util;
+
+use super::proof::R1CSCommitments;
+use super::{
+    check_range_bitsize, flattened_constraints, range_error, range_generators, ConstraintSystem,
+    JointProof, LinearCombination, R1CSError, R1CSProof, RandomizedConstraints, Variable,
+};
+
+/// A `ConstraintSystem` implementation for use by the verifier.
+///
+/// The verifier adds the high-level value commitments with `commit`,
+/// builds the same constraints as the prover through the
+/// `ConstraintSystem` methods, and finally checks the proof with
+/// `verify`.
+pub struct Verifier<'a> {
+    generators: GeneratorsView<'a>,
+    transcript: &'a mut ProofTranscript,
+    /// The constraints accumulated so far
+    constraints: Vec<LinearCombination>,
+    /// Records the number of multiplication gates
+    num_vars: usize,
+    /// The high-level value commitments
+    V: Vec<RistrettoPoint>,
+    /// The randomized constraints, built after the first phase
+    deferred_constraints: Vec<RandomizedConstraints>,
+    /// Whether the randomized constraints are being built
+    in_second_phase: bool,
+}
+
+impl<'a> Verifier<'a> {
+    /// Constructs a new `Verifier`, verifying against `generators`
+    /// and replaying the proof on `transcript`.
+    pub fn new(generators: GeneratorsView<'a>, transcript: &'a mut ProofTranscript) -> Self {
+        transcript.commit(b"R1CS");
+
+        Verifier {
+            generators,
+            transcript,
+            constraints: Vec::new(),
+            num_vars: 0,
+            V: Vec::new(),
+            deferred_constraints: Vec::new(),
+            in_second_phase: false,
+        }
+    }
+
+    /// Adds the value commitment `V`, returning a variable for the
+    /// committed value.
+    pub fn commit(&mut self, V: RistrettoPoint) -> Variable {
+        let i = self.V.len();
+        self.transcript.commit(V.compress().as_bytes());
+        self.V.push(V);
+
+        Variable::Committed(i)
+    }
+
+    /// Verifies the `n`-bit range proof `range_proof` for the value
+    /// commitment `V`, and adds `V`, returning a variable for the
+    /// committed value.
+    ///
+    /// This must be called at the same point as the prover's
+    /// `Prover::commit_with_range_proof`.
+    pub fn commit_with_range_proof<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        n: usize,
+        V: RistrettoPoint,
+        range_proof: &RangeProof,
+    ) -> Result<Variable, R1CSError> {
+        let range_generators = range_generators(&self.generators, n)?;
+        range_proof
+            .verify(&V, range_generators, self.transcript, rng, n)
+            .map_err(|_| R1CSError::VerificationError)?;
+
+        Ok(self.commit(V))
+    }
+
+    /// Consumes the `Verifier` to verify `proof` against the
+    /// constraints, building the randomized constraints after the
+    /// first-phase commitments.
+    pub fn verify<R: Rng>(self, proof: &R1CSProof, rng: &mut R) -> Result<(), R1CSError> {
+        let (transcript, generators, statement) = self.reduce(&proof.commitments(), rng)?;
+        let padded_n = statement.G.len();
+        statement
+            .verify(
+                &proof.ipp_proof,
+                transcript,
+                generators.pedersen_generators,
+                &generators.G[..padded_n],
+                &generators.H[..padded_n],
+            )
+            .map_err(|_| R1CSError::VerificationError)
+    }
+
+    /// Consumes the `Verifier` to verify the `JointProof` `proof` of the
+    /// constraints and of an `n`-bit range for the value committed in
+    /// `V`, created by `Prover::prove_with_range_proof`.
+    pub fn verify_with_range_proof<R: Rng>(
+        self,
+        proof: &JointProof,
+        rng: &mut R,
+        n: usize,
+        V: &RistrettoPoint,
+    ) -> Result<(), R1CSError> {
+        check_range_bitsize(n)?;
+        let (transcript, generators, circuit_statement) = self.reduce(&proof.circuit, rng)?;
+        let offset = circuit_statement.G.len();
+        let joint_n = (offset + n).next_power_of_two();
+        if generators.G.len() < joint_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+        let pedersen_generators = generators.pedersen_generators;
+
+        transcript.commit(b"JointRangeProof");
+        let range_generators = GeneratorsView {
+            pedersen_generators,
+            G: &generators.G[offset..offset + n],
+            H: &generators.H[offset..offset + n],
+        };
+        let range_statement = RangeVerifier::new(range_generators, n)
+            .map_err(range_error)?
+            .reduced_statement(&proof.range, V, transcript, rng);
+
+        let rho = transcript.challenge_scalar_labeled(b"rho");
+        let mut statement = circuit_statement.concat(range_statement, &rho);
+        statement.pad(joint_n);
+        statement
+            .verify(
+                &proof.ipp_proof,
+                transcript,
+                pedersen_generators,
+                &generators.G[..joint_n],
+                &generators.H[..joint_n],
+            )
+            .map_err(|_| R1CSError::VerificationError)
+    }
+
+    /// Replays the proof protocol for `commitments` up to, but not
+    /// including, the inner-product argument, returning the transcript,
+    /// the generators and the scalars of the verification equation
+    /// except those of the argument.
+    fn reduce<R: Rng>(
+        mut self,
+        commitments: &R1CSCommitments,
+        rng: &mut R,
+    ) -> Result<(&'a mut ProofTranscript, GeneratorsView<'a>, ReducedStatement), R1CSError> {
+        let n1 = self.num_vars;
+        let m = self.V.len();
+
+        self.transcript.commit_u64(m as u64);
+        self.transcript.commit_u64(n1 as u64);
+
+        self.transcript.commit(commitments.A_I1.compress().as_bytes());
+        self.transcript.commit(commitments.A_O1.compress().as_bytes());
+        self.transcript.commit(commitments.S1.compress().as_bytes());
+
+        self.in_second_phase = true;
+        let deferred_constraints = mem::replace(&mut self.deferred_constraints, Vec::new());
+        for mut callback in deferred_constraints.into_iter() {
+            callback(&mut self)?;
+        }
+
+        let Verifier {
+            generators,
+            transcript,
+            constraints,
+            num_vars,
+            V,
+            ..
+        } = self;
+
+        let n = num_vars;
+        let padded_n = n.next_power_of_two();
+        if generators.G.len() < padded_n {
+            return Err(R1CSError::InvalidGeneratorsLength);
+        }
+
+        transcript.commit_u64((n - n1) as u64);
+
+        transcript.commit(commitments.A_I2.compress().as_bytes());
+        transcript.commit(commitments.A_O2.compress().as_bytes());
+        transcript.commit(commitments.S2.compress().as_bytes());
+        let y = transcript.challenge_scalar_labeled(b"y");
+        let z = transcript.challenge_scalar_labeled(b"z");
+
+        transcript.commit(commitments.T_1.compress().as_bytes());
+        transcript.commit(commitments.T_3.compress().as_bytes());
+        transcript.commit(commitments.T_4.compress().as_bytes());
+        transcript.commit(commitments.T_5.compress().as_bytes());
+        transcript.commit(commitments.T_6.compress().as_bytes());
+        let u = transcript.challenge_scalar_labeled(b"u");
+        let x = transcript.challenge_scalar_labeled(b"x");
+
+        transcript.commit(commitments.t_x.as_bytes());
+        transcript.commit(commitments.t_x_blinding.as_bytes());
+        transcript.commit(commitments.e_blinding.as_bytes());
+
+        let (wL, wR, wO, wV, wc) = flattened_constraints(&constraints, &z, padded_n, m);
+
+        // Challenge value for combining the two statements to be verified
+        let c = Scalar::random(rng);
+
+        let y_inv_powers = util::ScalarPowers::new(y.invert(), padded_n);
+        let y_inv = y_inv_powers.as_slice();
+
+        // delta(y, z) = <y^-n * wR, wL>
+        let delta = (0..padded_n).fold(Scalar::zero(), |acc, i| acc + y_inv[i] * wR[i] * wL[i]);
+
+        let x_sq = x * x;
+        let x_cu = x_sq * x;
+
+        // The second-phase generators are weighted by u.
+        let u_or_one = |i: usize| if i < n1 { Scalar::one() } else { u };
+
+        let statement = ReducedStatement {
+            t_x: commitments.t_x,
+            B: c * (x_sq * (wc + delta) - commitments.t_x),
+            B_blinding: -commitments.e_blinding - c * commitments.t_x_blinding,
+            G: (0..padded_n)
+                .map(|i| u_or_one(i) * (x * y_inv[i] * wR[i]))
+                .collect(),
+            H: (0..padded_n)
+                .map(|i| u_or_one(i) * (y_inv[i] * (x * wL[i] + wO[i]) - Scalar::one()))
+                .collect(),
+            G_factors: (0..padded_n).map(u_or_one).collect(),
+            H_factors: (0..padded_n).map(|i| u_or_one(i) * y_inv[i]).collect(),
+            proof_scalars: iter::once(x) // A_I1
+                .chain(iter::once(x_sq)) // A_O1
+                .chain(iter::once(x_cu)) // S1
+                .chain(iter::once(u * x)) // A_I2
+                .chain(iter::once(u * x_sq)) // A_O2
+                .chain(iter::once(u * x_cu)) // S2
+                .chain(wV.iter().map(|wV_j| c * x_sq * wV_j)) // V
+                .chain(iter::once(c * x)) // T_1
+                .chain(iter::once(c * x_cu)) // T_3
+                .chain(iter::once(c * x_sq * x_sq)) // T_4
+                .chain(iter::once(c * x_cu * x_sq)) // T_5
+                .chain(iter::once(c * x_cu * x_cu)) // T_6
+                .collect(),
+            proof_points: iter::once(&commitments.A_I1)
+                .chain(iter::once(&commitments.A_O1))
+                .chain(iter::once(&commitments.S1))
+                .chain(iter::once(&commitments.A_I2))
+                .chain(iter::once(&commitments.A_O2))
+                .chain(iter::once(&commitments.S2))
+                .chain(V.iter())
+                .chain(iter::once(&commitments.T_1))
+                .chain(iter::once(&commitments.T_3))
+                .chain(iter::once(&commitments.T_4))
+                .chain(iter::once(&commitments.T_5))
+                .chain(iter::once(&commitments.T_6))
+                .cloned()
+                .collect(),
+        };
+        Ok((transcript, generators, statement))
+    }
+}
+
+impl<'a> ConstraintSystem for Verifier<'a> {
+    fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let (l_var, r_var, o_var) = self.allocate(None)
+            .expect("the verifier never needs assignments");
+
+        self.constrain(left - l_var);
+        self.constrain(right - r_var);
+
+        (l_var, r_var, o_var)
+    }
+
+    fn allocate(
+        &mut self,
+        _assignment: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let i = self.num_vars;
+        self.num_vars += 1;
+
+        Ok((
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        ))
+    }
+
+    fn constrain(&mut self, lc: LinearCombination) {
+        self.constraints.push(lc);
+    }
+
+    fn challenge_scalar(&mut self, label: &[u8]) -> Scalar {
+        self.transcript.challenge_scalar_labeled(label)
+    }
+
+    fn specify_randomized_constraints(
+        &mut self,
+        callback: RandomizedConstraints,
+    ) -> Result<(), R1CSError> {
+        if self.in_second_phase {
+            return Err(R1CSError::NestedRandomizedConstraints);
+        }
+        self.deferred_constraints.push(callback);
+        Ok(())
+    }
+}
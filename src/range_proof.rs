@@ -5,20 +5,60 @@
 
 use rand::Rng;
 
-use std::iter;
+use core::convert::TryFrom;
+use core::iter;
+use core::mem;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
-use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::ristretto;
-use curve25519_dalek::traits::IsIdentity;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::{Identity, IsIdentity};
 use curve25519_dalek::scalar::Scalar;
 
-use inner_product_proof::InnerProductProof;
+use subtle::{Choice, ConstantTimeEq};
+
+use sha2::{Digest, Sha512};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use inner_product_proof::{InnerProductProof, ReducedStatement, ReducedWitness};
 
 use proof_transcript::ProofTranscript;
 
 use util;
 
-use generators::GeneratorsView;
+use sizes;
+
+use bits;
+
+use profiling;
+
+use generators::{GeneratorsView, PedersenGenerators};
+
+use range_proof_transcript::{Initialized, PolyCommitted, RangeProofTranscript};
+
+use scratch::ProverScratch;
+
+use secret::{Blinding, SecretValue};
+
+use session::ProofSession;
+#[cfg(feature = "zeroize")]
+use secret;
+
+use view::ProofView;
+
+use errors::ProofError;
+
+use params::ProofParams;
+
+use backend::{DefaultBackend, MultiscalarMul};
+
+/// The number of terms of the verification equation that
+/// `Verifier::verify_fallible`, `Verifier::verify_streaming` and
+/// `Verifier::verify_view` evaluate with each multiscalar multiplication.
+const MSM_CHUNK_LEN: usize = 64;
 
 /// The `RangeProof` struct represents a single range proof.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -32,10 +72,13 @@ pub struct RangeProof {
     /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
     T_2: RistrettoPoint,
     /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
     t_x: Scalar,
     /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
     t_x_blinding: Scalar,
     /// Blinding factor for the synthetic commitment to the inner-product arguments
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
     e_blinding: Scalar,
     /// Proof data for the inner-product argument.
     ipp_proof: InnerProductProof,
@@ -45,138 +88,425 @@ impl RangeProof {
     /// Create a rangeproof for a given pair of value `v` and
     /// blinding scalar `v_blinding`.
     ///
+    /// The proving functions take the secrets only as `SecretValue`
+    /// and `Blinding`, so that they are wiped and redacted wherever the
+    /// caller keeps them.  They all return `InvalidBitsize` if `n` is
+    /// not a power of two of at most 64, and `InvalidGeneratorsLength`
+    /// if the generators do not have exactly `n` bases in each of `G`
+    /// and `H`.
+    ///
     /// Usage:
     /// ```ascii
     /// let n = 64;
-    /// let generators = Generators::new(PedersenGenerators::default(), n, 1);
-    /// let mut transcript = ProofTranscript::new(b"RangeproofTest");
+    /// let generators = Generators::new(b"MyApp", PedersenGenerators::default(), n, 1);
+    /// let mut transcript = ProofTranscript::new(b"RangeproofTest")?;
+    /// let v = SecretValue::new(1000);
+    /// let v_blinding = Blinding::random(&mut rng);
     /// let proof = RangeProof::generate_proof(
     ///     generators.share(0),
     ///     &mut transcript,
     ///     &mut rng,
     ///     n,
-    ///     v,
+    ///     &v,
     ///     &v_blinding,
-    /// );
+    /// )?;
     /// ```
     pub fn generate_proof<R: Rng>(
         generators: GeneratorsView,
         transcript: &mut ProofTranscript,
         rng: &mut R,
         n: usize,
-        v: u64,
-        v_blinding: &Scalar,
-    ) -> RangeProof {
-        use subtle::{Choice, ConditionallyAssignable};
-
-        // Commit the range size to domain-separate from rangeproofs of different lengths.
-        transcript.commit_u64(n as u64);
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        RangeProof::generate_proof_with_scratch(
+            generators,
+            transcript,
+            rng,
+            &mut ProverScratch::new(),
+            n,
+            v,
+            v_blinding,
+        )
+    }
 
-        // Create copies of G, H, so we can pass them to the
-        // (consuming) IPP API later.
-        let G = generators.G.to_vec();
-        let H = generators.H.to_vec();
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, consuming `session`, so that no other proof
+    /// can be made from the same transcript state.
+    pub fn generate_proof_in_session<R: Rng>(
+        generators: GeneratorsView,
+        session: ProofSession,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        let mut transcript = session.into_transcript();
+        RangeProof::generate_proof(generators, &mut transcript, rng, n, v, v_blinding)
+    }
 
-        let V = generators.pedersen_generators.commit(Scalar::from_u64(v), *v_blinding);
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding`, or return `ValueOutOfRange` if `v` is not
+    /// less than \\(2^n\\).
+    ///
+    /// `generate_proof` proves the low `n` bits of any `v`, which does
+    /// not verify if `v` is out of range; this fails fast instead, and
+    /// its timing reveals whether `v` was in range.
+    pub fn try_generate_proof<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        if bits::in_range(v.value(), n).unwrap_u8() == 0 {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        RangeProof::generate_proof(generators, transcript, rng, n, v, v_blinding)
+    }
 
-        let a_blinding = Scalar::random(rng);
+    /// Create a rangeproof for a given pair of value `v` and blinding
+    /// scalar `v_blinding` as `try_generate_proof`, then verify it
+    /// before returning it.
+    ///
+    /// A fault injected into the prover, or memory corruption, can
+    /// produce a proof that does not verify, or worse, one made from
+    /// corrupted secrets.  This returns `VerificationError` rather than
+    /// such a proof, for services that must not ship one.  The check
+    /// replays a copy of `transcript` from before the proof and costs
+    /// about as much as `Verifier::verify`.
+    pub fn prove_and_check<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        let mut check_transcript = transcript.fork();
+        let proof = RangeProof::try_generate_proof(generators, transcript, rng, n, v, v_blinding)?;
+
+        let V = generators.pedersen_generators.commit(v.to_scalar(), *v_blinding.as_scalar());
+        Verifier::new(generators, n)?.verify(&proof, &V, &mut check_transcript, rng)?;
+        Ok(proof)
+    }
 
-        // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding.
-        let mut A = generators.pedersen_generators.B_blinding * a_blinding;
-        for i in 0..n {
-            // If v_i = 0, we add a_L[i] * G[i] + a_R[i] * H[i] = - H[i]
-            // If v_i = 1, we add a_L[i] * G[i] + a_R[i] * H[i] =   G[i]
-            let v_i = Choice::from(((v >> i) & 1) as u8);
-            let mut point = -H[i];
-            point.conditional_assign(&G[i], v_i);
-            A += point;
-        }
+    /// Create a rangeproof for the low `n` bits of `v`, in time
+    /// independent of whether `v` is less than \\(2^n\\), and return it
+    /// with a `Choice` that is true if it is.
+    ///
+    /// If `v` is out of range, the proof is for `v` masked to its low
+    /// `n` bits, which does not verify for the commitment to `v`.  The
+    /// caller decides when to branch on the flag, so that a proving
+    /// service can respond to every request alike.
+    pub fn generate_proof_masked<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<(RangeProof, Choice), ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        let in_range = bits::in_range(v.value(), n);
+        let v_masked = SecretValue::new(v.value() & bits::range_mask(n));
+        let proof =
+            RangeProof::generate_proof(generators, transcript, rng, n, &v_masked, v_blinding)?;
+        Ok((proof, in_range))
+    }
 
-        let s_blinding = Scalar::random(rng);
-        let s_L: Vec<_> = (0..n).map(|_| Scalar::random(rng)).collect();
-        let s_R: Vec<_> = (0..n).map(|_| Scalar::random(rng)).collect();
+    /// Create a rangeproof for a value `v` committed as
+    /// \\(v B + v\_{blinding} \widetilde{B}\\) with the value generator
+    /// `B`, rather than the Pedersen base of the `generators`.
+    ///
+    /// The proof verifies with `Verifier::verify_with_value_generator`
+    /// for the same `B`.
+    pub fn generate_proof_with_value_generator<R: Rng>(
+        generators: GeneratorsView,
+        B: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        let pg = PedersenGenerators::new(*B, generators.pedersen_generators.B_blinding);
+        let generators = GeneratorsView {
+            pedersen_generators: &pg,
+            G: generators.G,
+            H: generators.H,
+        };
+        RangeProof::generate_proof(generators, transcript, rng, n, v, v_blinding)
+    }
 
-        // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding.
-        let S = ristretto::multiscalar_mul(
-            iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
-            iter::once(&generators.pedersen_generators.B_blinding).chain(G.iter()).chain(H.iter()),
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`, using the buffers in `scratch`
+    /// as working memory instead of allocating new ones.
+    pub fn generate_proof_with_scratch<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        scratch: &mut ProverScratch,
+        n: usize,
+        v: &SecretValue,
+        v_blinding: &Blinding,
+    ) -> Result<RangeProof, ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, commitments, y) = prove_commitments(
+            generators,
+            transcript,
+            rng,
+            scratch,
+            n,
+            v.value(),
+            v_blinding.as_scalar(),
         );
 
-        // Commit to V, A, S and get challenges y, z
-        transcript.commit(V.compress().as_bytes());
-        transcript.commit(A.compress().as_bytes());
-        transcript.commit(S.compress().as_bytes());
-        let y = transcript.challenge_scalar();
-        let z = transcript.challenge_scalar();
-        let zz = z * z;
+        // Get a challenge value to combine statements for the IPP
+        let (transcript, w) = transcript.commit_evaluation(
+            &commitments.t_x,
+            &commitments.t_x_blinding,
+            &commitments.e_blinding,
+        );
+        let Q = w * generators.pedersen_generators.B;
 
-        // Compute l, r
-        let mut l_poly = util::VecPoly1::zero(n);
-        let mut r_poly = util::VecPoly1::zero(n);
-        let mut exp_y = Scalar::one(); // start at y^0 = 1
-        let mut exp_2 = Scalar::one(); // start at 2^0 = 1
+        // Generate the IPP proof
+        let ProverScratch {
+            ref mut G,
+            ref mut H,
+            ref mut l_vec,
+            ref mut r_vec,
+            ..
+        } = *scratch;
+        let y_inv_powers = util::ScalarPowers::new(y.invert(), n);
+        let ipp_proof = InnerProductProof::create_in_place(
+            &DefaultBackend,
+            transcript,
+            &Q,
+            y_inv_powers.as_slice(),
+            G,
+            H,
+            l_vec,
+            r_vec,
+        )?;
+
+        Ok(RangeProof {
+            A: commitments.A,
+            S: commitments.S,
+            T_1: commitments.T_1,
+            T_2: commitments.T_2,
+            t_x: commitments.t_x,
+            t_x_blinding: commitments.t_x_blinding,
+            e_blinding: commitments.e_blinding,
+            ipp_proof,
+        })
+    }
 
-        for i in 0..n {
-            let a_L_i = Scalar::from_u64((v >> i) & 1);
-            let a_R_i = a_L_i - Scalar::one();
+    /// Runs the range proof protocol up to, but not including, the
+    /// inner-product argument, so that its final statement can be
+    /// combined with another protocol's.
+    pub(crate) fn prove_reduced<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        v: u64,
+        v_blinding: &Scalar,
+    ) -> Result<(RangeCommitments, ReducedWitness), ProofError> {
+        ProofParams::single(n)?.check_share(&generators)?;
+        let mut scratch = ProverScratch::new();
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, commitments, y) =
+            prove_commitments(generators, transcript, rng, &mut scratch, n, v, v_blinding);
+        transcript.commit_evaluation_only(
+            &commitments.t_x,
+            &commitments.t_x_blinding,
+            &commitments.e_blinding,
+        );
 
-            l_poly.0[i] = a_L_i - z;
-            l_poly.1[i] = s_L[i];
-            r_poly.0[i] = exp_y * (a_R_i + z) + zz * exp_2;
-            r_poly.1[i] = exp_y * s_R[i];
+        let witness = ReducedWitness {
+            G_factors: vec![Scalar::one(); n],
+            H_factors: util::ScalarPowers::new(y.invert(), n).as_slice().to_vec(),
+            a: mem::replace(&mut scratch.l_vec, Vec::new()).into(),
+            b: mem::replace(&mut scratch.r_vec, Vec::new()).into(),
+        };
+        Ok((commitments, witness))
+    }
 
-            exp_y *= y; // y^i -> y^(i+1)
-            exp_2 += exp_2; // 2^i -> 2^(i+1)
+    /// Assembles a range proof from the commitments of `prove_reduced`
+    /// and the inner-product argument proving its witness.
+    pub(crate) fn from_parts(
+        commitments: RangeCommitments,
+        ipp_proof: InnerProductProof,
+    ) -> RangeProof {
+        RangeProof {
+            A: commitments.A,
+            S: commitments.S,
+            T_1: commitments.T_1,
+            T_2: commitments.T_2,
+            t_x: commitments.t_x,
+            t_x_blinding: commitments.t_x_blinding,
+            e_blinding: commitments.e_blinding,
+            ipp_proof,
         }
+    }
 
-        // Compute t(x) = <l(x),r(x)>
-        let t_poly = l_poly.inner_product(&r_poly);
+    /// Returns the commitments of the proof and its inner-product argument.
+    #[cfg(any(
+        feature = "std",
+        feature = "proto",
+        feature = "cbor",
+        feature = "json",
+        feature = "proptest-strategies"
+    ))]
+    pub(crate) fn parts(&self) -> (RangeCommitments, &InnerProductProof) {
+        let commitments = RangeCommitments {
+            A: self.A,
+            S: self.S,
+            T_1: self.T_1,
+            T_2: self.T_2,
+            t_x: self.t_x,
+            t_x_blinding: self.t_x_blinding,
+            e_blinding: self.e_blinding,
+        };
+        (commitments, &self.ipp_proof)
+    }
 
-        // Form commitments T_1, T_2 to t.1, t.2
-        let t_1_blinding = Scalar::random(rng);
-        let t_2_blinding = Scalar::random(rng);
-        let T_1 = generators.pedersen_generators.commit(t_poly.1, t_1_blinding);
-        let T_2 = generators.pedersen_generators.commit(t_poly.2, t_2_blinding);
+    /// Returns the commitment \\(A\\) to the bits of the value.
+    pub fn A(&self) -> &RistrettoPoint {
+        &self.A
+    }
 
-        // Commit to T_1, T_2 to get the challenge point x
-        transcript.commit(T_1.compress().as_bytes());
-        transcript.commit(T_2.compress().as_bytes());
-        let x = transcript.challenge_scalar();
+    /// Returns the commitment \\(S\\) to the blinding factors.
+    pub fn S(&self) -> &RistrettoPoint {
+        &self.S
+    }
 
-        // Evaluate t at x and run the IPP
-        let t_x = t_poly.eval(x);
-        let t_x_blinding = zz * v_blinding + x * (t_1_blinding + x * t_2_blinding);
-        let e_blinding = a_blinding + x * s_blinding;
+    /// Returns the commitment \\(T\_1\\) to the \\(t\_1\\) coefficient
+    /// of \\(t(x)\\).
+    pub fn T_1(&self) -> &RistrettoPoint {
+        &self.T_1
+    }
 
-        transcript.commit(t_x.as_bytes());
-        transcript.commit(t_x_blinding.as_bytes());
-        transcript.commit(e_blinding.as_bytes());
+    /// Returns the commitment \\(T\_2\\) to the \\(t\_2\\) coefficient
+    /// of \\(t(x)\\).
+    pub fn T_2(&self) -> &RistrettoPoint {
+        &self.T_2
+    }
 
-        // Get a challenge value to combine statements for the IPP
-        let w = transcript.challenge_scalar();
-        let Q = w * generators.pedersen_generators.B;
+    /// Returns the evaluation \\(t(x)\\) at the challenge point.
+    pub fn t_x(&self) -> &Scalar {
+        &self.t_x
+    }
 
-        // Generate the IPP proof
-        let ipp_proof = InnerProductProof::create(
-            transcript,
-            &Q,
-            util::exp_iter(y.invert()),
-            G,
-            H,
-            l_poly.eval(x),
-            r_poly.eval(x),
-        );
+    /// Returns the blinding factor \\(\tilde{t}(x)\\) of the synthetic
+    /// commitment to \\(t(x)\\).
+    pub fn t_x_blinding(&self) -> &Scalar {
+        &self.t_x_blinding
+    }
 
-        RangeProof {
-            A,
-            S,
-            T_1,
-            T_2,
-            t_x,
-            t_x_blinding,
-            e_blinding,
-            ipp_proof,
+    /// Returns the blinding factor \\(\tilde{e}\\) of the synthetic
+    /// commitment to the inner-product arguments.
+    pub fn e_blinding(&self) -> &Scalar {
+        &self.e_blinding
+    }
+
+    /// Returns the inner-product argument.
+    ///
+    /// The value commitment \\(V\\) is not part of the proof; verifiers
+    /// receive it separately.
+    pub fn ipp_proof(&self) -> &InnerProductProof {
+        &self.ipp_proof
+    }
+
+    /// Returns the length of `to_bytes` for a proof of an `n`-bit range,
+    /// \\(32 (9 + 2 \lg n)\\) bytes, for a power of two `n` of at most 64.
+    ///
+    /// As a `const fn` this can size a buffer at compile time, e.g.
+    /// `[0u8; RangeProof::serialized_size(64)]`.
+    pub const fn serialized_size(n: usize) -> usize {
+        sizes::range_proof_size(n)
+    }
+
+    /// Serializes the proof into `buf`, in the format of `to_bytes` but
+    /// without allocating.
+    ///
+    /// Returns an error if `buf` is not exactly as long as the encoding.
+    pub fn write_bytes(&self, buf: &mut [u8]) -> Result<(), ProofError> {
+        if buf.len() != 7 * 32 + (2 * self.ipp_proof.L_vec.len() + 2) * 32 {
+            return Err(ProofError::FormatError);
+        }
+        let (head, ipp) = buf.split_at_mut(7 * 32);
+        let points = [self.A, self.S, self.T_1, self.T_2];
+        for (chunk, P) in head.chunks_mut(32).zip(points.iter()) {
+            chunk.copy_from_slice(P.compress().as_bytes());
+        }
+        let scalars = [self.t_x, self.t_x_blinding, self.e_blinding];
+        for (chunk, s) in head[4 * 32..].chunks_mut(32).zip(scalars.iter()) {
+            chunk.copy_from_slice(s.as_bytes());
         }
+        self.ipp_proof.write_bytes(ipp);
+        Ok(())
+    }
+
+    /// Serializes the proof as the 32-byte encodings of
+    /// \\(A, S, T\_1, T\_2, t(x), \tilde{t}(x), \tilde{e}\\), followed by
+    /// the inner-product argument's `to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(7 * 32 + (2 * self.ipp_proof.L_vec.len() + 2) * 32);
+        for P in [self.A, self.S, self.T_1, self.T_2].iter() {
+            buf.extend_from_slice(P.compress().as_bytes());
+        }
+        for s in [self.t_x, self.t_x_blinding, self.e_blinding].iter() {
+            buf.extend_from_slice(s.as_bytes());
+        }
+        buf.extend_from_slice(&self.ipp_proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes a proof serialized with `to_bytes`.
+    ///
+    /// Returns an error if the length is wrong, or if a point or scalar
+    /// is not a canonical encoding.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        if slice.len() < 7 * 32 {
+            return Err(ProofError::FormatError);
+        }
+        let chunk = |i: usize| &slice[i * 32..(i + 1) * 32];
+        let point = |i: usize| util::read_point(chunk(i));
+        let scalar = |i: usize| util::read_scalar(chunk(i));
+        Ok(RangeProof {
+            A: point(0)?,
+            S: point(1)?,
+            T_1: point(2)?,
+            T_2: point(3)?,
+            t_x: scalar(4)?,
+            t_x_blinding: scalar(5)?,
+            e_blinding: scalar(6)?,
+            ipp_proof: InnerProductProof::from_bytes(&slice[7 * 32..])?,
+        })
+    }
+
+    /// Replays the transcript of the proof for the commitment `V`,
+    /// returning the challenges \\(y, z, x, w\\) followed by the
+    /// inner-product challenges \\(u\_j\\) in creation order.
+    #[cfg(feature = "test-vectors")]
+    pub(crate) fn challenges(
+        &self,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        n: usize,
+    ) -> Vec<Scalar> {
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &self.A, &self.S);
+        let (transcript, x) = transcript.commit_poly(&self.T_1, &self.T_2);
+        let (transcript, w) =
+            transcript.commit_evaluation(&self.t_x, &self.t_x_blinding, &self.e_blinding);
+        let (u, _, _) = self.ipp_proof.challenges(transcript);
+        vec![y, z, x, w].into_iter().chain(u).collect()
     }
 
     /// Verifies a rangeproof for a given value commitment \\(V\\).
@@ -184,8 +514,8 @@ impl RangeProof {
     /// Usage:
     /// ```ascii
     /// let n = 64;
-    /// let generators = Generators::new(PedersenGenerators::default(), n, 1);
-    /// let mut transcript = ProofTranscript::new(b"RangeproofTest");
+    /// let generators = Generators::new(b"MyApp", PedersenGenerators::default(), n, 1);
+    /// let mut transcript = ProofTranscript::new(b"RangeproofTest")?;
     /// proof.verify(
     ///     &V,
     ///     generators.share(0),
@@ -201,102 +531,1143 @@ impl RangeProof {
         transcript: &mut ProofTranscript,
         rng: &mut R,
         n: usize,
-    ) -> Result<(), ()> {
-        // First, replay the "interactive" protocol using the proof
-        // data to recompute all challenges.
+    ) -> Result<(), ProofError> {
+        Verifier::new(gens, n)?.verify(self, V, transcript, rng)
+    }
+
+    /// Feeds the encoding of every field of the proof into `hash`.
+    pub(crate) fn hash_into(&self, hash: &mut Sha512) {
+        for P in [self.A, self.S, self.T_1, self.T_2].iter() {
+            hash.input(P.compress().as_bytes());
+        }
+        for s in [self.t_x, self.t_x_blinding, self.e_blinding].iter() {
+            hash.input(s.as_bytes());
+        }
+        for points in [&self.ipp_proof.L_vec, &self.ipp_proof.R_vec].iter() {
+            let mut len_bytes = [0u8; 8];
+            LittleEndian::write_u64(&mut len_bytes, points.len() as u64);
+            hash.input(&len_bytes);
+            for P in points.iter() {
+                hash.input(P.compress().as_bytes());
+            }
+        }
+        hash.input(self.ipp_proof.a.as_bytes());
+        hash.input(self.ipp_proof.b.as_bytes());
+    }
+}
+
+/// Runs the range proof protocol for `v` up to the evaluation of
+/// \\(t(x)\\), returning the commitments and the challenge \\(y\\).
+///
+/// On return, `scratch` holds the generators and the vectors
+/// \\(l(x)\\), \\(r(x)\\) for the inner-product argument.
+fn prove_commitments<'a, R: Rng>(
+    generators: GeneratorsView,
+    transcript: RangeProofTranscript<'a, Initialized>,
+    rng: &mut R,
+    scratch: &mut ProverScratch,
+    n: usize,
+    v: u64,
+    v_blinding: &Scalar,
+) -> (RangeProofTranscript<'a, PolyCommitted>, RangeCommitments, Scalar) {
+    use subtle::ConditionallyAssignable;
+
+    // Copy G, H into the scratch space, so the IPP can use them
+    // as working memory later.
+    scratch.reset(&generators, n);
+    let ProverScratch {
+        ref mut G,
+        ref mut H,
+        ref mut s_L,
+        ref mut s_R,
+        ref mut l_poly,
+        ref mut r_poly,
+        ref mut l_vec,
+        ref mut r_vec,
+    } = *scratch;
+
+    let V = generators.pedersen_generators.commit(Scalar::from_u64(v), *v_blinding);
+
+    // Derive every nonce from the transcript state and the witness as
+    // well as from `rng`, so that a repeated or weak RNG output cannot
+    // produce two proofs sharing nonces, which would leak the witness.
+    let mut v_bytes = [0u8; 8];
+    LittleEndian::write_u64(&mut v_bytes, v);
+    let mut nonces = transcript.build_rng(&[&v_bytes, v_blinding.as_bytes()], rng);
+    let rng = &mut nonces;
+    #[cfg(feature = "zeroize")]
+    secret::wipe_bytes(&mut v_bytes);
+
+    // The blinding factors are wiped on return with the `zeroize` feature.
+    let a_blinding = Blinding::random(rng);
+
+    // Compute A = <a_L, G> + <a_R, H> + a_blinding * B_blinding.
+    let mut A = generators.pedersen_generators.B_blinding * a_blinding.as_scalar();
+    for i in 0..n {
+        // If v_i = 0, we add a_L[i] * G[i] + a_R[i] * H[i] = - H[i]
+        // If v_i = 1, we add a_L[i] * G[i] + a_R[i] * H[i] =   G[i]
+        let v_i = bits::bit_choice(v, i);
+        let mut point = -H[i];
+        point.conditional_assign(&G[i], v_i);
+        A += point;
+    }
+    profiling::record_point_adds(n);
+
+    let s_blinding = Blinding::random(rng);
+    for s_L_i in s_L.iter_mut() {
+        *s_L_i = Scalar::random(rng);
+    }
+    for s_R_i in s_R.iter_mut() {
+        *s_R_i = Scalar::random(rng);
+    }
+
+    // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding.
+    let S = util::multiscalar_mul(
+        iter::once(s_blinding.as_scalar()).chain(s_L.iter()).chain(s_R.iter()),
+        iter::once(&generators.pedersen_generators.B_blinding).chain(G.iter()).chain(H.iter()),
+    );
+
+    // Commit to V, A, S and get challenges y, z
+    let (transcript, y, z) = transcript.commit_bits(&V, &A, &S);
+    let zz = z * z;
+
+    // Compute l, r
+    let y_powers = util::ScalarPowers::new(y, n);
+    let two_powers = util::ScalarPowers::new(Scalar::from_u64(2), n);
+
+    // The bits are selected in constant time, rather than branched on.
+    #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+    let (mut a_L, mut a_R) = bits::bit_vectors(v, n);
+    for i in 0..n {
+        let a_L_i = a_L[i];
+        let a_R_i = a_R[i];
+        let exp_y = y_powers.as_slice()[i];
+
+        l_poly.0[i] = a_L_i - z;
+        l_poly.1[i] = s_L[i];
+        r_poly.0[i] = exp_y * (a_R_i + z) + zz * two_powers.as_slice()[i];
+        r_poly.1[i] = exp_y * s_R[i];
+    }
+
+    // Compute t(x) = <l(x),r(x)>
+    let t_poly = l_poly.inner_product(r_poly);
+
+    // Form commitments T_1, T_2 to t.1, t.2
+    let t_1_blinding = Blinding::random(rng);
+    let t_2_blinding = Blinding::random(rng);
+    let T_1 = generators.pedersen_generators.commit(t_poly.1, *t_1_blinding.as_scalar());
+    let T_2 = generators.pedersen_generators.commit(t_poly.2, *t_2_blinding.as_scalar());
+
+    // Commit to T_1, T_2 to get the challenge point x
+    let (transcript, x) = transcript.commit_poly(&T_1, &T_2);
+
+    // Evaluate t, l and r at x
+    let t_x = t_poly.eval(x);
+    let t_x_blinding =
+        zz * v_blinding + x * (t_1_blinding.as_scalar() + x * t_2_blinding.as_scalar());
+    let e_blinding = a_blinding.as_scalar() + x * s_blinding.as_scalar();
+    l_poly.eval_into(x, l_vec);
+    r_poly.eval_into(x, r_vec);
+    #[cfg(feature = "zeroize")]
+    {
+        secret::wipe_scalars(&mut a_L);
+        secret::wipe_scalars(&mut a_R);
+    }
+
+    let commitments = RangeCommitments {
+        A,
+        S,
+        T_1,
+        T_2,
+        t_x,
+        t_x_blinding,
+        e_blinding,
+    };
+    (transcript, commitments, y)
+}
+
+/// The commitments and evaluations of a range proof, without its
+/// inner-product argument.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RangeCommitments {
+    /// Commitment to the bits of the value
+    pub(crate) A: RistrettoPoint,
+    /// Commitment to the blinding factors
+    pub(crate) S: RistrettoPoint,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(crate) T_1: RistrettoPoint,
+    /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
+    pub(crate) T_2: RistrettoPoint,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\(t(x)\\)
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the inner-product arguments
+    #[serde(deserialize_with = "::util::deserialize_scalar")]
+    pub(crate) e_blinding: Scalar,
+}
+
+/// The `Verifier` struct holds everything needed to verify `n`-bit
+/// range proofs that depends only on the parameters, so that it can be
+/// computed once and reused for many proofs.
+///
+/// Usage:
+/// ```ascii
+/// let n = 64;
+/// let generators = Generators::new(b"MyApp", PedersenGenerators::default(), n, 1);
+/// let verifier = Verifier::new(generators.share(0), n)?;
+/// for (proof, V) in proofs {
+///     let mut transcript = ProofTranscript::new(b"RangeproofTest")?;
+///     verifier.verify(&proof, &V, &mut transcript, &mut rng)?;
+/// }
+/// ```
+pub struct Verifier<'a, M = DefaultBackend> {
+    /// Multiscalar multiplication implementation
+    backend: M,
+    /// Generators for the proofs being verified
+    generators: GeneratorsView<'a>,
+    /// The checked bitsize of the proofs
+    params: ProofParams,
+    /// The powers \\(\mathbf{2}^n\\)
+    two_powers: util::ScalarPowers,
+    /// The sum \\(\langle \mathbf{1}, \mathbf{2}^n \rangle\\)
+    sum_of_powers_of_2: Scalar,
+    /// Whether degenerate proofs are rejected
+    strict: bool,
+}
+
+impl<'a> Verifier<'a, DefaultBackend> {
+    /// Precomputes the parameter-dependent data for verifying `n`-bit
+    /// range proofs with the given generators.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not a power of two of at most
+    /// 64, and `InvalidGeneratorsLength` if the generators do not have
+    /// exactly `n` bases in each of `G` and `H`.
+    pub fn new(generators: GeneratorsView<'a>, n: usize) -> Result<Self, ProofError> {
+        Verifier::with_backend(generators, n, DefaultBackend)
+    }
+
+    /// Precomputes the data for verifying range proofs of the bitsize of
+    /// `params` with the given share of generators.
+    ///
+    /// Returns `InvalidGeneratorsLength` if the share does not have
+    /// exactly `params.n()` bases in each of `G` and `H`.
+    pub fn with_params(
+        generators: GeneratorsView<'a>,
+        params: &ProofParams,
+    ) -> Result<Self, ProofError> {
+        Verifier::new(generators, params.n())
+    }
+}
+
+impl<'a, M: MultiscalarMul> Verifier<'a, M> {
+    /// Precomputes the parameter-dependent data for verifying `n`-bit
+    /// range proofs with the given generators, using `backend` for the
+    /// multiscalar multiplication.
+    ///
+    /// Returns the errors of `new`.  The parameters are checked only
+    /// here, so that the verification methods need not recheck them.
+    pub fn with_backend(
+        generators: GeneratorsView<'a>,
+        n: usize,
+        backend: M,
+    ) -> Result<Self, ProofError> {
+        let params = ProofParams::single(n)?;
+        params.check_share(&generators)?;
+        let two_powers = util::ScalarPowers::new(Scalar::from_u64(2), n);
+        let sum_of_powers_of_2 = two_powers.sum();
+        Ok(Verifier {
+            backend,
+            generators,
+            params,
+            two_powers,
+            sum_of_powers_of_2,
+            strict: false,
+        })
+    }
+
+    /// Makes the verifier strict, rejecting degenerate proofs with
+    /// `DegenerateProof` even if they verify.
+    ///
+    /// A proof whose value commitment \\(V\\) or one of \\(A, S, T\_1,
+    /// T\_2\\) is the identity, or whose transcript produces a zero
+    /// challenge, can still be valid: the identity is a commitment to
+    /// zero with a zero blinding factor, and a zero challenge has
+    /// negligible but nonzero probability.  An honest prover produces
+    /// such a proof with negligible probability, though, so seeing one
+    /// means the prover chose its randomness badly or searched for it,
+    /// and chains that treat any proof as consensus data may prefer to
+    /// exclude them.  Strictness applies to every verification method,
+    /// and to proofs accumulated or batched with this verifier, but not
+    /// to `verify_with_diagnostics`.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment \\(V\\).
+    pub fn verify<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let terms = self.verification_terms(proof, V, transcript, rng)?;
+        self.check(terms, &self.generators.pedersen_generators.B)
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment
+    /// \\(V\\), consuming `session`.
+    pub fn verify_in_session<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        session: ProofSession,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.verify(proof, V, &mut session.into_transcript(), rng)
+    }
+
+    /// Verifies a rangeproof `proof` for a value commitment \\(V\\)
+    /// whose value generator is `B` rather than the Pedersen base of
+    /// the generators, e.g. the generator of an asset.
+    ///
+    /// The proof must have been made with `B` as the value generator,
+    /// as by `RangeProof::generate_proof_with_value_generator`.  A
+    /// single `Verifier` serves proofs for any number of value
+    /// generators.
+    pub fn verify_with_value_generator<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        B: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let terms = self.verification_terms(proof, V, transcript, rng)?;
+        self.check(terms, B)
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment \\(V\\),
+    /// in time independent of which parts of the proof are correct.
+    ///
+    /// `verify` evaluates the verification equation with a
+    /// variable-time multiscalar multiplication, whose running time
+    /// depends on the proof's scalars.  This performs the same
+    /// computation with the constant-time multiscalar multiplication of
+    /// the `backend`, and compares the result to the identity in
+    /// constant time, so that a service verifying untrusted proofs does
+    /// not reveal through its timing how close a forgery came.  Only the
+    /// final result is branched on, and it is slower than `verify`.
+    pub fn verify_uniform_time<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let terms = self.verification_terms(proof, V, transcript, rng)?;
+        let (scalars, points) = self.mega_check(terms, &self.generators.pedersen_generators.B);
+        let mega_check = self.backend.multiscalar_mul(&scalars, &points)?;
+
+        if mega_check.ct_eq(&RistrettoPoint::identity()).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies a rangeproof `proof` for the value commitment \\(V\\)
+    /// shipped with it, and that \\(V\\) is the caller's `expected`
+    /// commitment, in time independent of whether either holds.
+    ///
+    /// Rather than comparing the commitments up front, which would
+    /// reveal through an early return whether a forged proof got the
+    /// commitment right, the difference \\(V - V\_{expected}\\) is
+    /// added with a random weight to the verification equation, as in
+    /// `verify_uniform_time`, so that the final constant-time check
+    /// fails if either the proof or the commitment is wrong.
+    pub fn verify_expected_commitment<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        expected: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let terms = self.verification_terms(proof, V, transcript, rng)?;
+        let (mut scalars, mut points) =
+            self.mega_check(terms, &self.generators.pedersen_generators.B);
+        scalars.push(Scalar::random(rng));
+        points.push(V - expected);
+        let mega_check = self.backend.multiscalar_mul(&scalars, &points)?;
+
+        if mega_check.ct_eq(&RistrettoPoint::identity()).unwrap_u8() == 1 {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment
+    /// \\(V\\) and reports which of its checks failed, for debugging
+    /// proofs from other implementations.
+    ///
+    /// `verify` checks a random combination of the check of \\(t(x)\\)
+    /// against \\(V, T\_1, T\_2\\) and of the inner-product argument
+    /// against \\(A, S\\), which fails without saying which part was
+    /// wrong; this evaluates the two separately.  The inner-product
+    /// argument has a single final check, so its failure cannot be
+    /// attributed to one of its rounds.  The report and the timing
+    /// reveal more about a proof than `verify` does, so this is meant
+    /// for debugging, not for verifying untrusted proofs.
+    pub fn verify_with_diagnostics(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+    ) -> VerificationReport {
+        let mut report = VerificationReport {
+            expected_rounds: self.params.rounds(),
+            rounds: proof.ipp_proof.L_vec.len(),
+            polynomial_check: false,
+            inner_product_check: false,
+        };
+        if self.check_bitsize(proof).is_err() {
+            return report;
+        }
 
-        transcript.commit_u64(n as u64);
-        transcript.commit(V.compress().as_bytes());
-        transcript.commit(self.A.compress().as_bytes());
-        transcript.commit(self.S.compress().as_bytes());
+        let B = &self.generators.pedersen_generators.B;
+        let evaluate = |terms: VerificationTerms| {
+            let (scalars, points) = self.mega_check(terms, B);
+            self.backend.vartime_multiscalar_mul(&scalars, &points)
+        };
+        // With weight zero only the inner-product check remains, and
+        // with weight one the difference is the check of t(x).
+        let mut ipp_transcript = transcript.fork();
+        let terms = (
+            self.weighted_verification_terms(proof, V, &mut ipp_transcript, Scalar::zero()),
+            self.weighted_verification_terms(proof, V, transcript, Scalar::one()),
+        );
+        let (ipp_terms, all_terms) = match terms {
+            (Ok(ipp_terms), Ok(all_terms)) => (ipp_terms, all_terms),
+            _ => return report,
+        };
+        let (ipp_check, all_check) = match (evaluate(ipp_terms), evaluate(all_terms)) {
+            (Ok(ipp_check), Ok(all_check)) => (ipp_check, all_check),
+            _ => return report,
+        };
+        let polynomial_check = all_check - ipp_check;
+
+        report.polynomial_check = polynomial_check.is_identity();
+        report.inner_product_check = ipp_check.is_identity();
+        report
+    }
+
+    /// Checks what can be checked of `proof` before its verification
+    /// equation: that it is a proof of an `n`-bit range, and, if the
+    /// verifier is strict, that it is not degenerate.  The `transcript`
+    /// is left unchanged.
+    pub(crate) fn precheck(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &ProofTranscript,
+    ) -> Result<(), ProofError> {
+        self.check_bitsize(proof)?;
+        if self.strict {
+            self.check_degenerate(proof, V, transcript)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `DegenerateProof` if \\(V\\), \\(A\\), \\(S\\), \\(T\_1\\) or
+    /// \\(T\_2\\) is the identity, or if replaying a copy of `transcript`
+    /// for `proof` produces a zero challenge.
+    fn check_degenerate(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &ProofTranscript,
+    ) -> Result<(), ProofError> {
+        let points = [V, &proof.A, &proof.S, &proof.T_1, &proof.T_2];
+        if points.iter().any(|P| P.is_identity()) {
+            return Err(ProofError::DegenerateProof);
+        }
+
+        let mut transcript = transcript.fork();
+        let transcript = RangeProofTranscript::new(&mut transcript, self.params.n());
+        let (transcript, y, z) = transcript.commit_bits(V, &proof.A, &proof.S);
+        let (transcript, x) = transcript.commit_poly(&proof.T_1, &proof.T_2);
+        let (transcript, w) =
+            transcript.commit_evaluation(&proof.t_x, &proof.t_x_blinding, &proof.e_blinding);
+        let (u, _, _) = proof.ipp_proof.challenges(transcript);
+        let zero = Scalar::zero();
+        if [y, z, x, w].iter().chain(u.iter()).any(|c| *c == zero) {
+            return Err(ProofError::DegenerateProof);
+        }
+        Ok(())
+    }
+
+    /// Returns `InvalidBitsize` if `proof` is not a proof of an `n`-bit
+    /// range.
+    fn check_bitsize(&self, proof: &RangeProof) -> Result<(), ProofError> {
+        proof
+            .ipp_proof
+            .check_rounds(self.params.n())
+            .map_err(|_| ProofError::InvalidBitsize)
+    }
+
+    /// Checks that the verification equation `terms` holds for the
+    /// value generator `B`.
+    fn check(&self, terms: VerificationTerms, B: &RistrettoPoint) -> Result<(), ProofError> {
+        let (scalars, points) = self.mega_check(terms, B);
+        let mega_check = self.backend.vartime_multiscalar_mul(&scalars, &points)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the scalars and points of the verification equation
+    /// `terms` for the value generator `B`.
+    fn mega_check(
+        &self,
+        terms: VerificationTerms,
+        B: &RistrettoPoint,
+    ) -> (Vec<Scalar>, Vec<RistrettoPoint>) {
+        let gens = &self.generators;
+
+        let mega_check_scalars: Vec<Scalar> = iter::once(terms.B)
+            .chain(iter::once(terms.B_blinding))
+            .chain(terms.G.into_iter())
+            .chain(terms.H.into_iter())
+            .chain(terms.proof_scalars.into_iter())
+            .collect();
+        let mega_check_points: Vec<RistrettoPoint> = iter::once(B)
+            .chain(iter::once(&gens.pedersen_generators.B_blinding))
+            .chain(gens.G.iter())
+            .chain(gens.H.iter())
+            .chain(terms.proof_points.iter())
+            .cloned()
+            .collect();
+
+        (mega_check_scalars, mega_check_points)
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment \\(V\\),
+    /// without materializing any of the length-\\(n\\) scalar vectors.
+    ///
+    /// The powers of \\(y^{-1}\\) and \\(2\\) and the inner product
+    /// scalars \\(s\_i\\) are generated on demand, and the equation is
+    /// evaluated in chunks of `MSM_CHUNK_LEN` terms pulled from those
+    /// iterators, so that beyond the \\(O(\lg n)\\) challenges the memory
+    /// used does not grow with \\(n\\).  This costs \\(O(\lg n)\\) scalar
+    /// multiplications per \\(s\_i\\) and one multiscalar multiplication
+    /// per chunk instead of a single large one, which suits
+    /// memory-constrained verifiers.  It always uses the built-in
+    /// variable-time multiscalar multiplication, not the `backend`.
+    pub fn verify_streaming<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let n = self.params.n();
+        let gens = &self.generators;
+
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &proof.A, &proof.S);
+        let zz = z * z;
+        let minus_z = -z;
+        let (transcript, x) = transcript.commit_poly(&proof.T_1, &proof.T_2);
+        let (transcript, w) =
+            transcript.commit_evaluation(&proof.t_x, &proof.t_x_blinding, &proof.e_blinding);
+
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+
+        let (x_sq, x_inv_sq, s) = proof.ipp_proof.verification_scalars_lazy(transcript, n)?;
+
+        let a = proof.ipp_proof.a;
+        let b = proof.ipp_proof.b;
+
+        let sum_of_powers_of_y = util::exp_iter(y)
+            .take(n)
+            .fold(Scalar::zero(), |acc, y_i| acc + y_i);
+
+        let g = s.clone().map(|s_i| minus_z - a * s_i);
+        let h = s.rev()
+            .zip(util::exp_iter(Scalar::from_u64(2)))
+            .zip(util::exp_iter(y.invert()))
+            .map(|((s_i_inv, exp_2), exp_y_inv)| z + exp_y_inv * (zz * exp_2 - b * s_i_inv));
+
+        profiling::record_scalar_muls(7 + 2 * n + x_sq.len() + x_inv_sq.len());
+        let mega_check = chunked_multiscalar_mul(
+            iter::once(Scalar::one())
+                .chain(iter::once(x))
+                .chain(iter::once(c * zz))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(iter::once(
+                    w * (proof.t_x - a * b)
+                        + c * (delta_from_sums(&sum_of_powers_of_y, &self.sum_of_powers_of_2, &z)
+                            - proof.t_x),
+                ))
+                .chain(iter::once(-proof.e_blinding - c * proof.t_x_blinding))
+                .chain(g)
+                .chain(h)
+                .chain(x_sq.iter().cloned())
+                .chain(x_inv_sq.iter().cloned()),
+            iter::once(&proof.A)
+                .chain(iter::once(&proof.S))
+                .chain(iter::once(V))
+                .chain(iter::once(&proof.T_1))
+                .chain(iter::once(&proof.T_2))
+                .chain(iter::once(&gens.pedersen_generators.B))
+                .chain(iter::once(&gens.pedersen_generators.B_blinding))
+                .chain(gens.G.iter())
+                .chain(gens.H.iter())
+                .chain(proof.ipp_proof.L_vec.iter())
+                .chain(proof.ipp_proof.R_vec.iter())
+                .cloned(),
+        );
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies a rangeproof `proof` for a given value commitment
+    /// \\(V\\), returning `OutOfMemory` rather than aborting the process
+    /// if the memory for its verification equation cannot be allocated.
+    ///
+    /// The \\(2n + 2 \lg n + 7\\) scalars and points of the equation are
+    /// stored in vectors reserved with `try_reserve`, and the equation
+    /// is evaluated in chunks of `MSM_CHUNK_LEN` terms, so that the
+    /// multiscalar multiplication's working memory does not grow with
+    /// \\(n\\).  This suits verification services under memory pressure,
+    /// at the cost of one multiscalar multiplication per chunk.
+    pub fn verify_fallible<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.precheck(proof, V, transcript)?;
+        let n = self.params.n();
+        let gens = &self.generators;
+
+        let len = 7 + 2 * n + 2 * proof.ipp_proof.L_vec.len();
+        let mut scalars: Vec<Scalar> = Vec::new();
+        let mut points: Vec<RistrettoPoint> = Vec::new();
+        scalars.try_reserve(len).map_err(|_| ProofError::OutOfMemory)?;
+        points.try_reserve(len).map_err(|_| ProofError::OutOfMemory)?;
+
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &proof.A, &proof.S);
+        let zz = z * z;
+        let minus_z = -z;
+        let (transcript, x) = transcript.commit_poly(&proof.T_1, &proof.T_2);
+        let (transcript, w) =
+            transcript.commit_evaluation(&proof.t_x, &proof.t_x_blinding, &proof.e_blinding);
+
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+
+        let (x_sq, x_inv_sq, s) = proof.ipp_proof.verification_scalars_lazy(transcript, n)?;
+
+        let a = proof.ipp_proof.a;
+        let b = proof.ipp_proof.b;
+
+        let sum_of_powers_of_y = util::exp_iter(y)
+            .take(n)
+            .fold(Scalar::zero(), |acc, y_i| acc + y_i);
+
+        let g = s.clone().map(|s_i| minus_z - a * s_i);
+        let h = s.rev()
+            .zip(util::exp_iter(Scalar::from_u64(2)))
+            .zip(util::exp_iter(y.invert()))
+            .map(|((s_i_inv, exp_2), exp_y_inv)| z + exp_y_inv * (zz * exp_2 - b * s_i_inv));
+
+        // The vectors have room for every term, so neither reallocates.
+        scalars.extend(
+            iter::once(Scalar::one())
+                .chain(iter::once(x))
+                .chain(iter::once(c * zz))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(iter::once(
+                    w * (proof.t_x - a * b)
+                        + c * (delta_from_sums(&sum_of_powers_of_y, &self.sum_of_powers_of_2, &z)
+                            - proof.t_x),
+                ))
+                .chain(iter::once(-proof.e_blinding - c * proof.t_x_blinding))
+                .chain(g)
+                .chain(h)
+                .chain(x_sq.iter().cloned())
+                .chain(x_inv_sq.iter().cloned()),
+        );
+        points.extend(
+            iter::once(&proof.A)
+                .chain(iter::once(&proof.S))
+                .chain(iter::once(V))
+                .chain(iter::once(&proof.T_1))
+                .chain(iter::once(&proof.T_2))
+                .chain(iter::once(&gens.pedersen_generators.B))
+                .chain(iter::once(&gens.pedersen_generators.B_blinding))
+                .chain(gens.G.iter())
+                .chain(gens.H.iter())
+                .chain(proof.ipp_proof.L_vec.iter())
+                .chain(proof.ipp_proof.R_vec.iter())
+                .cloned(),
+        );
+
+        let mut mega_check = RistrettoPoint::identity();
+        let chunks = scalars
+            .chunks(MSM_CHUNK_LEN)
+            .zip(points.chunks(MSM_CHUNK_LEN));
+        for (scalars, points) in chunks {
+            mega_check = mega_check + self.backend.vartime_multiscalar_mul(scalars, points)?;
+        }
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Verifies the range proof borrowed by `view` for a given value
+    /// commitment \\(V\\), with the same result as `verify` on the
+    /// decoded proof.
+    ///
+    /// The inner-product argument's points are decoded from the view as
+    /// they are streamed into the multiscalar multiplication, as in
+    /// `verify_streaming`, so the proof is never copied.
+    pub fn verify_view<R: Rng>(
+        &self,
+        view: &ProofView,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let n = self.params.n();
+        if view.n() != n {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let gens = &self.generators;
+        let (A, S, T_1, T_2) = (view.point(0), view.point(1), view.point(2), view.point(3));
+        let (t_x, t_x_blinding, e_blinding) = (view.scalar(4), view.scalar(5), view.scalar(6));
 
-        let y = transcript.challenge_scalar();
-        let z = transcript.challenge_scalar();
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &A, &S);
         let zz = z * z;
         let minus_z = -z;
-
-        transcript.commit(self.T_1.compress().as_bytes());
-        transcript.commit(self.T_2.compress().as_bytes());
-
-        let x = transcript.challenge_scalar();
-
-        transcript.commit(self.t_x.as_bytes());
-        transcript.commit(self.t_x_blinding.as_bytes());
-        transcript.commit(self.e_blinding.as_bytes());
-
-        let w = transcript.challenge_scalar();
+        let (transcript, x) = transcript.commit_poly(&T_1, &T_2);
+        let (transcript, w) = transcript.commit_evaluation(&t_x, &t_x_blinding, &e_blinding);
 
         // Challenge value for batching statements to be verified
         let c = Scalar::random(rng);
 
-        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(transcript);
-        let s_inv = s.iter().rev();
+        let (x_sq, x_inv_sq, s) = view.ipp_verification_scalars(transcript);
+        let (a, b) = view.ipp_scalars();
+
+        // The squares of the challenges are zero exactly when they are.
+        if self.strict
+            && ([V, &A, &S, &T_1, &T_2].iter().any(|P| P.is_identity())
+                || [y, z, x, w].iter().chain(x_sq.iter()).any(|c| *c == Scalar::zero()))
+        {
+            return Err(ProofError::DegenerateProof);
+        }
 
-        let a = self.ipp_proof.a;
-        let b = self.ipp_proof.b;
+        let sum_of_powers_of_y = util::exp_iter(y)
+            .take(n)
+            .fold(Scalar::zero(), |acc, y_i| acc + y_i);
 
-        let g = s.iter().map(|s_i| minus_z - a * s_i);
-        let h = s_inv
+        let g = s.clone().map(|s_i| minus_z - a * s_i);
+        let h = s.rev()
             .zip(util::exp_iter(Scalar::from_u64(2)))
             .zip(util::exp_iter(y.invert()))
             .map(|((s_i_inv, exp_2), exp_y_inv)| z + exp_y_inv * (zz * exp_2 - b * s_i_inv));
 
-        let mega_check = ristretto::vartime::multiscalar_mul(
+        profiling::record_scalar_muls(7 + 2 * n + x_sq.len() + x_inv_sq.len());
+        let mega_check = chunked_multiscalar_mul(
             iter::once(Scalar::one())
                 .chain(iter::once(x))
                 .chain(iter::once(c * zz))
                 .chain(iter::once(c * x))
                 .chain(iter::once(c * x * x))
                 .chain(iter::once(
-                    w * (self.t_x - a * b) + c * (delta(n, &y, &z) - self.t_x),
+                    w * (t_x - a * b)
+                        + c * (delta_from_sums(&sum_of_powers_of_y, &self.sum_of_powers_of_2, &z)
+                            - t_x),
                 ))
-                .chain(iter::once(-self.e_blinding - c * self.t_x_blinding))
+                .chain(iter::once(-e_blinding - c * t_x_blinding))
                 .chain(g)
                 .chain(h)
                 .chain(x_sq.iter().cloned())
                 .chain(x_inv_sq.iter().cloned()),
-            iter::once(&self.A)
-                .chain(iter::once(&self.S))
-                .chain(iter::once(V))
-                .chain(iter::once(&self.T_1))
-                .chain(iter::once(&self.T_2))
-                .chain(iter::once(&gens.pedersen_generators.B))
-                .chain(iter::once(&gens.pedersen_generators.B_blinding))
+            [A, S, *V, T_1, T_2, gens.pedersen_generators.B, gens.pedersen_generators.B_blinding]
+                .iter()
                 .chain(gens.G.iter())
                 .chain(gens.H.iter())
-                .chain(self.ipp_proof.L_vec.iter())
-                .chain(self.ipp_proof.R_vec.iter()),
+                .cloned()
+                .chain(view.ipp_points()),
         );
 
         if mega_check.is_identity() {
             Ok(())
         } else {
-            Err(())
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the generators the proofs are verified against.
+    pub(crate) fn generators(&self) -> &GeneratorsView<'a> {
+        &self.generators
+    }
+
+    /// Returns the multiscalar multiplication implementation.
+    pub(crate) fn backend(&self) -> &M {
+        &self.backend
+    }
+
+    /// Replays the transcript of `proof` and computes the scalars of
+    /// its verification equation, which must sum to the identity.
+    pub(crate) fn verification_terms<R: Rng>(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Result<VerificationTerms, ProofError> {
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+        self.weighted_verification_terms(proof, V, transcript, c)
+    }
+
+    /// Computes the scalars of the verification equation of `proof` as
+    /// `verification_terms`, with the check of \\(t(x)\\) weighted by `c`
+    /// against the inner-product check.
+    fn weighted_verification_terms(
+        &self,
+        proof: &RangeProof,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        c: Scalar,
+    ) -> Result<VerificationTerms, ProofError> {
+        let n = self.params.n();
+
+        // First, replay the "interactive" protocol using the proof
+        // data to recompute all challenges.
+
+        let transcript = RangeProofTranscript::new(transcript, n);
+
+        let (transcript, y, z) = transcript.commit_bits(V, &proof.A, &proof.S);
+        let zz = z * z;
+        let minus_z = -z;
+
+        let (transcript, x) = transcript.commit_poly(&proof.T_1, &proof.T_2);
+
+        let (transcript, w) =
+            transcript.commit_evaluation(&proof.t_x, &proof.t_x_blinding, &proof.e_blinding);
+
+        let (x_sq, x_inv_sq, s) = proof.ipp_proof.verification_scalars(transcript, n)?;
+        #[cfg(not(feature = "parallel"))]
+        let s_inv = s.iter().rev();
+        #[cfg(feature = "parallel")]
+        let s_inv = {
+            use rayon::prelude::*;
+            s.par_iter().rev()
+        };
+
+        let a = proof.ipp_proof.a;
+        let b = proof.ipp_proof.b;
+
+        let two_powers = &self.two_powers;
+        let y_inv_powers = util::ScalarPowers::new(y.invert(), n);
+        let sum_of_powers_of_y = util::ScalarPowers::new(y, n).sum();
+
+        #[cfg(not(feature = "parallel"))]
+        let (g, h) = {
+            let g: Vec<Scalar> = s.iter().map(|s_i| minus_z - a * s_i).collect();
+            let h: Vec<Scalar> = s_inv
+                .zip(two_powers.as_slice().iter())
+                .zip(y_inv_powers.as_slice().iter())
+                .map(|((s_i_inv, exp_2), exp_y_inv)| z + exp_y_inv * (zz * exp_2 - b * s_i_inv))
+                .collect();
+            (g, h)
+        };
+
+        // The scalars are computed independently of each other, so the
+        // parallel expansion produces exactly the same values.
+        #[cfg(feature = "parallel")]
+        let (g, h) = {
+            use rayon::prelude::*;
+
+            let g: Vec<Scalar> = s.par_iter().map(|s_i| minus_z - a * s_i).collect();
+            let h: Vec<Scalar> = s_inv
+                .zip(two_powers.as_slice().par_iter())
+                .zip(y_inv_powers.as_slice().par_iter())
+                .map(|((s_i_inv, exp_2), exp_y_inv)| z + exp_y_inv * (zz * exp_2 - b * s_i_inv))
+                .collect();
+            (g, h)
+        };
+
+        Ok(VerificationTerms {
+            B: w * (proof.t_x - a * b)
+                + c * (delta_from_sums(&sum_of_powers_of_y, &self.sum_of_powers_of_2, &z)
+                    - proof.t_x),
+            B_blinding: -proof.e_blinding - c * proof.t_x_blinding,
+            G: g,
+            H: h,
+            proof_scalars: iter::once(Scalar::one())
+                .chain(iter::once(x))
+                .chain(iter::once(c * zz))
+                .chain(iter::once(c * x))
+                .chain(iter::once(c * x * x))
+                .chain(x_sq.iter().cloned())
+                .chain(x_inv_sq.iter().cloned())
+                .collect(),
+            proof_points: iter::once(&proof.A)
+                .chain(iter::once(&proof.S))
+                .chain(iter::once(V))
+                .chain(iter::once(&proof.T_1))
+                .chain(iter::once(&proof.T_2))
+                .chain(proof.ipp_proof.L_vec.iter())
+                .chain(proof.ipp_proof.R_vec.iter())
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Replays the transcript of the range proof `commitments`, whose
+    /// inner-product argument is combined with another protocol's, and
+    /// computes the scalars of its verification equation except those
+    /// of the argument.
+    pub(crate) fn reduced_statement<R: Rng>(
+        &self,
+        commitments: &RangeCommitments,
+        V: &RistrettoPoint,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> ReducedStatement {
+        let n = self.params.n();
+
+        let transcript = RangeProofTranscript::new(transcript, n);
+        let (transcript, y, z) = transcript.commit_bits(V, &commitments.A, &commitments.S);
+        let zz = z * z;
+        let (transcript, x) = transcript.commit_poly(&commitments.T_1, &commitments.T_2);
+        transcript.commit_evaluation_only(
+            &commitments.t_x,
+            &commitments.t_x_blinding,
+            &commitments.e_blinding,
+        );
+
+        // Challenge value for batching statements to be verified
+        let c = Scalar::random(rng);
+
+        let y_inv_powers = util::ScalarPowers::new(y.invert(), n);
+        let sum_of_powers_of_y = util::ScalarPowers::new(y, n).sum();
+
+        ReducedStatement {
+            t_x: commitments.t_x,
+            B: c * (delta_from_sums(&sum_of_powers_of_y, &self.sum_of_powers_of_2, &z)
+                - commitments.t_x),
+            B_blinding: -commitments.e_blinding - c * commitments.t_x_blinding,
+            G: vec![-z; n],
+            H: self.two_powers
+                .as_slice()
+                .iter()
+                .zip(y_inv_powers.as_slice().iter())
+                .map(|(exp_2, exp_y_inv)| z + exp_y_inv * zz * exp_2)
+                .collect(),
+            G_factors: vec![Scalar::one(); n],
+            H_factors: y_inv_powers.as_slice().to_vec(),
+            proof_scalars: vec![Scalar::one(), x, c * zz, c * x, c * x * x],
+            proof_points: vec![commitments.A, commitments.S, *V, commitments.T_1, commitments.T_2],
+        }
+    }
+}
+
+/// The outcome of each check of `Verifier::verify_with_diagnostics`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// The number of inner-product rounds for the verifier's bitsize
+    pub expected_rounds: usize,
+    /// The number of inner-product rounds of the proof
+    pub rounds: usize,
+    /// Whether \\(t(x)\\) and its blinding factor open
+    /// \\(z^2 V + \delta(y, z) B + x T\_1 + x^2 T\_2\\)
+    pub polynomial_check: bool,
+    /// Whether the inner-product argument proves that
+    /// \\(\langle l(x), r(x) \rangle = t(x)\\) for \\(A\\) and \\(S\\)
+    pub inner_product_check: bool,
+}
+
+impl VerificationReport {
+    /// Returns whether the proof verifies.
+    pub fn is_ok(&self) -> bool {
+        self.rounds == self.expected_rounds && self.polynomial_check && self.inner_product_check
+    }
+
+    /// Returns the result `verify` gives for the proof.
+    pub fn to_result(&self) -> Result<(), ProofError> {
+        if self.rounds != self.expected_rounds {
+            Err(ProofError::InvalidBitsize)
+        } else if !self.is_ok() {
+            Err(ProofError::VerificationError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The scalars of a proof's verification equation, split into those
+/// multiplying the generators shared by all proofs and those
+/// multiplying points specific to this proof.
+pub(crate) struct VerificationTerms {
+    /// Scalar for the Pedersen base \\(B\\)
+    pub(crate) B: Scalar,
+    /// Scalar for the Pedersen blinding base \\(\widetilde{B}\\)
+    pub(crate) B_blinding: Scalar,
+    /// Scalars for the generators \\(\mathbf{G}\\)
+    pub(crate) G: Vec<Scalar>,
+    /// Scalars for the generators \\(\mathbf{H}\\)
+    pub(crate) H: Vec<Scalar>,
+    /// Scalars for `proof_points`
+    pub(crate) proof_scalars: Vec<Scalar>,
+    /// \\(A, S, V, T\_1, T\_2\\) followed by the IPP's \\(L\_j\\) and \\(R\_j\\)
+    pub(crate) proof_points: Vec<RistrettoPoint>,
+}
+
+/// Implements conversions between `RangeProof` and the fixed-size
+/// arrays holding the encodings of proofs of each supported bitsize.
+macro_rules! impl_fixed_size_conversions {
+    ($($n:expr => $size:expr),*) => {$(
+        impl<'a> TryFrom<&'a RangeProof> for [u8; $size] {
+            type Error = ProofError;
+
+            /// Serializes a proof of an `n`-bit range as `to_bytes` does.
+            /// Returns an error if the proof is of another bitsize.
+            fn try_from(proof: &'a RangeProof) -> Result<Self, ProofError> {
+                let mut buf = [0u8; $size];
+                proof.write_bytes(&mut buf[..])?;
+                Ok(buf)
+            }
+        }
+
+        impl<'a> TryFrom<&'a [u8; $size]> for RangeProof {
+            type Error = ProofError;
+
+            /// Deserializes a proof of an `n`-bit range.
+            fn try_from(bytes: &'a [u8; $size]) -> Result<Self, ProofError> {
+                RangeProof::from_bytes(&bytes[..])
+            }
         }
+    )*}
+}
+
+impl_fixed_size_conversions!(8 => 480, 16 => 544, 32 => 608, 64 => 672);
+
+impl ConstantTimeEq for RangeCommitments {
+    fn ct_eq(&self, other: &RangeCommitments) -> Choice {
+        self.A.ct_eq(&other.A)
+            & self.S.ct_eq(&other.S)
+            & self.T_1.ct_eq(&other.T_1)
+            & self.T_2.ct_eq(&other.T_2)
+            & self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+    }
+}
+
+impl ConstantTimeEq for RangeProof {
+    fn ct_eq(&self, other: &RangeProof) -> Choice {
+        self.A.ct_eq(&other.A)
+            & self.S.ct_eq(&other.S)
+            & self.T_1.ct_eq(&other.T_1)
+            & self.T_2.ct_eq(&other.T_2)
+            & self.t_x.ct_eq(&other.t_x)
+            & self.t_x_blinding.ct_eq(&other.t_x_blinding)
+            & self.e_blinding.ct_eq(&other.e_blinding)
+            & self.ipp_proof.ct_eq(&other.ipp_proof)
+    }
+}
+
+impl PartialEq for RangeProof {
+    fn eq(&self, other: &RangeProof) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
     }
 }
 
+impl Eq for RangeProof {}
+
 /// Compute
 /// \\[
 /// \delta(y,z) = (z - z^{2}) \langle 1, {\mathbf{y}}^{n} \rangle + z^{3} \langle \mathbf{1}, {\mathbf{2}}^{n} \rangle
 /// \\]
+#[cfg(test)]
 fn delta(n: usize, y: &Scalar, z: &Scalar) -> Scalar {
     let two = Scalar::from_u64(2);
 
     // XXX this could be more efficient, esp for powers of 2
-    let sum_of_powers_of_y = util::exp_iter(*y)
-        .take(n)
-        .fold(Scalar::zero(), |acc, x| acc + x);
+    let sum_of_powers_of_y = util::ScalarPowers::new(*y, n).sum();
+    let sum_of_powers_of_2 = util::ScalarPowers::new(two, n).sum();
 
-    let sum_of_powers_of_2 = util::exp_iter(two)
-        .take(n)
-        .fold(Scalar::zero(), |acc, x| acc + x);
+    delta_from_sums(&sum_of_powers_of_y, &sum_of_powers_of_2, z)
+}
 
+/// Compute \\(\delta(y,z)\\) from precomputed sums
+/// \\(\langle 1, {\mathbf{y}}^{n} \rangle\\) and \\(\langle \mathbf{1}, {\mathbf{2}}^{n} \rangle\\).
+pub(crate) fn delta_from_sums(sum_of_powers_of_y: &Scalar, sum_of_powers_of_2: &Scalar, z: &Scalar) -> Scalar {
     let zz = z * z;
 
     (z - zz) * sum_of_powers_of_y - z * zz * sum_of_powers_of_2
 }
 
+/// Computes the multiscalar multiplication of `scalars` and `points` in
+/// chunks of `MSM_CHUNK_LEN` terms, pulling each chunk from the
+/// iterators only when it is multiplied, so that neither the terms nor
+/// the multiplication's working memory grow with their number.
+fn chunked_multiscalar_mul<I, J>(scalars: I, points: J) -> RistrettoPoint
+where
+    I: IntoIterator<Item = Scalar>,
+    J: IntoIterator<Item = RistrettoPoint>,
+{
+    let mut terms = scalars.into_iter().zip(points);
+    let mut chunk_scalars = Vec::with_capacity(MSM_CHUNK_LEN);
+    let mut chunk_points = Vec::with_capacity(MSM_CHUNK_LEN);
+    let mut sum = RistrettoPoint::identity();
+    loop {
+        chunk_scalars.clear();
+        chunk_points.clear();
+        for (s, P) in terms.by_ref().take(MSM_CHUNK_LEN) {
+            chunk_scalars.push(s);
+            chunk_points.push(P);
+        }
+        if chunk_scalars.is_empty() {
+            return sum;
+        }
+        sum = sum + ristretto::vartime::multiscalar_mul(&chunk_scalars, &chunk_points);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,7 +1714,7 @@ mod tests {
 
         // Both prover and verifier have access to the generators and the proof
         use generators::{PedersenGenerators,Generators};
-        let generators = Generators::new(PedersenGenerators::default(), n, 1);
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
 
         // Serialized proof data
         let proof_bytes: Vec<u8>;
@@ -352,7 +1723,7 @@ mod tests {
         // Prover's scope
         {
             // Use a customization label for testing proofs
-            let mut transcript = ProofTranscript::new(b"RangeproofTest");
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
             let mut rng = OsRng::new().unwrap();
 
             let v: u64 = rng.gen_range(0, (1 << (n - 1)) - 1);
@@ -363,9 +1734,9 @@ mod tests {
                 &mut transcript,
                 &mut rng,
                 n,
-                v,
-                &v_blinding,
-            );
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
 
             // 2. Serialize
             proof_bytes = bincode::serialize(&range_proof).unwrap();
@@ -387,7 +1758,7 @@ mod tests {
             let mut rng = OsRng::new().unwrap();
 
             // 4. Use the same customization label as above to verify
-            let mut transcript = ProofTranscript::new(b"RangeproofTest");
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
             assert!(
                 range_proof
                     .verify(
@@ -400,7 +1771,7 @@ mod tests {
             );
 
             // Verification with a different label fails
-            let mut transcript = ProofTranscript::new(b"");
+            let mut transcript = ProofTranscript::without_domain();
             assert!(
                 range_proof
                     .verify(
@@ -414,6 +1785,592 @@ mod tests {
         }
     }
 
+    /// The verifier folds the \\(t(x)\\) check, the \\(A, S, V\\) check
+    /// and the inner product check into a single multiscalar
+    /// multiplication, so corrupting any single component must still
+    /// make it fail.
+    #[test]
+    fn combined_check_covers_every_component() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v = 12345u64;
+        let v_blinding = Scalar::random(&mut rng);
+        let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(v), v_blinding);
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(v),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let corruptions: Vec<fn(&mut RangeProof)> = vec![
+            |p: &mut RangeProof| p.t_x += Scalar::one(),
+            |p: &mut RangeProof| p.t_x_blinding += Scalar::one(),
+            |p: &mut RangeProof| p.e_blinding += Scalar::one(),
+            |p: &mut RangeProof| p.T_1 = p.T_1 + p.T_2,
+            |p: &mut RangeProof| p.A = p.A + p.S,
+            |p: &mut RangeProof| p.ipp_proof.a += Scalar::one(),
+            |p: &mut RangeProof| p.ipp_proof.b += Scalar::one(),
+        ];
+
+        for corrupt in corruptions {
+            let mut bad_proof = proof.clone();
+            corrupt(&mut bad_proof);
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(
+                bad_proof
+                    .verify(&V, generators.share(0), &mut transcript, &mut rng, n)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn precomputed_verifier_is_reusable() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let mut rng = OsRng::new().unwrap();
+
+        for v in 0..4u64 {
+            let v_blinding = Scalar::random(&mut rng);
+            let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(v), v_blinding);
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify(&proof, &V, &mut transcript, &mut rng).is_ok());
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify_streaming(&proof, &V, &mut transcript, &mut rng).is_ok());
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify_fallible(&proof, &V, &mut transcript, &mut rng).is_ok());
+
+            let wrong_V = V + generators.share(0).pedersen_generators.B;
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify_streaming(&proof, &wrong_V, &mut transcript, &mut rng).is_err());
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify_fallible(&proof, &wrong_V, &mut transcript, &mut rng).is_err());
+        }
+    }
+
+    #[test]
+    fn misconfigured_provers_and_verifiers_return_errors() {
+        use generators::{PedersenGenerators, Generators};
+
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), 8, 1);
+        let wide = Generators::new(b"Test", PedersenGenerators::default(), 16, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(3), v_blinding);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            8,
+            &SecretValue::new(3),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let bytes = proof.to_bytes();
+        let view = ProofView::from_bytes(&bytes).unwrap();
+
+        for &(ref generators, n, err) in [
+            (&generators, 24, ProofError::InvalidBitsize),
+            (&wide, 8, ProofError::InvalidGeneratorsLength),
+        ].iter()
+        {
+            assert_eq!(Verifier::new(generators.share(0), n).err(), Some(err));
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let result = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(3),
+                &Blinding::new(v_blinding),
+            );
+            assert_eq!(result, Err(err));
+        }
+
+        // A 16-bit verifier expects the four rounds of a 16-bit proof.
+        let verifier = Verifier::new(wide.share(0), 16).unwrap();
+        let err = Err(ProofError::InvalidBitsize);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(verifier.verify(&proof, &V, &mut transcript, &mut rng), err);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(verifier.verify_streaming(&proof, &V, &mut transcript, &mut rng), err);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(verifier.verify_view(&view, &V, &mut transcript, &mut rng), err);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let report = verifier.verify_with_diagnostics(&proof, &V, &mut transcript);
+        assert!(!report.polynomial_check && !report.inner_product_check);
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(
+            proof.verify(&V, generators.share(0), &mut transcript, &mut rng, 128),
+            Err(ProofError::InvalidBitsize)
+        );
+    }
+
+    #[test]
+    fn proofs_compare_in_constant_time() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof_1 = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(7),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof_2 = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(7),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        assert_eq!(proof_1.ct_eq(&proof_1.clone()).unwrap_u8(), 1);
+        assert_eq!(proof_1.ct_eq(&proof_2).unwrap_u8(), 0);
+        assert_eq!(proof_1, proof_1.clone());
+        assert_ne!(proof_1, proof_2);
+    }
+
+    #[test]
+    fn proofs_convert_to_and_from_fixed_size_arrays() {
+        use generators::{PedersenGenerators, Generators};
+
+        assert_eq!(RangeProof::serialized_size(8), 480);
+        assert_eq!(RangeProof::serialized_size(64), 672);
+
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(9),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let bytes = <[u8; RangeProof::serialized_size(16)]>::try_from(&proof).unwrap();
+        assert_eq!(&bytes[..], &proof.to_bytes()[..]);
+        assert_eq!(RangeProof::try_from(&bytes).unwrap(), proof);
+        assert!(<[u8; 480]>::try_from(&proof).is_err());
+    }
+
+    #[test]
+    fn accessors_expose_the_encoded_components() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(2),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(&bytes[..32], proof.A().compress().as_bytes());
+        assert_eq!(&bytes[3 * 32..4 * 32], proof.T_2().compress().as_bytes());
+        assert_eq!(&bytes[4 * 32..5 * 32], proof.t_x().as_bytes());
+        assert_eq!(proof.ipp_proof().L_vec().len(), 3);
+        assert_eq!(&bytes[bytes.len() - 32..], proof.ipp_proof().b().as_bytes());
+    }
+
+    #[test]
+    fn every_parser_rejects_invalid_points_and_scalars() {
+        use bincode;
+        use envelope::ProofEnvelope;
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(5),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let bytes = proof.to_bytes();
+
+        let mut bad_point = bytes.clone();
+        bad_point[32..64].copy_from_slice(&[0xff; 32]);
+        let mut bad_scalar = bytes.clone();
+        bad_scalar[5 * 32..6 * 32].copy_from_slice(&[0xff; 32]);
+        let mut bad_ipp_point = bytes.clone();
+        bad_ipp_point[7 * 32..8 * 32].copy_from_slice(&[0xff; 32]);
+
+        for &(ref encoding, err) in [
+            (bad_point, ProofError::InvalidPoint),
+            (bad_scalar, ProofError::InvalidScalar),
+            (bad_ipp_point, ProofError::InvalidPoint),
+        ].iter()
+        {
+            assert_eq!(RangeProof::from_bytes(encoding), Err(err));
+            assert_eq!(ProofView::from_bytes(encoding).unwrap_err(), err);
+            let hex: String = encoding.iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(hex.parse::<RangeProof>(), Err(err));
+
+            let mut envelope = ProofEnvelope::new(n, vec![proof.clone()]).unwrap().to_bytes();
+            let len = envelope.len();
+            envelope[len - encoding.len()..].copy_from_slice(encoding);
+            assert_eq!(ProofEnvelope::from_bytes(&envelope).unwrap_err(), err);
+        }
+
+        // Adding the group order to t_x gives the same scalar modulo the
+        // order, in a non-canonical encoding that serde must not accept.
+        let l: [u8; 32] = [
+            0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
+            0xde, 0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+        ];
+        let t_x = proof.t_x().as_bytes();
+        let mut unreduced = [0u8; 32];
+        let mut carry = 0u16;
+        for ((u, &a), &b) in unreduced.iter_mut().zip(t_x.iter()).zip(l.iter()) {
+            let sum = u16::from(a) + u16::from(b) + carry;
+            *u = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut serialized = bincode::serialize(&proof).unwrap();
+        let offset = serialized.windows(32).position(|w| w == &t_x[..]).unwrap();
+        serialized[offset..offset + 32].copy_from_slice(&unreduced);
+        assert!(bincode::deserialize::<RangeProof>(&serialized).is_err());
+    }
+
+    #[test]
+    fn uniform_time_verification_agrees_with_verify() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(3),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let pg = generators.share(0).pedersen_generators;
+        let V = pg.commit(Scalar::from_u64(3), v_blinding);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        for &(ref V, ok) in [(V, true), (V + pg.B, false)].iter() {
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert_eq!(
+                verifier.verify_uniform_time(&proof, V, &mut transcript, &mut rng).is_ok(),
+                ok
+            );
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert_eq!(verifier.verify(&proof, V, &mut transcript, &mut rng).is_ok(), ok);
+        }
+    }
+
+    #[test]
+    fn checked_proofs_verify() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let pg = generators.share(0).pedersen_generators;
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::prove_and_check(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(9),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let V = pg.commit(Scalar::from_u64(9), v_blinding);
+        assert!(proof.verify(&V, generators.share(0), &mut transcript, &mut rng, n).is_ok());
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(
+            RangeProof::prove_and_check(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(256),
+                &Blinding::new(v_blinding),
+            ).unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+    }
+
+    #[test]
+    fn strict_verifiers_reject_degenerate_proofs() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let strict = Verifier::new(generators.share(0), n).unwrap().strict();
+
+        // A commitment to zero with a zero blinding factor is the identity.
+        for &(v, v_blinding, strict_result) in [
+            (5, Scalar::from_u64(7), Ok(())),
+            (0, Scalar::zero(), Err(ProofError::DegenerateProof)),
+        ].iter()
+        {
+            let pg = generators.share(0).pedersen_generators;
+            let V = pg.commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(verifier.verify(&proof, &V, &mut transcript, &mut rng).is_ok());
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert_eq!(strict.verify(&proof, &V, &mut transcript, &mut rng), strict_result);
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let bytes = proof.to_bytes();
+            let view = ProofView::from_bytes(&bytes).unwrap();
+            assert_eq!(strict.verify_view(&view, &V, &mut transcript, &mut rng), strict_result);
+        }
+    }
+
+    #[test]
+    fn expected_commitments_are_checked_in_the_final_check() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(3),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let pg = generators.share(0).pedersen_generators;
+        let V = pg.commit(Scalar::from_u64(3), v_blinding);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let cases = [(V, V, true), (V, V + pg.B, false), (V + pg.B, V + pg.B, false)];
+        for &(ref V, ref expected, ok) in cases.iter() {
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let result =
+                verifier.verify_expected_commitment(&proof, V, expected, &mut transcript, &mut rng);
+            assert_eq!(result.is_ok(), ok);
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_fail_fast_or_are_masked() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let pg = generators.share(0).pedersen_generators;
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        assert_eq!(
+            RangeProof::try_generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(256),
+                &Blinding::new(v_blinding),
+            ).unwrap_err(),
+            ProofError::ValueOutOfRange
+        );
+
+        for &(v, in_range) in [(255u64, 1u8), (256 + 7, 0)].iter() {
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let (proof, flag) = RangeProof::generate_proof_masked(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+            assert_eq!(flag.unwrap_u8(), in_range);
+
+            let V = pg.commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let verified = verifier.verify(&proof, &V, &mut transcript, &mut rng);
+            assert_eq!(verified.is_ok(), in_range == 1);
+        }
+    }
+
+    #[test]
+    fn sessions_are_consumed_by_one_proof() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut context = ProofTranscript::new(b"RangeproofTest").unwrap();
+        context.commit(b"context");
+        let proof = RangeProof::generate_proof_in_session(
+            generators.share(0),
+            ProofSession::from_transcript(context.fork()),
+            &mut rng,
+            n,
+            &SecretValue::new(4),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(4), v_blinding);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let session = ProofSession::from_transcript(context);
+        assert!(verifier.verify_in_session(&proof, &V, session, &mut rng).is_ok());
+        let session = ProofSession::new(b"RangeproofTest").unwrap();
+        assert!(verifier.verify_in_session(&proof, &V, session, &mut rng).is_err());
+    }
+
+    #[test]
+    fn diagnostics_report_the_failing_check() {
+        use generators::{PedersenGenerators, Generators};
+
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(6),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+        let pg = generators.share(0).pedersen_generators;
+        let V = pg.commit(Scalar::from_u64(6), v_blinding);
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let report = |proof: &RangeProof, V: &RistrettoPoint| {
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            verifier.verify_with_diagnostics(proof, V, &mut transcript)
+        };
+
+        assert!(report(&proof, &V).is_ok());
+        assert_eq!(report(&proof, &V).to_result(), Ok(()));
+
+        // The wrong V changes every challenge, so t(x) no longer matches.
+        let wrong_V = report(&proof, &(V + pg.B));
+        assert!(!wrong_V.polynomial_check);
+        assert_eq!(wrong_V.to_result(), Err(ProofError::VerificationError));
+
+        let mut wrong_ipp = proof.clone();
+        wrong_ipp.ipp_proof.a = wrong_ipp.ipp_proof.a + Scalar::one();
+        let wrong_ipp = report(&wrong_ipp, &V);
+        assert!(wrong_ipp.polynomial_check);
+        assert!(!wrong_ipp.inner_product_check);
+
+        let mut wrong_rounds = proof.clone();
+        wrong_rounds.ipp_proof.L_vec.pop();
+        wrong_rounds.ipp_proof.R_vec.pop();
+        let wrong_rounds = report(&wrong_rounds, &V);
+        assert_eq!((wrong_rounds.expected_rounds, wrong_rounds.rounds), (3, 2));
+        assert_eq!(wrong_rounds.to_result(), Err(ProofError::InvalidBitsize));
+    }
+
+    #[test]
+    fn scratch_is_reusable_across_proofs() {
+        use generators::{PedersenGenerators, Generators};
+
+        let mut rng = OsRng::new().unwrap();
+        let mut scratch = ProverScratch::new();
+
+        for &n in [16, 8, 32].iter() {
+            let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+            let v: u64 = rng.gen_range(0, (1 << (n - 1)) - 1);
+            let v_blinding = Scalar::random(&mut rng);
+
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            let proof = RangeProof::generate_proof_with_scratch(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                &mut scratch,
+                n,
+                &SecretValue::new(v),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+
+            let V = generators.share(0).pedersen_generators.commit(Scalar::from_u64(v), v_blinding);
+            let mut transcript = ProofTranscript::new(b"RangeproofTest").unwrap();
+            assert!(
+                proof
+                    .verify(&V, generators.share(0), &mut transcript, &mut rng, n)
+                    .is_ok()
+            );
+        }
+    }
+
     #[test]
     fn create_and_verify_8() {
         create_and_verify_helper(8);
@@ -0,0 +1,167 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `range_proof_transcript` module contains a phase-typed wrapper
+//! around `ProofTranscript` for the range proof protocol.
+//!
+//! Each phase of the protocol is a distinct type, and moving from one
+//! phase to the next consumes the wrapper.  This makes it a compile
+//! error to request a challenge before the data it depends on has been
+//! committed, or to commit the same phase twice.
+
+use core::marker::PhantomData;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand::Rng;
+
+use proof_transcript::{ProofTranscript, TranscriptRng};
+
+/// Version of the range proof transcript schedule.
+///
+/// This is committed to the transcript before anything else, so that
+/// proofs created under a different challenge schedule never verify.
+pub const RANGEPROOF_FORMAT_VERSION: u64 = 1;
+
+/// The transcript has been bound to the protocol version and bitsize.
+pub enum Initialized {}
+
+/// The commitments \\(V, A, S\\) have been committed.
+pub enum BitsCommitted {}
+
+/// The commitments \\(T\_1, T\_2\\) have been committed.
+pub enum PolyCommitted {}
+
+/// A `ProofTranscript` in a given phase of the range proof protocol.
+pub struct RangeProofTranscript<'a, State> {
+    transcript: &'a mut ProofTranscript,
+    _state: PhantomData<State>,
+}
+
+impl<'a, State> RangeProofTranscript<'a, State> {
+    /// Forks the underlying transcript into a generator of the prover's
+    /// nonces, as `ProofTranscript::build_rng`.
+    pub(crate) fn build_rng<R: Rng>(&self, witness: &[&[u8]], rng: &mut R) -> TranscriptRng {
+        self.transcript.build_rng(witness, rng)
+    }
+
+    fn advance<Next>(self) -> RangeProofTranscript<'a, Next> {
+        RangeProofTranscript {
+            transcript: self.transcript,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<'a> RangeProofTranscript<'a, Initialized> {
+    /// Begins a range proof over `transcript` for an `n`-bit range.
+    pub fn new(transcript: &'a mut ProofTranscript, n: usize) -> Self {
+        // Commit the format version and the range size to domain-separate
+        // from rangeproofs of other versions and different lengths.
+        transcript.commit_u64(RANGEPROOF_FORMAT_VERSION);
+        transcript.commit_u64(n as u64);
+        RangeProofTranscript {
+            transcript,
+            _state: PhantomData,
+        }
+    }
+
+    /// Commits \\(V, A, S\\) and returns the challenges \\(y, z\\).
+    pub fn commit_bits(
+        self,
+        V: &RistrettoPoint,
+        A: &RistrettoPoint,
+        S: &RistrettoPoint,
+    ) -> (RangeProofTranscript<'a, BitsCommitted>, Scalar, Scalar) {
+        self.transcript.commit(V.compress().as_bytes());
+        self.transcript.commit(A.compress().as_bytes());
+        self.transcript.commit(S.compress().as_bytes());
+        let y = self.transcript.challenge_scalar_labeled(b"y");
+        let z = self.transcript.challenge_scalar_labeled(b"z");
+        (self.advance(), y, z)
+    }
+}
+
+impl<'a> RangeProofTranscript<'a, BitsCommitted> {
+    /// Commits \\(T\_1, T\_2\\) and returns the challenge \\(x\\).
+    pub fn commit_poly(
+        self,
+        T_1: &RistrettoPoint,
+        T_2: &RistrettoPoint,
+    ) -> (RangeProofTranscript<'a, PolyCommitted>, Scalar) {
+        self.transcript.commit(T_1.compress().as_bytes());
+        self.transcript.commit(T_2.compress().as_bytes());
+        let x = self.transcript.challenge_scalar_labeled(b"x");
+        (self.advance(), x)
+    }
+}
+
+impl<'a> RangeProofTranscript<'a, PolyCommitted> {
+    /// Commits \\(t(x), {\tilde{t}}(x), \tilde{e}\\) and returns the
+    /// challenge \\(w\\), together with the underlying transcript for
+    /// use by the inner product argument.
+    pub fn commit_evaluation(
+        self,
+        t_x: &Scalar,
+        t_x_blinding: &Scalar,
+        e_blinding: &Scalar,
+    ) -> (&'a mut ProofTranscript, Scalar) {
+        let transcript = self.commit_evaluation_only(t_x, t_x_blinding, e_blinding);
+        let w = transcript.challenge_scalar_labeled(b"w");
+        (transcript, w)
+    }
+
+    /// Commits \\(t(x), {\tilde{t}}(x), \tilde{e}\\) without drawing the
+    /// challenge \(w\), returning the underlying transcript, for when
+    /// the inner product argument is combined with another protocol's.
+    pub fn commit_evaluation_only(
+        self,
+        t_x: &Scalar,
+        t_x_blinding: &Scalar,
+        e_blinding: &Scalar,
+    ) -> &'a mut ProofTranscript {
+        self.transcript.commit(t_x.as_bytes());
+        self.transcript.commit(t_x_blinding.as_bytes());
+        self.transcript.commit(e_blinding.as_bytes());
+        self.transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    #[test]
+    fn typed_transcript_matches_raw_schedule() {
+        let B = RISTRETTO_BASEPOINT_POINT;
+        let one = Scalar::one();
+
+        let mut typed = ProofTranscript::new(b"RangeProofTranscriptTest").unwrap();
+        let (y, z, x, w, u) = {
+            let t = RangeProofTranscript::new(&mut typed, 8);
+            let (t, y, z) = t.commit_bits(&B, &B, &B);
+            let (t, x) = t.commit_poly(&B, &B);
+            let (inner, w) = t.commit_evaluation(&one, &one, &one);
+            (y, z, x, w, inner.challenge_scalar())
+        };
+
+        let mut raw = ProofTranscript::new(b"RangeProofTranscriptTest").unwrap();
+        raw.commit_u64(RANGEPROOF_FORMAT_VERSION);
+        raw.commit_u64(8);
+        for _ in 0..3 {
+            raw.commit(B.compress().as_bytes());
+        }
+        assert_eq!(y, raw.challenge_scalar_labeled(b"y"));
+        assert_eq!(z, raw.challenge_scalar_labeled(b"z"));
+        raw.commit(B.compress().as_bytes());
+        raw.commit(B.compress().as_bytes());
+        assert_eq!(x, raw.challenge_scalar_labeled(b"x"));
+        for _ in 0..3 {
+            raw.commit(one.as_bytes());
+        }
+        assert_eq!(w, raw.challenge_scalar_labeled(b"w"));
+        assert_eq!(u, raw.challenge_scalar());
+    }
+}
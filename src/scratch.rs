@@ -0,0 +1,150 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `scratch` module contains reusable buffers for the prover.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use generators::GeneratorsView;
+use poly::VecPoly1;
+#[cfg(feature = "zeroize")]
+use secret::{self, Zeroize, ZeroizeOnDrop};
+
+/// `ProverScratch` owns the working memory needed to create a range
+/// proof, so that it can be reused across many proofs instead of
+/// being allocated afresh for each one.
+///
+/// Pass the same `ProverScratch` to successive calls of
+/// `RangeProof::generate_proof_with_scratch`.  The buffers grow to the
+/// largest bitsize they have been used with, and their contents are
+/// overwritten on every use.  With the `zeroize` feature, the buffers
+/// holding the blinding vectors and \\(l(x)\\), \\(r(x)\\) are
+/// wiped when the scratch space is dropped.
+///
+/// # Example
+///
+/// ```ascii
+/// let mut scratch = ProverScratch::new();
+/// for (v, v_blinding) in jobs {
+///     let mut transcript = ProofTranscript::new(b"RangeproofTest")?;
+///     let proof = RangeProof::generate_proof_with_scratch(
+///         generators.share(0),
+///         &mut transcript,
+///         &mut rng,
+///         &mut scratch,
+///         n,
+///         &v,
+///         &v_blinding,
+///     )?;
+/// }
+/// ```
+pub struct ProverScratch {
+    /// Copy of the per-bit generators for the bit values
+    pub(crate) G: Vec<RistrettoPoint>,
+    /// Copy of the per-bit generators for the bit blinding factors
+    pub(crate) H: Vec<RistrettoPoint>,
+    /// Blinding vector for the bit values
+    pub(crate) s_L: Vec<Scalar>,
+    /// Blinding vector for the bit blinding factors
+    pub(crate) s_R: Vec<Scalar>,
+    /// Vector polynomial \\(l(x)\\)
+    pub(crate) l_poly: VecPoly1,
+    /// Vector polynomial \\(r(x)\\)
+    pub(crate) r_poly: VecPoly1,
+    /// Evaluation \\(l(x)\\) at the challenge point
+    pub(crate) l_vec: Vec<Scalar>,
+    /// Evaluation \\(r(x)\\) at the challenge point
+    pub(crate) r_vec: Vec<Scalar>,
+}
+
+impl ProverScratch {
+    /// Creates an empty set of buffers.
+    pub fn new() -> Self {
+        ProverScratch {
+            G: Vec::new(),
+            H: Vec::new(),
+            s_L: Vec::new(),
+            s_R: Vec::new(),
+            l_poly: VecPoly1::zero(0),
+            r_poly: VecPoly1::zero(0),
+            l_vec: Vec::new(),
+            r_vec: Vec::new(),
+        }
+    }
+
+    /// Prepares the buffers for an `n`-bit proof over `generators`.
+    pub(crate) fn reset(&mut self, generators: &GeneratorsView, n: usize) {
+        self.G.clear();
+        self.G.extend_from_slice(generators.G);
+        self.H.clear();
+        self.H.extend_from_slice(generators.H);
+
+        for buf in [
+            &mut self.s_L,
+            &mut self.s_R,
+            &mut self.l_poly.0,
+            &mut self.l_poly.1,
+            &mut self.r_poly.0,
+            &mut self.r_poly.1,
+            &mut self.l_vec,
+            &mut self.r_vec,
+        ].iter_mut()
+        {
+            // Wipe the previous proof's vectors, including any beyond `n`.
+            #[cfg(feature = "zeroize")]
+            secret::wipe_scalars(buf);
+            buf.clear();
+            buf.resize(n, Scalar::zero());
+        }
+    }
+}
+
+impl fmt::Debug for ProverScratch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The buffers hold the blinding vectors of the last proof.
+        f.debug_struct("ProverScratch")
+            .field("capacity", &self.G.capacity())
+            .finish()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for ProverScratch {
+    fn zeroize(&mut self) {
+        for buf in [
+            &mut self.s_L,
+            &mut self.s_R,
+            &mut self.l_poly.0,
+            &mut self.l_poly.1,
+            &mut self.r_poly.0,
+            &mut self.r_poly.1,
+            &mut self.l_vec,
+            &mut self.r_vec,
+        ].iter_mut()
+        {
+            secret::wipe_scalars(buf);
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ProverScratch {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for ProverScratch {}
+
+impl Default for ProverScratch {
+    fn default() -> Self {
+        ProverScratch::new()
+    }
+}
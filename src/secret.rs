@@ -0,0 +1,358 @@
+#![deny(missing_docs)]
+
+//! The `secret` module contains `SecretValue` and `Blinding`, owners of
+//! the secret inputs of a proof, which wipe them when they are dropped,
+//! and `SecretScalar`, for scalars derived from them.
+//!
+//! A plain `Scalar` is public, like a challenge, and a secret scalar is
+//! one of these types, so that the helpers for public scalars, such as
+//! the iterator computing the powers of challenges, cannot silently be
+//! fed secrets.  The constant-time helpers of the `ct` module take and
+//! return the secret types instead.
+//!
+//! The public provers of the crate, from `RangeProof` to the sigma
+//! protocols and the constraint system `Prover`, accept their secret
+//! inputs only as these types, whose `Debug` output is redacted, so
+//! that a secret is not logged by a stray `{:?}` or left behind in a
+//! copy.
+//!
+//! With the `zeroize` feature, the newtypes, the buffers of
+//! `ProverScratch`, the assignments of the R1CS prover and the blinding
+//! factors the prover draws for a proof are overwritten with zeros when
+//! they are dropped, and implement `Zeroize` and `ZeroizeOnDrop`.  The
+//! traits are this module's own rather than those of the `zeroize`
+//! crate, whose releases need a newer compiler than this crate's, and
+//! the wiping is done with volatile writes that the compiler cannot
+//! elide.  Neither newtype is `Copy`,
+//! so the only copies of a secret are the ones made from its accessors
+//! or by an explicit `clone`:
+//!
+//! ```compile_fail
+//! # extern crate ristretto_bulletproofs;
+//! # use ristretto_bulletproofs::SecretValue;
+//! # fn main() {
+//! let v = SecretValue::new(1000);
+//! let copy = v;
+//! assert_eq!(v.value(), copy.value());
+//! # }
+//! ```
+//!
+//! With the `mlock` feature, the newtypes keep their contents in the
+//! page-locked memory of a `locked::SecretBuffer`, or in unlocked pages
+//! counted by `locked::unlocked_buffers` if the pages cannot be locked.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let v = SecretValue::new(1000);
+//! let v_blinding = Blinding::random(&mut rng);
+//! let V = pg.commit(v.to_scalar(), *v_blinding.as_scalar());
+//! let proof = RangeProof::generate_proof(
+//!     generators.share(0),
+//!     &mut transcript,
+//!     &mut rng,
+//!     n,
+//!     &v,
+//!     &v_blinding,
+//! )?;
+//! // v and v_blinding are wiped here.
+//! ```
+
+use core::borrow::Borrow;
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "zeroize")]
+use core::borrow::BorrowMut;
+#[cfg(feature = "zeroize")]
+use core::ptr;
+#[cfg(feature = "zeroize")]
+use core::sync::atomic;
+
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+#[cfg(feature = "mlock")]
+use locked::SecretBuffer;
+
+/// The storage of a secret: page-locked with the `mlock` feature, and
+/// inline otherwise.
+#[cfg(feature = "mlock")]
+type Storage<T> = SecretBuffer<T>;
+#[cfg(not(feature = "mlock"))]
+type Storage<T> = T;
+
+#[cfg(feature = "mlock")]
+fn store<T: Copy>(value: T) -> Storage<T> {
+    SecretBuffer::new(value)
+}
+
+#[cfg(not(feature = "mlock"))]
+fn store<T: Copy>(value: T) -> Storage<T> {
+    value
+}
+
+/// Types whose secret contents can be overwritten with zeros.
+#[cfg(feature = "zeroize")]
+pub trait Zeroize {
+    /// Overwrites the secret contents with zeros.
+    fn zeroize(&mut self);
+}
+
+/// Marks types that call `Zeroize::zeroize` when they are dropped.
+#[cfg(feature = "zeroize")]
+pub trait ZeroizeOnDrop: Zeroize {}
+
+/// A secret value, such as the amount committed to by a range proof.
+#[derive(Clone)]
+pub struct SecretValue(Storage<u64>);
+
+impl SecretValue {
+    /// Takes ownership of the secret `value`.
+    pub fn new(value: u64) -> Self {
+        SecretValue(store(value))
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> u64 {
+        *Borrow::<u64>::borrow(&self.0)
+    }
+
+    /// Returns the value as a scalar, for commitments.
+    pub fn to_scalar(&self) -> Scalar {
+        Scalar::from_u64(self.value())
+    }
+}
+
+/// A secret blinding factor of a commitment.
+#[derive(Clone)]
+pub struct Blinding(Storage<Scalar>);
+
+impl Blinding {
+    /// Takes ownership of the blinding factor `blinding`.
+    pub fn new(blinding: Scalar) -> Self {
+        Blinding(store(blinding))
+    }
+
+    /// Draws a uniformly random blinding factor from `rng`.
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        Blinding::new(Scalar::random(rng))
+    }
+
+    /// Returns the blinding factor.
+    pub fn as_scalar(&self) -> &Scalar {
+        Borrow::<Scalar>::borrow(&self.0)
+    }
+}
+
+/// A secret scalar derived from secret inputs, such as a power of a
+/// secret base.
+#[derive(Clone)]
+pub struct SecretScalar(Storage<Scalar>);
+
+impl SecretScalar {
+    /// Takes ownership of the secret `scalar`.
+    pub fn new(scalar: Scalar) -> Self {
+        SecretScalar(store(scalar))
+    }
+
+    /// Draws a uniformly random secret scalar from `rng`.
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        SecretScalar::new(Scalar::random(rng))
+    }
+
+    /// Returns the scalar.
+    pub fn as_scalar(&self) -> &Scalar {
+        Borrow::<Scalar>::borrow(&self.0)
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretValue(..)")
+    }
+}
+
+impl fmt::Debug for Blinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Blinding(..)")
+    }
+}
+
+impl fmt::Debug for SecretScalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretScalar(..)")
+    }
+}
+
+impl From<Scalar> for Blinding {
+    fn from(blinding: Scalar) -> Blinding {
+        Blinding::new(blinding)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for SecretValue {
+    fn zeroize(&mut self) {
+        BorrowMut::<u64>::borrow_mut(&mut self.0).zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretValue {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SecretValue {}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Blinding {
+    fn zeroize(&mut self) {
+        wipe_scalar(BorrowMut::<Scalar>::borrow_mut(&mut self.0));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Blinding {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Blinding {}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for SecretScalar {
+    fn zeroize(&mut self) {
+        wipe_scalar(BorrowMut::<Scalar>::borrow_mut(&mut self.0));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretScalar {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SecretScalar {}
+
+/// A vector of secret scalars, such as the assignments of a constraint
+/// system, which is wiped on drop with the `zeroize` feature.
+#[derive(Clone, Default)]
+pub(crate) struct SecretScalars(Vec<Scalar>);
+
+impl Deref for SecretScalars {
+    type Target = Vec<Scalar>;
+
+    fn deref(&self) -> &Vec<Scalar> {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretScalars {
+    fn deref_mut(&mut self) -> &mut Vec<Scalar> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<Scalar>> for SecretScalars {
+    fn from(scalars: Vec<Scalar>) -> SecretScalars {
+        SecretScalars(scalars)
+    }
+}
+
+impl<'a> From<&'a [SecretScalar]> for SecretScalars {
+    fn from(scalars: &'a [SecretScalar]) -> SecretScalars {
+        SecretScalars(scalars.iter().map(|s| *s.as_scalar()).collect())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretScalars {
+    fn drop(&mut self) {
+        wipe_scalars(&mut self.0);
+    }
+}
+
+/// Overwrites `s` with zero in a way the compiler cannot elide.
+///
+/// `Scalar` exposes no mutable bytes, so this writes a zero scalar
+/// over it with a volatile write.
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe_scalar(s: &mut Scalar) {
+    unsafe {
+        ptr::write_volatile(s, Scalar::zero());
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// Overwrites `bytes` with zeros, as `wipe_scalar`.
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe_bytes(bytes: &mut [u8]) {
+    for b in bytes.iter_mut() {
+        unsafe {
+            ptr::write_volatile(b, 0);
+        }
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// Overwrites every scalar of `scalars` with zero, as `wipe_scalar`.
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe_scalars(scalars: &mut [Scalar]) {
+    for s in scalars.iter_mut() {
+        wipe_scalar(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    #[test]
+    fn secrets_expose_their_contents() {
+        let v = SecretValue::new(1000);
+        assert_eq!(v.value(), 1000);
+        assert_eq!(v.to_scalar(), Scalar::from_u64(1000));
+
+        let v_blinding = Blinding::random(&mut OsRng::new().unwrap());
+        assert_eq!(v_blinding.clone().as_scalar(), v_blinding.as_scalar());
+        assert_eq!(Blinding::from(Scalar::one()).as_scalar(), &Scalar::one());
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        assert_eq!(format!("{:?}", SecretValue::new(1000)), "SecretValue(..)");
+        assert_eq!(format!("{:?}", Blinding::from(Scalar::one())), "Blinding(..)");
+        assert_eq!(format!("{:?}", SecretScalar::new(Scalar::one())), "SecretScalar(..)");
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn secrets_are_wiped() {
+        let mut v = SecretValue::new(1000);
+        v.zeroize();
+        assert_eq!(v.value(), 0);
+
+        let mut v_blinding = Blinding::random(&mut OsRng::new().unwrap());
+        v_blinding.zeroize();
+        assert_eq!(v_blinding.as_scalar(), &Scalar::zero());
+
+        let mut s = SecretScalar::new(Scalar::one());
+        s.zeroize();
+        assert_eq!(s.as_scalar(), &Scalar::zero());
+
+        let mut scalars = vec![Scalar::one(); 3];
+        wipe_scalars(&mut scalars);
+        assert_eq!(scalars, vec![Scalar::zero(); 3]);
+    }
+}
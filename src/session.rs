@@ -0,0 +1,66 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `session` module contains `ProofSession`, a transcript for exactly
+//! one range proof.
+//!
+//! `ProofTranscript` is not `Clone`, so no caller can hold two copies of
+//! one transcript state to make two proofs from.  The proving and
+//! verification functions taking a `&mut ProofTranscript` move it on to
+//! a new state and leave it usable afterwards, which composite protocols
+//! rely on to chain several proofs.  A caller who only wants one proof
+//! can instead use `generate_proof_in_session` and `verify_in_session`,
+//! which take a `ProofSession` by value, so that even chaining a second
+//! proof onto the same transcript is a compile error:
+//!
+//! ```compile_fail
+//! # extern crate rand;
+//! # extern crate ristretto_bulletproofs;
+//! # use rand::OsRng;
+//! # use ristretto_bulletproofs::{Blinding, Generators, PedersenGenerators, ProofSession};
+//! # use ristretto_bulletproofs::{RangeProof, SecretValue};
+//! # fn main() {
+//! # let generators = Generators::new(b"MyApp", PedersenGenerators::default(), 8, 1);
+//! # let mut rng = OsRng::new().unwrap();
+//! # let v_blinding = Blinding::random(&mut rng);
+//! let session = ProofSession::new(b"Example").unwrap();
+//! let proof_1 = RangeProof::generate_proof_in_session(
+//!     generators.share(0), session, &mut rng, 8, &SecretValue::new(1), &v_blinding,
+//! ).unwrap();
+//! let proof_2 = RangeProof::generate_proof_in_session(
+//!     generators.share(0), session, &mut rng, 8, &SecretValue::new(2), &v_blinding,
+//! ).unwrap();
+//! # }
+//! ```
+
+use errors::ProofError;
+use proof_transcript::ProofTranscript;
+
+/// A transcript that is consumed by a single proof or verification.
+#[derive(Debug)]
+pub struct ProofSession {
+    /// The underlying transcript
+    transcript: ProofTranscript,
+}
+
+impl ProofSession {
+    /// Begins a session with a fresh transcript for `label`.
+    ///
+    /// Returns `EmptyLabel` if `label` is empty.
+    pub fn new(label: &[u8]) -> Result<Self, ProofError> {
+        Ok(ProofSession {
+            transcript: ProofTranscript::new(label)?,
+        })
+    }
+
+    /// Begins a session from `transcript`, after the caller has
+    /// committed its context to it.
+    pub fn from_transcript(transcript: ProofTranscript) -> Self {
+        ProofSession { transcript }
+    }
+
+    /// Ends the session, returning its transcript for the one proof.
+    pub(crate) fn into_transcript(self) -> ProofTranscript {
+        self.transcript
+    }
+}
@@ -0,0 +1,624 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `sigma` module contains classical sigma protocols, made
+//! non-interactive with the crate's `ProofTranscript`.
+//!
+//! Every protocol here proves knowledge of a witness
+//! \\(x\_0, \ldots, x\_{k-1}\\) satisfying a set of linear relations
+//! \\( Y\_j = \sum\_i x\_i G\_{j,i} \\), described by a `Statement`:
+//!
+//! * `Statement::dlog` is a proof of knowledge of a discrete log,
+//!   \\(Y = x G\\);
+//! * `Statement::opening` is a proof of knowledge of the opening of a
+//!   Pedersen commitment, \\(V = v B + r \widetilde{B}\\);
+//! * `Statement::dleq` is a proof of equality of discrete logs,
+//!   \\(Y\_1 = x G\_1\\) and \\(Y\_2 = x G\_2\\).
+//!
+//! The prover sends \\(T\_j = \sum\_i k\_i G\_{j,i}\\) for random nonces
+//! \\(k\_i\\), receives a challenge \\(c\\) from the transcript, and
+//! answers \\(s\_i = k\_i + c x\_i\\).  The verifier checks that
+//! \\( T\_j = \sum\_i s\_i G\_{j,i} - c Y\_j \\).
+//!
+//! Statements compose: `Statement::and` proves two statements with
+//! independent witnesses at once, and an `OrProof` proves one out of a
+//! list of statements without revealing which.  The OR proof splits the
+//! transcript challenge \\(c = \sum\_i c\_i\\) between the branches, and
+//! simulates every branch but the true one with a challenge chosen in
+//! advance.  Only sigma protocols can be simulated this way, so range
+//! proofs and constraint system proofs cannot be branches of an OR.  A
+//! disjunction involving a range, such as "the value is zero or at
+//! least a minimum", is proven in a constraint system instead, with
+//! `r1cs::gadgets::zero_or_range`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let statement = Statement::opening(&pg, &V);
+//!
+//! let mut transcript = ProofTranscript::new(b"Opening")?;
+//! let witness = [SecretScalar::new(v.to_scalar()), SecretScalar::new(*v_blinding.as_scalar())];
+//! let proof = SigmaProof::prove(&statement, &mut transcript, &mut rng, &witness)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Opening")?;
+//! proof.verify(&statement, &mut transcript)?;
+//! ```
+
+use core::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use proof_transcript::{ProofTranscript, TranscriptRng};
+use secret::SecretScalar;
+use util;
+
+/// A set of linear relations \\( Y\_j = \sum\_i x\_i G\_{j,i} \\) over a
+/// secret witness \\(x\\).
+#[derive(Clone, Debug)]
+pub struct Statement {
+    /// The bases \\(G\_{j,i}\\), one row per relation
+    bases: Vec<Vec<RistrettoPoint>>,
+    /// The public images \\(Y\_j\\)
+    images: Vec<RistrettoPoint>,
+}
+
+impl Statement {
+    /// Creates the statement \\( Y\_j = \sum\_i x\_i G\_{j,i} \\) for the
+    /// rows of `bases` and the `images`.
+    ///
+    /// Returns `InvalidStatement` if there are no relations, if the
+    /// number of rows and images differ, or if the rows have different
+    /// lengths.
+    pub fn new(
+        bases: Vec<Vec<RistrettoPoint>>,
+        images: Vec<RistrettoPoint>,
+    ) -> Result<Self, ProofError> {
+        if bases.is_empty()
+            || bases.len() != images.len()
+            || bases.iter().any(|row| row.len() != bases[0].len())
+        {
+            return Err(ProofError::InvalidStatement);
+        }
+        Ok(Statement { bases, images })
+    }
+
+    /// Creates the statement \\(Y = x G\\), for a proof of knowledge of
+    /// the discrete log \\(x\\).
+    pub fn dlog(G: &RistrettoPoint, Y: &RistrettoPoint) -> Self {
+        Statement {
+            bases: vec![vec![*G]],
+            images: vec![*Y],
+        }
+    }
+
+    /// Creates the statement \\(V = v B + r \widetilde{B}\\), for a proof
+    /// of knowledge of the opening \\((v, r)\\) of the commitment \\(V\\).
+    pub fn opening(pg: &PedersenGenerators, V: &RistrettoPoint) -> Self {
+        Statement {
+            bases: vec![vec![pg.B, pg.B_blinding]],
+            images: vec![*V],
+        }
+    }
+
+    /// Creates the statement \\(Y\_1 = x G\_1 \wedge Y\_2 = x G\_2\\), for
+    /// a proof that \\(Y\_1\\) and \\(Y\_2\\) have the same discrete log.
+    pub fn dleq(
+        G_1: &RistrettoPoint,
+        Y_1: &RistrettoPoint,
+        G_2: &RistrettoPoint,
+        Y_2: &RistrettoPoint,
+    ) -> Self {
+        Statement {
+            bases: vec![vec![*G_1], vec![*G_2]],
+            images: vec![*Y_1, *Y_2],
+        }
+    }
+
+    /// Creates the statement that both `self` and `other` hold, with
+    /// the witness of `self` followed by the witness of `other`.
+    pub fn and(&self, other: &Statement) -> Statement {
+        let left_len = self.witness_len();
+        let right_len = other.witness_len();
+        let left_rows = self.bases.iter().map(|row| {
+            row.iter()
+                .cloned()
+                .chain(iter::repeat(RistrettoPoint::identity()).take(right_len))
+                .collect()
+        });
+        let right_rows = other.bases.iter().map(|row| {
+            iter::repeat(RistrettoPoint::identity())
+                .take(left_len)
+                .chain(row.iter().cloned())
+                .collect()
+        });
+        Statement {
+            bases: left_rows.chain(right_rows).collect(),
+            images: self.images.iter().chain(other.images.iter()).cloned().collect(),
+        }
+    }
+
+    /// Returns the number of witness scalars.
+    pub fn witness_len(&self) -> usize {
+        self.bases[0].len()
+    }
+
+    /// Commits the statement to the transcript.
+    pub(crate) fn commit_to(&self, transcript: &mut ProofTranscript) {
+        transcript.commit(b"Sigma");
+        transcript.commit_u64(self.bases.len() as u64);
+        transcript.commit_u64(self.witness_len() as u64);
+        for (row, Y) in self.bases.iter().zip(self.images.iter()) {
+            for G in row.iter() {
+                transcript.commit(G.compress().as_bytes());
+            }
+            transcript.commit(Y.compress().as_bytes());
+        }
+    }
+
+    /// Returns \\( \sum\_i x\_i G\_{j,i} \\) for each relation \\(j\\).
+    pub(crate) fn apply(&self, x: &[Scalar]) -> Vec<RistrettoPoint> {
+        self.bases
+            .iter()
+            .map(|row| util::multiscalar_mul(x, row))
+            .collect()
+    }
+
+    /// Returns the nonce commitments \\( \sum\_i s\_i G\_{j,i} - c Y\_j \\)
+    /// that verify with the challenge `c` and the responses `s`.
+    ///
+    /// Returns `FormatError` if there is not one response per witness
+    /// scalar.
+    pub(crate) fn simulate(
+        &self,
+        c: &Scalar,
+        s: &[Scalar],
+    ) -> Result<Vec<RistrettoPoint>, ProofError> {
+        self.bases
+            .iter()
+            .zip(self.images.iter())
+            .map(|(row, Y)| {
+                util::vartime_multiscalar_mul(
+                    s.iter().cloned().chain(iter::once(-c)),
+                    row.iter().cloned().chain(iter::once(*Y)),
+                )
+            })
+            .collect()
+    }
+}
+
+/// A non-interactive proof of knowledge of a witness for a `Statement`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SigmaProof {
+    /// Nonce commitments, one per relation
+    T: Vec<RistrettoPoint>,
+    /// Responses, one per witness scalar
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    s: Vec<Scalar>,
+}
+
+impl SigmaProof {
+    /// Proves knowledge of the `witness` for the `statement`.
+    ///
+    /// Returns `InvalidOpening` if the witness has the wrong length.  The
+    /// proof only verifies if the witness satisfies the statement.
+    pub fn prove<R: Rng>(
+        statement: &Statement,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        witness: &[SecretScalar],
+    ) -> Result<SigmaProof, ProofError> {
+        if witness.len() != statement.witness_len() {
+            return Err(ProofError::InvalidOpening);
+        }
+        statement.commit_to(transcript);
+
+        let mut nonces = nonce_rng(transcript, &[], witness, rng);
+        let k: Vec<Scalar> = witness.iter().map(|_| Scalar::random(&mut nonces)).collect();
+        let T = statement.apply(&k);
+        for T_j in T.iter() {
+            transcript.commit(T_j.compress().as_bytes());
+        }
+        let c = transcript.challenge_scalar_labeled(b"sigma c");
+
+        let s = k
+            .iter()
+            .zip(witness.iter())
+            .map(|(k_i, x_i)| k_i + c * x_i.as_scalar())
+            .collect();
+        Ok(SigmaProof { T, s })
+    }
+
+    /// Verifies the proof of knowledge of a witness for the `statement`.
+    ///
+    /// Returns `FormatError` if the proof does not have one nonce
+    /// commitment per relation and one response per witness scalar,
+    /// and `VerificationError` if it does not verify.
+    pub fn verify(
+        &self,
+        statement: &Statement,
+        transcript: &mut ProofTranscript,
+    ) -> Result<(), ProofError> {
+        if self.T.len() != statement.images.len() || self.s.len() != statement.witness_len() {
+            return Err(ProofError::FormatError);
+        }
+        statement.commit_to(transcript);
+        for T_j in self.T.iter() {
+            transcript.commit(T_j.compress().as_bytes());
+        }
+        let c = transcript.challenge_scalar_labeled(b"sigma c");
+
+        if statement.simulate(&c, &self.s)? == self.T {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+/// A non-interactive proof of knowledge of a witness for one out of a
+/// list of statements, without revealing which.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrProof {
+    /// Nonce commitments of each branch
+    T: Vec<Vec<RistrettoPoint>>,
+    /// Challenges of each branch, adding up to the transcript challenge
+    #[serde(deserialize_with = "::util::deserialize_scalars")]
+    c: Vec<Scalar>,
+    /// Responses of each branch
+    #[serde(deserialize_with = "::util::deserialize_scalar_vecs")]
+    s: Vec<Vec<Scalar>>,
+}
+
+impl OrProof {
+    /// Proves knowledge of the `witness` for `statements[index]`.
+    ///
+    /// Returns `InvalidStatement` if `index` is out of range, and
+    /// `InvalidOpening` if the witness has the wrong length.  The proof
+    /// only verifies if the witness satisfies the statement.
+    pub fn prove<R: Rng>(
+        statements: &[Statement],
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        index: usize,
+        witness: &[SecretScalar],
+    ) -> Result<OrProof, ProofError> {
+        if index >= statements.len() {
+            return Err(ProofError::InvalidStatement);
+        }
+        if witness.len() != statements[index].witness_len() {
+            return Err(ProofError::InvalidOpening);
+        }
+        commit_statements(transcript, statements);
+
+        // The index is part of the witness: it is the only secret of the
+        // simulated branches.
+        let mut index_bytes = [0u8; 8];
+        LittleEndian::write_u64(&mut index_bytes, index as u64);
+        let mut nonces = nonce_rng(transcript, &index_bytes, witness, rng);
+        let rng = &mut nonces;
+
+        // Simulate the other branches with random challenges and
+        // responses, and commit to nonces for the true branch.
+        let mut c: Vec<Scalar> = Vec::with_capacity(statements.len());
+        let mut s: Vec<Vec<Scalar>> = Vec::with_capacity(statements.len());
+        let mut T: Vec<Vec<RistrettoPoint>> = Vec::with_capacity(statements.len());
+        for (i, statement) in statements.iter().enumerate() {
+            let s_i: Vec<Scalar> = (0..statement.witness_len())
+                .map(|_| Scalar::random(rng))
+                .collect();
+            if i == index {
+                T.push(statement.apply(&s_i));
+                c.push(Scalar::zero());
+            } else {
+                let c_i = Scalar::random(rng);
+                T.push(statement.simulate(&c_i, &s_i)?);
+                c.push(c_i);
+            }
+            s.push(s_i);
+        }
+
+        for T_i in T.iter() {
+            for T_ij in T_i.iter() {
+                transcript.commit(T_ij.compress().as_bytes());
+            }
+        }
+        let challenge = transcript.challenge_scalar_labeled(b"sigma or c");
+
+        // The true branch gets what remains of the challenge.
+        let c_index = c.iter().fold(challenge, |acc, c_i| acc - c_i);
+        for (s_j, x_j) in s[index].iter_mut().zip(witness.iter()) {
+            *s_j += c_index * x_j.as_scalar();
+        }
+        c[index] = c_index;
+
+        Ok(OrProof { T, c, s })
+    }
+
+    /// Verifies the proof of knowledge of a witness for one of the
+    /// `statements`.
+    ///
+    /// Returns `InvalidStatement` if there are no statements,
+    /// `FormatError` if the proof does not match their shapes, and
+    /// `VerificationError` if it does not verify.
+    pub fn verify(
+        &self,
+        statements: &[Statement],
+        transcript: &mut ProofTranscript,
+    ) -> Result<(), ProofError> {
+        let k = statements.len();
+        if k == 0 {
+            return Err(ProofError::InvalidStatement);
+        }
+        if self.T.len() != k || self.c.len() != k || self.s.len() != k {
+            return Err(ProofError::FormatError);
+        }
+        for (statement, (T_i, s_i)) in statements.iter().zip(self.T.iter().zip(self.s.iter())) {
+            if T_i.len() != statement.images.len() || s_i.len() != statement.witness_len() {
+                return Err(ProofError::FormatError);
+            }
+        }
+        commit_statements(transcript, statements);
+        for T_i in self.T.iter() {
+            for T_ij in T_i.iter() {
+                transcript.commit(T_ij.compress().as_bytes());
+            }
+        }
+        let challenge = transcript.challenge_scalar_labeled(b"sigma or c");
+
+        if self.c.iter().fold(Scalar::zero(), |acc, c_i| acc + c_i) != challenge {
+            return Err(ProofError::VerificationError);
+        }
+        for (i, statement) in statements.iter().enumerate() {
+            if statement.simulate(&self.c[i], &self.s[i])? != self.T[i] {
+                return Err(ProofError::VerificationError);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Forks the prover's nonces from the `transcript`, bound to `extra`,
+/// the `witness` and `rng`, so that a repeated or weak `rng` output
+/// cannot make two proofs with different witnesses share nonces.
+fn nonce_rng<R: Rng>(
+    transcript: &ProofTranscript,
+    extra: &[u8],
+    witness: &[SecretScalar],
+    rng: &mut R,
+) -> TranscriptRng {
+    let messages: Vec<&[u8]> = iter::once(extra)
+        .chain(witness.iter().map(|x| &x.as_scalar().as_bytes()[..]))
+        .collect();
+    transcript.build_rng(&messages, rng)
+}
+
+/// Commits the list of statements of an OR proof to the transcript.
+fn commit_statements(transcript: &mut ProofTranscript, statements: &[Statement]) {
+    transcript.commit(b"SigmaOr");
+    transcript.commit_u64(statements.len() as u64);
+    for statement in statements.iter() {
+        statement.commit_to(transcript);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+    use sha2::Sha512;
+
+    fn secrets(scalars: &[Scalar]) -> Vec<SecretScalar> {
+        scalars.iter().map(|x| SecretScalar::new(*x)).collect()
+    }
+
+    fn prove_and_verify(statement: &Statement, witness: &[Scalar]) -> Result<(), ProofError> {
+        let witness = secrets(witness);
+        let mut rng = OsRng::new().unwrap();
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let proof = SigmaProof::prove(statement, &mut transcript, &mut rng, &witness).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        proof.verify(statement, &mut transcript)
+    }
+
+    #[test]
+    fn dlog_proof() {
+        let G = RistrettoPoint::hash_from_bytes::<Sha512>(b"G");
+        let x = Scalar::from_u64(42);
+        assert!(prove_and_verify(&Statement::dlog(&G, &(G * x)), &[x]).is_ok());
+        assert!(prove_and_verify(&Statement::dlog(&G, &(G * x)), &[-x]).is_err());
+    }
+
+    #[test]
+    fn opening_proof() {
+        let pg = PedersenGenerators::default();
+        let v = Scalar::from_u64(7);
+        let r = Scalar::from_u64(11);
+        let V = pg.commit(v, r);
+        assert!(prove_and_verify(&Statement::opening(&pg, &V), &[v, r]).is_ok());
+        assert!(prove_and_verify(&Statement::opening(&pg, &V), &[r, v]).is_err());
+    }
+
+    #[test]
+    fn dleq_proof() {
+        let G_1 = RistrettoPoint::hash_from_bytes::<Sha512>(b"G_1");
+        let G_2 = RistrettoPoint::hash_from_bytes::<Sha512>(b"G_2");
+        let x = Scalar::from_u64(42);
+        let statement = Statement::dleq(&G_1, &(G_1 * x), &G_2, &(G_2 * x));
+        assert!(prove_and_verify(&statement, &[x]).is_ok());
+
+        // Different discrete logs
+        let statement = Statement::dleq(&G_1, &(G_1 * x), &G_2, &G_2);
+        assert!(prove_and_verify(&statement, &[x]).is_err());
+    }
+
+    #[test]
+    fn proof_is_bound_to_the_statement() {
+        let G = RistrettoPoint::hash_from_bytes::<Sha512>(b"G");
+        let x = Scalar::from_u64(42);
+        let mut rng = OsRng::new().unwrap();
+
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let statement = Statement::dlog(&G, &(G * x));
+        let proof =
+            SigmaProof::prove(&statement, &mut transcript, &mut rng, &secrets(&[x])).unwrap();
+
+        let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"H");
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        assert!(proof.verify(&Statement::dlog(&H, &(H * x)), &mut transcript).is_err());
+    }
+
+    #[test]
+    fn and_composition() {
+        let pg = PedersenGenerators::default();
+        let G = RistrettoPoint::hash_from_bytes::<Sha512>(b"G");
+        let x = Scalar::from_u64(42);
+        let v = Scalar::from_u64(7);
+        let r = Scalar::from_u64(11);
+        let V = pg.commit(v, r);
+        let statement = Statement::dlog(&G, &(G * x)).and(&Statement::opening(&pg, &V));
+
+        assert!(prove_and_verify(&statement, &[x, v, r]).is_ok());
+        assert!(prove_and_verify(&statement, &[x, v, v]).is_err());
+    }
+
+    #[test]
+    fn or_composition() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let witness = [SecretScalar::random(&mut rng)];
+        let r = *witness[0].as_scalar();
+
+        // V commits to 0 or to 1, and opens to 1.
+        let V = pg.commit(Scalar::one(), r);
+        let statements = [
+            Statement::dlog(&pg.B_blinding, &V),
+            Statement::dlog(&pg.B_blinding, &(V - pg.B)),
+        ];
+
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let proof = OrProof::prove(&statements, &mut transcript, &mut rng, 1, &witness).unwrap();
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        assert!(proof.verify(&statements, &mut transcript).is_ok());
+
+        // The witness does not satisfy the claimed branch.
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let proof = OrProof::prove(&statements, &mut transcript, &mut rng, 0, &witness).unwrap();
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        assert!(proof.verify(&statements, &mut transcript).is_err());
+
+        // Neither branch holds for a commitment to 2.
+        let V = pg.commit(Scalar::from_u64(2), r);
+        let statements = [
+            Statement::dlog(&pg.B_blinding, &V),
+            Statement::dlog(&pg.B_blinding, &(V - pg.B)),
+        ];
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let proof = OrProof::prove(&statements, &mut transcript, &mut rng, 1, &witness).unwrap();
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        assert!(proof.verify(&statements, &mut transcript).is_err());
+    }
+
+    #[test]
+    fn nonces_depend_on_the_witness() {
+        use rand::ChaChaRng;
+
+        let G = RistrettoPoint::hash_from_bytes::<Sha512>(b"G");
+        let Y = RistrettoPoint::hash_from_bytes::<Sha512>(b"Y");
+        let statement = Statement::dlog(&G, &Y);
+        let prove = |x: u64| {
+            let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+            let witness = secrets(&[Scalar::from_u64(x)]);
+            let mut rng = ChaChaRng::new_unseeded();
+            SigmaProof::prove(&statement, &mut transcript, &mut rng, &witness).unwrap()
+        };
+        // The same RNG output gives different nonces for different witnesses.
+        assert!(prove(1).T != prove(2).T);
+        assert_eq!(prove(1).T, prove(1).T);
+
+        let statements = [statement.clone(), Statement::dlog(&Y, &G)];
+        let prove_or = |index: usize| {
+            let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+            let witness = secrets(&[Scalar::one()]);
+            let mut rng = ChaChaRng::new_unseeded();
+            OrProof::prove(&statements, &mut transcript, &mut rng, index, &witness).unwrap()
+        };
+        let (proof_0, proof_1) = (prove_or(0), prove_or(1));
+        assert!(proof_0.T[0] != proof_1.T[0] && proof_0.T[1] != proof_1.T[1]);
+    }
+
+    #[test]
+    fn malformed_statements_and_witnesses_are_rejected() {
+        let G = RistrettoPoint::hash_from_bytes::<Sha512>(b"G");
+        let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"H");
+        assert_eq!(
+            Statement::new(vec![], vec![]).unwrap_err(),
+            ProofError::InvalidStatement
+        );
+        assert_eq!(
+            Statement::new(vec![vec![G]], vec![G, H]).unwrap_err(),
+            ProofError::InvalidStatement
+        );
+        assert_eq!(
+            Statement::new(vec![vec![G], vec![G, H]], vec![G, H]).unwrap_err(),
+            ProofError::InvalidStatement
+        );
+        let statement = Statement::new(vec![vec![G, H]], vec![G + H]).unwrap();
+        assert!(prove_and_verify(&statement, &[Scalar::one(), Scalar::one()]).is_ok());
+
+        let mut rng = OsRng::new().unwrap();
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let witness = secrets(&[Scalar::one()]);
+        assert_eq!(
+            SigmaProof::prove(&statement, &mut transcript, &mut rng, &witness).unwrap_err(),
+            ProofError::InvalidOpening
+        );
+
+        let statements = [Statement::dlog(&G, &G), statement];
+        assert_eq!(
+            OrProof::prove(&statements, &mut transcript, &mut rng, 2, &witness).unwrap_err(),
+            ProofError::InvalidStatement
+        );
+        assert_eq!(
+            OrProof::prove(&statements, &mut transcript, &mut rng, 1, &witness).unwrap_err(),
+            ProofError::InvalidOpening
+        );
+    }
+
+    #[test]
+    fn or_proof_responses_must_be_canonical() {
+        use bincode;
+
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let witness = [SecretScalar::random(&mut rng)];
+        let V = pg.commit(Scalar::zero(), *witness[0].as_scalar());
+        let statements = [
+            Statement::dlog(&pg.B_blinding, &V),
+            Statement::dlog(&pg.B_blinding, &(V - pg.B)),
+        ];
+        let mut transcript = ProofTranscript::new(b"SigmaTest").unwrap();
+        let proof = OrProof::prove(&statements, &mut transcript, &mut rng, 0, &witness).unwrap();
+
+        let bytes = bincode::serialize(&proof).unwrap();
+        assert_eq!(bincode::deserialize::<OrProof>(&bytes).unwrap(), proof);
+
+        // The last response of the last branch, with the high bits set,
+        // is not the canonical encoding of a scalar.
+        let mut bad = bytes.clone();
+        let len = bad.len();
+        bad[len - 32..].copy_from_slice(&[0xff; 32]);
+        assert!(bincode::deserialize::<OrProof>(&bad).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+#![deny(missing_docs)]
+
+//! The `sizes` module contains the lengths of the crate's encodings, as
+//! constants and `const fn`s, so that protocol specifications, fee
+//! schedules and fixed buffers can be derived from the crate.
+//!
+//! Bitsizes `n` must be powers of two, and the functions are meaningless
+//! otherwise.  Range proofs are not aggregated, so \\(m\\) values take
+//! \\(m\\) proofs and the sizes grow linearly in \\(m\\); there is no
+//! multi-party protocol and so no sizes of its messages.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut buf = [0u8; sizes::range_proof_size(64)];
+//! proof.write_bytes(&mut buf)?;
+//! ```
+
+use envelope;
+use util;
+
+pub use params::MAX_BITSIZE;
+
+/// The length of a compressed Ristretto point.
+pub const POINT_SIZE: usize = 32;
+
+/// The length of a scalar.
+pub const SCALAR_SIZE: usize = 32;
+
+/// The length of a Pedersen commitment.
+pub const COMMITMENT_SIZE: usize = POINT_SIZE;
+
+/// Returns the length of `InnerProductProof::to_bytes` for vectors of
+/// length `n`: \\(2 \lg n\\) points and two scalars.
+pub const fn ipp_size(n: usize) -> usize {
+    2 * util::lg_pow2(n) * POINT_SIZE + 2 * SCALAR_SIZE
+}
+
+/// Returns the length of `RangeProof::to_bytes` for an `n`-bit range:
+/// four points, three scalars and the inner-product argument.
+pub const fn range_proof_size(n: usize) -> usize {
+    4 * POINT_SIZE + 3 * SCALAR_SIZE + ipp_size(n)
+}
+
+/// Returns the total length of the proofs for `m` values in `n`-bit
+/// ranges.
+pub const fn proof_size(n: usize, m: usize) -> usize {
+    m * range_proof_size(n)
+}
+
+/// Returns the length of `ProofEnvelope::to_bytes` for `m` proofs of
+/// `n`-bit ranges.
+pub const fn envelope_size(n: usize, m: usize) -> usize {
+    envelope::V1_HEADER_LEN + proof_size(n, m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use envelope::ProofEnvelope;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use range_proof::RangeProof;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn sizes_match_the_encodings() {
+        assert_eq!(ipp_size(1), 64);
+        assert_eq!(ipp_size(1 << 20), 42 * 32);
+        assert_eq!(range_proof_size(64), 672);
+
+        let mut rng = OsRng::new().unwrap();
+        for &n in [8, 16, 32, 64].iter() {
+            let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+            let v_blinding = Scalar::random(&mut rng);
+            let mut transcript = ProofTranscript::new(b"SizesTest").unwrap();
+            let proof = RangeProof::generate_proof(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &SecretValue::new(1),
+                &Blinding::new(v_blinding),
+            ).unwrap();
+            assert_eq!(proof.to_bytes().len(), range_proof_size(n));
+            let envelope = ProofEnvelope::new(n, vec![proof.clone(), proof]).unwrap();
+            assert_eq!(envelope.to_bytes().len(), envelope_size(n, 2));
+        }
+    }
+}
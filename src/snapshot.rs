@@ -0,0 +1,298 @@
+#![deny(missing_docs)]
+
+//! The `snapshot` module contains `TranscriptSnapshot`, an initial
+//! transcript state that can be shipped to verifiers as bytes.
+//!
+//! A coordinator distributing verification across a fleet ships each
+//! shard the generators, with `Generators::to_bytes`, and the state the
+//! transcript is in before the proof is read, with
+//! `TranscriptSnapshot::to_bytes`.  Both encodings end in an integrity
+//! hash that decoding checks, and the shards can report
+//! `Generators::integrity_hash` and `TranscriptSnapshot::integrity_hash`
+//! back, so the coordinator can check that they all use identical
+//! parameters.
+//!
+//! The Keccak sponge of a `ProofTranscript` cannot be exported, so a
+//! snapshot records the label and the messages committed after it, and
+//! replays them into a fresh transcript.  A snapshot therefore only
+//! covers the commitments made before the first challenge, which is the
+//! state a verifier starts from.
+//!
+//! With serde, a snapshot serializes as the bytes of `to_bytes`, and
+//! deserializing it checks them with `from_bytes`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut snapshot = TranscriptSnapshot::new(b"Ledger")?;
+//! snapshot.commit_u64(block_height);
+//! let params = (generators.to_bytes(), snapshot.to_bytes());
+//!
+//! // On each shard:
+//! let generators = Generators::from_bytes(&params.0)?;
+//! let mut transcript = TranscriptSnapshot::from_bytes(&params.1)?.transcript();
+//! proof.verify(&V, generators.share(0), &mut transcript, &mut rng, n)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha512};
+
+use errors::ProofError;
+use proof_transcript::{length_prefix, ProofTranscript, LONG_MESSAGE, MAX_LENGTH_PREFIX};
+
+/// The length of the integrity hash of a `TranscriptSnapshot`.
+pub const INTEGRITY_HASH_LEN: usize = 32;
+
+/// The label of a transcript and the messages committed to it, before
+/// any challenge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptSnapshot {
+    /// The label the transcript was created with
+    label: Vec<u8>,
+    /// The committed messages, in order
+    messages: Vec<Vec<u8>>,
+}
+
+impl TranscriptSnapshot {
+    /// Begins a snapshot of a transcript created with `label`.
+    ///
+    /// Returns `EmptyLabel` if `label` is empty, as `ProofTranscript::new`
+    /// would.
+    pub fn new(label: &[u8]) -> Result<Self, ProofError> {
+        if label.is_empty() {
+            return Err(ProofError::EmptyLabel);
+        }
+        Ok(TranscriptSnapshot {
+            label: label.to_vec(),
+            messages: Vec::new(),
+        })
+    }
+
+    /// Records the commitment of `message`.
+    pub fn commit(&mut self, message: &[u8]) {
+        self.messages.push(message.to_vec());
+    }
+
+    /// Records the commitment of the little-endian bytes of `value`, as
+    /// `ProofTranscript::commit_u64` does.
+    pub fn commit_u64(&mut self, value: u64) {
+        let mut value_bytes = [0u8; 8];
+        LittleEndian::write_u64(&mut value_bytes, value);
+        self.commit(&value_bytes);
+    }
+
+    /// Returns the label the transcript is created with.
+    pub fn label(&self) -> &[u8] {
+        &self.label
+    }
+
+    /// Returns the committed messages, in order.
+    pub fn messages(&self) -> &[Vec<u8>] {
+        &self.messages
+    }
+
+    /// Creates a transcript in the recorded state.
+    pub fn transcript(&self) -> ProofTranscript {
+        // The label was checked when the snapshot was made or decoded.
+        let mut transcript = ProofTranscript::labeled(&self.label);
+        for message in self.messages.iter() {
+            transcript.commit(message);
+        }
+        transcript
+    }
+
+    /// Serializes the snapshot.
+    ///
+    /// The encoding is the label and then each message, each prefixed
+    /// with its length as in the transcript, and finally the
+    /// `integrity_hash` of everything before it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let records = Some(&self.label).into_iter().chain(self.messages.iter());
+        let len = records.clone().map(|m| MAX_LENGTH_PREFIX + m.len()).sum::<usize>();
+        let mut buf = Vec::with_capacity(len + INTEGRITY_HASH_LEN);
+        for record in records {
+            let mut len_prefix = [0u8; MAX_LENGTH_PREFIX];
+            let prefix_len = length_prefix(record.len(), &mut len_prefix);
+            buf.extend_from_slice(&len_prefix[..prefix_len]);
+            buf.extend_from_slice(record);
+        }
+        let hash = hash_encoding(&buf);
+        buf.extend_from_slice(&hash);
+        buf
+    }
+
+    /// Deserializes a snapshot from the encoding of `to_bytes`.
+    ///
+    /// Returns an error if the integrity hash does not match, if a
+    /// length prefix runs past the end of the encoding, or if the label
+    /// is empty.
+    pub fn from_bytes(slice: &[u8]) -> Result<TranscriptSnapshot, ProofError> {
+        if slice.len() < 2 + INTEGRITY_HASH_LEN {
+            return Err(ProofError::FormatError);
+        }
+        let (mut body, hash) = slice.split_at(slice.len() - INTEGRITY_HASH_LEN);
+        if hash_encoding(body)[..] != hash[..] {
+            return Err(ProofError::IntegrityError);
+        }
+
+        let mut records = Vec::new();
+        while !body.is_empty() {
+            let (len, rest) = read_length_prefix(body)?;
+            records.push(rest[..len].to_vec());
+            body = &rest[len..];
+        }
+        let label = records.remove(0);
+        if label.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(TranscriptSnapshot {
+            label,
+            messages: records,
+        })
+    }
+
+    /// Returns a hash of the encoding of the snapshot, which verifiers
+    /// can compare to check they start from identical transcripts.
+    pub fn integrity_hash(&self) -> [u8; INTEGRITY_HASH_LEN] {
+        let bytes = self.to_bytes();
+        let mut hash = [0u8; INTEGRITY_HASH_LEN];
+        hash.copy_from_slice(&bytes[bytes.len() - INTEGRITY_HASH_LEN..]);
+        hash
+    }
+}
+
+impl Serialize for TranscriptSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for TranscriptSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(SnapshotVisitor)
+    }
+}
+
+/// Reads the encoding of a snapshot, as bytes or as a sequence.
+struct SnapshotVisitor;
+
+impl<'de> Visitor<'de> for SnapshotVisitor {
+    type Value = TranscriptSnapshot;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the encoding of a transcript snapshot")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<TranscriptSnapshot, E> {
+        TranscriptSnapshot::from_bytes(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TranscriptSnapshot, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        TranscriptSnapshot::from_bytes(&bytes).map_err(A::Error::custom)
+    }
+}
+
+/// Reads the length prefix at the start of `body`, returning the length
+/// and the rest of `body`, which is checked to be at least that long.
+fn read_length_prefix(body: &[u8]) -> Result<(usize, &[u8]), ProofError> {
+    if body.len() < 2 {
+        return Err(ProofError::FormatError);
+    }
+    let (len, rest) = match LittleEndian::read_u16(&body[..2]) {
+        LONG_MESSAGE if body.len() < MAX_LENGTH_PREFIX => return Err(ProofError::FormatError),
+        LONG_MESSAGE => (
+            LittleEndian::read_u64(&body[2..MAX_LENGTH_PREFIX]),
+            &body[MAX_LENGTH_PREFIX..],
+        ),
+        len => (len as u64, &body[2..]),
+    };
+    if len > rest.len() as u64 {
+        return Err(ProofError::FormatError);
+    }
+    Ok((len as usize, rest))
+}
+
+/// Returns the first `INTEGRITY_HASH_LEN` bytes of a domain-separated
+/// SHA-512 hash of the encoding `body` of a snapshot.
+fn hash_encoding(body: &[u8]) -> [u8; INTEGRITY_HASH_LEN] {
+    let mut hasher = Sha512::default();
+    hasher.input(b"Bulletproofs.TranscriptSnapshot.IntegrityHash");
+    hasher.input(body);
+    let mut hash = [0u8; INTEGRITY_HASH_LEN];
+    hash.copy_from_slice(&hasher.result()[..INTEGRITY_HASH_LEN]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_replay_the_transcript() {
+        let mut snapshot = TranscriptSnapshot::new(b"SnapshotTest").unwrap();
+        snapshot.commit(b"");
+        snapshot.commit_u64(7);
+
+        let mut expected = ProofTranscript::new(b"SnapshotTest").unwrap();
+        expected.commit(b"");
+        expected.commit_u64(7);
+
+        let mut bytes = snapshot.to_bytes();
+        let decoded = TranscriptSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(
+            decoded.transcript().challenge_scalar(),
+            expected.challenge_scalar()
+        );
+
+        bytes[3] ^= 1;
+        assert!(TranscriptSnapshot::from_bytes(&bytes).is_err());
+        let other = TranscriptSnapshot::new(b"Other").unwrap();
+        assert!(other.integrity_hash() != snapshot.integrity_hash());
+        assert_eq!(TranscriptSnapshot::new(b""), Err(ProofError::EmptyLabel));
+    }
+
+    #[test]
+    fn long_messages_are_recorded() {
+        let long = vec![7u8; 70000];
+        let mut snapshot = TranscriptSnapshot::new(b"SnapshotTest").unwrap();
+        snapshot.commit(&long);
+        snapshot.commit(&long[..65535]);
+        snapshot.commit(b"short");
+
+        let mut expected = ProofTranscript::new(b"SnapshotTest").unwrap();
+        expected.commit(&long);
+        expected.commit(&long[..65535]);
+        expected.commit(b"short");
+
+        let bytes = snapshot.to_bytes();
+        let decoded = TranscriptSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(
+            decoded.transcript().challenge_scalar(),
+            expected.challenge_scalar()
+        );
+
+        // A long prefix claiming more bytes than there are.
+        let mut body = vec![12, 0];
+        body.extend_from_slice(b"SnapshotTest");
+        body.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        let hash = hash_encoding(&body);
+        body.extend_from_slice(&hash);
+        assert_eq!(
+            TranscriptSnapshot::from_bytes(&body),
+            Err(ProofError::FormatError)
+        );
+    }
+}
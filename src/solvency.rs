@@ -0,0 +1,289 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `solvency` module contains a Provisions-style proof that an
+//! exchange's committed customer balances add up to at most its
+//! committed reserves.
+//!
+//! The exchange publishes a commitment \\(C\_i = b\_i B + r\_i \widetilde{B}\\)
+//! to each customer balance \\(b\_i\\), and a commitment \\(R\\) to its
+//! reserves.  The proof contains a range proof for every \\(C\_i\\), so
+//! that no balance is negative, and a range proof for the surplus
+//! \\(R - \sum\_i C\_i\\).  Each customer receives the opening of its
+//! own commitment, and checks its inclusion with `verify_inclusion`.
+//!
+//! With \\(k\\) balances of \\(n\\) bits, \\(k 2^n\\) is far below the
+//! group order, so the sum of the balances cannot wrap around.
+//!
+//! Proving that the reserve commitment opens to assets the exchange
+//! actually controls is outside the scope of this module.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Solvency")?;
+//! let (proof, liabilities) = SolvencyProof::prove(
+//!     generators.share(0), &mut transcript, &mut rng, 32,
+//!     &balances, &reserve, &reserve_blinding,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Solvency")?;
+//! proof.verify(generators.share(0), &mut transcript, &mut rng, 32, &liabilities, &R)?;
+//!
+//! // Customer i checks that its balance was included.
+//! let (ref balance, ref blinding) = balances[i];
+//! assert!(solvency::verify_inclusion(&pg, &liabilities, i, balance, blinding));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use generators::{GeneratorsView, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// A proof that committed liabilities are covered by committed
+/// reserves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SolvencyProof {
+    /// Range proofs for each of the balance commitments
+    balance_proofs: Vec<RangeProof>,
+    /// Range proof for the surplus of the reserves over the liabilities
+    surplus_proof: RangeProof,
+}
+
+impl SolvencyProof {
+    /// Proves that the `n`-bit `balances`, given with their blinding
+    /// factors, add up to at most the `reserve` committed with
+    /// `reserve_blinding`.
+    ///
+    /// Returns the proof and the balance commitments, to be published.
+    /// Returns `ProofError::ValueOutOfRange` if a balance or the surplus
+    /// does not fit in `n` bits, or if the liabilities exceed the
+    /// reserve.  An unsupported `n` is rejected as by
+    /// `RangeProof::generate_proof`.
+    pub fn prove<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        balances: &[(SecretValue, Blinding)],
+        reserve: &SecretValue,
+        reserve_blinding: &Blinding,
+    ) -> Result<(SolvencyProof, Vec<RistrettoPoint>), ProofError> {
+        let fits = |x: u64| n == 64 || x < (1u64 << n);
+
+        let total = balances
+            .iter()
+            .fold(Some(0u64), |acc, &(ref b, _)| {
+                acc.and_then(|acc| acc.checked_add(b.value()))
+            })
+            .ok_or(ProofError::ValueOutOfRange)?;
+        let surplus = reserve
+            .value()
+            .checked_sub(total)
+            .ok_or(ProofError::ValueOutOfRange)?;
+        let surplus = SecretValue::new(surplus);
+        if !balances.iter().all(|&(ref b, _)| fits(b.value())) || !fits(surplus.value()) {
+            return Err(ProofError::ValueOutOfRange);
+        }
+
+        let pg = generators.pedersen_generators;
+        let liabilities: Vec<RistrettoPoint> = balances
+            .iter()
+            .map(|&(ref b, ref r)| pg.commit(b.to_scalar(), *r.as_scalar()))
+            .collect();
+        let R = pg.commit(reserve.to_scalar(), *reserve_blinding.as_scalar());
+        transcript_prefix(transcript, &liabilities, &R);
+
+        let balance_proofs = balances
+            .iter()
+            .map(|&(ref b, ref r)| RangeProof::generate_proof(generators, transcript, rng, n, b, r))
+            .collect::<Result<_, _>>()?;
+
+        let surplus_blinding = Blinding::new(
+            balances
+                .iter()
+                .fold(*reserve_blinding.as_scalar(), |acc, &(_, ref r)| acc - r.as_scalar()),
+        );
+        let surplus_proof = RangeProof::generate_proof(
+            generators,
+            transcript,
+            rng,
+            n,
+            &surplus,
+            &surplus_blinding,
+        )?;
+
+        let proof = SolvencyProof {
+            balance_proofs,
+            surplus_proof,
+        };
+        Ok((proof, liabilities))
+    }
+
+    /// Verifies that the `liabilities` commit to `n`-bit balances,
+    /// whose sum is at most the value committed in `reserve`.
+    ///
+    /// Returns `ProofError::WrongNumCommitments` if the number of range
+    /// proofs does not match the number of liabilities.
+    pub fn verify<R: Rng>(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        liabilities: &[RistrettoPoint],
+        reserve: &RistrettoPoint,
+    ) -> Result<(), ProofError> {
+        if self.balance_proofs.len() != liabilities.len() {
+            return Err(ProofError::WrongNumCommitments);
+        }
+        transcript_prefix(transcript, liabilities, reserve);
+
+        for (proof, C) in self.balance_proofs.iter().zip(liabilities.iter()) {
+            proof.verify(C, generators, transcript, rng, n)?;
+        }
+
+        let surplus = liabilities.iter().fold(*reserve, |acc, C| acc - C);
+        self.surplus_proof
+            .verify(&surplus, generators, transcript, rng, n)
+    }
+}
+
+/// Checks that the customer whose balance `balance` was committed with
+/// `blinding` appears at position `index` of the published
+/// `liabilities`.
+pub fn verify_inclusion(
+    pg: &PedersenGenerators,
+    liabilities: &[RistrettoPoint],
+    index: usize,
+    balance: &SecretValue,
+    blinding: &Blinding,
+) -> bool {
+    liabilities
+        .get(index)
+        .map_or(false, |C| *C == pg.commit(balance.to_scalar(), *blinding.as_scalar()))
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(
+    transcript: &mut ProofTranscript,
+    liabilities: &[RistrettoPoint],
+    reserve: &RistrettoPoint,
+) {
+    transcript.commit(b"Solvency");
+    transcript.commit_u64(liabilities.len() as u64);
+    for C in liabilities.iter() {
+        transcript.commit(C.compress().as_bytes());
+    }
+    transcript.commit(reserve.compress().as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::Generators;
+    use rand::OsRng;
+
+    fn prove_and_verify(balances: &[u64], reserve: u64) -> Result<(), ProofError> {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let balances: Vec<(SecretValue, Blinding)> = balances
+            .iter()
+            .map(|&b| (SecretValue::new(b), Blinding::random(&mut rng)))
+            .collect();
+        let reserve_blinding = Blinding::random(&mut rng);
+        let R = generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(reserve), *reserve_blinding.as_scalar());
+
+        let mut transcript = ProofTranscript::new(b"SolvencyTest").unwrap();
+        let (proof, liabilities) = SolvencyProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &balances,
+            &SecretValue::new(reserve),
+            &reserve_blinding,
+        )?;
+
+        for (i, &(ref b, ref r)) in balances.iter().enumerate() {
+            let pg = generators.share(0).pedersen_generators;
+            assert!(verify_inclusion(pg, &liabilities, i, b, r));
+            let wrong = SecretValue::new(b.value() + 1);
+            assert!(!verify_inclusion(pg, &liabilities, i, &wrong, r));
+        }
+
+        let mut transcript = ProofTranscript::new(b"SolvencyTest").unwrap();
+        proof.verify(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &liabilities,
+            &R,
+        )
+    }
+
+    #[test]
+    fn solvent_exchange_verifies() {
+        assert!(prove_and_verify(&[100, 250, 0, 4000], 5000).is_ok());
+        assert!(prove_and_verify(&[100, 250, 0, 4000], 4350).is_ok());
+        assert!(prove_and_verify(&[], 0).is_ok());
+    }
+
+    #[test]
+    fn insolvent_exchange_is_rejected() {
+        assert_eq!(
+            prove_and_verify(&[100, 250, 0, 4000], 4349),
+            Err(ProofError::ValueOutOfRange)
+        );
+        // The surplus does not fit in 16 bits.
+        assert_eq!(prove_and_verify(&[100], 1 << 20), Err(ProofError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn proof_does_not_verify_for_a_smaller_reserve() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let balances = [
+            (SecretValue::new(300), Blinding::random(&mut rng)),
+            (SecretValue::new(400), Blinding::random(&mut rng)),
+        ];
+        let reserve_blinding = Blinding::random(&mut rng);
+
+        let mut transcript = ProofTranscript::new(b"SolvencyTest").unwrap();
+        let (proof, liabilities) = SolvencyProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &balances,
+            &SecretValue::new(1000),
+            &reserve_blinding,
+        ).unwrap();
+
+        let R = generators
+            .share(0)
+            .pedersen_generators
+            .commit(Scalar::from_u64(500), *reserve_blinding.as_scalar());
+        let mut transcript = ProofTranscript::new(b"SolvencyTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, &mut rng, n, &liabilities, &R)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,280 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `strategies` module contains `proptest` strategies for property
+//! tests of code built on range proofs.
+//!
+//! The strategies generate valid statements (a bitsize \\(n\\), a value
+//! in \\([0, 2^n)\\) and a blinding factor, or \\(m\\) of them for a
+//! bundle) and proofs of them, and `corrupt` changes a single field of a
+//! proof, so that a property like "any corruption fails verification"
+//! needs no proof construction of its own.  Proofs are made with
+//! generators and a transcript for the domain `LABEL`, from a ChaCha20 generator seeded by the
+//! strategy, so failing cases shrink and replay deterministically.
+//!
+//! # Example
+//!
+//! ```ascii
+//! proptest! {
+//!     #[test]
+//!     fn corrupted_proofs_fail((statement, proof, _) in strategies::corrupted_range_proof()) {
+//!         prop_assert!(statement.verify(&proof).is_err());
+//!     }
+//! }
+//! ```
+
+use proptest::collection;
+use proptest::prelude::*;
+
+use rand::{ChaChaRng, SeedableRng};
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use errors::ProofError;
+use generators::{Generators, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// The transcript label of the generated proofs.
+pub const LABEL: &[u8] = b"Bulletproofs.Strategies";
+
+/// A range statement with its witness.
+#[derive(Clone, Debug)]
+pub struct RangeStatement {
+    /// The bitsize of the range
+    pub n: usize,
+    /// The value, in \\([0, 2^n)\\)
+    pub value: u64,
+    /// The blinding factor of the commitment
+    pub blinding: Scalar,
+}
+
+impl RangeStatement {
+    /// Returns the commitment to the value, with the default Pedersen
+    /// generators.
+    pub fn commitment(&self) -> RistrettoPoint {
+        PedersenGenerators::default().commit(Scalar::from_u64(self.value), self.blinding)
+    }
+
+    /// Proves the statement, drawing the prover's randomness from a
+    /// ChaCha20 generator seeded with `seed`.
+    ///
+    /// Returns `InvalidBitsize` if `n` is not a supported bitsize.
+    pub fn prove(&self, seed: &[u32; 8]) -> Result<RangeProof, ProofError> {
+        let generators = Generators::new(LABEL, PedersenGenerators::default(), self.n, 1);
+        let mut rng = ChaChaRng::from_seed(&seed[..]);
+        let mut transcript = ProofTranscript::new(LABEL).expect("the label is not empty");
+        RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            self.n,
+            &SecretValue::new(self.value),
+            &Blinding::new(self.blinding),
+        )
+    }
+
+    /// Verifies `proof` against the commitment to the value, with the
+    /// verifier's weights drawn from an unseeded ChaCha20 generator so
+    /// that failures replay.
+    pub fn verify(&self, proof: &RangeProof) -> Result<(), ProofError> {
+        let generators = Generators::new(LABEL, PedersenGenerators::default(), self.n, 1);
+        let mut rng = ChaChaRng::new_unseeded();
+        let mut transcript = ProofTranscript::new(LABEL).expect("the label is not empty");
+        proof.verify(
+            &self.commitment(),
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            self.n,
+        )
+    }
+}
+
+/// A field of a range proof, for `corrupt`.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProofField {
+    /// The commitment \\(A\\)
+    A,
+    /// The commitment \\(S\\)
+    S,
+    /// The commitment \\(T\_1\\)
+    T_1,
+    /// The commitment \\(T\_2\\)
+    T_2,
+    /// The evaluation \\(t(x)\\)
+    t_x,
+    /// The blinding factor \\(\tilde{t}(x)\\)
+    t_x_blinding,
+    /// The blinding factor \\(\tilde{e}\\)
+    e_blinding,
+    /// The inner-product argument's \\(L\_j\\)
+    L(usize),
+    /// The inner-product argument's \\(R\_j\\)
+    R(usize),
+    /// The inner-product argument's final \\(a\\)
+    a,
+    /// The inner-product argument's final \\(b\\)
+    b,
+}
+
+/// Returns a copy of `proof` with `field` changed: points have the
+/// basepoint added and scalars one added.
+///
+/// Panics if `field` is an \\(L\_j\\) or \\(R\_j\\) the proof does not
+/// have.
+pub fn corrupt(proof: &RangeProof, field: ProofField) -> RangeProof {
+    let (mut c, ipp_proof) = proof.parts();
+    let mut ipp_proof = ipp_proof.clone();
+    let B = RISTRETTO_BASEPOINT_POINT;
+    match field {
+        ProofField::A => c.A = c.A + B,
+        ProofField::S => c.S = c.S + B,
+        ProofField::T_1 => c.T_1 = c.T_1 + B,
+        ProofField::T_2 => c.T_2 = c.T_2 + B,
+        ProofField::t_x => c.t_x = c.t_x + Scalar::one(),
+        ProofField::t_x_blinding => c.t_x_blinding = c.t_x_blinding + Scalar::one(),
+        ProofField::e_blinding => c.e_blinding = c.e_blinding + Scalar::one(),
+        ProofField::L(j) => ipp_proof.L_vec[j] = ipp_proof.L_vec[j] + B,
+        ProofField::R(j) => ipp_proof.R_vec[j] = ipp_proof.R_vec[j] + B,
+        ProofField::a => ipp_proof.a = ipp_proof.a + Scalar::one(),
+        ProofField::b => ipp_proof.b = ipp_proof.b + Scalar::one(),
+    }
+    RangeProof::from_parts(c, ipp_proof)
+}
+
+/// Generates the supported bitsizes 8, 16, 32 and 64.
+pub fn bitsize() -> BoxedStrategy<usize> {
+    prop_oneof![Just(8), Just(16), Just(32), Just(64)].boxed()
+}
+
+/// Generates uniformly random scalars.
+pub fn scalar() -> BoxedStrategy<Scalar> {
+    prop::array::uniform32(any::<u8>())
+        .prop_map(Scalar::from_bytes_mod_order)
+        .boxed()
+}
+
+/// Generates values in \\([0, 2^n)\\).
+pub fn value(n: usize) -> BoxedStrategy<u64> {
+    let mask = if n == 64 { u64::max_value() } else { (1 << n) - 1 };
+    any::<u64>().prop_map(move |v| v & mask).boxed()
+}
+
+/// Generates range statements of any supported bitsize.
+pub fn range_statement() -> BoxedStrategy<RangeStatement> {
+    bitsize().prop_flat_map(range_statement_for).boxed()
+}
+
+/// Generates range statements of `n` bits.
+pub fn range_statement_for(n: usize) -> BoxedStrategy<RangeStatement> {
+    (value(n), scalar())
+        .prop_map(move |(value, blinding)| RangeStatement { n, value, blinding })
+        .boxed()
+}
+
+/// Generates statements with their proofs.
+pub fn range_proof() -> BoxedStrategy<(RangeStatement, RangeProof)> {
+    (range_statement(), seed())
+        .prop_map(|(statement, seed)| {
+            let proof = statement
+                .prove(&seed)
+                .expect("the bitsizes are supported");
+            (statement, proof)
+        })
+        .boxed()
+}
+
+/// Generates the fields of a proof of an `n`-bit range.
+pub fn proof_field(n: usize) -> BoxedStrategy<ProofField> {
+    let rounds = n.trailing_zeros() as usize;
+    prop_oneof![
+        Just(ProofField::A),
+        Just(ProofField::S),
+        Just(ProofField::T_1),
+        Just(ProofField::T_2),
+        Just(ProofField::t_x),
+        Just(ProofField::t_x_blinding),
+        Just(ProofField::e_blinding),
+        (0..rounds).prop_map(ProofField::L),
+        (0..rounds).prop_map(ProofField::R),
+        Just(ProofField::a),
+        Just(ProofField::b),
+    ].boxed()
+}
+
+/// Generates statements with proofs of them that have one field
+/// corrupted, and the field.
+pub fn corrupted_range_proof() -> BoxedStrategy<(RangeStatement, RangeProof, ProofField)> {
+    range_proof()
+        .prop_flat_map(|(statement, proof)| {
+            let n = statement.n;
+            (Just(statement), Just(proof), proof_field(n))
+        })
+        .prop_map(|(statement, proof, field)| {
+            let corrupted = corrupt(&proof, field);
+            (statement, corrupted, field)
+        })
+        .boxed()
+}
+
+/// Generates between 1 and `max_m` statements of one bitsize, with a
+/// bundle of proofs of them.
+pub fn proof_bundle(max_m: usize) -> BoxedStrategy<(Vec<RangeStatement>, ProofBundle)> {
+    bitsize()
+        .prop_flat_map(move |n| (collection::vec(range_statement_for(n), 1..max_m + 1), seed()))
+        .prop_map(|(statements, seed)| {
+            let n = statements[0].n;
+            let generators = Generators::new(LABEL, PedersenGenerators::default(), n, 1);
+            let openings: Vec<(SecretValue, Blinding)> = statements
+                .iter()
+                .map(|s| (SecretValue::new(s.value), Blinding::new(s.blinding)))
+                .collect();
+            let mut rng = ChaChaRng::from_seed(&seed[..]);
+            let mut transcript = ProofTranscript::new(LABEL).expect("the label is not empty");
+            let (bundle, _) = ProofBundle::prove_many(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &openings,
+            ).expect("the bitsizes are supported");
+            (statements, bundle)
+        })
+        .boxed()
+}
+
+/// Generates a position in an encoding of `len` bytes and a non-zero
+/// mask to XOR into the byte there.
+pub fn byte_corruption(len: usize) -> BoxedStrategy<(usize, u8)> {
+    (0..len, 0..255u8).prop_map(|(i, m)| (i, m + 1)).boxed()
+}
+
+/// Generates seeds for the prover's random number generator.
+fn seed() -> BoxedStrategy<[u32; 8]> {
+    prop::array::uniform8(any::<u32>()).boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn generated_proofs_verify((statement, proof) in range_proof()) {
+            prop_assert!(statement.verify(&proof).is_ok());
+        }
+
+        #[test]
+        fn corrupted_proofs_fail((statement, proof, _) in corrupted_range_proof()) {
+            prop_assert!(statement.verify(&proof).is_err());
+        }
+    }
+}
@@ -0,0 +1,163 @@
+#![allow(non_snake_case)]
+
+//! Streaming serialization of range proofs over `io::Read` and
+//! `io::Write`, for proofs embedded in larger encodings.
+//!
+//! The format is that of `RangeProof::to_bytes`, written and read one
+//! 32-byte component at a time, so no intermediate buffer is built.
+//! The encoding carries no length, so the reader is told the bitsize.
+
+use std::io::{self, Read, Write};
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use inner_product_proof::InnerProductProof;
+use params::ProofParams;
+use range_proof::{RangeCommitments, RangeProof};
+use util;
+
+fn write_point<W: Write>(P: &RistrettoPoint, writer: &mut W) -> io::Result<()> {
+    writer.write_all(P.compress().as_bytes())
+}
+
+fn write_scalar<W: Write>(s: &Scalar, writer: &mut W) -> io::Result<()> {
+    writer.write_all(s.as_bytes())
+}
+
+fn read_point<R: Read>(reader: &mut R) -> io::Result<RistrettoPoint> {
+    let mut buf = [0u8; 32];
+    reader.read_exact(&mut buf)?;
+    util::read_point(&buf).map_err(|_| invalid("invalid point"))
+}
+
+fn read_scalar<R: Read>(reader: &mut R) -> io::Result<Scalar> {
+    let mut buf = [0u8; 32];
+    reader.read_exact(&mut buf)?;
+    util::read_scalar(&buf).map_err(|_| invalid("non-canonical scalar"))
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl InnerProductProof {
+    /// Writes the proof to `writer` in the encoding of `to_bytes`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            write_point(L, writer)?;
+            write_point(R, writer)?;
+        }
+        write_scalar(&self.a, writer)?;
+        write_scalar(&self.b, writer)
+    }
+
+    /// Reads a proof of `rounds` rounds written by `write_to`.
+    ///
+    /// Returns an error of kind `InvalidInput` if `rounds` is more than
+    /// the 32 that `from_bytes` accepts, and of kind `InvalidData` if a
+    /// point or scalar is not a canonical encoding.
+    pub fn read_from<R: Read>(reader: &mut R, rounds: usize) -> io::Result<InnerProductProof> {
+        if rounds > 32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many rounds"));
+        }
+        let mut L_vec = Vec::with_capacity(rounds);
+        let mut R_vec = Vec::with_capacity(rounds);
+        for _ in 0..rounds {
+            L_vec.push(read_point(reader)?);
+            R_vec.push(read_point(reader)?);
+        }
+        Ok(InnerProductProof {
+            L_vec,
+            R_vec,
+            a: read_scalar(reader)?,
+            b: read_scalar(reader)?,
+        })
+    }
+}
+
+impl RangeProof {
+    /// Writes the proof to `writer` in the encoding of `to_bytes`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (c, ipp_proof) = self.parts();
+        for P in [c.A, c.S, c.T_1, c.T_2].iter() {
+            write_point(P, writer)?;
+        }
+        for s in [c.t_x, c.t_x_blinding, c.e_blinding].iter() {
+            write_scalar(s, writer)?;
+        }
+        ipp_proof.write_to(writer)
+    }
+
+    /// Reads a proof of an `n`-bit range written by `write_to`, leaving
+    /// the reader just past it.
+    ///
+    /// Returns an error of kind `InvalidInput` if `n` is not a power of
+    /// two of at most 64, and of kind `InvalidData` if a point or scalar
+    /// is not a canonical encoding.
+    pub fn read_from<R: Read>(reader: &mut R, n: usize) -> io::Result<RangeProof> {
+        if ProofParams::single(n).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "n must be a power of two of at most 64",
+            ));
+        }
+        let commitments = RangeCommitments {
+            A: read_point(reader)?,
+            S: read_point(reader)?,
+            T_1: read_point(reader)?,
+            T_2: read_point(reader)?,
+            t_x: read_scalar(reader)?,
+            t_x_blinding: read_scalar(reader)?,
+            e_blinding: read_scalar(reader)?,
+        };
+        let ipp_proof = InnerProductProof::read_from(reader, n.trailing_zeros() as usize)?;
+        Ok(RangeProof::from_parts(commitments, ipp_proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use proof_transcript::ProofTranscript;
+    use rand::OsRng;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn proofs_stream_through_io() {
+        let n = 16;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let mut transcript = ProofTranscript::new(b"StreamingTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(3),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        // The proof is embedded between other fields of a transaction.
+        let mut tx = b"header".to_vec();
+        proof.write_to(&mut tx).unwrap();
+        tx.extend_from_slice(b"trailer");
+        assert_eq!(&tx[6..tx.len() - 7], &proof.to_bytes()[..]);
+
+        let mut reader = &tx[6..];
+        assert_eq!(RangeProof::read_from(&mut reader, n).unwrap(), proof);
+        assert_eq!(reader, b"trailer");
+
+        // An unsupported bitsize is rejected before anything is read.
+        let mut reader = &tx[6..];
+        let err = RangeProof::read_from(&mut reader, 24).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.len(), tx.len() - 6);
+
+        let mut truncated = &tx[6..100];
+        let err = RangeProof::read_from(&mut truncated, n).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
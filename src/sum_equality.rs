@@ -0,0 +1,253 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `sum_equality` module contains a proof that two sets of
+//! commitments hold the same total value.
+//!
+//! For input commitments \\(I\_i\\) and output commitments \\(O\_j\\), the
+//! difference \\( E = \sum\_i I\_i - \sum\_j O\_j \\) commits to the
+//! difference of the sums with the blinding factor
+//! \\( r = \sum\_i r\_{I,i} - \sum\_j r\_{O,j} \\).  The sums are equal
+//! exactly when \\(E = r \widetilde{B}\\), which a Schnorr proof of
+//! knowledge of \\(r\\) shows.  The proof is a single point and scalar,
+//! whatever the number of commitments.
+//!
+//! Unlike a `BalanceProof`, this proves nothing about the individual
+//! values, which can wrap around the group order unless they are range
+//! proven separately.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let mut transcript = ProofTranscript::new(b"Rebalance")?;
+//! let proof = sum_equality::prove_sum_equals(
+//!     &pg, &mut transcript, &mut rng, &inputs, &outputs, &input_openings, &output_openings,
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Rebalance")?;
+//! proof.verify(&pg, &mut transcript, &inputs, &outputs)?;
+//! ```
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use errors::ProofError;
+use generators::PedersenGenerators;
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar, SecretValue};
+use sigma::{SigmaProof, Statement};
+
+/// A proof that two sets of commitments hold the same total value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SumEqualityProof {
+    /// Proof of knowledge of the blinding factor of the difference
+    proof: SigmaProof,
+}
+
+/// Proves that the values committed in `commitments_in` sum to the same
+/// total as those committed in `commitments_out`, given the openings of
+/// the commitments as pairs of a value and a blinding factor.
+///
+/// Returns `ProofError::WrongNumCommitments` if either set is empty or
+/// there are not as many openings as commitments,
+/// `ProofError::InvalidOpening` if an opening does not match its
+/// commitment, and `ProofError::ValueOutOfRange` if the sums differ.
+pub fn prove_sum_equals<R: Rng>(
+    pg: &PedersenGenerators,
+    transcript: &mut ProofTranscript,
+    rng: &mut R,
+    commitments_in: &[RistrettoPoint],
+    commitments_out: &[RistrettoPoint],
+    openings_in: &[(SecretValue, Blinding)],
+    openings_out: &[(SecretValue, Blinding)],
+) -> Result<SumEqualityProof, ProofError> {
+    let (v_in, r_in) = sum_openings(pg, commitments_in, openings_in)?;
+    let (v_out, r_out) = sum_openings(pg, commitments_out, openings_out)?;
+    if v_in != v_out {
+        return Err(ProofError::ValueOutOfRange);
+    }
+
+    let E = excess(commitments_in, commitments_out)?;
+    transcript_prefix(transcript, commitments_in, commitments_out);
+    let proof = SigmaProof::prove(
+        &Statement::dlog(&pg.B_blinding, &E),
+        transcript,
+        rng,
+        &[SecretScalar::new(r_in - r_out)],
+    )?;
+    Ok(SumEqualityProof { proof })
+}
+
+impl SumEqualityProof {
+    /// Verifies that the values committed in `commitments_in` sum to
+    /// the same total as those committed in `commitments_out`, modulo
+    /// the group order.
+    ///
+    /// Returns `ProofError::WrongNumCommitments` if either set is empty.
+    pub fn verify(
+        &self,
+        pg: &PedersenGenerators,
+        transcript: &mut ProofTranscript,
+        commitments_in: &[RistrettoPoint],
+        commitments_out: &[RistrettoPoint],
+    ) -> Result<(), ProofError> {
+        let E = excess(commitments_in, commitments_out)?;
+        transcript_prefix(transcript, commitments_in, commitments_out);
+        self.proof
+            .verify(&Statement::dlog(&pg.B_blinding, &E), transcript)
+    }
+}
+
+/// Checks the `openings` of the `commitments`, returning the sums of
+/// the values and of the blinding factors.
+fn sum_openings(
+    pg: &PedersenGenerators,
+    commitments: &[RistrettoPoint],
+    openings: &[(SecretValue, Blinding)],
+) -> Result<(Scalar, Scalar), ProofError> {
+    if commitments.len() != openings.len() {
+        return Err(ProofError::WrongNumCommitments);
+    }
+    let mut v_sum = Scalar::zero();
+    let mut r_sum = Scalar::zero();
+    for (C, &(ref v, ref r)) in commitments.iter().zip(openings.iter()) {
+        let (v, r) = (v.to_scalar(), *r.as_scalar());
+        if *C != pg.commit(v, r) {
+            return Err(ProofError::InvalidOpening);
+        }
+        v_sum += v;
+        r_sum += r;
+    }
+    Ok((v_sum, r_sum))
+}
+
+/// Returns \\( \sum\_i I\_i - \sum\_j O\_j \\), or an error if either
+/// set is empty.
+fn excess(
+    commitments_in: &[RistrettoPoint],
+    commitments_out: &[RistrettoPoint],
+) -> Result<RistrettoPoint, ProofError> {
+    if commitments_in.is_empty() || commitments_out.is_empty() {
+        return Err(ProofError::WrongNumCommitments);
+    }
+    let sum_in = commitments_in
+        .iter()
+        .fold(RistrettoPoint::identity(), |acc, C| acc + C);
+    let sum_out = commitments_out
+        .iter()
+        .fold(RistrettoPoint::identity(), |acc, C| acc + C);
+    Ok(sum_in - sum_out)
+}
+
+/// Commits the statement to the transcript.
+fn transcript_prefix(
+    transcript: &mut ProofTranscript,
+    commitments_in: &[RistrettoPoint],
+    commitments_out: &[RistrettoPoint],
+) {
+    transcript.commit(b"SumEquality");
+    for commitments in [commitments_in, commitments_out].iter() {
+        transcript.commit_u64(commitments.len() as u64);
+        for C in commitments.iter() {
+            transcript.commit(C.compress().as_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::OsRng;
+
+    fn commit_all<R: Rng>(
+        pg: &PedersenGenerators,
+        rng: &mut R,
+        values: &[u64],
+    ) -> (Vec<RistrettoPoint>, Vec<(SecretValue, Blinding)>) {
+        let openings: Vec<(SecretValue, Blinding)> = values
+            .iter()
+            .map(|&v| (SecretValue::new(v), Blinding::random(rng)))
+            .collect();
+        let commitments = openings
+            .iter()
+            .map(|&(ref v, ref r)| pg.commit(v.to_scalar(), *r.as_scalar()))
+            .collect();
+        (commitments, openings)
+    }
+
+    #[test]
+    fn equal_sums_verify() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let (inputs, input_openings) = commit_all(&pg, &mut rng, &[30, 70]);
+        let (outputs, output_openings) = commit_all(&pg, &mut rng, &[25, 25, 50]);
+
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        let proof = prove_sum_equals(
+            &pg,
+            &mut transcript,
+            &mut rng,
+            &inputs,
+            &outputs,
+            &input_openings,
+            &output_openings,
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        assert!(proof.verify(&pg, &mut transcript, &inputs, &outputs).is_ok());
+
+        // Dropping an output changes the statement.
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        assert!(
+            proof
+                .verify(&pg, &mut transcript, &inputs, &outputs[..2])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn unequal_sums_and_empty_sets_are_rejected() {
+        let pg = PedersenGenerators::default();
+        let mut rng = OsRng::new().unwrap();
+        let (inputs, input_openings) = commit_all(&pg, &mut rng, &[30, 70]);
+        let (outputs, output_openings) = commit_all(&pg, &mut rng, &[25, 50]);
+
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        assert_eq!(
+            prove_sum_equals(
+                &pg,
+                &mut transcript,
+                &mut rng,
+                &inputs,
+                &outputs,
+                &input_openings,
+                &output_openings,
+            ),
+            Err(ProofError::ValueOutOfRange)
+        );
+
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        assert_eq!(
+            prove_sum_equals(&pg, &mut transcript, &mut rng, &[], &[], &[], &[]),
+            Err(ProofError::WrongNumCommitments)
+        );
+
+        // Openings must match the commitments.
+        let mut transcript = ProofTranscript::new(b"SumEqualityTest").unwrap();
+        assert!(
+            prove_sum_equals(
+                &pg,
+                &mut transcript,
+                &mut rng,
+                &inputs,
+                &inputs[..1],
+                &input_openings,
+                &input_openings[1..],
+            ),
+            Err(ProofError::InvalidOpening)
+        );
+    }
+}
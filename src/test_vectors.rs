@@ -0,0 +1,234 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `test_vectors` module generates test vectors for implementations
+//! of the verifier in other languages, as JSON.
+//!
+//! Each vector records the inputs of a proof, the seed of the ChaCha20
+//! generator the prover drew all its randomness from (the blinding
+//! factors first, then the proof's), every challenge of the transcript,
+//! and the proof in the encoding of `RangeProof::to_bytes`.  Byte
+//! strings are hex, points are compressed and scalars little-endian.
+//! An implementation that recomputes the challenges from the proof and
+//! accepts every proof is compatible with this crate.
+//!
+//! The crate has no aggregated or multi-party proofs; bundles of proofs
+//! in one transcript are covered instead.  `cargo run --example
+//! test_vectors --features test-vectors` prints the vectors.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let json = test_vectors::to_json(&test_vectors::generate());
+//! ```
+
+use byteorder::{ByteOrder, LittleEndian};
+use rand::{ChaChaRng, SeedableRng};
+use serde_json;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use bundle::ProofBundle;
+use encoding::to_hex;
+use generators::{Generators, PedersenGenerators};
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+/// The full set of test vectors.
+#[derive(Serialize, Clone, Debug)]
+pub struct TestVectors {
+    /// The Pedersen base \\(B\\)
+    pub B: String,
+    /// The Pedersen blinding base \\(\widetilde{B}\\)
+    pub B_blinding: String,
+    /// Vectors for single range proofs
+    pub range_proofs: Vec<RangeProofVector>,
+    /// Vectors for bundles of range proofs
+    pub bundles: Vec<BundleVector>,
+}
+
+/// A single range proof.
+#[derive(Serialize, Clone, Debug)]
+pub struct RangeProofVector {
+    /// The transcript label
+    pub label: String,
+    /// The bitsize of the range
+    pub n: usize,
+    /// The seed of the prover's random number generator
+    pub seed: String,
+    /// The value
+    pub value: u64,
+    /// The blinding factor of the commitment
+    pub blinding: String,
+    /// The commitment \\(V\\)
+    pub commitment: String,
+    /// The challenges \\(y, z, x, w\\) and \\(u\_1, \ldots, u\_{\lg n}\\)
+    pub challenges: Vec<String>,
+    /// The proof
+    pub proof: String,
+}
+
+/// A bundle of range proofs made in one transcript.
+#[derive(Serialize, Clone, Debug)]
+pub struct BundleVector {
+    /// The transcript label
+    pub label: String,
+    /// The bitsize of the ranges
+    pub n: usize,
+    /// The seed of the prover's random number generator
+    pub seed: String,
+    /// The values
+    pub values: Vec<u64>,
+    /// The blinding factors of the commitments
+    pub blindings: Vec<String>,
+    /// The commitments
+    pub commitments: Vec<String>,
+    /// The proofs, in the order of the commitments
+    pub proofs: Vec<String>,
+}
+
+/// Generates the test vectors.
+///
+/// The vectors are deterministic, so their JSON can be regenerated and
+/// compared with a published copy.
+pub fn generate() -> TestVectors {
+    let pg = PedersenGenerators::default();
+    let mut range_proofs = Vec::new();
+    let mut bundles = Vec::new();
+
+    for (i, &n) in [8, 16, 32, 64].iter().enumerate() {
+        let max = if n == 64 { u64::max_value() } else { (1 << n) - 1 };
+        for (j, &value) in [0, 1, max / 3, max].iter().enumerate() {
+            let seed = [(4 * i + j) as u8; 32];
+            range_proofs.push(range_proof_vector(&pg, n, &seed, value));
+        }
+
+        let seed = [0x80 | i as u8; 32];
+        bundles.push(bundle_vector(&pg, n, &seed, &[max, 0, max / 7]));
+    }
+
+    TestVectors {
+        B: to_hex(pg.B.compress().as_bytes()),
+        B_blinding: to_hex(pg.B_blinding.compress().as_bytes()),
+        range_proofs,
+        bundles,
+    }
+}
+
+/// Encodes the test vectors as pretty-printed JSON.
+pub fn to_json(vectors: &TestVectors) -> String {
+    serde_json::to_string_pretty(vectors).expect("test vectors are serializable")
+}
+
+fn range_proof_vector(
+    pg: &PedersenGenerators,
+    n: usize,
+    seed: &[u8; 32],
+    value: u64,
+) -> RangeProofVector {
+    let label = b"Bulletproofs.TestVector.RangeProof";
+    let generators = Generators::without_domain(pg.clone(), n, 1);
+    let mut rng = rng_from_seed(seed);
+    let blinding = Scalar::random(&mut rng);
+    let V = pg.commit(Scalar::from_u64(value), blinding);
+
+    let mut transcript = ProofTranscript::new(label).expect("the label is not empty");
+    let proof = RangeProof::generate_proof(
+        generators.share(0),
+        &mut transcript,
+        &mut rng,
+        n,
+        &SecretValue::new(value),
+        &Blinding::new(blinding),
+    ).expect("the vectors use supported bitsizes");
+    let mut transcript = ProofTranscript::new(label).expect("the label is not empty");
+    let challenges = proof.challenges(&V, &mut transcript, n);
+
+    RangeProofVector {
+        label: to_hex(label),
+        n,
+        seed: to_hex(seed),
+        value,
+        blinding: to_hex(blinding.as_bytes()),
+        commitment: point_hex(&V),
+        challenges: challenges.iter().map(|c| to_hex(c.as_bytes())).collect(),
+        proof: to_hex(&proof.to_bytes()),
+    }
+}
+
+fn bundle_vector(
+    pg: &PedersenGenerators,
+    n: usize,
+    seed: &[u8; 32],
+    values: &[u64],
+) -> BundleVector {
+    let label = b"Bulletproofs.TestVector.Bundle";
+    let generators = Generators::without_domain(pg.clone(), n, 1);
+    let mut rng = rng_from_seed(seed);
+    let statements: Vec<(SecretValue, Blinding)> = values
+        .iter()
+        .map(|&v| (SecretValue::new(v), Blinding::random(&mut rng)))
+        .collect();
+
+    let mut transcript = ProofTranscript::new(label).expect("the label is not empty");
+    let (bundle, commitments) =
+        ProofBundle::prove_many(generators.share(0), &mut transcript, &mut rng, n, &statements)
+            .expect("the vectors use supported bitsizes");
+
+    BundleVector {
+        label: to_hex(label),
+        n,
+        seed: to_hex(seed),
+        values: values.to_vec(),
+        blindings: statements.iter().map(|s| to_hex(s.1.as_scalar().as_bytes())).collect(),
+        commitments: commitments.iter().map(point_hex).collect(),
+        proofs: bundle.proofs().iter().map(|p| to_hex(&p.to_bytes())).collect(),
+    }
+}
+
+fn point_hex(P: &RistrettoPoint) -> String {
+    to_hex(P.compress().as_bytes())
+}
+
+fn rng_from_seed(seed: &[u8; 32]) -> ChaChaRng {
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(seed.chunks(4)) {
+        *word = LittleEndian::read_u32(chunk);
+    }
+    ChaChaRng::from_seed(&words[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding::from_hex;
+    use rand::OsRng;
+    use range_proof::Verifier;
+    use util;
+
+    #[test]
+    fn vectors_are_deterministic_and_verify() {
+        let vectors = generate();
+        assert_eq!(to_json(&vectors), to_json(&generate()));
+
+        let mut rng = OsRng::new().unwrap();
+        let pg = PedersenGenerators::default();
+        for vector in vectors.range_proofs.iter() {
+            let generators = Generators::without_domain(pg.clone(), vector.n, 1);
+            let proof = RangeProof::from_bytes(&from_hex(&vector.proof).unwrap()).unwrap();
+            let V = util::read_point(&from_hex(&vector.commitment).unwrap()).unwrap();
+            let label = from_hex(&vector.label).unwrap();
+            assert_eq!(vector.challenges.len(), 4 + vector.n.trailing_zeros() as usize);
+
+            let mut transcript = ProofTranscript::new(&label).unwrap();
+            assert!(
+                Verifier::new(generators.share(0), vector.n)
+                    .unwrap()
+                    .verify(&proof, &V, &mut transcript, &mut rng)
+                    .is_ok()
+            );
+        }
+    }
+}
@@ -1,18 +1,27 @@
 #![deny(missing_docs)]
 #![allow(non_snake_case)]
 
-use curve25519_dalek::scalar::Scalar;
-use inner_product_proof::inner_product;
+use core::borrow::Borrow;
+use core::cmp;
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
-/// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
-pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
+use curve25519_dalek::ristretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
 
-/// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
-pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
+use errors::ProofError;
+use profiling;
 
-/// Provides an iterator over the powers of a `Scalar`.
+/// Provides an iterator over the powers of a public `Scalar`.
 ///
-/// This struct is created by the `exp_iter` function.
+/// This struct is created by the `exp_iter` function.  The powers of a
+/// secret are computed with `ct::secret_exp_iter` instead.
 pub struct ScalarExp {
     x: Scalar,
     next_exp_x: Scalar,
@@ -28,17 +37,78 @@ impl Iterator for ScalarExp {
     }
 }
 
-/// Return an iterator of the powers of `x`.
+/// Return an iterator of the powers of the public `x`, such as a
+/// challenge.
 pub fn exp_iter(x: Scalar) -> ScalarExp {
     let next_exp_x = Scalar::one();
     ScalarExp { x, next_exp_x }
 }
 
+/// Caches the first `n` powers \\(1, x, x^2, \ldots, x^{n-1}\\) of a public
+/// `Scalar`, so that they can be shared between several consumers.
+pub struct ScalarPowers {
+    powers: Vec<Scalar>,
+}
+
+impl ScalarPowers {
+    /// Computes the first `n` powers of `x`.
+    pub fn new(x: Scalar, n: usize) -> Self {
+        ScalarPowers {
+            powers: exp_iter(x).take(n).collect(),
+        }
+    }
+
+    /// Returns all of the cached powers.
+    pub fn as_slice(&self) -> &[Scalar] {
+        &self.powers[..]
+    }
+
+    /// Returns the powers \\(x^{n j}, \ldots, x^{n (j+1) - 1}\\)
+    /// belonging to the `j`-th of several `n`-sized shares.
+    pub fn share(&self, j: usize, n: usize) -> &[Scalar] {
+        &self.powers[n * j..n * (j + 1)]
+    }
+
+    /// Returns the sum of the cached powers.
+    pub fn sum(&self) -> Scalar {
+        self.powers.iter().fold(Scalar::zero(), |acc, x| acc + x)
+    }
+}
+
+/// Inverts every element of `inputs` in place using Montgomery's trick,
+/// at the cost of a single field inversion, and returns the inverse of
+/// the product of all the inputs.
+///
+/// All of the inputs must be nonzero.
+pub fn batch_invert(inputs: &mut [Scalar]) -> Scalar {
+    // scratch[i] holds the product of inputs[0..i].
+    let mut scratch = Vec::with_capacity(inputs.len());
+    let mut acc = Scalar::one();
+    for input in inputs.iter() {
+        scratch.push(acc);
+        acc = acc * input;
+    }
+
+    acc = acc.invert();
+    let allinv = acc;
+
+    // acc holds the inverse of the product of inputs[0..=i].
+    for (input, prefix) in inputs.iter_mut().rev().zip(scratch.into_iter().rev()) {
+        let next_acc = acc * *input;
+        *input = acc * prefix;
+        acc = next_acc;
+    }
+
+    allinv
+}
+
+/// Computes the entrywise sum of two vectors.
+///
+/// Panics if the lengths of the vectors are not equal.
 pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
-    let mut out = Vec::new();
+    let mut out = Vec::with_capacity(a.len());
     if a.len() != b.len() {
-        // throw some error
-        println!("lengths of vectors don't match for vector addition");
+        panic!("add_vec(a,b): lengths of vectors do not match");
     }
     for i in 0..a.len() {
         out.push(a[i] + b[i]);
@@ -46,43 +116,262 @@ pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
     out
 }
 
-impl VecPoly1 {
-    pub fn zero(n: usize) -> Self {
-        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
+/// Decodes a 32-byte compressed Ristretto point.
+///
+/// Returns `FormatError` if `bytes` has the wrong length and
+/// `InvalidPoint` if it is not the canonical encoding of a point.
+pub fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, ProofError> {
+    if bytes.len() != 32 {
+        return Err(ProofError::FormatError);
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    ristretto::CompressedRistretto(buf)
+        .decompress()
+        .ok_or(ProofError::InvalidPoint)
+}
+
+/// Decodes a 32-byte scalar.
+///
+/// Returns `FormatError` if `bytes` has the wrong length and
+/// `InvalidScalar` if it is not the canonical encoding of a scalar.
+pub fn read_scalar(bytes: &[u8]) -> Result<Scalar, ProofError> {
+    if bytes.len() != 32 {
+        return Err(ProofError::FormatError);
     }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    let s = Scalar::from_bytes_mod_order(buf);
+    if s.as_bytes() != &buf {
+        return Err(ProofError::InvalidScalar);
+    }
+    Ok(s)
+}
 
-    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
-        // Uses Karatsuba's method
-        let l = self;
-        let r = rhs;
+/// Deserializes a scalar with serde, rejecting encodings that are not
+/// reduced modulo the group order, for `#[serde(deserialize_with)]` on
+/// the scalar fields of proofs.
+///
+/// The 32 bytes are read as `Scalar` serializes them and checked with
+/// `read_scalar`, rather than through `Scalar`'s own `Deserialize`,
+/// which may reduce them.
+pub fn deserialize_scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Scalar, D::Error> {
+    deserializer.deserialize_bytes(ScalarVisitor)
+}
 
-        let t0 = inner_product(&l.0, &r.0);
-        let t2 = inner_product(&l.1, &r.1);
+/// Deserializes a vector of scalars as `deserialize_scalar` does.
+pub fn deserialize_scalars<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Scalar>, D::Error> {
+    let scalars = Vec::<CanonicalScalar>::deserialize(deserializer)?;
+    Ok(scalars.into_iter().map(|s| s.0).collect())
+}
 
-        let l0_plus_l1 = add_vec(&l.0, &l.1);
-        let r0_plus_r1 = add_vec(&r.0, &r.1);
+/// Deserializes a vector of vectors of scalars as `deserialize_scalar`
+/// does.
+pub fn deserialize_scalar_vecs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<Scalar>>, D::Error> {
+    let vecs = Vec::<Vec<CanonicalScalar>>::deserialize(deserializer)?;
+    Ok(vecs
+        .into_iter()
+        .map(|scalars| scalars.into_iter().map(|s| s.0).collect())
+        .collect())
+}
 
-        let t1 = inner_product(&l0_plus_l1, &r0_plus_r1) - t0 - t2;
+/// A scalar deserialized with `deserialize_scalar`.
+struct CanonicalScalar(Scalar);
 
-        Poly2(t0, t1, t2)
+impl<'de> Deserialize<'de> for CanonicalScalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_scalar(deserializer).map(CanonicalScalar)
     }
+}
+
+/// Reads the 32 bytes of a scalar, as bytes or as a sequence.
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = Scalar;
 
-    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
-        let n = self.0.len();
-        let mut out = vec![Scalar::zero(); n];
-        for i in 0..n {
-            out[i] += self.0[i] + self.1[i] * x;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a canonical 32-byte scalar")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Scalar, E> {
+        read_scalar(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Scalar, A::Error> {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(i, &self))?;
         }
-        out
+        read_scalar(&bytes).map_err(A::Error::custom)
     }
 }
 
-impl Poly2 {
-    pub fn eval(&self, x: Scalar) -> Scalar {
-        self.0 + x * (self.1 + x * self.2)
+/// Returns \\(\lg n\\) for a power of two `n`.
+///
+/// This counts the ones of \\(n - 1\\) without branches, loops or
+/// overflow, so that it can be a `const fn`; for other `n` the result
+/// is meaningless.
+pub const fn lg_pow2(n: usize) -> usize {
+    (sum_bytes(count_nibbles(count_pairs(count_bits(n as u64 - 1)))) & 0x7f) as usize
+}
+
+/// Replaces each pair of bits of `x` with the number of its ones.
+const fn count_bits(x: u64) -> u64 {
+    x - ((x >> 1) & 0x5555_5555_5555_5555)
+}
+
+/// Sums the pairs of `count_bits` into nibbles.
+const fn count_pairs(x: u64) -> u64 {
+    (x & 0x3333_3333_3333_3333) + ((x >> 2) & 0x3333_3333_3333_3333)
+}
+
+/// Sums the nibbles of `count_pairs` into bytes.
+const fn count_nibbles(x: u64) -> u64 {
+    (x + (x >> 4)) & 0x0f0f_0f0f_0f0f_0f0f
+}
+
+/// Sums the bytes of `count_nibbles` into the lowest byte.
+const fn sum_bytes(x: u64) -> u64 {
+    x + (x >> 8) + (x >> 16) + (x >> 24) + (x >> 32) + (x >> 40) + (x >> 48) + (x >> 56)
+}
+
+/// Below this many terms, Straus' method is faster than Pippenger's.
+#[cfg(not(feature = "yolocrypto"))]
+const PIPPENGER_THRESHOLD: usize = 190;
+
+/// Below this many terms, Straus' method is faster than Pippenger's.
+///
+/// The AVX2 backend only vectorizes the upstream multiscalar
+/// multiplication, not the serial point additions used by our
+/// Pippenger implementation, so it pays to stay on Straus much longer.
+#[cfg(feature = "yolocrypto")]
+const PIPPENGER_THRESHOLD: usize = 1024;
+
+/// The largest window Pippenger's method uses, in bits.
+///
+/// Each window needs \\(2^w - 1\\) buckets.  On 32-bit targets the
+/// bucket table competes with everything else for a small cache, and
+/// the savings from wider windows are eaten by the slower field
+/// arithmetic, so the window is capped lower there.
+#[cfg(not(feature = "u32_backend"))]
+const PIPPENGER_MAX_WINDOW: usize = 8;
+
+/// The largest window Pippenger's method uses, in bits.
+#[cfg(feature = "u32_backend")]
+const PIPPENGER_MAX_WINDOW: usize = 6;
+
+/// Computes the constant-time multiscalar multiplication
+/// \\( \sum\_i s\_i P\_i \\).
+///
+/// Use this whenever any of the scalars are secret.  The scalars are
+/// passed straight through, so no copy of them is left in a buffer of
+/// this crate; with the `profiling` feature, the points are counted as
+/// they are consumed instead.
+pub fn multiscalar_mul<I, J>(scalars: I, points: J) -> RistrettoPoint
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+    J: IntoIterator,
+    J::Item: Borrow<RistrettoPoint>,
+{
+    #[cfg(feature = "profiling")]
+    let points = points
+        .into_iter()
+        .inspect(|_| profiling::record_scalar_muls(1));
+    ristretto::multiscalar_mul(scalars, points)
+}
+
+/// Computes the variable-time multiscalar multiplication
+/// \\( \sum\_i s\_i P\_i \\).
+///
+/// Uses Straus' method for small inputs and Pippenger's bucket method
+/// for large ones.  This must only be used when all the scalars are public.
+///
+/// Returns `FormatError` if there are more scalars than points or more
+/// points than scalars, which a malformed proof can cause.
+pub fn vartime_multiscalar_mul<I, J>(scalars: I, points: J) -> Result<RistrettoPoint, ProofError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Scalar>,
+    J: IntoIterator,
+    J::Item: Borrow<RistrettoPoint>,
+{
+    let scalars: Vec<Scalar> = scalars.into_iter().map(|s| *s.borrow()).collect();
+    let points: Vec<RistrettoPoint> = points.into_iter().map(|P| *P.borrow()).collect();
+    if scalars.len() != points.len() {
+        return Err(ProofError::FormatError);
+    }
+    profiling::record_scalar_muls(scalars.len());
+
+    if scalars.len() < PIPPENGER_THRESHOLD {
+        Ok(ristretto::vartime::multiscalar_mul(&scalars, &points))
+    } else {
+        Ok(pippenger_vartime(&scalars, &points))
+    }
+}
+
+/// Returns the `k`-th `w`-bit unsigned digit of the little-endian `bytes`.
+fn window_digit(bytes: &[u8; 32], k: usize, w: usize) -> usize {
+    let mut digit = 0usize;
+    for j in 0..w {
+        let bit = k * w + j;
+        if bit >= 256 {
+            break;
+        }
+        let b = (bytes[bit / 8] >> (bit % 8)) & 1;
+        digit |= (b as usize) << j;
     }
+    digit
 }
 
+/// Pippenger's bucket method for a variable-time multiscalar multiplication.
+fn pippenger_vartime(scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+    let w = if scalars.len() < 500 {
+        6
+    } else if scalars.len() < 800 {
+        7
+    } else {
+        8
+    };
+    let w = cmp::min(w, PIPPENGER_MAX_WINDOW);
+    let num_windows = (256 + w - 1) / w;
+    let scalars: Vec<[u8; 32]> = scalars.iter().map(|s| s.to_bytes()).collect();
+
+    let mut result = RistrettoPoint::identity();
+    let mut buckets = vec![RistrettoPoint::identity(); (1 << w) - 1];
+    for k in (0..num_windows).rev() {
+        for _ in 0..w {
+            result = &result + &result;
+        }
+
+        for bucket in buckets.iter_mut() {
+            *bucket = RistrettoPoint::identity();
+        }
+        for (s, P) in scalars.iter().zip(points.iter()) {
+            let digit = window_digit(s, k, w);
+            if digit != 0 {
+                buckets[digit - 1] += P;
+            }
+        }
+
+        // Sum_i i * bucket[i-1], computed as a sum of running sums.
+        let mut running_sum = RistrettoPoint::identity();
+        let mut window_sum = RistrettoPoint::identity();
+        for bucket in buckets.iter().rev() {
+            running_sum += bucket;
+            window_sum += &running_sum;
+        }
+        result += &window_sum;
+    }
+    result
+}
 
 #[cfg(test)]
 mod tests {
@@ -97,4 +386,61 @@ mod tests {
         assert_eq!(exp_2[2], Scalar::from_u64(4));
         assert_eq!(exp_2[3], Scalar::from_u64(8));
     }
+
+    #[test]
+    fn scalar_powers_match_exp_iter() {
+        let x = Scalar::from_u64(3);
+        let powers = ScalarPowers::new(x, 6);
+        let expected: Vec<_> = exp_iter(x).take(6).collect();
+
+        assert_eq!(powers.as_slice(), &expected[..]);
+        assert_eq!(powers.share(1, 2), &expected[2..4]);
+        assert_eq!(powers.sum(), Scalar::from_u64(1 + 3 + 9 + 27 + 81 + 243));
+    }
+
+    #[test]
+    fn batch_invert_matches_invert() {
+        let inputs: Vec<_> = (1..6).map(|i| Scalar::from_u64(i * 7)).collect();
+        let mut inverted = inputs.clone();
+        let allinv = batch_invert(&mut inverted);
+
+        for (x, x_inv) in inputs.iter().zip(inverted.iter()) {
+            assert_eq!(x.invert(), *x_inv);
+        }
+        let product = inputs.iter().fold(Scalar::one(), |acc, x| acc * x);
+        assert_eq!(product.invert(), allinv);
+    }
+
+    fn multiscalar_mul_helper(n: usize) {
+        use rand::OsRng;
+        use sha2::Sha512;
+
+        let mut rng = OsRng::new().unwrap();
+        let scalars: Vec<_> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let points: Vec<_> = (0..n)
+            .map(|i| RistrettoPoint::hash_from_bytes::<Sha512>(&[i as u8, (i >> 8) as u8]))
+            .collect();
+
+        let expected = scalars
+            .iter()
+            .zip(points.iter())
+            .fold(RistrettoPoint::identity(), |acc, (s, P)| acc + P * s);
+
+        assert_eq!(multiscalar_mul(&scalars, &points), expected);
+        assert_eq!(vartime_multiscalar_mul(&scalars, &points), Ok(expected));
+        assert_eq!(
+            vartime_multiscalar_mul(&scalars[1..], &points),
+            Err(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn multiscalar_mul_straus() {
+        multiscalar_mul_helper(16);
+    }
+
+    #[test]
+    fn multiscalar_mul_pippenger() {
+        multiscalar_mul_helper(PIPPENGER_THRESHOLD + 10);
+    }
 }
@@ -0,0 +1,291 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `vector_commitment` module contains a commitment to a vector of
+//! scalars with proofs that given positions hold given values.
+//!
+//! A vector \\(\mathbf{v}\\) of at most \\(n\\) scalars is committed as
+//! \\(C = \langle \mathbf{v}, \mathbf{G} \rangle + r \widetilde{B}\\),
+//! exactly like a polynomial in the `polynomial_commitment` module.
+//! Position \\(i\\) holds \\(v\_i = \langle \mathbf{v}, \mathbf{e}\_i \rangle\\),
+//! so an opening is an inner product proof with a unit vector.  A batch
+//! of positions \\(i\_j\\) is opened at once by drawing a challenge
+//! \\(\beta\\) and proving
+//! \\(\langle \mathbf{v}, \sum\_j \beta^j \mathbf{e}\_{i\_j} \rangle = \sum\_j \beta^j v\_{i\_j}\\),
+//! so the proof has the same size for any number of positions.
+//!
+//! Openings reveal nothing about the other positions.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let C = VectorCommitment::commit(generators.share(0), &values, &blinding)?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Positions")?;
+//! let proof = PositionProof::prove(
+//!     generators.share(0), &mut transcript, &mut rng, 64, &values, &blinding, &[3, 17],
+//! )?;
+//!
+//! let mut transcript = ProofTranscript::new(b"Positions")?;
+//! let openings = [(3, *values[3].as_scalar()), (17, *values[17].as_scalar())];
+//! proof.verify(generators.share(0), &mut transcript, 64, &C, &openings)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConstantTimeEq};
+
+use errors::ProofError;
+use generators::GeneratorsView;
+use polynomial_commitment::{self, OpeningProof};
+use proof_transcript::ProofTranscript;
+use secret::{Blinding, SecretScalar, SecretScalars};
+
+/// A commitment to a vector of scalars.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct VectorCommitment {
+    /// The commitment \\(\langle \mathbf{v}, \mathbf{G} \rangle + r \widetilde{B}\\)
+    point: RistrettoPoint,
+}
+
+impl VectorCommitment {
+    /// Commits to `values` with the blinding factor `blinding`.
+    ///
+    /// Returns `ProofError::InvalidGeneratorsLength` if there are more
+    /// values than generators.
+    pub fn commit(
+        generators: GeneratorsView,
+        values: &[SecretScalar],
+        blinding: &Blinding,
+    ) -> Result<VectorCommitment, ProofError> {
+        let point = polynomial_commitment::commit(generators, values, blinding)?;
+        Ok(VectorCommitment { point })
+    }
+
+    /// Wraps a commitment point received from the committer.
+    pub fn from_point(point: RistrettoPoint) -> VectorCommitment {
+        VectorCommitment { point }
+    }
+
+    /// Returns the commitment point.
+    pub fn as_point(&self) -> &RistrettoPoint {
+        &self.point
+    }
+}
+
+/// A proof that given positions of a committed vector hold given values.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PositionProof {
+    /// Inner product proof for the weighted sum of the positions
+    proof: OpeningProof,
+}
+
+impl PositionProof {
+    /// Proves the values at `positions` of the vector `values`, of at
+    /// most `n` entries, committed with `blinding`.
+    ///
+    /// Returns `ProofError::InvalidBitsize` if `n` is not a power of
+    /// two, `ProofError::InvalidGeneratorsLength` if it exceeds the
+    /// number of generators, `ProofError::ValueOutOfRange` if there are
+    /// more than `n` values, and `ProofError::InvalidStatement` if there
+    /// are no positions or a position is out of range.
+    pub fn prove<R: Rng>(
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+        n: usize,
+        values: &[SecretScalar],
+        blinding: &Blinding,
+        positions: &[usize],
+    ) -> Result<PositionProof, ProofError> {
+        if values.len() > n {
+            return Err(ProofError::ValueOutOfRange);
+        }
+        if positions.iter().any(|&i| i >= values.len()) {
+            return Err(ProofError::InvalidStatement);
+        }
+        let C = VectorCommitment::commit(generators, values, blinding)?;
+        let openings: Vec<(usize, Scalar)> = positions
+            .iter()
+            .map(|&i| (i, *values[i].as_scalar()))
+            .collect();
+
+        let (b, _) = weighted_positions(transcript, n, &C, &openings)?;
+        let values = SecretScalars::from(values);
+        let blinding = blinding.as_scalar();
+        let proof = OpeningProof::create(generators, transcript, rng, &values, blinding, b)?;
+        Ok(PositionProof { proof })
+    }
+
+    /// Verifies that the vector of at most `n` entries committed in `C`
+    /// holds the value `v` at position `i` for each pair `(i, v)` of
+    /// `openings`.
+    ///
+    /// Returns `ProofError::InvalidStatement` if there are no openings
+    /// or a position is not below `n`.
+    pub fn verify(
+        &self,
+        generators: GeneratorsView,
+        transcript: &mut ProofTranscript,
+        n: usize,
+        C: &VectorCommitment,
+        openings: &[(usize, Scalar)],
+    ) -> Result<(), ProofError> {
+        let (b, y) = weighted_positions(transcript, n, C, openings)?;
+        self.proof
+            .verify_inner_product(generators, transcript, &C.point, &b, &y)
+    }
+}
+
+/// Commits the statement to the transcript and combines the `openings`
+/// with the challenge \\(\beta\\), returning the vector
+/// \\(\sum\_j \beta^j \mathbf{e}\_{i\_j}\\) and the value
+/// \\(\sum\_j \beta^j v\_{i\_j}\\).
+fn weighted_positions(
+    transcript: &mut ProofTranscript,
+    n: usize,
+    C: &VectorCommitment,
+    openings: &[(usize, Scalar)],
+) -> Result<(Vec<Scalar>, Scalar), ProofError> {
+    if openings.is_empty() || openings.iter().any(|&(i, _)| i >= n) {
+        return Err(ProofError::InvalidStatement);
+    }
+
+    transcript.commit(b"VectorCommitment");
+    transcript.commit_u64(n as u64);
+    transcript.commit(C.point.compress().as_bytes());
+    transcript.commit_u64(openings.len() as u64);
+    for &(i, ref v) in openings.iter() {
+        transcript.commit_u64(i as u64);
+        transcript.commit(v.as_bytes());
+    }
+    let beta = transcript.challenge_scalar_labeled(b"beta");
+
+    let mut b = vec![Scalar::zero(); n];
+    let mut y = Scalar::zero();
+    let mut exp_beta = Scalar::one();
+    for &(i, ref v) in openings.iter() {
+        b[i] += exp_beta;
+        y += exp_beta * v;
+        exp_beta = exp_beta * beta;
+    }
+    Ok((b, y))
+}
+
+impl ConstantTimeEq for VectorCommitment {
+    fn ct_eq(&self, other: &VectorCommitment) -> Choice {
+        self.point.ct_eq(&other.point)
+    }
+}
+
+impl PartialEq for VectorCommitment {
+    fn eq(&self, other: &VectorCommitment) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl Eq for VectorCommitment {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+
+    fn values() -> Vec<SecretScalar> {
+        (0..6)
+            .map(|i| SecretScalar::new(Scalar::from_u64(100 + i)))
+            .collect()
+    }
+
+    #[test]
+    fn single_and_batched_openings_verify() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let values = values();
+        let blinding = Blinding::random(&mut rng);
+        let C = VectorCommitment::commit(generators.share(0), &values, &blinding).unwrap();
+
+        for positions in [vec![2], vec![0, 5, 3]].iter() {
+            let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+            let proof = PositionProof::prove(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &values,
+                &blinding,
+                positions,
+            ).unwrap();
+
+            let openings: Vec<(usize, Scalar)> = positions
+                .iter()
+                .map(|&i| (i, *values[i].as_scalar()))
+                .collect();
+            let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+            assert!(
+                proof
+                    .verify(generators.share(0), &mut transcript, n, &C, &openings)
+                    .is_ok()
+            );
+
+            // A wrong value at the last position does not verify.
+            let mut wrong = openings.clone();
+            wrong.last_mut().unwrap().1 += Scalar::one();
+            let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+            assert!(
+                proof
+                    .verify(generators.share(0), &mut transcript, n, &C, &wrong)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn opening_is_bound_to_its_position() {
+        let n = 8;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let values = values();
+        let blinding = Blinding::random(&mut rng);
+        let C = VectorCommitment::commit(generators.share(0), &values, &blinding).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+        let proof = PositionProof::prove(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &values,
+            &blinding,
+            &[1],
+        ).unwrap();
+
+        let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+        assert!(
+            proof
+                .verify(generators.share(0), &mut transcript, n, &C, &[(4, *values[1].as_scalar())])
+                .is_err()
+        );
+
+        // Positions beyond the vector cannot be opened.
+        let mut transcript = ProofTranscript::new(b"VectorTest").unwrap();
+        assert!(
+            PositionProof::prove(
+                generators.share(0),
+                &mut transcript,
+                &mut rng,
+                n,
+                &values,
+                &blinding,
+                &[6],
+            ).is_err()
+        );
+    }
+}
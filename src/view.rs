@@ -0,0 +1,202 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `view` module contains `ProofView`, a range proof borrowed from
+//! its encoding, for verifying proofs straight out of network buffers.
+//!
+//! `ProofView::from_bytes` checks the structure of an encoding of
+//! `RangeProof::to_bytes` in place: its length, that every scalar is
+//! canonical and that every point decompresses.  Nothing is copied, and
+//! `Verifier::verify_view` then reads the points and scalars from the
+//! buffer as it needs them, committing the compressed points to the
+//! transcript as they are.  The result is the same as decoding the
+//! proof with `RangeProof::from_bytes` and verifying it.
+//!
+//! # Example
+//!
+//! ```ascii
+//! let view = ProofView::from_bytes(&packet[offset..offset + len])?;
+//! let mut transcript = ProofTranscript::new(b"Mempool")?;
+//! verifier.verify_view(&view, &V, &mut transcript, &mut rng)?;
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use errors::ProofError;
+use inner_product_proof::LazyS;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use util;
+
+/// The largest number of rounds of the inner-product argument, for
+/// 64-bit ranges.
+const MAX_ROUNDS: usize = 6;
+
+/// A range proof borrowed from its encoding.
+#[derive(Copy, Clone, Debug)]
+pub struct ProofView<'a> {
+    /// The encoding
+    bytes: &'a [u8],
+    /// The number \\(\lg n\\) of rounds of the inner-product argument
+    rounds: usize,
+}
+
+impl<'a> ProofView<'a> {
+    /// Checks that `bytes` is an encoding of a range proof of at most 64
+    /// bits, in the format of `RangeProof::to_bytes`, and borrows it.
+    ///
+    /// Returns an error if the length is wrong, or if a point or scalar
+    /// is not a canonical encoding.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<ProofView<'a>, ProofError> {
+        if bytes.len() < 9 * 32 || (bytes.len() - 9 * 32) % 64 != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let rounds = (bytes.len() - 9 * 32) / 64;
+        if rounds > MAX_ROUNDS {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let view = ProofView { bytes, rounds };
+
+        let scalars = [4, 5, 6, 7 + 2 * rounds, 8 + 2 * rounds];
+        for &i in scalars.iter() {
+            util::read_scalar(view.chunk(i))?;
+        }
+        let points = (0..4).chain(7..7 + 2 * rounds);
+        for i in points {
+            view.compressed(i).decompress().ok_or(ProofError::InvalidPoint)?;
+        }
+        Ok(view)
+    }
+
+    /// Returns the bitsize \\(n\\) of the range.
+    pub fn n(&self) -> usize {
+        1 << self.rounds
+    }
+
+    /// Returns the encoding the view borrows.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the proof into an owned `RangeProof`.
+    pub fn to_proof(&self) -> RangeProof {
+        RangeProof::from_bytes(self.bytes).expect("the encoding was checked by from_bytes")
+    }
+
+    /// Returns the `i`-th 32-byte chunk of the encoding.
+    fn chunk(&self, i: usize) -> &'a [u8] {
+        &self.bytes[i * 32..(i + 1) * 32]
+    }
+
+    /// Returns the `i`-th chunk as a compressed point.
+    fn compressed(&self, i: usize) -> CompressedRistretto {
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(self.chunk(i));
+        CompressedRistretto(buf)
+    }
+
+    /// Decodes the point in the `i`-th chunk, which `from_bytes` checked.
+    pub(crate) fn point(&self, i: usize) -> RistrettoPoint {
+        self.compressed(i)
+            .decompress()
+            .expect("the points were checked by from_bytes")
+    }
+
+    /// Decodes the scalar in the `i`-th chunk, which `from_bytes` checked.
+    pub(crate) fn scalar(&self, i: usize) -> Scalar {
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(self.chunk(i));
+        Scalar::from_bytes_mod_order(buf)
+    }
+
+    /// Returns the inner-product argument's final scalars \\(a, b\\).
+    pub(crate) fn ipp_scalars(&self) -> (Scalar, Scalar) {
+        (self.scalar(7 + 2 * self.rounds), self.scalar(8 + 2 * self.rounds))
+    }
+
+    /// Returns the points \\(L\_j\\) and then \\(R\_j\\) of the
+    /// inner-product argument, in creation order, decoded on demand.
+    pub(crate) fn ipp_points<'b>(&'b self) -> impl Iterator<Item = RistrettoPoint> + 'b {
+        let L = (0..self.rounds).map(move |j| self.point(7 + 2 * j));
+        let R = (0..self.rounds).map(move |j| self.point(8 + 2 * j));
+        L.chain(R)
+    }
+
+    /// Recomputes the inner-product challenges from the transcript,
+    /// returning their squares, the squares of their inverses and the
+    /// verification scalars \\(s\_i\\), as
+    /// `InnerProductProof::verification_scalars_lazy` does.
+    pub(crate) fn ipp_verification_scalars(
+        &self,
+        transcript: &mut ProofTranscript,
+    ) -> (Vec<Scalar>, Vec<Scalar>, LazyS) {
+        let mut challenges = Vec::with_capacity(self.rounds);
+        for j in 0..self.rounds {
+            transcript.commit(self.chunk(7 + 2 * j));
+            transcript.commit(self.chunk(8 + 2 * j));
+            challenges.push(transcript.challenge_scalar_labeled(b"u"));
+        }
+        let mut challenges_inv = challenges.clone();
+        util::batch_invert(&mut challenges_inv);
+        LazyS::with_squares(challenges, challenges_inv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::{Generators, PedersenGenerators};
+    use rand::OsRng;
+    use range_proof::Verifier;
+    use secret::{Blinding, SecretValue};
+
+    #[test]
+    fn views_verify_like_decoded_proofs() {
+        let n = 32;
+        let generators = Generators::new(b"Test", PedersenGenerators::default(), n, 1);
+        let mut rng = OsRng::new().unwrap();
+        let v_blinding = Scalar::random(&mut rng);
+        let pg = generators.share(0).pedersen_generators;
+        let V = pg.commit(Scalar::from_u64(1000), v_blinding);
+        let mut transcript = ProofTranscript::new(b"ViewTest").unwrap();
+        let proof = RangeProof::generate_proof(
+            generators.share(0),
+            &mut transcript,
+            &mut rng,
+            n,
+            &SecretValue::new(1000),
+            &Blinding::new(v_blinding),
+        ).unwrap();
+
+        // The proof sits in the middle of a larger buffer.
+        let mut packet = vec![0xaa; 3];
+        packet.extend_from_slice(&proof.to_bytes());
+        packet.push(0xbb);
+        let view = ProofView::from_bytes(&packet[3..packet.len() - 1]).unwrap();
+        assert_eq!(view.n(), n);
+        assert_eq!(view.to_proof(), proof);
+
+        let verifier = Verifier::new(generators.share(0), n).unwrap();
+        let mut transcript = ProofTranscript::new(b"ViewTest").unwrap();
+        assert!(verifier.verify_view(&view, &V, &mut transcript, &mut rng).is_ok());
+        let mut transcript = ProofTranscript::new(b"ViewTest").unwrap();
+        assert!(
+            verifier
+                .verify_view(&view, &(V + pg.B), &mut transcript, &mut rng)
+                .is_err()
+        );
+
+        // The view covers the whole slice, so neighbouring bytes are rejected.
+        assert!(ProofView::from_bytes(&packet[3..]).is_err());
+        assert!(ProofView::from_bytes(&packet[2..packet.len() - 1]).is_err());
+        assert!(ProofView::from_bytes(&packet[3..packet.len() - 2]).is_err());
+
+        // A non-canonical scalar is caught before verification.
+        packet[3 + 4 * 32 + 31] = 0xff;
+        assert!(ProofView::from_bytes(&packet[3..packet.len() - 1]).is_err());
+    }
+}
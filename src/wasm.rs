@@ -0,0 +1,152 @@
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+//! The `wasm` module contains a thin `wasm-bindgen` wrapper around the
+//! range proof API, for use from JavaScript.
+//!
+//! `wasm32-unknown-unknown` has no operating system RNG, so every
+//! function that needs randomness takes a 32-byte `seed`, which must be
+//! freshly generated with `crypto.getRandomValues()` for each call.
+//!
+//! Values are passed as 8 little-endian bytes, scalars as 32 bytes, and
+//! commitments as 32-byte compressed Ristretto points.  Proofs are
+//! encoded with `bincode`.
+//!
+//! # Example
+//!
+//! ```ascii
+//! const seed = crypto.getRandomValues(new Uint8Array(32));
+//! const proof = bulletproofs.prove(64, label, value, blinding, seed);
+//! const V = bulletproofs.commit(value, blinding);
+//! const ok = bulletproofs.verify(64, label, proof, V, seed2);
+//! ```
+
+use bincode;
+use byteorder::{ByteOrder, LittleEndian};
+use rand::{ChaChaRng, SeedableRng};
+use wasm_bindgen::prelude::*;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use generators::{Generators, PedersenGenerators};
+use params::ProofParams;
+use proof_transcript::ProofTranscript;
+use range_proof::RangeProof;
+use secret::{Blinding, SecretValue};
+
+fn rng_from_seed(seed: &[u8]) -> Result<ChaChaRng, JsValue> {
+    if seed.len() != 32 {
+        return Err(JsValue::from_str("seed must be 32 bytes"));
+    }
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(seed.chunks(4)) {
+        *word = LittleEndian::read_u32(chunk);
+    }
+    Ok(ChaChaRng::from_seed(&words[..]))
+}
+
+fn check_label(label: &[u8]) -> Result<(), JsValue> {
+    if label.is_empty() {
+        return Err(JsValue::from_str("label must not be empty"));
+    }
+    Ok(())
+}
+
+fn transcript(label: &[u8]) -> Result<ProofTranscript, JsValue> {
+    ProofTranscript::new(label).map_err(|_| JsValue::from_str("label must not be empty"))
+}
+
+fn check_bitsize(n: usize) -> Result<(), JsValue> {
+    ProofParams::single(n)
+        .map(|_| ())
+        .map_err(|_| JsValue::from_str("n must be a power of two of at most 64"))
+}
+
+fn read_value(value: &[u8]) -> Result<u64, JsValue> {
+    if value.len() != 8 {
+        return Err(JsValue::from_str("value must be 8 bytes"));
+    }
+    Ok(LittleEndian::read_u64(value))
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, JsValue> {
+    if bytes.len() != 32 {
+        return Err(JsValue::from_str("scalar must be 32 bytes"));
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    Ok(Scalar::from_bytes_mod_order(buf))
+}
+
+/// Computes the Pedersen commitment to `value` with `blinding`,
+/// using the default generators.
+#[wasm_bindgen]
+pub fn commit(value: &[u8], blinding: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let v = read_value(value)?;
+    let v_blinding = read_scalar(blinding)?;
+    let V = PedersenGenerators::default().commit(Scalar::from_u64(v), v_blinding);
+    Ok(V.compress().as_bytes().to_vec())
+}
+
+/// Creates an `n`-bit range proof for `value` with `blinding`, using
+/// the generators and a transcript for the domain `label`.
+#[wasm_bindgen]
+pub fn prove(
+    n: usize,
+    label: &[u8],
+    value: &[u8],
+    blinding: &[u8],
+    seed: &[u8],
+) -> Result<Vec<u8>, JsValue> {
+    check_label(label)?;
+    check_bitsize(n)?;
+    let v = SecretValue::new(read_value(value)?);
+    let v_blinding = Blinding::new(read_scalar(blinding)?);
+    let mut rng = rng_from_seed(seed)?;
+
+    let generators = Generators::new(label, PedersenGenerators::default(), n, 1);
+    let mut transcript = transcript(label)?;
+    let proof = RangeProof::generate_proof(
+        generators.share(0),
+        &mut transcript,
+        &mut rng,
+        n,
+        &v,
+        &v_blinding,
+    ).map_err(|_| JsValue::from_str("could not create proof"))?;
+
+    bincode::serialize(&proof).map_err(|_| JsValue::from_str("could not serialize proof"))
+}
+
+/// Verifies an `n`-bit range proof for the compressed `commitment`,
+/// using the generators and a transcript for the domain `label`.
+#[wasm_bindgen]
+pub fn verify(
+    n: usize,
+    label: &[u8],
+    proof: &[u8],
+    commitment: &[u8],
+    seed: &[u8],
+) -> Result<bool, JsValue> {
+    check_label(label)?;
+    check_bitsize(n)?;
+    let proof: RangeProof =
+        bincode::deserialize(proof).map_err(|_| JsValue::from_str("could not parse proof"))?;
+    if commitment.len() != 32 {
+        return Err(JsValue::from_str("commitment must be 32 bytes"));
+    }
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(commitment);
+    let V = match CompressedRistretto(buf).decompress() {
+        Some(V) => V,
+        None => return Err(JsValue::from_str("commitment is not a valid point")),
+    };
+    let mut rng = rng_from_seed(seed)?;
+
+    let generators = Generators::new(label, PedersenGenerators::default(), n, 1);
+    let mut transcript = transcript(label)?;
+    Ok(proof
+        .verify(&V, generators.share(0), &mut transcript, &mut rng, n)
+        .is_ok())
+}